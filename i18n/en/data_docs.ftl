@@ -166,4 +166,494 @@ return-main-page = return to the main page
 
 ## Record not found
 record-not-found = Record not found
-record-not-found-explain = The Record you are searching for is not available.
\ No newline at end of file
+record-not-found-explain = The Record you are searching for is not available.
+
+## Ownership Report
+ownership-report = Ownership Report
+ownership-report-explain = Active teams and organizational tiers that do not have exactly one current primary owner.
+teams = Teams
+org-tiers = Organizational Tiers
+name = Name
+primary-owners = Primary owners
+status = Status
+orphaned = No current owner
+covered-by-acting = covered by an acting owner
+multiple-owners = More than one owner
+
+## Org Chart
+org-chart = Organization Chart
+org-chart-explain = Organizational tiers, teams and role holders. Choose a date to see the organization as it was.
+as-of = As of
+show-button = Show
+expand-all = Expand all
+collapse-all = Collapse all
+no-org-tiers = No organizational tiers were in place on this date.
+substantive = Substantive
+acting = Acting
+secondment = Secondment
+deployment = Deployment
+suspended = Suspended
+
+## Person Profile
+edit-profile = Edit profile
+edit-profile-explain = Update your profile as it appears across { -app-name }.
+names-from-hrms = Your name comes from the HR system. Ask HR to correct it there.
+current-roles = Current roles
+past-roles = Past roles
+role-history = Role history
+role-event = Event
+event-date = Date
+note = Note
+role-event-started = Started
+role-event-transferred = Transferred in
+role-event-ended = Ended
+role-event-acting-started = Acting started
+role-event-acting-ended = Acting ended
+role-event-secondment-started = Secondment started
+role-event-secondment-ended = Secondment ended
+role-event-deployment-started = Deployment started
+role-event-deployment-ended = Deployment ended
+title = Title
+team = Team
+effort = Effort
+start-date = Start date
+end-date = End date
+capabilities = Capabilities
+credentials = Credentials
+affiliations = Affiliations
+level-desired = Desired
+level-novice = Novice
+level-experienced = Experienced
+level-expert = Expert
+level-specialist = Specialist
+given-name = Given name
+family-name = Family name
+no-person-profile = No person profile is linked to this account yet.
+link-person = Link person profile
+link-person-title = Link a person profile to { $user }
+link-person-explain = Choose the person record that belongs to this user account, or unlink the current one.
+currently-linked = Currently linked:
+person = Person
+no-linked-person = No linked person
+
+## Team Page
+edit-team = Edit team
+create-team = Create team
+owners = Owners
+ownership-owner = Owner
+ownership-delegate = Delegate
+ownership-acting = Acting owner
+members = Members
+team-capacity = Team capacity
+fte = FTE
+skill-profile = Skill profile
+skill = Skill
+people = People
+max-level = Highest level
+average-level = Average level
+work = Work
+outcome = Outcome
+target-date = Target date
+work-planning = Planning
+work-in-progress = In progress
+work-complete = Complete
+work-blocked = Blocked
+name-en = Name (English)
+name-fr = Name (French)
+description-en = Description (English)
+description-fr = Description (French)
+org-tier = Organizational tier
+save-button = Save
+
+## Organizations
+organization-index = Organizations
+create-organization = Create organization
+edit-organization = Edit organization
+acronym = Acronym
+acronym-en = Acronym (English)
+acronym-fr = Acronym (French)
+org-type = Organization type
+org-type-department = Department
+org-type-agency = Agency
+org-type-crown_corporation = Crown corporation
+org-type-university = University
+org-type-private_sector = Private sector
+org-type-non_profit = Non-profit
+org-type-international = International organization
+create-org-tier = Create organizational tier
+edit-org-tier = Edit organizational tier
+tier-level = Tier level
+parent-tier = Parent tier
+no-parent-tier = None (top level)
+parent-tier-helper = The tier this one reports into. Its level is set from the parent.
+child-tiers = Child tiers
+retired = Retired
+
+## Bulk Import
+bulk-import = Bulk import
+bulk-import-explain = Load organizations, org tiers, teams, persons or roles from a CSV file. Load files in that order so later files can refer to earlier ones. Nothing is saved unless every row succeeds.
+import-kind = Data type
+import-kind-organizations = Organizations
+import-kind-org_tiers = Organizational tiers
+import-kind-teams = Teams
+import-kind-persons = Persons
+import-kind-roles = Roles
+import-file = CSV file
+column-mapping = Column mapping
+column-mapping-explain = One field=Header pair per line, for columns whose header differs from the field name.
+dry-run = Dry run (report only, save nothing)
+import-button = Import
+import-columns = Expected columns
+required-columns = Required
+optional-columns = Optional
+import-report = Import report
+import-committed = All rows were saved.
+import-dry-run = Dry run: nothing was saved.
+import-rolled-back = Some rows have conflicts or errors. Nothing was saved.
+import-action-create = Create
+import-action-update = Update
+import-action-unchanged = Unchanged
+import-action-conflict = Conflict
+import-action-error = Error
+line = Line
+key = Key
+action = Action
+details = Details
+
+## Exports
+export = Export
+export-persons = Person directory
+export-team_roster = Team rosters
+export-capacity = Capacity
+export-skills = Skill inventory
+export-team-roster = Export roster
+
+## HRMS Sync
+hr-sync = HRMS sync
+hr-sync-explain = Changes from HRMS extracts are applied when the current value came from a previous sync. Values entered in the app are flagged here instead of being overwritten.
+hr-sync-conflicts = Conflicts
+hr-no-conflicts = No open conflicts.
+hr-sync-runs = Recent extracts
+hr-sync-run = HRMS extract changes
+hr-key = Employee or org unit
+hr-entity = Record
+hr-entity-person = Person
+hr-entity-org_tier = Org tier
+hr-entity-role = Role
+hr-field = Field
+hr-app-value = App value
+hr-hrms-value = HRMS value
+hr-accept = Use HRMS value
+hr-keep = Keep app value
+hr-started = Started
+hr-source = Source
+hr-file = File
+hr-rows = Rows
+hr-applied = Changes applied
+hr-status-applied = Applied
+hr-status-conflict = Conflict
+hr-status-accepted = Accepted
+hr-status-kept = Kept app value
+hr-status-superseded = Superseded
+
+## ORCID
+orcid = ORCID iD
+orcid-explain = e.g. 0000-0002-1825-0097. Leave blank if you do not have one.
+orcid-import = Import from ORCID
+orcid-unmatched-organizations = Organizations not found
+orcid-unmatched-explain = Employments with these organizations were not imported. Add the organization and import again.
+orcid-skipped = Skipped entries
+
+## Publications
+publications = Publications
+no-publications = No publications found.
+publication-topic = Topic
+publication-topics = Topics
+publication-year = Year
+publication-venue = Venue
+publication-type = Type
+publication-type-journal_article = Journal article
+publication-type-preprint = Preprint
+publication-type-book = Book
+publication-type-book_chapter = Book chapter
+publication-type-conference_paper = Conference paper
+publication-type-report = Report
+publication-type-dataset = Dataset
+publication-type-other = Other
+open-access = Open access
+open-access-short = OA
+open-access-gold = Gold
+open-access-green = Green
+open-access-hybrid = Hybrid
+open-access-bronze = Bronze
+open-access-closed = Closed
+open-access-unknown = Unknown
+publication-authors = Authors
+external-author = External
+author-order-unknown = position unknown
+publication-output = Publication output
+publication-output-explain = Distinct publications per year by people who held a role on each team during the publication year. Tier counts include all teams below the tier.
+from-year = From
+to-year = To
+total = Total
+
+## Publication import
+publication = Publication
+author = Author
+publication-import = Import publications
+publication-import-explain = Load publication metadata from a Crossref JSON, RIS (Dimensions, Scopus) or BibTeX export, or from a list of DOIs looked up on Crossref. Entries are de-duplicated by DOI, then by a near-identical title in the same year. Authors are matched to people by ORCID iD and name, and each match waits for review.
+publication-format = Format
+publication-format-crossref = Crossref JSON
+publication-format-ris = RIS
+publication-format-bibtex = BibTeX
+publication-format-dois = List of DOIs
+publication-dois = DOIs
+publication-dois-explain = One DOI per line. Used when no file is chosen.
+publication-entry = Entry
+author-matches = Author matches
+author-matches-explain = Proposed links between publication authors and people. Accepting a match links the authorship to the person and rejects the other proposals for that author.
+author-matches-none = No author matches to review.
+author-match-method = Matched by
+author-match-method-orcid = ORCID iD
+author-match-method-name = Name
+author-match-method-initials = Family name and initial
+author-match-accept = Accept
+author-match-reject = Reject
+
+## Collaborations
+collaborations = Collaborations
+collaborations-explain = Links between people from overlapping roles on a team and co-authored publications, and with external organizations through affiliations and co-authors. Use the graph export in network tools such as Gephi or Cytoscape.
+collaborators = Collaborators
+collaboration-graph = Collaboration graph
+graph-nodes = nodes
+graph-edges = edges
+team-links = Strongest links between teams
+external-organizations = Most connected external organizations
+organization = Organization
+shared-people = People on both
+shared-teams = Shared teams
+co-publications = Co-authored publications
+people-connected = People connected
+no-collaborations = No collaborations found.
+
+## Surge
+surge-events = Surge events
+surge-events-explain = Emergencies and other events that need people mobilized quickly. Deployments are temporary roles on the event's team, alongside the person's home role.
+surge-event = Surge event
+surge-status = Status
+surge-status-active = Active
+surge-status-closed = Closed
+no-surge-events = No surge events.
+create-surge-event = Create surge event
+close-surge-event = Close event and release everyone
+surge-team = Team
+surge-team-explain = The team deployed people join.
+surge-headcount = People needed
+surge-open-positions = open
+surge-deployment-effort = Effort per deployment
+surge-languages = Languages required
+surge-requirements = Requirements
+no-surge-requirements = No requirements; anyone may be deployed.
+surge-credential = Credential
+surge-credential-only = Credential only
+surge-add-requirement = Add requirement
+surge-delete-requirement = Remove
+surge-current-deployments = Current and upcoming deployments
+surge-past-deployments = Past deployments
+no-surge-deployments = Nobody is deployed.
+surge-deployments = Surge deployments
+surge-released = released early
+surge-release-button = Release
+surge-candidates = Candidates
+surge-candidates-explain = People who meet every requirement and work in every required language. Those with effort available come first, then people rested longest and deployed least.
+surge-any-organization = Any organization
+surge-include-unavailable = Include people without enough effort available
+surge-matched = Requirements met
+surge-headroom = Effort available
+surge-unavailable = Over capacity
+surge-note = Note
+surge-deploy-button = Deploy
+no-surge-candidates = No eligible candidates.
+working-languages = Working languages
+working-languages-explain = Two-letter codes separated by commas, e.g. en, fr, es.
+deployment-fatigue = Deployment fatigue
+deployment-fatigue-explain = People deployed in the chosen number of months, those carrying the heaviest load first. People deployed now or back for less than 90 days are resting and are listed last among surge candidates.
+fatigue-window-months = Months of history
+fatigue-deployments = Deployments in period
+fatigue-extra-effort = Extra effort (effort-months)
+fatigue-days-since-last = Days since last deployment
+fatigue-deployed-now = Deployed now
+fatigue-never = Never deployed
+fatigue-resting = Resting
+
+## Official languages
+official-languages = Official languages
+first-official-language = First official language
+official-language-en = English
+official-language-fr = French
+evaluation-language = Second language
+evaluation-reading = Reading
+evaluation-writing = Writing
+evaluation-oral = Oral
+evaluation-tested = Tested
+evaluation-expires = Expires
+add-evaluation = Add result
+delete-evaluation = Remove
+language-requirement = Language requirement
+language-requirement-english_essential = English essential
+language-requirement-french_essential = French essential
+language-requirement-either_or = English or French
+language-requirement-bilingual = Bilingual
+language-report = Language requirements report
+language-report-explain = Current roles whose incumbent doesn't meet the position's language requirement. Bilingual profiles apply to the incumbent's second official language. Results other than E lapse on their expiry date.
+language-gap = Gap
+language-gap-no_first_official_language = First official language not recorded
+language-gap-no_evaluation = Not evaluated
+language-gap-expired = Results expired
+language-gap-below_profile = Below the required levels
+no-language-gaps = Every incumbent meets their position's language requirement.
+
+## Rosters
+rosters = Talent rosters
+rosters-explain = Pools of pre-qualified people ready to be placed in a role. Qualifications lapse on their expiry date.
+roster = Roster
+roster-any = Any
+roster-search = Search rosters
+roster-language = Working language
+roster-include-expired = Include expired qualifications
+roster-qualified = Qualified
+roster-expires = Expires
+roster-expired = Expired
+roster-skills = Required skills
+roster-add-skill = Add skill
+roster-members = Members
+roster-add-member = Add member
+roster-remove = Remove
+roster-place = Place
+roster-placements = Placements
+no-rosters = No rosters yet.
+no-roster-matches = No qualified members match this search.
+create-roster = Create roster
+classification = Classification
+roster-classification-explain = Roles filled from this roster take this classification, e.g. EC-05. Leave blank if it varies.
+work-location = Work location
+
+## Skill gaps
+skill-gaps = Skill gaps
+skill-gaps-explain = Skills needed by active work and expected of role classifications, against the people on the team or in the tier and every tier below it. Each active work item needing a skill counts as one person; each classified role counts as its effort. Supply is the effort of people who hold the skill at the required level or above.
+skill-demand = Demand and supply
+required-skills = Required skills
+required-level = Required level
+level = Level
+demand-work = Active work
+demand-classification = Classified roles (FTE)
+demand = Demand
+supply = Supply (FTE)
+shortfall = Shortfall
+single-point = Single point of failure
+single-points = Single points of failure
+single-points-explain = Skills held by only one person, at Novice or above.
+skill-demanded = Needed
+no-skill-demand = No active work or classified role requires a skill.
+add-requirement = Add
+remove-requirement = Remove
+classification-skills = Classification skill expectations
+classification-skills-explain = Skills expected of anyone in a role of a classification. They count toward demand in skill gap reports.
+
+## Succession
+succession-report = Succession risk report
+succession-report-explain = Team and org tier owners and the only people holding a skill at Experienced or above, with their nearest successors. A ready successor holds each of the incumbent's skills at Experienced or above and no more than one level below the incumbent. Risk is high with no ready successor and retirement eligibility within the horizon, medium with either one.
+retirement-horizon = Retirement horizon (months)
+retirement-eligible-on = Retirement eligibility
+retirement-eligible = Eligible within horizon
+succession-risk = Risk
+succession-risk-high = High
+succession-risk-medium = Medium
+succession-risk-low = Low
+critical-position = Critical position
+critical-team_owner = Team owner
+critical-org_tier_owner = Org tier owner
+critical-sole_skill_holder = Sole holder of
+successors = Successors
+successor-ready = Ready
+successor-developing = Developing
+direct-report = Direct report
+no-successor = None identified
+no-key-skills = No skills at Experienced or above recorded
+no-critical-positions = No critical positions found.
+
+## Workforce analytics
+workforce-analytics = Workforce analytics
+workforce-analytics-explain = Monthly headcount, FTE and movement for each team and org tier, rebuilt from role history when refreshed. Figures are as of the last day of each month and org tiers include every tier below them. Hires and departures are people joining or leaving the organization, moves are people changing team or tier within it. Surge deployments are left out.
+workforce-last-refreshed = Last refreshed
+workforce-never-refreshed = Never
+workforce-refresh = Refresh
+month = Month
+headcount = Headcount
+hires = Hires
+departures = Departures
+moves-in = Moves in
+moves-out = Moves out
+turnover-rate = Turnover rate
+average-tenure = Average tenure (months)
+no-workforce-metrics = No figures for this unit. Refresh to rebuild them.
+export-workforce = Workforce series
+
+## Search
+search = Search
+search-placeholder = People, teams, skills, publications…
+search-any-kind = Anything
+search-any-org-tier = Any org tier
+search-any-classification = Any classification
+search-any-level = Any skill level
+search-results = results
+search-shown = shown
+search-kind-person = Person
+search-kind-team = Team
+search-kind-org_tier = Org tier
+search-kind-skill = Skill
+search-kind-publication = Publication
+no-search-results = Nothing matches your search.
+
+## Expert finder
+expert-finder = Find an expert
+expert-finder-description = Choose up to three skills and the level needed. People are ranked by how many skills they meet, then by a score out of 100 built from their self-assessed level, peer validations, related credentials and publications, effort headroom and, if asked for, working language.
+expert-finder-search = Find experts
+expert-finder-none = Nobody holds these skills at the levels needed.
+expert-required-level = Level needed
+expert-self-level = Self-assessed
+expert-component = Component
+expert-points = Points
+expert-explanation = Why
+expert-skill-level = Skill level
+expert-peer-validation = Peer validation
+expert-evidence = Credentials and publications
+expert-availability = Availability
+expert-language = Language
+validated-by = Validators
+validated-level = consensus
+validate-capability = Validate
+
+## API tokens
+api-tokens = API tokens
+api-tokens-explain = Personal tokens let scripts and notebooks call the API as you. Send one in an Authorization: Bearer header. Each token only works for the scopes chosen and until it expires or is revoked.
+api-token-copy-now = Copy this token now. It won't be shown again.
+api-token-create = Create a token
+api-token-name = Name
+api-token-name-placeholder = e.g. Workforce notebook
+api-token-days = Expires in (days)
+api-token-scopes = Scopes
+api-scope-graphql = GraphQL queries (/api/graphql)
+api-scope-export = Report exports (CSV and XLSX)
+api-token-prefix = Token
+api-token-expires = Expires
+api-token-last-used = Last used
+api-token-never-used = Never
+api-token-revoke = Revoke
+api-token-revoked = Revoked
+api-token-expired = Expired
+api-token-none = No API tokens yet.
+
+## Single sign-on
+sso-login = Log in with
+sso-or-password = Or log in with your email and password.
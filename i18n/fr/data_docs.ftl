@@ -166,4 +166,494 @@ return-main-page = return to the main page
 
 ## Record not found
 record-not-found = Record not found
-record-not-found-explain = The Record you are searching for is not available.
\ No newline at end of file
+record-not-found-explain = The Record you are searching for is not available.
+
+## Ownership Report
+ownership-report = Rapport sur les responsables
+ownership-report-explain = Équipes et niveaux organisationnels actifs qui n'ont pas exactement un responsable principal actuel.
+teams = Équipes
+org-tiers = Niveaux organisationnels
+name = Nom
+primary-owners = Responsables principaux
+status = Statut
+orphaned = Aucun responsable actuel
+covered-by-acting = assuré par un responsable par intérim
+multiple-owners = Plus d'un responsable
+
+## Org Chart
+org-chart = Organigramme
+org-chart-explain = Niveaux organisationnels, équipes et titulaires de rôles. Choisissez une date pour voir l'organisation telle qu'elle était.
+as-of = En date du
+show-button = Afficher
+expand-all = Tout déplier
+collapse-all = Tout replier
+no-org-tiers = Aucun niveau organisationnel n'était en place à cette date.
+substantive = Titulaire
+acting = Intérim
+secondment = Détachement
+deployment = Déploiement
+suspended = Suspendu
+
+## Person Profile
+edit-profile = Modifier le profil
+edit-profile-explain = Mettez à jour votre profil tel qu'il apparaît dans { -app-name }.
+names-from-hrms = Votre nom provient du système de RH. Demandez aux RH de le corriger à la source.
+current-roles = Rôles actuels
+past-roles = Rôles antérieurs
+role-history = Historique des rôles
+role-event = Événement
+event-date = Date
+note = Note
+role-event-started = Début
+role-event-transferred = Mutation
+role-event-ended = Fin
+role-event-acting-started = Début de l'intérim
+role-event-acting-ended = Fin de l'intérim
+role-event-secondment-started = Début de l'affectation
+role-event-secondment-ended = Fin de l'affectation
+role-event-deployment-started = Début du déploiement
+role-event-deployment-ended = Fin du déploiement
+title = Titre
+team = Équipe
+effort = Effort
+start-date = Date de début
+end-date = Date de fin
+capabilities = Capacités
+credentials = Titres de compétences
+affiliations = Affiliations
+level-desired = Souhaité
+level-novice = Débutant
+level-experienced = Expérimenté
+level-expert = Expert
+level-specialist = Spécialiste
+given-name = Prénom
+family-name = Nom de famille
+no-person-profile = Aucun profil de personne n'est encore lié à ce compte.
+link-person = Lier un profil de personne
+link-person-title = Lier un profil de personne à { $user }
+link-person-explain = Choisissez la fiche de personne qui correspond à ce compte utilisateur, ou retirez le lien actuel.
+currently-linked = Actuellement lié :
+person = Personne
+no-linked-person = Aucune personne liée
+
+## Team Page
+edit-team = Modifier l'équipe
+create-team = Créer une équipe
+owners = Responsables
+ownership-owner = Responsable
+ownership-delegate = Délégué
+ownership-acting = Responsable par intérim
+members = Membres
+team-capacity = Capacité de l'équipe
+fte = ETP
+skill-profile = Profil de compétences
+skill = Compétence
+people = Personnes
+max-level = Niveau le plus élevé
+average-level = Niveau moyen
+work = Travail
+outcome = Résultat
+target-date = Date cible
+work-planning = Planification
+work-in-progress = En cours
+work-complete = Terminé
+work-blocked = Bloqué
+name-en = Nom (anglais)
+name-fr = Nom (français)
+description-en = Description (anglais)
+description-fr = Description (français)
+org-tier = Niveau organisationnel
+save-button = Enregistrer
+
+## Organizations
+organization-index = Organisations
+create-organization = Créer une organisation
+edit-organization = Modifier l'organisation
+acronym = Acronyme
+acronym-en = Acronyme (anglais)
+acronym-fr = Acronyme (français)
+org-type = Type d'organisation
+org-type-department = Ministère
+org-type-agency = Agence
+org-type-crown_corporation = Société d'État
+org-type-university = Université
+org-type-private_sector = Secteur privé
+org-type-non_profit = Organisme sans but lucratif
+org-type-international = Organisation internationale
+create-org-tier = Créer un niveau organisationnel
+edit-org-tier = Modifier le niveau organisationnel
+tier-level = Niveau hiérarchique
+parent-tier = Niveau parent
+no-parent-tier = Aucun (niveau supérieur)
+parent-tier-helper = Le niveau dont celui-ci relève. Son niveau hiérarchique est déterminé par le parent.
+child-tiers = Niveaux enfants
+retired = Retiré
+
+## Bulk Import
+bulk-import = Importation en bloc
+bulk-import-explain = Charger des organisations, des niveaux organisationnels, des équipes, des personnes ou des rôles à partir d'un fichier CSV. Chargez les fichiers dans cet ordre afin que les fichiers suivants puissent faire référence aux précédents. Rien n'est enregistré à moins que toutes les lignes réussissent.
+import-kind = Type de données
+import-kind-organizations = Organisations
+import-kind-org_tiers = Niveaux organisationnels
+import-kind-teams = Équipes
+import-kind-persons = Personnes
+import-kind-roles = Rôles
+import-file = Fichier CSV
+column-mapping = Correspondance des colonnes
+column-mapping-explain = Une paire champ=En-tête par ligne, pour les colonnes dont l'en-tête diffère du nom du champ.
+dry-run = Essai à blanc (rapport seulement, rien n'est enregistré)
+import-button = Importer
+import-columns = Colonnes attendues
+required-columns = Obligatoires
+optional-columns = Facultatives
+import-report = Rapport d'importation
+import-committed = Toutes les lignes ont été enregistrées.
+import-dry-run = Essai à blanc : rien n'a été enregistré.
+import-rolled-back = Certaines lignes comportent des conflits ou des erreurs. Rien n'a été enregistré.
+import-action-create = Création
+import-action-update = Mise à jour
+import-action-unchanged = Inchangé
+import-action-conflict = Conflit
+import-action-error = Erreur
+line = Ligne
+key = Clé
+action = Action
+details = Détails
+
+## Exports
+export = Exporter
+export-persons = Répertoire des personnes
+export-team_roster = Listes des équipes
+export-capacity = Capacité
+export-skills = Inventaire des compétences
+export-team-roster = Exporter la liste
+
+## HRMS Sync
+hr-sync = Synchronisation SGRH
+hr-sync-explain = Les changements provenant des extraits du SGRH sont appliqués lorsque la valeur actuelle provient d'une synchronisation précédente. Les valeurs saisies dans l'application sont signalées ici plutôt que d'être remplacées.
+hr-sync-conflicts = Conflits
+hr-no-conflicts = Aucun conflit ouvert.
+hr-sync-runs = Extraits récents
+hr-sync-run = Changements de l'extrait SGRH
+hr-key = Employé ou unité organisationnelle
+hr-entity = Enregistrement
+hr-entity-person = Personne
+hr-entity-org_tier = Niveau organisationnel
+hr-entity-role = Rôle
+hr-field = Champ
+hr-app-value = Valeur dans l'application
+hr-hrms-value = Valeur du SGRH
+hr-accept = Utiliser la valeur du SGRH
+hr-keep = Conserver la valeur de l'application
+hr-started = Début
+hr-source = Source
+hr-file = Fichier
+hr-rows = Lignes
+hr-applied = Changements appliqués
+hr-status-applied = Appliqué
+hr-status-conflict = Conflit
+hr-status-accepted = Accepté
+hr-status-kept = Valeur de l'application conservée
+hr-status-superseded = Remplacé
+
+## ORCID
+orcid = Identifiant ORCID
+orcid-explain = p. ex. 0000-0002-1825-0097. Laissez vide si vous n'en avez pas.
+orcid-import = Importer d'ORCID
+orcid-unmatched-organizations = Organisations introuvables
+orcid-unmatched-explain = Les emplois auprès de ces organisations n'ont pas été importés. Ajoutez l'organisation et importez de nouveau.
+orcid-skipped = Entrées ignorées
+
+## Publications
+publications = Publications
+no-publications = Aucune publication trouvée.
+publication-topic = Sujet
+publication-topics = Sujets
+publication-year = Année
+publication-venue = Publié dans
+publication-type = Type
+publication-type-journal_article = Article de revue
+publication-type-preprint = Prépublication
+publication-type-book = Livre
+publication-type-book_chapter = Chapitre de livre
+publication-type-conference_paper = Communication de conférence
+publication-type-report = Rapport
+publication-type-dataset = Jeu de données
+publication-type-other = Autre
+open-access = Libre accès
+open-access-short = LA
+open-access-gold = Doré
+open-access-green = Vert
+open-access-hybrid = Hybride
+open-access-bronze = Bronze
+open-access-closed = Fermé
+open-access-unknown = Inconnu
+publication-authors = Auteurs
+external-author = Externe
+author-order-unknown = position inconnue
+publication-output = Production de publications
+publication-output-explain = Publications distinctes par année des personnes ayant occupé un rôle dans chaque équipe pendant l'année de publication. Les totaux d'un palier incluent toutes les équipes sous ce palier.
+from-year = De
+to-year = À
+total = Total
+
+## Publication import
+publication = Publication
+author = Auteur
+publication-import = Importer des publications
+publication-import-explain = Charger les métadonnées de publications à partir d'une exportation Crossref JSON, RIS (Dimensions, Scopus) ou BibTeX, ou d'une liste de DOI recherchés dans Crossref. Les entrées sont dédoublonnées par DOI, puis par un titre quasi identique la même année. Les auteurs sont associés aux personnes par identifiant ORCID et par nom, et chaque association attend une révision.
+publication-format = Format
+publication-format-crossref = Crossref JSON
+publication-format-ris = RIS
+publication-format-bibtex = BibTeX
+publication-format-dois = Liste de DOI
+publication-dois = DOI
+publication-dois-explain = Un DOI par ligne. Utilisé si aucun fichier n'est choisi.
+publication-entry = Entrée
+author-matches = Associations d'auteurs
+author-matches-explain = Liens proposés entre les auteurs de publications et les personnes. Accepter une association lie la paternité à la personne et rejette les autres propositions pour cet auteur.
+author-matches-none = Aucune association d'auteur à réviser.
+author-match-method = Associé par
+author-match-method-orcid = Identifiant ORCID
+author-match-method-name = Nom
+author-match-method-initials = Nom de famille et initiale
+author-match-accept = Accepter
+author-match-reject = Rejeter
+
+## Collaborations
+collaborations = Collaborations
+collaborations-explain = Liens entre les personnes par des rôles simultanés dans une équipe et des publications coécrites, et avec des organisations externes par des affiliations et des coauteurs. Utilisez l'exportation du graphe dans des outils d'analyse de réseaux comme Gephi ou Cytoscape.
+collaborators = Collaborateurs
+collaboration-graph = Graphe des collaborations
+graph-nodes = nœuds
+graph-edges = liens
+team-links = Liens les plus forts entre équipes
+external-organizations = Organisations externes les plus liées
+organization = Organisation
+shared-people = Personnes dans les deux
+shared-teams = Équipes communes
+co-publications = Publications coécrites
+people-connected = Personnes liées
+no-collaborations = Aucune collaboration trouvée.
+
+## Surge
+surge-events = Événements de renfort
+surge-events-explain = Urgences et autres événements qui exigent une mobilisation rapide. Les déploiements sont des rôles temporaires dans l'équipe de l'événement, en plus du rôle d'attache de la personne.
+surge-event = Événement de renfort
+surge-status = Statut
+surge-status-active = Actif
+surge-status-closed = Terminé
+no-surge-events = Aucun événement de renfort.
+create-surge-event = Créer un événement de renfort
+close-surge-event = Terminer l'événement et libérer tout le monde
+surge-team = Équipe
+surge-team-explain = L'équipe que rejoignent les personnes déployées.
+surge-headcount = Personnes requises
+surge-open-positions = à pourvoir
+surge-deployment-effort = Effort par déploiement
+surge-languages = Langues requises
+surge-requirements = Exigences
+no-surge-requirements = Aucune exigence; tout le monde peut être déployé.
+surge-credential = Titre de compétence
+surge-credential-only = Titre de compétence seulement
+surge-add-requirement = Ajouter une exigence
+surge-delete-requirement = Retirer
+surge-current-deployments = Déploiements en cours et à venir
+surge-past-deployments = Déploiements passés
+no-surge-deployments = Personne n'est déployé.
+surge-deployments = Déploiements de renfort
+surge-released = libéré plus tôt
+surge-release-button = Libérer
+surge-candidates = Candidats
+surge-candidates-explain = Personnes qui satisfont à toutes les exigences et travaillent dans toutes les langues requises. Celles qui ont de l'effort disponible viennent en premier, puis les plus reposées et les moins déployées.
+surge-any-organization = Toute organisation
+surge-include-unavailable = Inclure les personnes sans effort disponible suffisant
+surge-matched = Exigences satisfaites
+surge-headroom = Effort disponible
+surge-unavailable = Surcapacité
+surge-note = Note
+surge-deploy-button = Déployer
+no-surge-candidates = Aucun candidat admissible.
+working-languages = Langues de travail
+working-languages-explain = Codes à deux lettres séparés par des virgules, p. ex. en, fr, es.
+deployment-fatigue = Fatigue de déploiement
+deployment-fatigue-explain = Personnes déployées au cours du nombre de mois choisi, la charge la plus lourde en premier. Les personnes déployées en ce moment ou revenues depuis moins de 90 jours sont en repos et figurent en dernier parmi les candidats de renfort.
+fatigue-window-months = Mois d'historique
+fatigue-deployments = Déploiements dans la période
+fatigue-extra-effort = Effort supplémentaire (effort-mois)
+fatigue-days-since-last = Jours depuis le dernier déploiement
+fatigue-deployed-now = Déployé en ce moment
+fatigue-never = Jamais déployé
+fatigue-resting = En repos
+
+## Official languages
+official-languages = Langues officielles
+first-official-language = Première langue officielle
+official-language-en = Anglais
+official-language-fr = Français
+evaluation-language = Langue seconde
+evaluation-reading = Compréhension de l'écrit
+evaluation-writing = Expression écrite
+evaluation-oral = Compétence orale
+evaluation-tested = Évalué le
+evaluation-expires = Expire le
+add-evaluation = Ajouter un résultat
+delete-evaluation = Retirer
+language-requirement = Exigence linguistique
+language-requirement-english_essential = Anglais essentiel
+language-requirement-french_essential = Français essentiel
+language-requirement-either_or = Anglais ou français
+language-requirement-bilingual = Bilingue
+language-report = Rapport sur les exigences linguistiques
+language-report-explain = Rôles actuels dont le titulaire ne satisfait pas à l'exigence linguistique du poste. Les profils bilingues s'appliquent à la seconde langue officielle du titulaire. Les résultats autres que E cessent d'être valides à leur date d'expiration.
+language-gap = Écart
+language-gap-no_first_official_language = Première langue officielle non consignée
+language-gap-no_evaluation = Non évalué
+language-gap-expired = Résultats expirés
+language-gap-below_profile = Sous les niveaux requis
+no-language-gaps = Chaque titulaire satisfait à l'exigence linguistique de son poste.
+
+## Rosters
+rosters = Bassins de talents
+rosters-explain = Bassins de personnes préqualifiées prêtes à être placées dans un poste. Les qualifications expirent à leur date d'échéance.
+roster = Bassin
+roster-any = Tous
+roster-search = Rechercher dans les bassins
+roster-language = Langue de travail
+roster-include-expired = Inclure les qualifications expirées
+roster-qualified = Qualifié
+roster-expires = Échéance
+roster-expired = Expirée
+roster-skills = Compétences requises
+roster-add-skill = Ajouter une compétence
+roster-members = Membres
+roster-add-member = Ajouter un membre
+roster-remove = Retirer
+roster-place = Placer
+roster-placements = Placements
+no-rosters = Aucun bassin pour l'instant.
+no-roster-matches = Aucun membre qualifié ne correspond à cette recherche.
+create-roster = Créer un bassin
+classification = Classification
+roster-classification-explain = Les postes pourvus à partir de ce bassin prennent cette classification, p. ex. EC-05. Laisser vide si elle varie.
+work-location = Lieu de travail
+
+## Skill gaps
+skill-gaps = Écarts de compétences
+skill-gaps-explain = Compétences requises par les travaux actifs et attendues des classifications des postes, comparées aux personnes de l'équipe ou du palier et de tous les paliers inférieurs. Chaque travail actif exigeant une compétence compte pour une personne; chaque poste classifié compte pour son effort. L'offre est l'effort des personnes qui possèdent la compétence au niveau requis ou plus.
+skill-demand = Demande et offre
+required-skills = Compétences requises
+required-level = Niveau requis
+level = Niveau
+demand-work = Travaux actifs
+demand-classification = Postes classifiés (ETP)
+demand = Demande
+supply = Offre (ETP)
+shortfall = Manque
+single-point = Point de défaillance unique
+single-points = Points de défaillance uniques
+single-points-explain = Compétences détenues par une seule personne, au niveau Débutant ou plus.
+skill-demanded = Requise
+no-skill-demand = Aucun travail actif ni poste classifié n'exige de compétence.
+add-requirement = Ajouter
+remove-requirement = Retirer
+classification-skills = Compétences attendues par classification
+classification-skills-explain = Compétences attendues de toute personne occupant un poste d'une classification. Elles comptent dans la demande des rapports d'écarts de compétences.
+
+## Succession
+succession-report = Rapport sur les risques de relève
+succession-report-explain = Responsables d'équipes et de paliers et seules personnes possédant une compétence au niveau Expérimenté ou plus, avec leurs successeurs les plus proches. Un successeur prêt possède chacune des compétences du titulaire au niveau Expérimenté ou plus et au plus un niveau sous celui du titulaire. Le risque est élevé sans successeur prêt et avec une admissibilité à la retraite dans l'horizon, moyen avec l'un ou l'autre.
+retirement-horizon = Horizon de retraite (mois)
+retirement-eligible-on = Admissibilité à la retraite
+retirement-eligible = Admissible dans l'horizon
+succession-risk = Risque
+succession-risk-high = Élevé
+succession-risk-medium = Moyen
+succession-risk-low = Faible
+critical-position = Poste essentiel
+critical-team_owner = Responsable d'équipe
+critical-org_tier_owner = Responsable de palier
+critical-sole_skill_holder = Seul détenteur de
+successors = Successeurs
+successor-ready = Prêt
+successor-developing = En développement
+direct-report = Subordonné direct
+no-successor = Aucun
+no-key-skills = Aucune compétence de niveau Expérimenté ou plus
+no-critical-positions = Aucun poste essentiel trouvé.
+
+## Workforce analytics
+workforce-analytics = Analyse des effectifs
+workforce-analytics-explain = Effectif, ETP et mouvements mensuels de chaque équipe et palier organisationnel, recalculés à partir de l'historique des rôles lors de l'actualisation. Les chiffres sont au dernier jour de chaque mois et les paliers incluent tous les paliers inférieurs. Les embauches et départs sont les personnes qui entrent dans l'organisation ou la quittent, les mutations sont les changements d'équipe ou de palier à l'interne. Les déploiements d'urgence sont exclus.
+workforce-last-refreshed = Dernière actualisation
+workforce-never-refreshed = Jamais
+workforce-refresh = Actualiser
+month = Mois
+headcount = Effectif
+hires = Embauches
+departures = Départs
+moves-in = Mutations entrantes
+moves-out = Mutations sortantes
+turnover-rate = Taux de roulement
+average-tenure = Ancienneté moyenne (mois)
+no-workforce-metrics = Aucun chiffre pour cette unité. Actualisez pour les recalculer.
+export-workforce = Séries d'effectifs
+
+## Search
+search = Rechercher
+search-placeholder = Personnes, équipes, compétences, publications…
+search-any-kind = Tout
+search-any-org-tier = Tout palier
+search-any-classification = Toute classification
+search-any-level = Tout niveau de compétence
+search-results = résultats
+search-shown = affichés
+search-kind-person = Personne
+search-kind-team = Équipe
+search-kind-org_tier = Palier
+search-kind-skill = Compétence
+search-kind-publication = Publication
+no-search-results = Aucun résultat pour votre recherche.
+
+## Expert finder
+expert-finder = Trouver un expert
+expert-finder-description = Choisissez jusqu'à trois compétences et le niveau requis. Les personnes sont classées selon le nombre de compétences satisfaites, puis selon une note sur 100 fondée sur leur autoévaluation, les validations par les pairs, les titres de compétence et publications connexes, leur disponibilité et, au besoin, leur langue de travail.
+expert-finder-search = Trouver des experts
+expert-finder-none = Personne ne possède ces compétences aux niveaux requis.
+expert-required-level = Niveau requis
+expert-self-level = Autoévaluation
+expert-component = Composante
+expert-points = Points
+expert-explanation = Pourquoi
+expert-skill-level = Niveau de compétence
+expert-peer-validation = Validation par les pairs
+expert-evidence = Titres de compétence et publications
+expert-availability = Disponibilité
+expert-language = Langue
+validated-by = Validateurs
+validated-level = consensus
+validate-capability = Valider
+
+## API tokens
+api-tokens = Jetons d'API
+api-tokens-explain = Les jetons personnels permettent à des scripts et carnets d'appeler l'API en votre nom. Envoyez-en un dans un en-tête Authorization: Bearer. Chaque jeton ne sert qu'aux portées choisies et jusqu'à son expiration ou sa révocation.
+api-token-copy-now = Copiez ce jeton maintenant. Il ne sera plus affiché.
+api-token-create = Créer un jeton
+api-token-name = Nom
+api-token-name-placeholder = p. ex. Carnet de l'effectif
+api-token-days = Expire dans (jours)
+api-token-scopes = Portées
+api-scope-graphql = Requêtes GraphQL (/api/graphql)
+api-scope-export = Exportations de rapports (CSV et XLSX)
+api-token-prefix = Jeton
+api-token-expires = Expiration
+api-token-last-used = Dernière utilisation
+api-token-never-used = Jamais
+api-token-revoke = Révoquer
+api-token-revoked = Révoqué
+api-token-expired = Expiré
+api-token-none = Aucun jeton d'API pour l'instant.
+
+## Single sign-on
+sso-login = Se connecter avec
+sso-or-password = Ou connectez-vous avec votre courriel et votre mot de passe.
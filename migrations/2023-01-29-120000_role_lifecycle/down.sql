@@ -0,0 +1,15 @@
+-- This file should undo anything in `up.sql`
+
+DROP TABLE IF EXISTS role_events;
+
+DROP INDEX IF EXISTS roles__team_id_idx;
+DROP INDEX IF EXISTS roles__person_id_idx;
+
+ALTER TABLE roles DROP CONSTRAINT IF EXISTS roles_home_role_id_fkey;
+ALTER TABLE roles DROP COLUMN IF EXISTS home_role_id;
+ALTER TABLE roles DROP COLUMN IF EXISTS role_type;
+
+ALTER TABLE roles ADD CONSTRAINT roles_person_id_key UNIQUE (person_id);
+ALTER TABLE roles ADD CONSTRAINT roles_team_id_key UNIQUE (team_id);
+ALTER TABLE roles ADD CONSTRAINT roles_title_en_key UNIQUE (title_en);
+ALTER TABLE roles ADD CONSTRAINT roles_title_fr_key UNIQUE (title_fr);
@@ -0,0 +1,42 @@
+-- Your SQL goes here
+
+-- Roles are now a history per person and team, so a person can hold
+-- several roles over time and a team can have several members
+ALTER TABLE roles DROP CONSTRAINT IF EXISTS roles_person_id_key;
+ALTER TABLE roles DROP CONSTRAINT IF EXISTS roles_team_id_key;
+ALTER TABLE roles DROP CONSTRAINT IF EXISTS roles_title_en_key;
+ALTER TABLE roles DROP CONSTRAINT IF EXISTS roles_title_fr_key;
+
+-- substantive, acting or secondment
+ALTER TABLE roles ADD COLUMN role_type VARCHAR(32) NOT NULL DEFAULT 'substantive';
+
+-- Substantive role a person returns to when an acting or secondment ends
+ALTER TABLE roles ADD COLUMN home_role_id UUID DEFAULT NULL;
+ALTER TABLE roles ADD CONSTRAINT roles_home_role_id_fkey
+    FOREIGN KEY(home_role_id) REFERENCES roles(id) ON DELETE RESTRICT;
+
+CREATE INDEX roles__person_id_idx ON roles(person_id);
+CREATE INDEX roles__team_id_idx ON roles(team_id);
+
+CREATE TABLE IF NOT EXISTS role_events (
+    id UUID DEFAULT gen_random_uuid() PRIMARY KEY,
+
+    person_id UUID NOT NULL,
+    FOREIGN KEY(person_id)
+        REFERENCES persons(id) ON DELETE RESTRICT,
+
+    role_id UUID NOT NULL,
+    FOREIGN KEY(role_id)
+        REFERENCES roles(id) ON DELETE RESTRICT,
+
+    related_role_id UUID,
+    FOREIGN KEY(related_role_id)
+        REFERENCES roles(id) ON DELETE RESTRICT,
+
+    event_type VARCHAR(32) NOT NULL,
+    note TEXT NOT NULL DEFAULT '',
+    event_date TIMESTAMP NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX role_events__person_id_idx ON role_events(person_id, event_date);
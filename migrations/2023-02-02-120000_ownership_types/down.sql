@@ -0,0 +1,9 @@
+-- This file should undo anything in `up.sql`
+
+DROP INDEX IF EXISTS org_tier_ownerships__primary_owner_idx;
+DROP INDEX IF EXISTS team_ownerships__primary_owner_idx;
+
+ALTER TABLE org_tier_ownerships DROP COLUMN IF EXISTS start_datestamp;
+ALTER TABLE org_tier_ownerships DROP COLUMN IF EXISTS ownership_type;
+
+ALTER TABLE team_ownerships DROP COLUMN IF EXISTS ownership_type;
@@ -0,0 +1,14 @@
+-- Your SQL goes here
+
+-- owner, delegate or acting
+ALTER TABLE team_ownerships ADD COLUMN ownership_type VARCHAR(32) NOT NULL DEFAULT 'owner';
+
+ALTER TABLE org_tier_ownerships ADD COLUMN ownership_type VARCHAR(32) NOT NULL DEFAULT 'owner';
+ALTER TABLE org_tier_ownerships ADD COLUMN start_datestamp TIMESTAMP NOT NULL DEFAULT NOW();
+
+-- A team or tier can only have one open-ended primary owner
+CREATE UNIQUE INDEX team_ownerships__primary_owner_idx ON team_ownerships(team_id)
+    WHERE ownership_type = 'owner' AND end_date IS NULL;
+
+CREATE UNIQUE INDEX org_tier_ownerships__primary_owner_idx ON org_tier_ownerships(org_tier_id)
+    WHERE ownership_type = 'owner' AND retired_at IS NULL;
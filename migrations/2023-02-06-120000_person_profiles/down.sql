@@ -0,0 +1,8 @@
+-- This file should undo anything in `up.sql`
+
+DROP TABLE IF EXISTS affiliations;
+DROP TABLE IF EXISTS credentials;
+DROP TABLE IF EXISTS capabilities;
+DROP TABLE IF EXISTS skills;
+
+ALTER TABLE persons ALTER COLUMN user_id SET NOT NULL;
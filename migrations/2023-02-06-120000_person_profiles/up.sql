@@ -0,0 +1,65 @@
+-- Your SQL goes here
+
+-- Persons can exist without a user account and be linked later
+ALTER TABLE persons ALTER COLUMN user_id DROP NOT NULL;
+
+CREATE TABLE IF NOT EXISTS skills (
+    id UUID DEFAULT gen_random_uuid() PRIMARY KEY,
+    name_en VARCHAR(256) UNIQUE NOT NULL,
+    name_fr VARCHAR(256) UNIQUE NOT NULL,
+    description_en TEXT NOT NULL,
+    description_fr TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS capabilities (
+    id UUID DEFAULT gen_random_uuid() PRIMARY KEY,
+
+    person_id UUID NOT NULL,
+    FOREIGN KEY(person_id)
+        REFERENCES persons(id) ON DELETE RESTRICT,
+
+    skill_id UUID NOT NULL,
+    FOREIGN KEY(skill_id)
+        REFERENCES skills(id) ON DELETE RESTRICT,
+
+    self_identified_level INT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW(),
+
+    UNIQUE(person_id, skill_id)
+);
+
+CREATE TABLE IF NOT EXISTS credentials (
+    id UUID DEFAULT gen_random_uuid() PRIMARY KEY,
+
+    person_id UUID NOT NULL,
+    FOREIGN KEY(person_id)
+        REFERENCES persons(id) ON DELETE RESTRICT,
+
+    provider VARCHAR(256) NOT NULL,
+    description TEXT NOT NULL,
+    received_date DATE NOT NULL,
+    validated bool NOT NULL DEFAULT false,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS affiliations (
+    id UUID DEFAULT gen_random_uuid() PRIMARY KEY,
+
+    person_id UUID NOT NULL,
+    FOREIGN KEY(person_id)
+        REFERENCES persons(id) ON DELETE RESTRICT,
+
+    organization_id UUID NOT NULL,
+    FOREIGN KEY(organization_id)
+        REFERENCES organizations(id) ON DELETE RESTRICT,
+
+    role VARCHAR(256) NOT NULL,
+    start_date DATE NOT NULL,
+    end_date DATE,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
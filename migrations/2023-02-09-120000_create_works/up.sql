@@ -0,0 +1,28 @@
+-- Your SQL goes here
+
+CREATE TABLE IF NOT EXISTS works (
+    id UUID DEFAULT gen_random_uuid() PRIMARY KEY,
+
+    team_id UUID NOT NULL,
+    FOREIGN KEY(team_id)
+        REFERENCES teams(id) ON DELETE RESTRICT,
+
+    person_id UUID,
+    FOREIGN KEY(person_id)
+        REFERENCES persons(id) ON DELETE RESTRICT,
+
+    parent_work_id UUID,
+    FOREIGN KEY(parent_work_id)
+        REFERENCES works(id) ON DELETE RESTRICT,
+
+    outcome_en TEXT NOT NULL,
+    outcome_fr TEXT NOT NULL,
+    start_date DATE NOT NULL,
+    target_completion_date DATE NOT NULL,
+    work_status INT NOT NULL DEFAULT 0,
+    completed_date DATE,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX works__team_id_idx ON works(team_id);
@@ -0,0 +1,19 @@
+-- This file should undo anything in `up.sql`
+
+ALTER TABLE affiliations DROP CONSTRAINT IF EXISTS affiliations_person_id_organization_id_role_start_date_key;
+ALTER TABLE credentials DROP CONSTRAINT IF EXISTS credentials_person_id_provider_description_key;
+ALTER TABLE org_tier_ownerships DROP CONSTRAINT IF EXISTS org_tier_ownerships_owner_id_org_tier_id_start_datestamp_key;
+ALTER TABLE team_ownerships DROP CONSTRAINT IF EXISTS team_ownerships_person_id_team_id_start_datestamp_key;
+ALTER TABLE roles DROP CONSTRAINT IF EXISTS roles_person_id_team_id_start_datestamp_key;
+
+ALTER TABLE teams DROP CONSTRAINT IF EXISTS teams_organization_id_name_fr_key;
+ALTER TABLE teams DROP CONSTRAINT IF EXISTS teams_organization_id_name_en_key;
+ALTER TABLE teams ADD CONSTRAINT teams_name_en_key UNIQUE (name_en);
+ALTER TABLE teams ADD CONSTRAINT teams_name_fr_key UNIQUE (name_fr);
+
+ALTER TABLE org_tiers DROP CONSTRAINT IF EXISTS org_tiers_organization_id_name_fr_key;
+ALTER TABLE org_tiers DROP CONSTRAINT IF EXISTS org_tiers_organization_id_name_en_key;
+ALTER TABLE org_tiers ADD CONSTRAINT org_tiers_name_en_key UNIQUE (name_en);
+ALTER TABLE org_tiers ADD CONSTRAINT org_tiers_name_fr_key UNIQUE (name_fr);
+
+ALTER TABLE persons DROP COLUMN IF EXISTS employee_number;
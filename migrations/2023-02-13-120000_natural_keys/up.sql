@@ -0,0 +1,31 @@
+-- Your SQL goes here
+
+-- Natural keys used by get_or_create upserts
+
+ALTER TABLE persons ADD COLUMN employee_number VARCHAR(32) UNIQUE;
+
+-- Tier and team names only need to be unique within an organization
+ALTER TABLE org_tiers DROP CONSTRAINT IF EXISTS org_tiers_name_en_key;
+ALTER TABLE org_tiers DROP CONSTRAINT IF EXISTS org_tiers_name_fr_key;
+ALTER TABLE org_tiers ADD CONSTRAINT org_tiers_organization_id_name_en_key UNIQUE (organization_id, name_en);
+ALTER TABLE org_tiers ADD CONSTRAINT org_tiers_organization_id_name_fr_key UNIQUE (organization_id, name_fr);
+
+ALTER TABLE teams DROP CONSTRAINT IF EXISTS teams_name_en_key;
+ALTER TABLE teams DROP CONSTRAINT IF EXISTS teams_name_fr_key;
+ALTER TABLE teams ADD CONSTRAINT teams_organization_id_name_en_key UNIQUE (organization_id, name_en);
+ALTER TABLE teams ADD CONSTRAINT teams_organization_id_name_fr_key UNIQUE (organization_id, name_fr);
+
+ALTER TABLE roles ADD CONSTRAINT roles_person_id_team_id_start_datestamp_key
+    UNIQUE (person_id, team_id, start_datestamp);
+
+ALTER TABLE team_ownerships ADD CONSTRAINT team_ownerships_person_id_team_id_start_datestamp_key
+    UNIQUE (person_id, team_id, start_datestamp);
+
+ALTER TABLE org_tier_ownerships ADD CONSTRAINT org_tier_ownerships_owner_id_org_tier_id_start_datestamp_key
+    UNIQUE (owner_id, org_tier_id, start_datestamp);
+
+ALTER TABLE credentials ADD CONSTRAINT credentials_person_id_provider_description_key
+    UNIQUE (person_id, provider, description);
+
+ALTER TABLE affiliations ADD CONSTRAINT affiliations_person_id_organization_id_role_start_date_key
+    UNIQUE (person_id, organization_id, role, start_date);
@@ -0,0 +1,11 @@
+-- This file should undo anything in `up.sql`
+
+DROP TABLE IF EXISTS hr_sync_changes;
+DROP TABLE IF EXISTS hr_sync_runs;
+DROP TABLE IF EXISTS reporting_relationships;
+
+DROP INDEX IF EXISTS roles__hr_position_number_idx;
+
+ALTER TABLE roles DROP COLUMN IF EXISTS classification;
+ALTER TABLE roles DROP COLUMN IF EXISTS hr_position_number;
+ALTER TABLE org_tiers DROP COLUMN IF EXISTS hr_org_unit;
@@ -0,0 +1,69 @@
+-- Your SQL goes here
+
+-- HRMS identifiers used to match extract rows to in-app records
+ALTER TABLE org_tiers ADD COLUMN hr_org_unit VARCHAR(32) UNIQUE DEFAULT NULL;
+ALTER TABLE roles ADD COLUMN hr_position_number VARCHAR(32) DEFAULT NULL;
+ALTER TABLE roles ADD COLUMN classification VARCHAR(16) DEFAULT NULL;
+
+CREATE INDEX roles__hr_position_number_idx ON roles(hr_position_number);
+
+CREATE TABLE IF NOT EXISTS reporting_relationships (
+    id UUID DEFAULT gen_random_uuid() PRIMARY KEY,
+
+    reporter UUID NOT NULL,
+    FOREIGN KEY(reporter)
+        REFERENCES persons(id) ON DELETE CASCADE,
+
+    reporting_to UUID NOT NULL,
+    FOREIGN KEY(reporting_to)
+        REFERENCES persons(id) ON DELETE CASCADE,
+
+    description VARCHAR(256) NOT NULL DEFAULT '',
+    start_date TIMESTAMP NOT NULL,
+    end_date TIMESTAMP DEFAULT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX reporting_relationships__reporter_idx ON reporting_relationships(reporter);
+CREATE INDEX reporting_relationships__reporting_to_idx ON reporting_relationships(reporting_to);
+
+-- One row per HRMS extract processed
+CREATE TABLE IF NOT EXISTS hr_sync_runs (
+    id UUID DEFAULT gen_random_uuid() PRIMARY KEY,
+    source VARCHAR(64) NOT NULL,
+    file_name VARCHAR(256) NOT NULL,
+    organization_id UUID NOT NULL,
+    FOREIGN KEY(organization_id)
+        REFERENCES organizations(id) ON DELETE CASCADE,
+    rows_read INT NOT NULL DEFAULT 0,
+    changes_applied INT NOT NULL DEFAULT 0,
+    conflicts INT NOT NULL DEFAULT 0,
+    started_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    completed_at TIMESTAMP DEFAULT NULL
+);
+
+-- Every field-level change proposed by an extract.
+-- status: applied, conflict, accepted (HR value applied by an admin),
+-- kept (app value retained by an admin) or superseded (by a later conflict)
+CREATE TABLE IF NOT EXISTS hr_sync_changes (
+    id UUID DEFAULT gen_random_uuid() PRIMARY KEY,
+
+    sync_run_id UUID NOT NULL,
+    FOREIGN KEY(sync_run_id)
+        REFERENCES hr_sync_runs(id) ON DELETE CASCADE,
+
+    -- Employee number, or org unit code for org tier changes
+    hr_key VARCHAR(32) NOT NULL,
+    entity_type VARCHAR(32) NOT NULL,
+    entity_id UUID NOT NULL,
+    field_name VARCHAR(64) NOT NULL,
+    old_value TEXT DEFAULT NULL,
+    new_value TEXT DEFAULT NULL,
+    status VARCHAR(16) NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    resolved_at TIMESTAMP DEFAULT NULL
+);
+
+CREATE INDEX hr_sync_changes__entity_idx ON hr_sync_changes(entity_id, field_name, created_at);
+CREATE INDEX hr_sync_changes__status_idx ON hr_sync_changes(status);
@@ -0,0 +1,4 @@
+-- Your SQL goes here
+
+-- ORCID iD in hyphenated form, e.g. 0000-0002-1825-0097
+ALTER TABLE persons ADD COLUMN orcid VARCHAR(19) UNIQUE DEFAULT NULL;
@@ -0,0 +1,43 @@
+-- Your SQL goes here
+
+CREATE TABLE IF NOT EXISTS publications (
+    id UUID DEFAULT gen_random_uuid() PRIMARY KEY,
+    title TEXT NOT NULL,
+    doi VARCHAR(256) UNIQUE DEFAULT NULL, -- Lower case, without the https://doi.org/ prefix
+    venue VARCHAR(512) DEFAULT NULL, -- Journal, conference or publisher
+    publication_year INT DEFAULT NULL,
+    publication_type VARCHAR(32) NOT NULL DEFAULT 'other',
+    open_access VARCHAR(16) NOT NULL DEFAULT 'unknown',
+    topics TEXT[] NOT NULL DEFAULT '{}',
+    url TEXT DEFAULT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX publications__publication_year_idx ON publications(publication_year);
+CREATE INDEX publications__topics_idx ON publications USING GIN (topics);
+
+CREATE TABLE IF NOT EXISTS publication_authors (
+    id UUID DEFAULT gen_random_uuid() PRIMARY KEY,
+
+    publication_id UUID NOT NULL,
+    FOREIGN KEY(publication_id)
+        REFERENCES publications(id) ON DELETE CASCADE,
+
+    -- Set for people in the app, otherwise the author is an external co-author
+    person_id UUID DEFAULT NULL,
+    FOREIGN KEY(person_id)
+        REFERENCES persons(id) ON DELETE SET NULL,
+
+    author_name VARCHAR(256) NOT NULL, -- As printed on the publication
+    author_order INT DEFAULT NULL, -- 1 for first author, NULL when the source does not say
+    external_affiliation VARCHAR(512) DEFAULT NULL,
+    external_orcid VARCHAR(19) DEFAULT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW(),
+
+    UNIQUE(publication_id, author_order),
+    UNIQUE(publication_id, person_id)
+);
+
+CREATE INDEX publication_authors__person_id_idx ON publication_authors(person_id);
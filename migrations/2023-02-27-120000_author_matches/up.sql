@@ -0,0 +1,24 @@
+-- Your SQL goes here
+
+-- Proposed links from publication authors to persons, confirmed by an admin
+CREATE TABLE IF NOT EXISTS author_matches (
+    id UUID DEFAULT gen_random_uuid() PRIMARY KEY,
+
+    publication_author_id UUID NOT NULL,
+    FOREIGN KEY(publication_author_id)
+        REFERENCES publication_authors(id) ON DELETE CASCADE,
+
+    person_id UUID NOT NULL,
+    FOREIGN KEY(person_id)
+        REFERENCES persons(id) ON DELETE CASCADE,
+
+    method VARCHAR(16) NOT NULL, -- orcid, name or initials
+    score FLOAT NOT NULL,
+    status VARCHAR(16) NOT NULL DEFAULT 'pending',
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    resolved_at TIMESTAMP DEFAULT NULL,
+
+    UNIQUE(publication_author_id, person_id)
+);
+
+CREATE INDEX author_matches__status_idx ON author_matches(status);
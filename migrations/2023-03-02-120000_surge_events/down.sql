@@ -0,0 +1,7 @@
+-- This file should undo anything in `up.sql`
+
+DROP TABLE IF EXISTS surge_deployments;
+DROP TABLE IF EXISTS surge_requirements;
+DROP TABLE IF EXISTS surge_events;
+
+ALTER TABLE persons DROP COLUMN IF EXISTS languages;
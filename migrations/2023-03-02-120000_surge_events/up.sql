@@ -0,0 +1,69 @@
+-- Your SQL goes here
+
+-- Languages a person can work in, as ISO 639-1 codes e.g. {en,fr,es}
+ALTER TABLE persons ADD COLUMN languages TEXT[] NOT NULL DEFAULT '{}';
+
+-- Emergency events people are mobilized to. Deployments are temporary
+-- roles on the event's response team.
+CREATE TABLE IF NOT EXISTS surge_events (
+    id UUID DEFAULT gen_random_uuid() PRIMARY KEY,
+    name_en VARCHAR(256) NOT NULL,
+    name_fr VARCHAR(256) NOT NULL,
+    description_en TEXT NOT NULL DEFAULT '',
+    description_fr TEXT NOT NULL DEFAULT '',
+
+    team_id UUID NOT NULL,
+    FOREIGN KEY(team_id)
+        REFERENCES teams(id) ON DELETE RESTRICT,
+
+    headcount INT NOT NULL DEFAULT 1,
+    deployment_effort FLOAT NOT NULL DEFAULT 1.0,
+    languages TEXT[] NOT NULL DEFAULT '{}', -- Every deployed person must work in all of these
+    status VARCHAR(16) NOT NULL DEFAULT 'active', -- active or closed
+    start_date TIMESTAMP NOT NULL,
+    end_date TIMESTAMP DEFAULT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+-- A skill at a minimum level, or a credential matched on its provider or description
+CREATE TABLE IF NOT EXISTS surge_requirements (
+    id UUID DEFAULT gen_random_uuid() PRIMARY KEY,
+
+    surge_event_id UUID NOT NULL,
+    FOREIGN KEY(surge_event_id)
+        REFERENCES surge_events(id) ON DELETE CASCADE,
+
+    skill_id UUID DEFAULT NULL,
+    FOREIGN KEY(skill_id)
+        REFERENCES skills(id) ON DELETE CASCADE,
+
+    min_level INT NOT NULL DEFAULT 1,
+    credential VARCHAR(256) DEFAULT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+
+    CHECK (skill_id IS NOT NULL OR credential IS NOT NULL)
+);
+
+CREATE TABLE IF NOT EXISTS surge_deployments (
+    id UUID DEFAULT gen_random_uuid() PRIMARY KEY,
+
+    surge_event_id UUID NOT NULL,
+    FOREIGN KEY(surge_event_id)
+        REFERENCES surge_events(id) ON DELETE RESTRICT,
+
+    person_id UUID NOT NULL,
+    FOREIGN KEY(person_id)
+        REFERENCES persons(id) ON DELETE RESTRICT,
+
+    role_id UUID NOT NULL UNIQUE,
+    FOREIGN KEY(role_id)
+        REFERENCES roles(id) ON DELETE RESTRICT,
+
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    released_at TIMESTAMP DEFAULT NULL -- Ended before the role's planned end_date
+);
+
+CREATE INDEX surge_requirements__surge_event_id_idx ON surge_requirements(surge_event_id);
+CREATE INDEX surge_deployments__surge_event_id_idx ON surge_deployments(surge_event_id);
+CREATE INDEX surge_deployments__person_id_idx ON surge_deployments(person_id);
@@ -0,0 +1,6 @@
+-- This file should undo anything in `up.sql`
+
+DROP TABLE IF EXISTS language_evaluations;
+
+ALTER TABLE roles DROP COLUMN IF EXISTS language_requirement;
+ALTER TABLE persons DROP COLUMN IF EXISTS first_official_language;
@@ -0,0 +1,31 @@
+-- Your SQL goes here
+
+-- en or fr
+ALTER TABLE persons ADD COLUMN first_official_language VARCHAR(2) DEFAULT NULL;
+
+-- english_essential, french_essential, either_or, or a bilingual
+-- profile of reading, writing and oral levels e.g. CBC
+ALTER TABLE roles ADD COLUMN language_requirement VARCHAR(32) DEFAULT NULL;
+
+-- Second language evaluation results. Levels are A, B, C, E (exempt) or X.
+-- E results never expire; others lapse on expires_at.
+CREATE TABLE IF NOT EXISTS language_evaluations (
+    id UUID DEFAULT gen_random_uuid() PRIMARY KEY,
+
+    person_id UUID NOT NULL,
+    FOREIGN KEY(person_id)
+        REFERENCES persons(id) ON DELETE CASCADE,
+
+    language VARCHAR(2) NOT NULL,
+    reading VARCHAR(1) DEFAULT NULL,
+    writing VARCHAR(1) DEFAULT NULL,
+    oral VARCHAR(1) DEFAULT NULL,
+    tested_at TIMESTAMP NOT NULL,
+    expires_at TIMESTAMP DEFAULT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW(),
+
+    CHECK (reading IS NOT NULL OR writing IS NOT NULL OR oral IS NOT NULL)
+);
+
+CREATE INDEX language_evaluations__person_id_idx ON language_evaluations(person_id);
@@ -0,0 +1,8 @@
+-- This file should undo anything in `up.sql`
+
+DROP TABLE IF EXISTS roster_placements;
+DROP TABLE IF EXISTS roster_members;
+DROP TABLE IF EXISTS roster_skills;
+DROP TABLE IF EXISTS rosters;
+
+ALTER TABLE persons DROP COLUMN IF EXISTS location;
@@ -0,0 +1,88 @@
+-- Your SQL goes here
+
+-- Work location, e.g. Ottawa, ON
+ALTER TABLE persons ADD COLUMN location VARCHAR(256) DEFAULT NULL;
+
+-- Pools of people pre-qualified for a classification or skill set
+CREATE TABLE IF NOT EXISTS rosters (
+    id UUID DEFAULT gen_random_uuid() PRIMARY KEY,
+    name_en VARCHAR(256) NOT NULL,
+    name_fr VARCHAR(256) NOT NULL,
+    description_en TEXT NOT NULL DEFAULT '',
+    description_fr TEXT NOT NULL DEFAULT '',
+
+    organization_id UUID NOT NULL,
+    FOREIGN KEY(organization_id)
+        REFERENCES organizations(id) ON DELETE RESTRICT,
+
+    classification VARCHAR(32) DEFAULT NULL, -- e.g. EC-05
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    retired_at TIMESTAMP DEFAULT NULL
+);
+
+-- Skill set people were qualified on
+CREATE TABLE IF NOT EXISTS roster_skills (
+    id UUID DEFAULT gen_random_uuid() PRIMARY KEY,
+
+    roster_id UUID NOT NULL,
+    FOREIGN KEY(roster_id)
+        REFERENCES rosters(id) ON DELETE CASCADE,
+
+    skill_id UUID NOT NULL,
+    FOREIGN KEY(skill_id)
+        REFERENCES skills(id) ON DELETE CASCADE,
+
+    min_level INT NOT NULL DEFAULT 1,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+
+    UNIQUE (roster_id, skill_id)
+);
+
+CREATE TABLE IF NOT EXISTS roster_members (
+    id UUID DEFAULT gen_random_uuid() PRIMARY KEY,
+
+    roster_id UUID NOT NULL,
+    FOREIGN KEY(roster_id)
+        REFERENCES rosters(id) ON DELETE CASCADE,
+
+    person_id UUID NOT NULL,
+    FOREIGN KEY(person_id)
+        REFERENCES persons(id) ON DELETE CASCADE,
+
+    qualified_at TIMESTAMP NOT NULL,
+    expires_at TIMESTAMP DEFAULT NULL,
+    note TEXT NOT NULL DEFAULT '',
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    removed_at TIMESTAMP DEFAULT NULL,
+
+    UNIQUE (roster_id, person_id)
+);
+
+-- Roles people were drawn from a roster into
+CREATE TABLE IF NOT EXISTS roster_placements (
+    id UUID DEFAULT gen_random_uuid() PRIMARY KEY,
+
+    roster_id UUID NOT NULL,
+    FOREIGN KEY(roster_id)
+        REFERENCES rosters(id) ON DELETE RESTRICT,
+
+    roster_member_id UUID NOT NULL,
+    FOREIGN KEY(roster_member_id)
+        REFERENCES roster_members(id) ON DELETE RESTRICT,
+
+    person_id UUID NOT NULL,
+    FOREIGN KEY(person_id)
+        REFERENCES persons(id) ON DELETE RESTRICT,
+
+    role_id UUID NOT NULL UNIQUE,
+    FOREIGN KEY(role_id)
+        REFERENCES roles(id) ON DELETE RESTRICT,
+
+    note TEXT NOT NULL DEFAULT '',
+    created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX roster_members__person_id_idx ON roster_members(person_id);
+CREATE INDEX roster_placements__roster_id_idx ON roster_placements(roster_id);
@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+
+DROP TABLE IF EXISTS classification_skills;
+DROP TABLE IF EXISTS work_skill_requirements;
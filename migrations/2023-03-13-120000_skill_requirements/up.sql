@@ -0,0 +1,36 @@
+-- Your SQL goes here
+
+-- Skills a piece of work needs, at a CapabilityLevel
+CREATE TABLE IF NOT EXISTS work_skill_requirements (
+    id UUID DEFAULT gen_random_uuid() PRIMARY KEY,
+
+    work_id UUID NOT NULL,
+    FOREIGN KEY(work_id)
+        REFERENCES works(id) ON DELETE CASCADE,
+
+    skill_id UUID NOT NULL,
+    FOREIGN KEY(skill_id)
+        REFERENCES skills(id) ON DELETE CASCADE,
+
+    required_level INT NOT NULL DEFAULT 2,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW(),
+
+    UNIQUE (work_id, skill_id)
+);
+
+-- Skills expected of anyone in a role of a classification, e.g. EC-05
+CREATE TABLE IF NOT EXISTS classification_skills (
+    id UUID DEFAULT gen_random_uuid() PRIMARY KEY,
+    classification VARCHAR(32) NOT NULL,
+
+    skill_id UUID NOT NULL,
+    FOREIGN KEY(skill_id)
+        REFERENCES skills(id) ON DELETE CASCADE,
+
+    expected_level INT NOT NULL DEFAULT 2,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW(),
+
+    UNIQUE (classification, skill_id)
+);
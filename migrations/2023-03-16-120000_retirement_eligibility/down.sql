@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+
+ALTER TABLE persons DROP COLUMN IF EXISTS retirement_eligible_on;
@@ -0,0 +1,4 @@
+-- Your SQL goes here
+
+-- Earliest date a person can retire with an unreduced pension, from the HRMS
+ALTER TABLE persons ADD COLUMN retirement_eligible_on DATE DEFAULT NULL;
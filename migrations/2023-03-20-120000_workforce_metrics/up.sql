@@ -0,0 +1,28 @@
+-- Your SQL goes here
+
+-- Monthly workforce rollup per team and org tier, rebuilt from role and
+-- person history on demand. Figures are as of the last day of the month.
+CREATE TABLE IF NOT EXISTS workforce_metrics (
+    id UUID DEFAULT gen_random_uuid() PRIMARY KEY,
+
+    organization_id UUID NOT NULL,
+    FOREIGN KEY(organization_id)
+        REFERENCES organizations(id) ON DELETE CASCADE,
+
+    scope_type VARCHAR(16) NOT NULL, -- team or org_tier
+    scope_id UUID NOT NULL,
+    period DATE NOT NULL, -- First day of the month
+    headcount INT NOT NULL DEFAULT 0,
+    fte FLOAT NOT NULL DEFAULT 0.0,
+    hires INT NOT NULL DEFAULT 0,
+    departures INT NOT NULL DEFAULT 0,
+    moves_in INT NOT NULL DEFAULT 0,
+    moves_out INT NOT NULL DEFAULT 0,
+    turnover_rate FLOAT NOT NULL DEFAULT 0.0,
+    average_tenure_months FLOAT NOT NULL DEFAULT 0.0,
+    refreshed_at TIMESTAMP NOT NULL DEFAULT NOW(),
+
+    UNIQUE (scope_type, scope_id, period)
+);
+
+CREATE INDEX workforce_metrics__organization_id_idx ON workforce_metrics(organization_id);
@@ -0,0 +1,11 @@
+-- This file should undo anything in `up.sql`
+
+DROP INDEX IF EXISTS publications__search_fr_idx;
+DROP INDEX IF EXISTS publications__search_en_idx;
+DROP INDEX IF EXISTS skills__search_fr_idx;
+DROP INDEX IF EXISTS skills__search_en_idx;
+DROP INDEX IF EXISTS org_tiers__search_fr_idx;
+DROP INDEX IF EXISTS org_tiers__search_en_idx;
+DROP INDEX IF EXISTS teams__search_fr_idx;
+DROP INDEX IF EXISTS teams__search_en_idx;
+DROP INDEX IF EXISTS persons__search_idx;
@@ -0,0 +1,28 @@
+-- Your SQL goes here
+
+-- Full-text search. Each index expression must match the document used by
+-- Search in src/models/search.rs. Names of people use the simple dictionary,
+-- bilingual columns the English and French dictionaries.
+CREATE INDEX persons__search_idx ON persons
+    USING GIN (to_tsvector('simple', given_name || ' ' || family_name));
+
+CREATE INDEX teams__search_en_idx ON teams
+    USING GIN (to_tsvector('english', name_en || ' ' || description_en));
+CREATE INDEX teams__search_fr_idx ON teams
+    USING GIN (to_tsvector('french', name_fr || ' ' || description_fr));
+
+CREATE INDEX org_tiers__search_en_idx ON org_tiers
+    USING GIN (to_tsvector('english', name_en));
+CREATE INDEX org_tiers__search_fr_idx ON org_tiers
+    USING GIN (to_tsvector('french', name_fr));
+
+CREATE INDEX skills__search_en_idx ON skills
+    USING GIN (to_tsvector('english', name_en || ' ' || description_en));
+CREATE INDEX skills__search_fr_idx ON skills
+    USING GIN (to_tsvector('french', name_fr || ' ' || description_fr));
+
+-- Publication titles are in either language
+CREATE INDEX publications__search_en_idx ON publications
+    USING GIN (to_tsvector('english', title || ' ' || coalesce(venue, '')));
+CREATE INDEX publications__search_fr_idx ON publications
+    USING GIN (to_tsvector('french', title || ' ' || coalesce(venue, '')));
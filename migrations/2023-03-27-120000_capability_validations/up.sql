@@ -0,0 +1,22 @@
+-- Your SQL goes here
+
+-- Other people's assessment of a person's Capability. One per validator.
+CREATE TABLE IF NOT EXISTS validations (
+    id UUID DEFAULT gen_random_uuid() PRIMARY KEY,
+
+    validator_id UUID NOT NULL,
+    FOREIGN KEY(validator_id)
+        REFERENCES persons(id) ON DELETE CASCADE,
+
+    capability_id UUID NOT NULL,
+    FOREIGN KEY(capability_id)
+        REFERENCES capabilities(id) ON DELETE CASCADE,
+
+    validated_level INT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW(),
+
+    UNIQUE (validator_id, capability_id)
+);
+
+CREATE INDEX validations__capability_id_idx ON validations(capability_id);
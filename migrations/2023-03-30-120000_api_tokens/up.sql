@@ -0,0 +1,23 @@
+-- Your SQL goes here
+
+-- Personal API tokens. Only the prefix is stored in the clear, for lookup.
+-- The rest of the token is kept as an argon2 hash.
+CREATE TABLE IF NOT EXISTS api_tokens (
+    id UUID DEFAULT gen_random_uuid() PRIMARY KEY,
+
+    user_id UUID NOT NULL,
+    FOREIGN KEY(user_id)
+        REFERENCES users(id) ON DELETE CASCADE,
+
+    name VARCHAR(128) NOT NULL,
+    token_prefix VARCHAR(16) UNIQUE NOT NULL,
+    token_hash VARCHAR NOT NULL,
+    scopes TEXT[] NOT NULL DEFAULT '{}',
+    expires_at TIMESTAMP NOT NULL,
+    last_used_at TIMESTAMP,
+    revoked_at TIMESTAMP,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX api_tokens__user_id_idx ON api_tokens(user_id);
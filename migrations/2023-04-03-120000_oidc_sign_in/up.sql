@@ -0,0 +1,30 @@
+-- Your SQL goes here
+
+-- Pending OIDC sign-ins, from the redirect to the identity provider until its callback
+CREATE TABLE IF NOT EXISTS oidc_login_states (
+    id UUID DEFAULT gen_random_uuid() PRIMARY KEY,
+    state VARCHAR(64) UNIQUE NOT NULL,
+    nonce VARCHAR(64) NOT NULL,
+    code_verifier VARCHAR(128) NOT NULL, -- PKCE
+    lang VARCHAR(2) NOT NULL,
+    expires_at TIMESTAMP NOT NULL
+);
+
+-- Identity provider accounts linked to users
+CREATE TABLE IF NOT EXISTS user_identities (
+    id UUID DEFAULT gen_random_uuid() PRIMARY KEY,
+
+    user_id UUID NOT NULL,
+    FOREIGN KEY(user_id)
+        REFERENCES users(id) ON DELETE CASCADE,
+
+    issuer VARCHAR NOT NULL,
+    subject VARCHAR NOT NULL,
+    email VARCHAR(128) NOT NULL, -- As last asserted by the provider
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    last_login_at TIMESTAMP NOT NULL DEFAULT NOW(),
+
+    UNIQUE (issuer, subject)
+);
+
+CREATE INDEX user_identities__user_id_idx ON user_identities(user_id);
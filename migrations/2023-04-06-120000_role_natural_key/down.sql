@@ -0,0 +1,6 @@
+-- This file should undo anything in `up.sql`
+
+ALTER TABLE roles DROP CONSTRAINT IF EXISTS roles_person_id_team_id_start_datestamp_role_type_title_en_key;
+
+ALTER TABLE roles ADD CONSTRAINT roles_person_id_team_id_start_datestamp_key
+    UNIQUE (person_id, team_id, start_datestamp);
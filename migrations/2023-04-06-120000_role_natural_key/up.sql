@@ -0,0 +1,8 @@
+-- Your SQL goes here
+
+-- A role can end and another start on the same team on the same day, e.g. a
+-- transfer to a new title, so the natural key includes the role type and title
+ALTER TABLE roles DROP CONSTRAINT IF EXISTS roles_person_id_team_id_start_datestamp_key;
+
+ALTER TABLE roles ADD CONSTRAINT roles_person_id_team_id_start_datestamp_role_type_title_en_key
+    UNIQUE (person_id, team_id, start_datestamp, role_type, title_en);
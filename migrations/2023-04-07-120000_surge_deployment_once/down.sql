@@ -0,0 +1,2 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE surge_deployments DROP CONSTRAINT surge_deployments_surge_event_id_person_id_key;
@@ -0,0 +1,5 @@
+-- Your SQL goes here
+
+-- A person is deployed to a surge event at most once
+ALTER TABLE surge_deployments ADD CONSTRAINT surge_deployments_surge_event_id_person_id_key
+    UNIQUE (surge_event_id, person_id);
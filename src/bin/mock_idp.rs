@@ -0,0 +1,30 @@
+// Local OpenID Connect provider for trying single sign-on without a real one.
+// Anyone can sign in as anyone with any email, so never expose it.
+//
+// cargo run --features mock-idp --bin mock_idp, then run the app with
+// OIDC_ISSUER=http://127.0.0.1:8090 OIDC_CLIENT_ID=people-data
+// OIDC_REDIRECT_URL=http://127.0.0.1:8080/oidc/callback
+
+use std::env;
+
+use actix_web::{web, App, HttpServer};
+
+use people_data::mock_idp::{self, MockIdp};
+
+#[actix_rt::main]
+async fn main() -> std::io::Result<()> {
+    let port = env::var("MOCK_IDP_PORT").unwrap_or_else(|_| "8090".to_string());
+
+    let idp = web::Data::new(MockIdp::new(&format!("http://127.0.0.1:{}", port)));
+
+    println!("Mock identity provider on: {}", idp.issuer);
+
+    HttpServer::new(move || {
+        App::new()
+            .app_data(idp.clone())
+            .configure(mock_idp::configure)
+    })
+    .bind(format!("127.0.0.1:{}", port))?
+    .run()
+    .await
+}
@@ -4,9 +4,13 @@ use diesel::r2d2::ConnectionManager;
 use lazy_static::lazy_static;
 use r2d2;
 use std::env;
-use crate::models::{User, UserData};
+use chrono::Utc;
+use crate::models::{User, UserData, Role};
 
 
+/// How often a running server ends temporary roles past their end date
+pub const ROLE_EXPIRY_INTERVAL_SECS: u64 = 60 * 60;
+
 type Pool = r2d2::Pool<ConnectionManager<PgConnection>>;
 pub type DbConnection = r2d2::PooledConnection<ConnectionManager<PgConnection>>;
 
@@ -25,6 +29,12 @@ pub fn init() {
     let conn = connection().expect("Failed to get DB connection");
     embedded_migrations::run(&conn).unwrap();
 
+    // Close acting assignments and secondments that have passed their end date
+    match expire_temporary_roles() {
+        Ok(expired) => println!("Expired {} temporary roles", expired),
+        Err(e) => println!("Unable to expire temporary roles: {:?}", e),
+    };
+
     // Auto-add admin if does not exist
     let admin_name = env::var("ADMIN_NAME").expect("Unable to load admin name");
     let admin_email = env::var("ADMIN_EMAIL").expect("Unable to load admin email");
@@ -56,4 +66,14 @@ pub fn init() {
 pub fn connection() -> Result<DbConnection, CustomError> {
     POOL.get()
         .map_err(|e| CustomError::new(500, format!("Failed getting DB connection: {}", e)))
-}
\ No newline at end of file
+}
+
+/// End temporary roles past their end date, returning how many were ended.
+/// Run at start up and then every ROLE_EXPIRY_INTERVAL_SECS while serving.
+pub fn expire_temporary_roles() -> Result<usize, CustomError> {
+    let conn = connection()?;
+
+    Role::expire_temporary_roles(&conn, Utc::now().naive_utc())
+        .map(|roles| roles.len())
+        .map_err(|e| CustomError::new(500, format!("Unable to expire temporary roles: {}", e.message)))
+}
@@ -51,6 +51,15 @@ impl ResponseError for CustomError {
         };
 
         match status_code.as_u16() {
+            // API clients get the status rather than a redirect to a page
+            401 => {
+                return HttpResponse::Unauthorized()
+                    .header("WWW-Authenticate", "Bearer")
+                    .body(self.error_message.clone())
+            },
+            403 => {
+                return HttpResponse::Forbidden().body(self.error_message.clone())
+            },
             406 => {
                 return HttpResponse::Found().header("Location","/not_authorized").finish()
             },
@@ -0,0 +1,7 @@
+//! Spreadsheet exports of people-data reports.
+
+mod tables;
+mod writers;
+
+pub use self::tables::*;
+pub use self::writers::*;
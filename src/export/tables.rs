@@ -0,0 +1,543 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use diesel::PgConnection;
+use uuid::Uuid;
+
+use crate::errors::CustomError;
+use crate::models::{Capability, CapabilityLevel, OrgTier, Organization, Person, Role, Skill, Team,
+    WorkforceMetric};
+use crate::schema::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportReport {
+    Persons,
+    TeamRoster,
+    Capacity,
+    Skills,
+    Workforce,
+}
+
+impl fmt::Display for ExportReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            ExportReport::Persons => "persons",
+            ExportReport::TeamRoster => "team_roster",
+            ExportReport::Capacity => "capacity",
+            ExportReport::Skills => "skills",
+            ExportReport::Workforce => "workforce",
+        };
+        f.write_str(s)
+    }
+}
+
+impl FromStr for ExportReport {
+    type Err = CustomError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "persons" => Ok(ExportReport::Persons),
+            "team_roster" => Ok(ExportReport::TeamRoster),
+            "capacity" => Ok(ExportReport::Capacity),
+            "skills" => Ok(ExportReport::Skills),
+            "workforce" => Ok(ExportReport::Workforce),
+            _ => Err(CustomError::new(408, format!("Unknown report: {}", s))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+/// What the caller may see. Admins export every organization with
+/// employee numbers; other users only their own organization without them.
+pub struct ExportScope {
+    pub organization_id: Option<Uuid>,
+    pub include_employee_numbers: bool,
+}
+
+impl ExportScope {
+    pub fn admin() -> Self {
+        ExportScope {
+            organization_id: None,
+            include_employee_numbers: true,
+        }
+    }
+
+    pub fn organization(organization_id: Uuid) -> Self {
+        ExportScope {
+            organization_id: Some(organization_id),
+            include_employee_numbers: false,
+        }
+    }
+
+    /// Limit the scope to one organization, e.g. the one shown on an org chart
+    pub fn narrow(self, organization_id: Option<Uuid>) -> Result<Self, CustomError> {
+        match organization_id {
+            None => Ok(self),
+            Some(id) if self.allows(id) => Ok(ExportScope {
+                organization_id: Some(id),
+                ..self
+            }),
+            Some(_) => Err(CustomError::new(403, "Exports are limited to your own organization".to_string())),
+        }
+    }
+
+    fn allows(&self, organization_id: Uuid) -> bool {
+        self.organization_id.map_or(true, |id| id == organization_id)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExportCell {
+    Text(String),
+    Number(f64),
+}
+
+impl From<&str> for ExportCell {
+    fn from(s: &str) -> Self {
+        ExportCell::Text(s.to_owned())
+    }
+}
+
+impl From<String> for ExportCell {
+    fn from(s: String) -> Self {
+        ExportCell::Text(s)
+    }
+}
+
+impl From<f64> for ExportCell {
+    fn from(n: f64) -> Self {
+        ExportCell::Number(n)
+    }
+}
+
+#[derive(Debug, Clone)]
+/// A report flattened to a header row and data rows, ready to write
+pub struct ExportTable {
+    pub name: String,
+    pub headers: Vec<String>,
+    pub rows: Vec<Vec<ExportCell>>,
+}
+
+/// Pick the English or French value for the requested language
+fn bilingual<'a>(lang: &str, en: &'a str, fr: &'a str) -> &'a str {
+    if lang == "fr" { fr } else { en }
+}
+
+fn headers(lang: &str, labels: &[(&str, &str)]) -> Vec<String> {
+    labels.iter()
+        .map(|(en, fr)| bilingual(lang, en, fr).to_owned())
+        .collect()
+}
+
+fn level_label(level: CapabilityLevel, lang: &str) -> &'static str {
+    let (en, fr) = level.names();
+    bilingual(lang, en, fr)
+}
+
+impl ExportTable {
+    /// Build the requested report as of a date, limited to the caller's scope.
+    /// `team_id` narrows the team roster to a single team, and the workforce
+    /// series to a team or, with `org_tier_id`, an org tier.
+    pub fn build(
+        conn: &PgConnection,
+        report: ExportReport,
+        scope: ExportScope,
+        lang: &str,
+        as_of: NaiveDateTime,
+        team_id: Option<Uuid>,
+        org_tier_id: Option<Uuid>,
+    ) -> Result<ExportTable, CustomError> {
+        match report {
+            ExportReport::Persons => Self::person_directory(conn, scope, lang, as_of),
+            ExportReport::TeamRoster => Self::team_roster(conn, scope, lang, as_of, team_id),
+            ExportReport::Capacity => Self::capacity(conn, scope, lang, as_of),
+            ExportReport::Skills => Self::skill_inventory(conn, scope, lang),
+            ExportReport::Workforce => Self::workforce(conn, scope, lang, team_id.or(org_tier_id)),
+        }
+    }
+
+    fn organizations(conn: &PgConnection) -> Result<HashMap<Uuid, Organization>, CustomError> {
+        let res = organizations::table.load::<Organization>(conn)?;
+
+        Ok(res.into_iter().map(|o| (o.id, o)).collect())
+    }
+
+    fn persons_in_scope(conn: &PgConnection, scope: ExportScope) -> Result<Vec<Person>, CustomError> {
+        let mut query = persons::table
+            .filter(persons::retired_at.is_null())
+            .order((persons::family_name.asc(), persons::given_name.asc()))
+            .into_boxed();
+
+        if let Some(organization_id) = scope.organization_id {
+            query = query.filter(persons::organization_id.eq(organization_id));
+        };
+
+        Ok(query.load::<Person>(conn)?)
+    }
+
+    /// Roles held on as_of, leaving out home roles suspended by an acting
+    /// assignment or secondment so their effort isn't counted twice
+    fn current_roles(conn: &PgConnection, as_of: NaiveDateTime) -> Result<Vec<(Role, Team)>, CustomError> {
+        let res = roles::table
+            .inner_join(teams::table)
+            .filter(roles::start_datestamp.le(as_of))
+            .filter(roles::end_date.is_null().or(roles::end_date.gt(as_of)))
+            .order(roles::effort.desc())
+            .load::<(Role, Team)>(conn)?;
+
+        // Compared with the roles current on as_of rather than active, which is false once a role ends
+        let suspended: HashSet<Uuid> = res.iter()
+            .filter(|(role, _)| role.get_role_type().suspends_home_role())
+            .filter_map(|(role, _)| role.home_role_id)
+            .collect();
+
+        Ok(res.into_iter()
+            .filter(|(role, _)| !suspended.contains(&role.id))
+            .collect())
+    }
+
+    /// One row per current role, or a single row for people without one
+    fn person_directory(conn: &PgConnection, scope: ExportScope, lang: &str, as_of: NaiveDateTime) -> Result<ExportTable, CustomError> {
+        let organizations = Self::organizations(conn)?;
+        let persons = Self::persons_in_scope(conn, scope)?;
+
+        let mut roles_by_person: HashMap<Uuid, Vec<(Role, Team)>> = HashMap::new();
+        for (role, team) in Self::current_roles(conn, as_of)? {
+            roles_by_person.entry(role.person_id).or_default().push((role, team));
+        };
+
+        let mut labels = vec![("Family name", "Nom de famille"), ("Given name", "Prénom")];
+        if scope.include_employee_numbers {
+            labels.push(("Employee number", "Numéro d'employé"));
+        };
+        labels.extend_from_slice(&[
+            ("Organization", "Organisation"),
+            ("Team", "Équipe"),
+            ("Title", "Titre"),
+            ("Effort (FTE)", "Effort (ETP)"),
+            ("Role type", "Type de rôle"),
+        ]);
+
+        let mut rows = Vec::new();
+
+        for person in persons {
+            let organization = organizations.get(&person.organization_id)
+                .map(|o| bilingual(lang, &o.acronym_en, &o.acronym_fr).to_owned())
+                .unwrap_or_default();
+
+            let mut prefix: Vec<ExportCell> = vec![
+                person.family_name.as_str().into(),
+                person.given_name.as_str().into(),
+            ];
+            if scope.include_employee_numbers {
+                prefix.push(person.employee_number.clone().unwrap_or_default().into());
+            };
+            prefix.push(organization.into());
+
+            match roles_by_person.get(&person.id) {
+                Some(roles) => {
+                    for (role, team) in roles {
+                        let mut row = prefix.clone();
+                        row.push(bilingual(lang, &team.name_en, &team.name_fr).into());
+                        row.push(bilingual(lang, &role.title_en, &role.title_fr).into());
+                        row.push(role.effort.into());
+                        row.push(role.role_type.as_str().into());
+                        rows.push(row);
+                    };
+                },
+                None => {
+                    let mut row = prefix;
+                    row.push("".into());
+                    row.push("".into());
+                    row.push(0.0.into());
+                    row.push("".into());
+                    rows.push(row);
+                },
+            };
+        };
+
+        Ok(ExportTable {
+            name: bilingual(lang, "Persons", "Personnes").to_owned(),
+            headers: headers(lang, &labels),
+            rows,
+        })
+    }
+
+    fn team_roster(conn: &PgConnection, scope: ExportScope, lang: &str, as_of: NaiveDateTime, team_id: Option<Uuid>) -> Result<ExportTable, CustomError> {
+        let organizations = Self::organizations(conn)?;
+
+        let persons: HashMap<Uuid, Person> = Self::persons_in_scope(conn, scope)?
+            .into_iter()
+            .map(|p| (p.id, p))
+            .collect();
+
+        let mut roles: Vec<(Role, Team)> = Self::current_roles(conn, as_of)?
+            .into_iter()
+            .filter(|(_, team)| scope.allows(team.organization_id))
+            .filter(|(_, team)| team_id.map_or(true, |id| id == team.id))
+            .collect();
+
+        roles.sort_by(|a, b| bilingual(lang, &a.1.name_en, &a.1.name_fr)
+            .cmp(bilingual(lang, &b.1.name_en, &b.1.name_fr)));
+
+        let mut labels = vec![
+            ("Organization", "Organisation"),
+            ("Team", "Équipe"),
+            ("Family name", "Nom de famille"),
+            ("Given name", "Prénom"),
+        ];
+        if scope.include_employee_numbers {
+            labels.push(("Employee number", "Numéro d'employé"));
+        };
+        labels.extend_from_slice(&[
+            ("Title", "Titre"),
+            ("Effort (FTE)", "Effort (ETP)"),
+            ("Role type", "Type de rôle"),
+            ("Start date", "Date de début"),
+            ("End date", "Date de fin"),
+        ]);
+
+        let mut rows = Vec::new();
+
+        for (role, team) in roles {
+            // Members from outside the caller's scope are left out
+            let person = match persons.get(&role.person_id) {
+                Some(p) => p,
+                None => continue,
+            };
+
+            let organization = organizations.get(&team.organization_id)
+                .map(|o| bilingual(lang, &o.acronym_en, &o.acronym_fr).to_owned())
+                .unwrap_or_default();
+
+            let mut row: Vec<ExportCell> = vec![
+                organization.into(),
+                bilingual(lang, &team.name_en, &team.name_fr).into(),
+                person.family_name.as_str().into(),
+                person.given_name.as_str().into(),
+            ];
+            if scope.include_employee_numbers {
+                row.push(person.employee_number.clone().unwrap_or_default().into());
+            };
+            row.push(bilingual(lang, &role.title_en, &role.title_fr).into());
+            row.push(role.effort.into());
+            row.push(role.role_type.as_str().into());
+            row.push(role.start_datestamp.format("%Y-%m-%d").to_string().into());
+            row.push(role.end_date.map(|d| d.format("%Y-%m-%d").to_string()).unwrap_or_default().into());
+
+            rows.push(row);
+        };
+
+        Ok(ExportTable {
+            name: bilingual(lang, "Team roster", "Liste de l'équipe").to_owned(),
+            headers: headers(lang, &labels),
+            rows,
+        })
+    }
+
+    /// Headcount and FTE per team, grouped by organization and tier
+    fn capacity(conn: &PgConnection, scope: ExportScope, lang: &str, as_of: NaiveDateTime) -> Result<ExportTable, CustomError> {
+        let organizations = Self::organizations(conn)?;
+
+        let tiers: HashMap<Uuid, OrgTier> = org_tiers::table
+            .load::<OrgTier>(conn)?
+            .into_iter()
+            .map(|t| (t.id, t))
+            .collect();
+
+        let mut teams: Vec<Team> = teams::table
+            .filter(teams::retired_at.is_null())
+            .load::<Team>(conn)?
+            .into_iter()
+            .filter(|t| scope.allows(t.organization_id))
+            .collect();
+
+        teams.sort_by(|a, b| (a.organization_id, a.org_tier_id, bilingual(lang, &a.name_en, &a.name_fr))
+            .cmp(&(b.organization_id, b.org_tier_id, bilingual(lang, &b.name_en, &b.name_fr))));
+
+        let mut capacity: HashMap<Uuid, (Vec<Uuid>, f64)> = HashMap::new();
+        for (role, _) in Self::current_roles(conn, as_of)? {
+            let entry = capacity.entry(role.team_id).or_insert_with(|| (Vec::new(), 0.0));
+            if !entry.0.contains(&role.person_id) {
+                entry.0.push(role.person_id);
+            };
+            entry.1 += role.effort;
+        };
+
+        let labels = [
+            ("Organization", "Organisation"),
+            ("Org tier", "Niveau organisationnel"),
+            ("Team", "Équipe"),
+            ("Headcount", "Effectif"),
+            ("Capacity (FTE)", "Capacité (ETP)"),
+        ];
+
+        let rows = teams.iter()
+            .map(|team| {
+                let organization = organizations.get(&team.organization_id)
+                    .map(|o| bilingual(lang, &o.acronym_en, &o.acronym_fr).to_owned())
+                    .unwrap_or_default();
+
+                let tier = tiers.get(&team.org_tier_id)
+                    .map(|t| bilingual(lang, &t.name_en, &t.name_fr).to_owned())
+                    .unwrap_or_default();
+
+                let (headcount, fte) = capacity.get(&team.id)
+                    .map(|(people, fte)| (people.len() as f64, *fte))
+                    .unwrap_or((0.0, 0.0));
+
+                vec![
+                    organization.into(),
+                    tier.into(),
+                    bilingual(lang, &team.name_en, &team.name_fr).into(),
+                    headcount.into(),
+                    fte.into(),
+                ]
+            })
+            .collect();
+
+        Ok(ExportTable {
+            name: bilingual(lang, "Capacity", "Capacité").to_owned(),
+            headers: headers(lang, &labels),
+            rows,
+        })
+    }
+
+    /// One row per person and skill
+    fn skill_inventory(conn: &PgConnection, scope: ExportScope, lang: &str) -> Result<ExportTable, CustomError> {
+        let organizations = Self::organizations(conn)?;
+
+        let persons: HashMap<Uuid, Person> = Self::persons_in_scope(conn, scope)?
+            .into_iter()
+            .map(|p| (p.id, p))
+            .collect();
+
+        let person_ids: Vec<Uuid> = persons.keys().cloned().collect();
+
+        let mut capabilities = capabilities::table
+            .inner_join(skills::table)
+            .filter(capabilities::person_id.eq_any(&person_ids))
+            .load::<(Capability, Skill)>(conn)?;
+
+        capabilities.sort_by(|a, b| bilingual(lang, &a.1.name_en, &a.1.name_fr)
+            .cmp(bilingual(lang, &b.1.name_en, &b.1.name_fr))
+            .then(b.0.self_identified_level.cmp(&a.0.self_identified_level)));
+
+        let mut labels = vec![
+            ("Skill", "Compétence"),
+            ("Family name", "Nom de famille"),
+            ("Given name", "Prénom"),
+        ];
+        if scope.include_employee_numbers {
+            labels.push(("Employee number", "Numéro d'employé"));
+        };
+        labels.extend_from_slice(&[
+            ("Organization", "Organisation"),
+            ("Level", "Niveau"),
+            ("Level name", "Nom du niveau"),
+        ]);
+
+        let mut rows = Vec::new();
+
+        for (capability, skill) in capabilities {
+            let person = match persons.get(&capability.person_id) {
+                Some(p) => p,
+                None => continue,
+            };
+
+            let organization = organizations.get(&person.organization_id)
+                .map(|o| bilingual(lang, &o.acronym_en, &o.acronym_fr).to_owned())
+                .unwrap_or_default();
+
+            let level = CapabilityLevel::from_i32(capability.self_identified_level);
+
+            let mut row: Vec<ExportCell> = vec![
+                bilingual(lang, &skill.name_en, &skill.name_fr).into(),
+                person.family_name.as_str().into(),
+                person.given_name.as_str().into(),
+            ];
+            if scope.include_employee_numbers {
+                row.push(person.employee_number.clone().unwrap_or_default().into());
+            };
+            row.push(organization.into());
+            row.push((level.as_i32() as f64).into());
+            row.push(level_label(level, lang).into());
+
+            rows.push(row);
+        };
+
+        Ok(ExportTable {
+            name: bilingual(lang, "Skills", "Compétences").to_owned(),
+            headers: headers(lang, &labels),
+            rows,
+        })
+    }
+
+    /// Monthly series from the workforce rollup, one row per unit and month.
+    /// Reads the last refresh rather than recomputing from history.
+    fn workforce(conn: &PgConnection, scope: ExportScope, lang: &str, scope_id: Option<Uuid>) -> Result<ExportTable, CustomError> {
+        let organizations = Self::organizations(conn)?;
+
+        let units: HashMap<Uuid, String> = WorkforceMetric::find_units(conn, scope.organization_id)?
+            .into_iter()
+            .map(|u| (u.scope_id, bilingual(lang, &u.name_en, &u.name_fr).to_owned()))
+            .collect();
+
+        let metrics: Vec<WorkforceMetric> = WorkforceMetric::find_by_organization(conn, scope.organization_id)?
+            .into_iter()
+            .filter(|m| scope_id.map_or(true, |id| id == m.scope_id))
+            .collect();
+
+        let labels = [
+            ("Organization", "Organisation"),
+            ("Unit type", "Type d'unité"),
+            ("Unit", "Unité"),
+            ("Month", "Mois"),
+            ("Headcount", "Effectif"),
+            ("FTE", "ETP"),
+            ("Hires", "Embauches"),
+            ("Departures", "Départs"),
+            ("Moves in", "Mutations entrantes"),
+            ("Moves out", "Mutations sortantes"),
+            ("Turnover rate", "Taux de roulement"),
+            ("Average tenure (months)", "Ancienneté moyenne (mois)"),
+        ];
+
+        let rows = metrics.iter()
+            .map(|m| {
+                let organization = organizations.get(&m.organization_id)
+                    .map(|o| bilingual(lang, &o.acronym_en, &o.acronym_fr).to_owned())
+                    .unwrap_or_default();
+
+                let unit_type = match m.scope_type.as_str() {
+                    "team" => bilingual(lang, "Team", "Équipe"),
+                    _ => bilingual(lang, "Org tier", "Niveau organisationnel"),
+                };
+
+                vec![
+                    organization.into(),
+                    unit_type.into(),
+                    units.get(&m.scope_id).cloned().unwrap_or_default().into(),
+                    m.period.format("%Y-%m").to_string().into(),
+                    (m.headcount as f64).into(),
+                    m.fte.into(),
+                    (m.hires as f64).into(),
+                    (m.departures as f64).into(),
+                    (m.moves_in as f64).into(),
+                    (m.moves_out as f64).into(),
+                    m.turnover_rate.into(),
+                    m.average_tenure_months.into(),
+                ]
+            })
+            .collect();
+
+        Ok(ExportTable {
+            name: bilingual(lang, "Workforce", "Effectifs").to_owned(),
+            headers: headers(lang, &labels),
+            rows,
+        })
+    }
+}
@@ -0,0 +1,117 @@
+use std::fmt;
+use std::str::FromStr;
+
+use rust_xlsxwriter::{Format, Workbook};
+
+use crate::errors::CustomError;
+use crate::export::{ExportCell, ExportTable};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    Xlsx,
+}
+
+impl ExportFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "text/csv; charset=utf-8",
+            ExportFormat::Xlsx => "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+        }
+    }
+}
+
+impl fmt::Display for ExportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Xlsx => "xlsx",
+        };
+        f.write_str(s)
+    }
+}
+
+impl FromStr for ExportFormat {
+    type Err = CustomError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "csv" => Ok(ExportFormat::Csv),
+            "xlsx" => Ok(ExportFormat::Xlsx),
+            _ => Err(CustomError::new(408, format!("Unknown export format: {}", s))),
+        }
+    }
+}
+
+/// Spreadsheets run cells starting with these as formulas
+const FORMULA_PREFIXES: [char; 6] = ['=', '+', '-', '@', '\t', '\r'];
+
+/// Quote text cells that would be read as formulas (CSV injection).
+/// Numbers are written as numbers, so negative values are left alone.
+fn escape_formula(s: &str) -> String {
+    if s.starts_with(&FORMULA_PREFIXES[..]) {
+        format!("'{}", s)
+    } else {
+        s.to_owned()
+    }
+}
+
+impl ExportTable {
+    pub fn write(&self, format: ExportFormat) -> Result<Vec<u8>, CustomError> {
+        match format {
+            ExportFormat::Csv => self.to_csv(),
+            ExportFormat::Xlsx => self.to_xlsx(),
+        }
+    }
+
+    /// UTF-8 CSV with a byte order mark so Excel keeps French accents
+    pub fn to_csv(&self) -> Result<Vec<u8>, CustomError> {
+        let to_error = |e: csv::Error| CustomError::new(500, format!("Unable to write CSV: {}", e));
+
+        let mut writer = csv::Writer::from_writer(b"\xEF\xBB\xBF".to_vec());
+
+        writer.write_record(&self.headers).map_err(to_error)?;
+
+        for row in &self.rows {
+            let record: Vec<String> = row.iter()
+                .map(|cell| match cell {
+                    ExportCell::Text(s) => escape_formula(s),
+                    ExportCell::Number(n) => n.to_string(),
+                })
+                .collect();
+
+            writer.write_record(&record).map_err(to_error)?;
+        };
+
+        writer.into_inner()
+            .map_err(|e| CustomError::new(500, format!("Unable to write CSV: {}", e)))
+    }
+
+    pub fn to_xlsx(&self) -> Result<Vec<u8>, CustomError> {
+        let to_error = |e: rust_xlsxwriter::XlsxError| CustomError::new(500, format!("Unable to write XLSX: {}", e));
+
+        let mut workbook = Workbook::new();
+        let bold = Format::new().set_bold();
+
+        let worksheet = workbook.add_worksheet();
+        // Sheet names are limited to 31 characters
+        worksheet.set_name(&self.name.chars().take(31).collect::<String>()).map_err(to_error)?;
+
+        for (col, header) in self.headers.iter().enumerate() {
+            worksheet.write_string_with_format(0, col as u16, header, &bold).map_err(to_error)?;
+        };
+
+        for (i, row) in self.rows.iter().enumerate() {
+            let r = i as u32 + 1;
+
+            for (col, cell) in row.iter().enumerate() {
+                match cell {
+                    ExportCell::Text(s) => worksheet.write_string(r, col as u16, s),
+                    ExportCell::Number(n) => worksheet.write_number(r, col as u16, *n),
+                }.map_err(to_error)?;
+            };
+        };
+
+        workbook.save_to_buffer().map_err(to_error)
+    }
+}
@@ -1,3 +1,9 @@
 mod utilities;
+mod schema;
+mod query;
+mod mutation;
 
-pub use self::utilities::*;
\ No newline at end of file
+pub use self::utilities::*;
+pub use self::schema::*;
+pub use self::query::*;
+pub use self::mutation::*;
@@ -0,0 +1,74 @@
+use std::str::FromStr;
+
+use async_graphql::*;
+use chrono::{NaiveDateTime, Utc};
+use uuid::Uuid;
+
+use crate::database::connection;
+use crate::graphql::GraphqlViewer;
+use crate::models::{Role, RoleAssignment, RoleType};
+
+pub struct Mutation;
+
+/// Role lifecycle changes are made by admins, as on the admin screens
+fn require_admin(ctx: &Context<'_>) -> FieldResult<()> {
+    if ctx.data::<GraphqlViewer>()?.is_admin() {
+        Ok(())
+    } else {
+        Err(FieldError::new("Not authorized"))
+    }
+}
+
+#[Object]
+impl Mutation {
+    /// Move a person to a new substantive role, ending their current substantive role
+    /// and any acting assignment or secondment on its start date
+    async fn transfer_role(
+        &self,
+        ctx: &Context<'_>,
+        assignment: RoleAssignment,
+        note: String,
+    ) -> FieldResult<Role> {
+        require_admin(ctx)?;
+
+        let conn = connection()?;
+
+        Role::transfer(&conn, &assignment.new_role(RoleType::Substantive), &note)
+    }
+
+    /// Start an acting assignment, secondment or deployment. role_type is acting,
+    /// secondment or deployment, and the assignment needs an end date.
+    async fn assign_temporary_role(
+        &self,
+        ctx: &Context<'_>,
+        assignment: RoleAssignment,
+        role_type: String,
+        note: String,
+    ) -> FieldResult<Role> {
+        require_admin(ctx)?;
+
+        let role_type = RoleType::from_str(&role_type)?;
+
+        let conn = connection()?;
+
+        Role::assign_temporary(&conn, &assignment.new_role(role_type), role_type, &note)
+    }
+
+    /// End a role, today unless end_date is given. Ending an acting assignment or
+    /// secondment restores the substantive role it suspended.
+    async fn end_role(
+        &self,
+        ctx: &Context<'_>,
+        role_id: Uuid,
+        end_date: Option<NaiveDateTime>,
+        note: String,
+    ) -> FieldResult<Role> {
+        require_admin(ctx)?;
+
+        let conn = connection()?;
+
+        let role = Role::find(role_id)?;
+
+        role.end_role(&conn, end_date.unwrap_or_else(|| Utc::now().naive_utc()), &note)
+    }
+}
@@ -0,0 +1,129 @@
+use std::str::FromStr;
+
+use async_graphql::*;
+use chrono::{NaiveDate, Utc};
+use uuid::Uuid;
+
+use crate::database::connection;
+use crate::graphql::GraphqlViewer;
+use crate::models::{Expert, ExpertQuery, Person, Role, RoleEvent, SearchFilters, SearchResults, WorkforceMetric,
+    WorkforceScope, WorkforceUnit};
+
+pub struct Query;
+
+#[Object]
+impl Query {
+    /// Monthly workforce figures for a team or org tier from the last refresh, oldest first.
+    /// scope_type is team or org_tier.
+    async fn workforce_series(
+        &self,
+        ctx: &Context<'_>,
+        scope_type: String,
+        scope_id: Uuid,
+        from: Option<NaiveDate>,
+        to: Option<NaiveDate>,
+    ) -> FieldResult<Vec<WorkforceMetric>> {
+        let viewer = ctx.data::<GraphqlViewer>()?;
+        let scope = WorkforceScope::from_str(&scope_type)?;
+
+        let conn = connection()?;
+
+        let series = WorkforceMetric::find_series(&conn, scope, scope_id, from, to)?
+            .into_iter()
+            .filter(|m| viewer.allows(m.organization_id))
+            .collect();
+
+        Ok(series)
+    }
+
+    /// Teams and org tiers with workforce figures
+    async fn workforce_units(
+        &self,
+        ctx: &Context<'_>,
+        organization_id: Option<Uuid>,
+    ) -> FieldResult<Vec<WorkforceUnit>> {
+        let viewer = ctx.data::<GraphqlViewer>()?;
+
+        let conn = connection()?;
+
+        let units = WorkforceMetric::find_units(&conn, organization_id.or(viewer.organization_id))?
+            .into_iter()
+            .filter(|u| viewer.allows(u.organization_id))
+            .collect();
+
+        Ok(units)
+    }
+
+    /// Full-text search across people, teams, org tiers, skills and publications in
+    /// English and French, with facet counts. Open to any signed-in user, as on the search page.
+    async fn search(
+        &self,
+        ctx: &Context<'_>,
+        query: String,
+        filters: Option<SearchFilters>,
+    ) -> FieldResult<SearchResults> {
+        ctx.data::<GraphqlViewer>()?;
+
+        let conn = connection()?;
+
+        let results = SearchResults::search(&conn, &query, &filters.unwrap_or_default())?;
+
+        Ok(results)
+    }
+
+    /// People ranked for one or more skills at minimum levels, with the points behind each
+    /// score. Open to any signed-in user, as on the expert finder page.
+    async fn find_experts(
+        &self,
+        ctx: &Context<'_>,
+        query: ExpertQuery,
+    ) -> FieldResult<Vec<Expert>> {
+        ctx.data::<GraphqlViewer>()?;
+
+        let conn = connection()?;
+
+        let experts = Expert::find(&conn, &query, Utc::now().naive_utc())?;
+
+        Ok(experts)
+    }
+
+    /// A person's role events in chronological order: starts, transfers, acting assignments,
+    /// secondments, deployments and ends. Limited to the viewer's organization.
+    async fn role_timeline(
+        &self,
+        ctx: &Context<'_>,
+        person_id: Uuid,
+    ) -> FieldResult<Vec<RoleEvent>> {
+        let viewer = ctx.data::<GraphqlViewer>()?;
+
+        let conn = connection()?;
+
+        let person = Person::find(person_id)?;
+
+        if !viewer.allows(person.organization_id) {
+            return Err(FieldError::new("Not authorized"));
+        };
+
+        RoleEvent::timeline(&conn, person.id)
+    }
+
+    /// Events for one role in chronological order. Limited to the viewer's organization.
+    async fn role_history(
+        &self,
+        ctx: &Context<'_>,
+        role_id: Uuid,
+    ) -> FieldResult<Vec<RoleEvent>> {
+        let viewer = ctx.data::<GraphqlViewer>()?;
+
+        let conn = connection()?;
+
+        let role = Role::find(role_id)?;
+        let person = Person::find(role.person_id)?;
+
+        if !viewer.allows(person.organization_id) {
+            return Err(FieldError::new("Not authorized"));
+        };
+
+        RoleEvent::find_by_role(&conn, role.id)
+    }
+}
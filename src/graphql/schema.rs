@@ -0,0 +1,40 @@
+use async_graphql::{EmptySubscription, Schema};
+use uuid::Uuid;
+
+use crate::graphql::{Mutation, Query};
+
+pub type AppSchema = Schema<Query, Mutation, EmptySubscription>;
+
+pub fn create_schema() -> AppSchema {
+    Schema::build(Query, Mutation, EmptySubscription).finish()
+}
+
+#[derive(Debug, Clone, Copy)]
+/// Who is asking, attached to each request by the GraphQL handler.
+/// In organization reports such as workforce metrics, admins see every
+/// organization and other users only their own.
+pub struct GraphqlViewer {
+    pub organization_id: Option<Uuid>,
+}
+
+impl GraphqlViewer {
+    pub fn admin() -> Self {
+        GraphqlViewer {
+            organization_id: None,
+        }
+    }
+
+    pub fn organization(organization_id: Uuid) -> Self {
+        GraphqlViewer {
+            organization_id: Some(organization_id),
+        }
+    }
+
+    pub fn is_admin(&self) -> bool {
+        self.organization_id.is_none()
+    }
+
+    pub fn allows(&self, organization_id: Uuid) -> bool {
+        self.organization_id.map_or(true, |id| id == organization_id)
+    }
+}
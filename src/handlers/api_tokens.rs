@@ -0,0 +1,181 @@
+use actix_web::{HttpRequest, HttpResponse, Responder, get, post, web, ResponseError};
+use actix_identity::{Identity};
+use chrono::Utc;
+use serde::{Deserialize};
+use tera::Context;
+use uuid::Uuid;
+
+use crate::{AppData, extract_identity_data, generate_basic_context};
+use crate::models::{ApiScope, ApiToken, User, MAX_TOKEN_DAYS};
+use crate::database::connection;
+use crate::errors::CustomError;
+
+#[derive(Deserialize, Debug)]
+pub struct ApiTokenForm {
+    name: String,
+    /// Days until the token expires, at most MAX_TOKEN_DAYS
+    days: i64,
+    /// Checkboxes, "on" when ticked
+    scope_graphql: Option<String>,
+    scope_export: Option<String>,
+}
+
+impl ApiTokenForm {
+    fn scopes(&self) -> Vec<ApiScope> {
+        let mut scopes = Vec::new();
+
+        if self.scope_graphql.is_some() {
+            scopes.push(ApiScope::Graphql);
+        };
+
+        if self.scope_export.is_some() {
+            scopes.push(ApiScope::Export);
+        };
+
+        scopes
+    }
+}
+
+/// Render the token page, with the full token if one was just issued
+fn render_api_tokens(
+    data: &AppData,
+    ctx: &mut Context,
+    user: &User,
+    new_token: Option<(ApiToken, String)>,
+) -> HttpResponse {
+
+    let conn = match connection() {
+        Ok(c) => c,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    let tokens = match ApiToken::find_by_user(&conn, user.id) {
+        Ok(t) => t,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    let now = Utc::now().naive_utc();
+
+    let active: Vec<bool> = tokens.iter().map(|t| t.is_active(now)).collect();
+
+    ctx.insert("tokens", &tokens.into_iter().zip(active).collect::<Vec<(ApiToken, bool)>>());
+    ctx.insert("new_token", &new_token);
+    ctx.insert("scopes", &ApiScope::all().iter().map(|s| s.to_string()).collect::<Vec<String>>());
+    ctx.insert("max_days", &MAX_TOKEN_DAYS);
+
+    let rendered = data.tmpl.render("users/api_tokens.html", ctx).unwrap();
+    HttpResponse::Ok().body(rendered)
+}
+
+#[get("/{lang}/api_tokens")]
+pub async fn api_tokens_page(
+    data: web::Data<AppData>,
+    web::Path(lang): web::Path<String>,
+
+    id: Identity,
+    req: HttpRequest,
+) -> impl Responder {
+
+    let (mut ctx, session_user, _role, _lang) = generate_basic_context(id, &lang, req.uri().path());
+
+    let user = match User::find_from_slug(&session_user) {
+        Ok(u) => u,
+        Err(_) => {
+            let err = CustomError::new(
+                406,
+                "Not authorized".to_string(),
+            );
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    render_api_tokens(&data, &mut ctx, &user, None)
+}
+
+#[post("/{lang}/api_tokens")]
+pub async fn create_api_token_post(
+    data: web::Data<AppData>,
+    web::Path(lang): web::Path<String>,
+    form: web::Form<ApiTokenForm>,
+
+    id: Identity,
+    req: HttpRequest,
+) -> impl Responder {
+
+    let (mut ctx, session_user, _role, _lang) = generate_basic_context(id, &lang, req.uri().path());
+
+    let user = match User::find_from_slug(&session_user) {
+        Ok(u) => u,
+        Err(_) => {
+            let err = CustomError::new(
+                406,
+                "Not authorized".to_string(),
+            );
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    let conn = match connection() {
+        Ok(c) => c,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    // Rendered rather than redirected, as the full token is only shown this once
+    match ApiToken::issue(&conn, user.id, &form.name, &form.scopes(), form.days) {
+        Ok(issued) => render_api_tokens(&data, &mut ctx, &user, Some(issued)),
+        Err(err) => {
+            println!("{}", &err);
+            HttpResponse::Found().header("Location", format!("/{}/api_tokens", &lang)).finish()
+        },
+    }
+}
+
+#[post("/{lang}/revoke_api_token/{id}")]
+pub async fn revoke_api_token_post(
+    web::Path((lang, token_id)): web::Path<(String, Uuid)>,
+
+    id: Identity,
+    _req: HttpRequest,
+) -> impl Responder {
+
+    let (session_user, _role) = extract_identity_data(&id);
+
+    let user = match User::find_from_slug(&session_user) {
+        Ok(u) => u,
+        Err(_) => {
+            let err = CustomError::new(
+                406,
+                "Not authorized".to_string(),
+            );
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    let conn = match connection() {
+        Ok(c) => c,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    match ApiToken::revoke(&conn, token_id, user.id) {
+        Ok(_) => HttpResponse::Found().header("Location", format!("/{}/api_tokens", &lang)).finish(),
+        Err(err) => {
+            println!("{}", &err);
+            err.error_response()
+        },
+    }
+}
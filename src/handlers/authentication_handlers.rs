@@ -12,6 +12,8 @@ use crate::models::{User, verify, UserData, EmailVerification,
     InsertableVerification, Email, PasswordResetToken, 
     InsertablePasswordResetToken};
 
+use crate::oidc::OidcConfig;
+
 use super::EmailForm;
 
 #[derive(Deserialize, Debug)]
@@ -46,7 +48,10 @@ pub async fn login_handler(
     id: Identity,
 ) -> impl Responder {
 
-    let (ctx, _session_user, _role, _lang) = generate_basic_context(id, &lang, req.uri().path());
+    let (mut ctx, _session_user, _role, _lang) = generate_basic_context(id, &lang, req.uri().path());
+
+    // Offer single sign-on when an identity provider is configured
+    ctx.insert("sso_provider", &OidcConfig::from_env().map(|c| c.provider_name));
 
     let rendered = data.tmpl.render("authentication/log_in.html", &ctx).unwrap();
     HttpResponse::Ok().body(rendered)
@@ -0,0 +1,181 @@
+use std::str::FromStr;
+
+use actix_web::{HttpRequest, HttpResponse, Responder, get, web, ResponseError};
+use actix_identity::{Identity};
+use uuid::Uuid;
+
+use crate::{AppData, extract_identity_data, generate_basic_context};
+use crate::handlers::find_organization;
+use crate::models::{CollaborationGraph, GraphFormat, Person};
+use crate::database::connection;
+use crate::errors::CustomError;
+
+/// Rows shown in each ranking on the collaboration page
+const TOP_LINKS: usize = 25;
+
+#[get("/{lang}/collaborations/{organization}")]
+pub async fn collaboration_page(
+    data: web::Data<AppData>,
+    web::Path((lang, organization)): web::Path<(String, String)>,
+
+    id: Identity,
+    req: HttpRequest,
+) -> impl Responder {
+
+    let (mut ctx, session_user, _role, _lang) = generate_basic_context(id, &lang, req.uri().path());
+
+    if session_user == "" {
+        let err = CustomError::new(
+            406,
+            "Not authorized".to_string(),
+        );
+        println!("{}", &err);
+        return err.error_response()
+    };
+
+    let organization = match find_organization(&organization) {
+        Ok(o) => o,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    let conn = match connection() {
+        Ok(c) => c,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    let graph = match CollaborationGraph::build(&conn, organization.id) {
+        Ok(g) => g,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    ctx.insert("organization", &organization);
+    ctx.insert("team_links", &graph.team_links(TOP_LINKS));
+    ctx.insert("external_organizations", &graph.external_organizations(TOP_LINKS));
+    ctx.insert("node_count", &graph.nodes.len());
+    ctx.insert("edge_count", &graph.edges.len());
+
+    let rendered = data.tmpl.render("collaborations/collaboration_page.html", &ctx).unwrap();
+    HttpResponse::Ok().body(rendered)
+}
+
+#[get("/{lang}/collaborators/{id}")]
+pub async fn collaborators_page(
+    data: web::Data<AppData>,
+    web::Path((lang, person_id)): web::Path<(String, Uuid)>,
+
+    id: Identity,
+    req: HttpRequest,
+) -> impl Responder {
+
+    let (mut ctx, session_user, _role, _lang) = generate_basic_context(id, &lang, req.uri().path());
+
+    if session_user == "" {
+        let err = CustomError::new(
+            406,
+            "Not authorized".to_string(),
+        );
+        println!("{}", &err);
+        return err.error_response()
+    };
+
+    let person = match Person::find(person_id) {
+        Ok(p) => p,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    let conn = match connection() {
+        Ok(c) => c,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    let graph = match CollaborationGraph::build(&conn, person.organization_id) {
+        Ok(g) => g,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    let person_node = format!("person:{}", person.id);
+
+    let external: Vec<_> = graph.external_organizations(usize::MAX)
+        .into_iter()
+        .filter(|o| graph.edges.iter().any(|e| e.source == person_node && e.target == o.node.id))
+        .collect();
+
+    ctx.insert("person", &person);
+    ctx.insert("collaborators", &graph.collaborators(person.id));
+    ctx.insert("external_organizations", &external);
+
+    let rendered = data.tmpl.render("collaborations/collaborators_page.html", &ctx).unwrap();
+    HttpResponse::Ok().body(rendered)
+}
+
+#[get("/{lang}/collaboration_graph/{organization}/{format}")]
+pub async fn collaboration_graph_export(
+    web::Path((_lang, organization, format)): web::Path<(String, String, String)>,
+
+    id: Identity,
+    _req: HttpRequest,
+) -> impl Responder {
+
+    let (session_user, _role) = extract_identity_data(&id);
+
+    if session_user == "" {
+        let err = CustomError::new(
+            406,
+            "Not authorized".to_string(),
+        );
+        println!("{}", &err);
+        return err.error_response()
+    };
+
+    let (organization, format) = match (find_organization(&organization), GraphFormat::from_str(&format)) {
+        (Ok(o), Ok(f)) => (o, f),
+        (Err(err), _) | (_, Err(err)) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    let conn = match connection() {
+        Ok(c) => c,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    let body = CollaborationGraph::build(&conn, organization.id)
+        .and_then(|graph| graph.write(format));
+
+    match body {
+        Ok(body) => {
+            let filename = format!("collaborations_{}.{}", organization.acronym_en.to_lowercase(), format);
+
+            HttpResponse::Ok()
+                .content_type(format.content_type())
+                .header("Content-Disposition", format!("attachment; filename=\"{}\"", filename))
+                .body(body)
+        },
+        Err(err) => {
+            println!("{}", &err);
+            err.error_response()
+        },
+    }
+}
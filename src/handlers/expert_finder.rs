@@ -0,0 +1,105 @@
+use actix_web::{HttpRequest, HttpResponse, Responder, get, web, ResponseError};
+use actix_identity::{Identity};
+use chrono::Utc;
+use serde::{Deserialize};
+use uuid::Uuid;
+
+use crate::{AppData, generate_basic_context};
+use crate::models::{CapabilityLevel, Expert, ExpertQuery, Organization, Skill, SkillCriterion};
+use crate::database::connection;
+use crate::errors::CustomError;
+
+#[derive(Deserialize, Debug)]
+/// Up to three skill rows, blank rows are ignored
+pub struct ExpertFinderQuery {
+    pub skill_1: Option<String>,
+    pub level_1: Option<i32>,
+    pub skill_2: Option<String>,
+    pub level_2: Option<i32>,
+    pub skill_3: Option<String>,
+    pub level_3: Option<i32>,
+    pub language: Option<String>,
+    pub organization: Option<String>,
+}
+
+impl ExpertFinderQuery {
+    pub fn expert_query(&self) -> ExpertQuery {
+        let rows = [
+            (self.skill_1.as_ref(), self.level_1),
+            (self.skill_2.as_ref(), self.level_2),
+            (self.skill_3.as_ref(), self.level_3),
+        ];
+
+        ExpertQuery {
+            skills: rows.iter()
+                .filter_map(|&(skill, level)| Some(SkillCriterion {
+                    skill_id: skill.and_then(|s| Uuid::parse_str(s).ok())?,
+                    min_level: level.unwrap_or(CapabilityLevel::Experienced.as_i32()),
+                }))
+                .collect(),
+            language: self.language.as_ref()
+                .map(|l| l.trim().to_lowercase())
+                .filter(|l| !l.is_empty()),
+            organization_id: self.organization.as_ref().and_then(|o| Uuid::parse_str(o).ok()),
+        }
+    }
+}
+
+#[get("/{lang}/expert_finder")]
+pub async fn expert_finder(
+    data: web::Data<AppData>,
+    web::Path(lang): web::Path<String>,
+    query: web::Query<ExpertFinderQuery>,
+
+    id: Identity,
+    req: HttpRequest,
+) -> impl Responder {
+
+    let (mut ctx, session_user, _role, _lang) = generate_basic_context(id, &lang, req.uri().path());
+
+    if session_user == "" {
+        let err = CustomError::new(
+            406,
+            "Not authorized".to_string(),
+        );
+        println!("{}", &err);
+        return err.error_response()
+    };
+
+    let conn = match connection() {
+        Ok(c) => c,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    let expert_query = query.expert_query();
+
+    let experts = match Expert::find(&conn, &expert_query, Utc::now().naive_utc()) {
+        Ok(e) => e,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    let levels: Vec<(i32, &str)> = (0..=4)
+        .map(|l| (l, CapabilityLevel::from_i32(l).key()))
+        .collect();
+
+    // Always offer three rows, filled from the query
+    let mut rows: Vec<Option<SkillCriterion>> = expert_query.skills.iter().cloned().map(Some).collect();
+    rows.resize(3, None);
+
+    ctx.insert("experts", &experts);
+    ctx.insert("expert_query", &expert_query);
+    ctx.insert("rows", &rows);
+    ctx.insert("searched", &!expert_query.skills.is_empty());
+    ctx.insert("skills", &Skill::find_all(&conn).unwrap_or_default());
+    ctx.insert("organizations", &Organization::find_all().unwrap_or_default());
+    ctx.insert("levels", &levels);
+
+    let rendered = data.tmpl.render("expert_finder/expert_finder.html", &ctx).unwrap();
+    HttpResponse::Ok().body(rendered)
+}
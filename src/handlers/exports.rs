@@ -0,0 +1,104 @@
+use std::str::FromStr;
+
+use actix_web::{HttpRequest, HttpResponse, Responder, get, web, ResponseError};
+use actix_identity::{Identity};
+use actix_web_httpauth::extractors::bearer::BearerAuth;
+use serde::{Deserialize};
+use uuid::Uuid;
+
+use crate::extract_api_identity;
+use crate::handlers::AsOfQuery;
+use crate::models::{ApiScope, Person, User};
+use crate::export::{ExportFormat, ExportReport, ExportScope, ExportTable};
+use crate::database::connection;
+use crate::errors::CustomError;
+
+#[derive(Deserialize, Debug)]
+pub struct ExportQuery {
+    pub as_of: Option<String>,
+    pub team_id: Option<Uuid>,
+    pub org_tier_id: Option<Uuid>,
+    pub organization_id: Option<Uuid>,
+}
+
+/// Admins see everything. Other users are limited to the organization
+/// of their linked person profile.
+fn export_scope(session_user: &String, role: &str) -> Result<ExportScope, CustomError> {
+    if role == "admin" {
+        return Ok(ExportScope::admin())
+    };
+
+    if session_user.is_empty() {
+        return Err(CustomError::new(406, "Not authorized".to_string()))
+    };
+
+    let user = User::find_from_slug(session_user)?;
+
+    let person = Person::find_by_user_id(user.id)
+        .map_err(|_| CustomError::new(406, "Exports require a linked person profile".to_string()))?;
+
+    Ok(ExportScope::organization(person.organization_id))
+}
+
+/// Signed-in users, or scripts with an API token carrying the export scope
+#[get("/{lang}/export/{report}/{format}")]
+pub async fn export_report(
+    web::Path((lang, report, format)): web::Path<(String, String, String)>,
+    query: web::Query<ExportQuery>,
+    bearer: Option<BearerAuth>,
+
+    id: Identity,
+    _req: HttpRequest,
+) -> impl Responder {
+
+    let lang = match lang.as_str() {
+        "fr" => "fr",
+        _ => "en",
+    };
+
+    let scope = match extract_api_identity(bearer, &id, ApiScope::Export)
+        .and_then(|(session_user, role)| export_scope(&session_user, &role))
+        .and_then(|scope| scope.narrow(query.organization_id)) {
+        Ok(s) => s,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    let (report, format) = match (ExportReport::from_str(&report), ExportFormat::from_str(&format)) {
+        (Ok(r), Ok(f)) => (r, f),
+        (Err(err), _) | (_, Err(err)) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    let as_of = AsOfQuery { as_of: query.as_of.clone() }.as_of_datetime();
+
+    let conn = match connection() {
+        Ok(c) => c,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    let body = ExportTable::build(&conn, report, scope, lang, as_of, query.team_id, query.org_tier_id)
+        .and_then(|table| table.write(format));
+
+    match body {
+        Ok(body) => {
+            let filename = format!("{}_{}_{}.{}", report, lang, as_of.format("%Y-%m-%d"), format);
+
+            HttpResponse::Ok()
+                .content_type(format.content_type())
+                .header("Content-Disposition", format!("attachment; filename=\"{}\"", filename))
+                .body(body)
+        },
+        Err(err) => {
+            println!("{}", &err);
+            err.error_response()
+        },
+    }
+}
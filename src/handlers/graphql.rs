@@ -0,0 +1,75 @@
+use actix_web::{HttpRequest, HttpResponse, Responder, get, post, web, ResponseError};
+use actix_identity::{Identity};
+use actix_web_httpauth::extractors::bearer::BearerAuth;
+use async_graphql::http::{playground_source, GraphQLPlaygroundConfig};
+
+use crate::{extract_api_identity, extract_identity_data};
+use crate::models::{ApiScope, Person, User};
+use crate::graphql::{AppSchema, GraphqlViewer};
+use crate::errors::CustomError;
+
+/// Admins query everything. Other users are limited to the organization
+/// of their linked person profile.
+fn graphql_viewer(session_user: &String, role: &str) -> Result<GraphqlViewer, CustomError> {
+    if role == "admin" {
+        return Ok(GraphqlViewer::admin())
+    };
+
+    if session_user.is_empty() {
+        return Err(CustomError::new(406, "Not authorized".to_string()))
+    };
+
+    let user = User::find_from_slug(session_user)?;
+
+    let person = Person::find_by_user_id(user.id)
+        .map_err(|_| CustomError::new(406, "GraphQL requires a linked person profile".to_string()))?;
+
+    Ok(GraphqlViewer::organization(person.organization_id))
+}
+
+/// Signed-in users, or scripts with an API token carrying the graphql scope
+#[post("/api/graphql")]
+pub async fn graphql_post(
+    schema: web::Data<AppSchema>,
+    request: web::Json<async_graphql::Request>,
+    bearer: Option<BearerAuth>,
+
+    id: Identity,
+    _req: HttpRequest,
+) -> impl Responder {
+
+    let viewer = match extract_api_identity(bearer, &id, ApiScope::Graphql)
+        .and_then(|(session_user, role)| graphql_viewer(&session_user, &role)) {
+        Ok(v) => v,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    let response = schema.execute(request.into_inner().data(viewer)).await;
+
+    HttpResponse::Ok().json(response)
+}
+
+#[get("/api/graphql")]
+pub async fn graphql_playground(
+    id: Identity,
+    _req: HttpRequest,
+) -> impl Responder {
+
+    let (session_user, _role) = extract_identity_data(&id);
+
+    if session_user.is_empty() {
+        let err = CustomError::new(
+            406,
+            "Not authorized".to_string(),
+        );
+        println!("{}", &err);
+        return err.error_response()
+    };
+
+    HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(playground_source(GraphQLPlaygroundConfig::new("/api/graphql")))
+}
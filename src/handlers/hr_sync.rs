@@ -0,0 +1,143 @@
+use actix_web::{HttpRequest, HttpResponse, Responder, get, post, web, ResponseError};
+use actix_identity::{Identity};
+use uuid::Uuid;
+
+use crate::{AppData, extract_identity_data, generate_basic_context};
+use crate::importer::resolve_hr_conflict;
+use crate::models::{HrSyncChange, HrSyncRun};
+use crate::database::connection;
+use crate::errors::CustomError;
+
+#[get("/{lang}/hr_sync")]
+pub async fn hr_sync_index(
+    data: web::Data<AppData>,
+    web::Path(lang): web::Path<String>,
+
+    id: Identity,
+    req: HttpRequest,
+) -> impl Responder {
+
+    let (mut ctx, _session_user, role, _lang) = generate_basic_context(id, &lang, req.uri().path());
+
+    if role != "admin".to_string() {
+        let err = CustomError::new(
+            406,
+            "Not authorized".to_string(),
+        );
+        println!("{}", &err);
+        return err.error_response()
+    };
+
+    let conn = match connection() {
+        Ok(c) => c,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    let runs = HrSyncRun::find_recent(&conn, 20).unwrap_or_else(|e| {
+        println!("{:?}", e);
+        Vec::new()
+    });
+
+    let conflicts = HrSyncChange::find_conflicts(&conn).unwrap_or_else(|e| {
+        println!("{:?}", e);
+        Vec::new()
+    });
+
+    ctx.insert("runs", &runs);
+    ctx.insert("conflicts", &conflicts);
+
+    let rendered = data.tmpl.render("hr_sync/hr_sync_index.html", &ctx).unwrap();
+    HttpResponse::Ok().body(rendered)
+}
+
+#[get("/{lang}/hr_sync_run/{id}")]
+pub async fn hr_sync_run_page(
+    data: web::Data<AppData>,
+    web::Path((lang, run_id)): web::Path<(String, Uuid)>,
+
+    id: Identity,
+    req: HttpRequest,
+) -> impl Responder {
+
+    let (mut ctx, _session_user, role, _lang) = generate_basic_context(id, &lang, req.uri().path());
+
+    if role != "admin".to_string() {
+        let err = CustomError::new(
+            406,
+            "Not authorized".to_string(),
+        );
+        println!("{}", &err);
+        return err.error_response()
+    };
+
+    let conn = match connection() {
+        Ok(c) => c,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    match HrSyncChange::find_by_run(&conn, run_id) {
+        Ok(changes) => {
+            ctx.insert("run_id", &run_id);
+            ctx.insert("changes", &changes);
+
+            let rendered = data.tmpl.render("hr_sync/hr_sync_run.html", &ctx).unwrap();
+            HttpResponse::Ok().body(rendered)
+        },
+        Err(e) => {
+            println!("{:?}", e);
+            HttpResponse::Found().header("Location", "/not_found").finish()
+        },
+    }
+}
+
+/// decision is "accept" (apply the HRMS value) or "keep" (retain the app value)
+#[post("/{lang}/hr_sync_resolve/{id}/{decision}")]
+pub async fn hr_sync_resolve(
+    web::Path((lang, change_id, decision)): web::Path<(String, Uuid, String)>,
+
+    id: Identity,
+    _req: HttpRequest,
+) -> impl Responder {
+
+    let (_session_user, role) = extract_identity_data(&id);
+
+    if role != "admin".to_string() {
+        let err = CustomError::new(
+            406,
+            "Not authorized".to_string(),
+        );
+        println!("{}", &err);
+        return err.error_response()
+    };
+
+    let accept = match decision.as_str() {
+        "accept" => true,
+        "keep" => false,
+        _ => return HttpResponse::Found().header("Location", "/not_found").finish(),
+    };
+
+    let conn = match connection() {
+        Ok(c) => c,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    match resolve_hr_conflict(&conn, change_id, accept) {
+        Ok(change) => {
+            println!("HR change {} resolved as {}", &change.id, &change.status);
+            HttpResponse::Found().header("Location", format!("/{}/hr_sync", &lang)).finish()
+        },
+        Err(err) => {
+            println!("{}", &err);
+            err.error_response()
+        },
+    }
+}
@@ -0,0 +1,137 @@
+use std::str::FromStr;
+
+use actix_web::{HttpRequest, HttpResponse, Responder, get, post, web, ResponseError};
+use actix_identity::{Identity};
+use actix_multipart::Multipart;
+use futures::{StreamExt, TryStreamExt};
+
+use crate::{AppData, generate_basic_context};
+use crate::importer::{import_csv, ColumnMapping, ImportKind};
+use crate::database::connection;
+use crate::errors::CustomError;
+
+/// Fields collected from the multipart import form
+#[derive(Default)]
+struct ImportUpload {
+    kind: String,
+    mapping: String,
+    dry_run: bool,
+    file: Vec<u8>,
+}
+
+impl ImportUpload {
+    async fn from_multipart(mut payload: Multipart) -> Result<Self, CustomError> {
+        let mut upload = ImportUpload::default();
+
+        while let Some(mut field) = payload.try_next().await
+            .map_err(|e| CustomError::new(400, format!("Invalid upload: {}", e)))? {
+
+            let name = field.content_disposition()
+                .and_then(|cd| cd.get_name().map(|n| n.to_owned()))
+                .unwrap_or_default();
+
+            let mut bytes = Vec::new();
+            while let Some(chunk) = field.next().await {
+                let chunk = chunk.map_err(|e| CustomError::new(400, format!("Invalid upload: {}", e)))?;
+                bytes.extend_from_slice(&chunk);
+            };
+
+            match name.as_str() {
+                "kind" => upload.kind = String::from_utf8_lossy(&bytes).trim().to_owned(),
+                "mapping" => upload.mapping = String::from_utf8_lossy(&bytes).to_string(),
+                "dry_run" => upload.dry_run = true,
+                "file" => upload.file = bytes,
+                _ => (),
+            };
+        };
+
+        Ok(upload)
+    }
+}
+
+#[get("/{lang}/import")]
+pub async fn import_page(
+    data: web::Data<AppData>,
+    web::Path(lang): web::Path<String>,
+
+    id: Identity,
+    req: HttpRequest,
+) -> impl Responder {
+
+    let (mut ctx, _session_user, role, _lang) = generate_basic_context(id, &lang, req.uri().path());
+
+    if role != "admin".to_string() {
+        let err = CustomError::new(
+            406,
+            "Not authorized".to_string(),
+        );
+        println!("{}", &err);
+        return err.error_response()
+    };
+
+    let kinds: Vec<String> = ImportKind::ALL.iter().map(|k| k.to_string()).collect();
+    let fields: Vec<(String, Vec<&str>, Vec<&str>)> = ImportKind::ALL.iter()
+        .map(|k| (k.to_string(), k.required_fields().to_vec(), k.optional_fields().to_vec()))
+        .collect();
+
+    ctx.insert("kinds", &kinds);
+    ctx.insert("fields", &fields);
+
+    let rendered = data.tmpl.render("imports/import_page.html", &ctx).unwrap();
+    HttpResponse::Ok().body(rendered)
+}
+
+#[post("/{lang}/import")]
+pub async fn import_post(
+    data: web::Data<AppData>,
+    web::Path(lang): web::Path<String>,
+    payload: Multipart,
+
+    id: Identity,
+    req: HttpRequest,
+) -> impl Responder {
+
+    let (mut ctx, _session_user, role, _lang) = generate_basic_context(id, &lang, req.uri().path());
+
+    if role != "admin".to_string() {
+        let err = CustomError::new(
+            406,
+            "Not authorized".to_string(),
+        );
+        println!("{}", &err);
+        return err.error_response()
+    };
+
+    let upload = match ImportUpload::from_multipart(payload).await {
+        Ok(u) => u,
+        Err(err) => {
+            println!("{}", &err);
+            return HttpResponse::Found().header("Location", format!("/{}/import", &lang)).finish()
+        },
+    };
+
+    let conn = match connection() {
+        Ok(c) => c,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    let result = ImportKind::from_str(&upload.kind)
+        .and_then(|kind| {
+            let mapping = ColumnMapping::parse(&upload.mapping)?;
+            import_csv(&conn, kind, upload.file.as_slice(), &mapping, upload.dry_run)
+        });
+
+    match result {
+        Ok(report) => ctx.insert("report", &report),
+        Err(err) => {
+            println!("{}", &err);
+            ctx.insert("import_error", &err.error_message);
+        },
+    };
+
+    let rendered = data.tmpl.render("imports/import_report.html", &ctx).unwrap();
+    HttpResponse::Ok().body(rendered)
+}
@@ -0,0 +1,245 @@
+use actix_web::{HttpRequest, HttpResponse, Responder, get, post, web, ResponseError};
+use actix_identity::{Identity};
+use chrono::{NaiveDate, NaiveDateTime, Utc};
+use serde::{Deserialize};
+use uuid::Uuid;
+
+use crate::{AppData, extract_identity_data, generate_basic_context};
+use crate::models::{LanguageEvaluation, LanguageGap, LanguageRequirement, NewLanguageEvaluation,
+    OfficialLanguage, Organization, ProficiencyLevel, Role};
+use crate::database::connection;
+use crate::errors::CustomError;
+
+#[derive(Deserialize, Debug)]
+pub struct LanguageEvaluationForm {
+    language: String,
+    /// A, B, C, E or X, blank if not tested
+    reading: String,
+    writing: String,
+    oral: String,
+    tested_at: String,
+    /// Blank for the standard validity period
+    expires_at: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct LanguageRequirementForm {
+    /// english_essential, french_essential, either_or or a profile e.g. CBC. Blank to clear.
+    requirement: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct LanguageReportQuery {
+    pub organization: Option<String>,
+}
+
+/// Start of the day of a YYYY-MM-DD form date
+fn parse_date(date: &str) -> Option<NaiveDateTime> {
+    NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d")
+        .ok()
+        .map(|d| d.and_hms(0, 0, 0))
+}
+
+#[post("/{lang}/language_evaluation/{id}")]
+pub async fn language_evaluation_post(
+    web::Path((lang, person_id)): web::Path<(String, Uuid)>,
+    _data: web::Data<AppData>,
+    _req: HttpRequest,
+    form: web::Form<LanguageEvaluationForm>,
+    id: Identity,
+) -> impl Responder {
+
+    let (_session_user, role) = extract_identity_data(&id);
+
+    if role != "admin".to_string() {
+        let err = CustomError::new(
+            406,
+            "Not authorized".to_string(),
+        );
+        println!("{}", &err);
+        return err.error_response()
+    };
+
+    let level = |value: &str| value.parse::<ProficiencyLevel>().ok();
+
+    let (language, tested_at) = match (form.language.parse::<OfficialLanguage>(), parse_date(&form.tested_at)) {
+        (Ok(l), Some(t)) => (l, t),
+        _ => return HttpResponse::Found().header("Location", format!("/{}/person/{}", &lang, &person_id)).finish(),
+    };
+
+    let (reading, writing, oral) = (level(&form.reading), level(&form.writing), level(&form.oral));
+
+    if reading.is_none() && writing.is_none() && oral.is_none() {
+        return HttpResponse::Found().header("Location", format!("/{}/person/{}", &lang, &person_id)).finish()
+    };
+
+    let conn = match connection() {
+        Ok(c) => c,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    let evaluation = NewLanguageEvaluation::new(
+        person_id,
+        language,
+        reading,
+        writing,
+        oral,
+        tested_at,
+        parse_date(&form.expires_at),
+    );
+
+    match LanguageEvaluation::create(&conn, &evaluation) {
+        Ok(_) => HttpResponse::Found().header("Location", format!("/{}/person/{}", &lang, &person_id)).finish(),
+        Err(err) => {
+            println!("{:?}", err);
+            HttpResponse::Found().header("Location", "/database_error").finish()
+        },
+    }
+}
+
+#[post("/{lang}/delete_language_evaluation/{id}")]
+pub async fn delete_language_evaluation_post(
+    web::Path((lang, evaluation_id)): web::Path<(String, Uuid)>,
+
+    id: Identity,
+    _req: HttpRequest,
+) -> impl Responder {
+
+    let (_session_user, role) = extract_identity_data(&id);
+
+    if role != "admin".to_string() {
+        let err = CustomError::new(
+            406,
+            "Not authorized".to_string(),
+        );
+        println!("{}", &err);
+        return err.error_response()
+    };
+
+    let conn = match connection() {
+        Ok(c) => c,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    match LanguageEvaluation::delete(&conn, evaluation_id) {
+        Ok(evaluation) => HttpResponse::Found()
+            .header("Location", format!("/{}/person/{}", &lang, &evaluation.person_id))
+            .finish(),
+        Err(err) => {
+            println!("{}", &err);
+            err.error_response()
+        },
+    }
+}
+
+#[post("/{lang}/role_language_requirement/{id}")]
+pub async fn role_language_requirement_post(
+    web::Path((lang, role_id)): web::Path<(String, Uuid)>,
+    _data: web::Data<AppData>,
+    _req: HttpRequest,
+    form: web::Form<LanguageRequirementForm>,
+    id: Identity,
+) -> impl Responder {
+
+    let (_session_user, role) = extract_identity_data(&id);
+
+    if role != "admin".to_string() {
+        let err = CustomError::new(
+            406,
+            "Not authorized".to_string(),
+        );
+        println!("{}", &err);
+        return err.error_response()
+    };
+
+    let requirement = match form.requirement.trim() {
+        "" => None,
+        r => match r.parse::<LanguageRequirement>() {
+            Ok(r) => Some(r.to_string()),
+            Err(err) => {
+                println!("{}", &err);
+                return err.error_response()
+            },
+        },
+    };
+
+    let conn = match connection() {
+        Ok(c) => c,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    let mut position = match Role::find(role_id) {
+        Ok(r) => r,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    position.language_requirement = requirement;
+    position.updated_at = Utc::now().naive_utc();
+
+    match position.update(&conn) {
+        Ok(role) => HttpResponse::Found().header("Location", format!("/{}/team/{}", &lang, &role.team_id)).finish(),
+        Err(err) => {
+            println!("{:?}", err);
+            HttpResponse::Found().header("Location", "/database_error").finish()
+        },
+    }
+}
+
+#[get("/{lang}/language_report")]
+pub async fn language_report(
+    data: web::Data<AppData>,
+    web::Path(lang): web::Path<String>,
+    query: web::Query<LanguageReportQuery>,
+
+    id: Identity,
+    req: HttpRequest,
+) -> impl Responder {
+
+    let (mut ctx, _session_user, role, _lang) = generate_basic_context(id, &lang, req.uri().path());
+
+    if role != "admin".to_string() {
+        let err = CustomError::new(
+            406,
+            "Not authorized".to_string(),
+        );
+        println!("{}", &err);
+        return err.error_response()
+    };
+
+    let conn = match connection() {
+        Ok(c) => c,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    let organization_id = query.organization.as_ref().and_then(|o| Uuid::parse_str(o).ok());
+
+    let gaps = match LanguageGap::find_all(&conn, organization_id, Utc::now().naive_utc()) {
+        Ok(g) => g,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    ctx.insert("gaps", &gaps);
+    ctx.insert("organization_id", &organization_id);
+    ctx.insert("organizations", &Organization::find_all().unwrap_or_default());
+
+    let rendered = data.tmpl.render("reports/language_report.html", &ctx).unwrap();
+    HttpResponse::Ok().body(rendered)
+}
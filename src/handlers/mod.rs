@@ -6,6 +6,25 @@ pub mod utility;
 pub mod errors;
 pub mod email;
 pub mod authentication_handlers;
+pub mod reports;
+pub mod organizations;
+pub mod persons;
+pub mod teams;
+pub mod imports;
+pub mod exports;
+pub mod hr_sync;
+pub mod publications;
+pub mod collaborations;
+pub mod surge;
+pub mod languages;
+pub mod rosters;
+pub mod skill_gaps;
+pub mod workforce;
+pub mod graphql;
+pub mod search;
+pub mod expert_finder;
+pub mod api_tokens;
+pub mod sso;
 
 pub use base::{index, raw_index};
 pub use routes::configure_services;
@@ -14,4 +33,23 @@ pub use forms::*;
 pub use utility::*;
 pub use email::*;
 pub use errors::*;
-pub use authentication_handlers::*;
\ No newline at end of file
+pub use authentication_handlers::*;
+pub use reports::*;
+pub use organizations::*;
+pub use persons::*;
+pub use teams::*;
+pub use imports::*;
+pub use exports::*;
+pub use hr_sync::*;
+pub use publications::*;
+pub use collaborations::*;
+pub use surge::*;
+pub use languages::*;
+pub use rosters::*;
+pub use skill_gaps::*;
+pub use workforce::*;
+pub use graphql::*;
+pub use search::*;
+pub use expert_finder::*;
+pub use api_tokens::*;
+pub use sso::*;
@@ -0,0 +1,618 @@
+use actix_web::{HttpRequest, HttpResponse, Responder, get, post, web, ResponseError};
+use actix_identity::{Identity};
+use chrono::{NaiveDate, NaiveDateTime, Utc};
+use serde::{Deserialize};
+use uuid::Uuid;
+
+use crate::{AppData, extract_identity_data, generate_basic_context};
+use crate::models::{Organization, NewOrganization, OrgChartNode, OrgTier, NewOrgTier,
+    OrgOwnership, Team, ORG_TYPES};
+use crate::database::connection;
+use crate::errors::CustomError;
+
+#[derive(Deserialize, Debug)]
+pub struct OrganizationForm {
+    name_en: String,
+    name_fr: String,
+    acronym_en: String,
+    acronym_fr: String,
+    org_type: String,
+}
+
+impl OrganizationForm {
+    fn is_valid(&self) -> bool {
+        !self.name_en.trim().is_empty() &&
+        !self.name_fr.trim().is_empty() &&
+        !self.acronym_en.trim().is_empty() &&
+        !self.acronym_fr.trim().is_empty() &&
+        ORG_TYPES.contains(&self.org_type.as_str())
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct OrgTierForm {
+    name_en: String,
+    name_fr: String,
+    /// Parent tier id, or "none" for a top-level tier
+    parent_tier: String,
+}
+
+impl OrgTierForm {
+    /// Parent tier and the tier_level a child of it should have
+    fn parent_and_level(&self) -> Result<(Option<Uuid>, i32), CustomError> {
+        if self.parent_tier == "none" {
+            return Ok((None, 1))
+        };
+
+        let parent_id = Uuid::parse_str(&self.parent_tier)
+            .map_err(|e| CustomError::new(400, format!("Invalid parent tier: {}", e)))?;
+
+        let parent = OrgTier::find(parent_id)?;
+
+        Ok((Some(parent.id), parent.tier_level + 1))
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct AsOfQuery {
+    pub as_of: Option<String>,
+}
+
+impl AsOfQuery {
+    /// End of the requested day, or now if no valid date was supplied
+    pub fn as_of_datetime(&self) -> NaiveDateTime {
+        match &self.as_of {
+            Some(d) => match NaiveDate::parse_from_str(d, "%Y-%m-%d") {
+                Ok(date) => date.and_hms(23, 59, 59),
+                Err(_) => Utc::now().naive_utc(),
+            },
+            None => Utc::now().naive_utc(),
+        }
+    }
+}
+
+/// Find an organization from its id or acronym
+pub fn find_organization(organization: &str) -> Result<Organization, CustomError> {
+    let res = match Uuid::parse_str(organization) {
+        Ok(id) => Organization::get_by_id(&id),
+        Err(_) => Organization::get_by_acronym(organization),
+    };
+
+    res.map_err(|e| CustomError::new(408, format!("Organization not found: {:?}", e.message)))
+}
+
+#[get("/{lang}/org/{organization}")]
+pub async fn org_chart(
+    data: web::Data<AppData>,
+    web::Path((lang, organization)): web::Path<(String, String)>,
+    query: web::Query<AsOfQuery>,
+
+    id: Identity,
+    req: HttpRequest,
+) -> impl Responder {
+
+    let (mut ctx, session_user, _role, _lang) = generate_basic_context(id, &lang, req.uri().path());
+
+    if session_user == "" {
+        let err = CustomError::new(
+            406,
+            "Not authorized".to_string(),
+        );
+        println!("{}", &err);
+        return err.error_response()
+    };
+
+    let organization = match find_organization(&organization) {
+        Ok(o) => o,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    let conn = match connection() {
+        Ok(c) => c,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    let as_of = query.as_of_datetime();
+
+    let tree = match OrgChartNode::build(&conn, organization.id, as_of) {
+        Ok(t) => t,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    ctx.insert("organization", &organization);
+    ctx.insert("tree", &tree);
+    ctx.insert("as_of", &as_of.date().format("%Y-%m-%d").to_string());
+
+    let rendered = data.tmpl.render("organizations/org_chart.html", &ctx).unwrap();
+    HttpResponse::Ok().body(rendered)
+}
+
+#[get("/{lang}/organization_index")]
+pub async fn organization_index(
+    data: web::Data<AppData>,
+    web::Path(lang): web::Path<String>,
+
+    id: Identity,
+    req: HttpRequest,
+) -> impl Responder {
+
+    let (mut ctx, _session_user, role, _lang) = generate_basic_context(id, &lang, req.uri().path());
+
+    if role != "admin".to_string() {
+        let err = CustomError::new(
+            406,
+            "Not authorized".to_string(),
+        );
+        println!("{}", &err);
+        return err.error_response()
+    };
+
+    let organizations = match Organization::find_all() {
+        Ok(o) => o,
+        Err(e) => {
+            println!("{:?}", e);
+            Vec::new()
+        }
+    };
+
+    ctx.insert("organizations", &organizations);
+
+    let rendered = data.tmpl.render("organizations/organization_index.html", &ctx).unwrap();
+    HttpResponse::Ok().body(rendered)
+}
+
+#[get("/{lang}/organization/{organization}")]
+pub async fn organization_page(
+    data: web::Data<AppData>,
+    web::Path((lang, organization)): web::Path<(String, String)>,
+
+    id: Identity,
+    req: HttpRequest,
+) -> impl Responder {
+
+    let (mut ctx, _session_user, role, _lang) = generate_basic_context(id, &lang, req.uri().path());
+
+    if role != "admin".to_string() {
+        let err = CustomError::new(
+            406,
+            "Not authorized".to_string(),
+        );
+        println!("{}", &err);
+        return err.error_response()
+    };
+
+    let organization = match find_organization(&organization) {
+        Ok(o) => o,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    let org_tiers = match OrgTier::find_by_organization(organization.id) {
+        Ok(t) => t,
+        Err(e) => {
+            println!("{:?}", e);
+            Vec::new()
+        }
+    };
+
+    ctx.insert("organization", &organization);
+    ctx.insert("org_tiers", &org_tiers);
+
+    let rendered = data.tmpl.render("organizations/organization_page.html", &ctx).unwrap();
+    HttpResponse::Ok().body(rendered)
+}
+
+#[get("/{lang}/create_organization")]
+pub async fn create_organization(
+    data: web::Data<AppData>,
+    web::Path(lang): web::Path<String>,
+
+    id: Identity,
+    req: HttpRequest,
+) -> impl Responder {
+
+    let (mut ctx, _session_user, role, _lang) = generate_basic_context(id, &lang, req.uri().path());
+
+    if role != "admin".to_string() {
+        let err = CustomError::new(
+            406,
+            "Not authorized".to_string(),
+        );
+        println!("{}", &err);
+        return err.error_response()
+    };
+
+    ctx.insert("org_types", &ORG_TYPES);
+
+    let rendered = data.tmpl.render("organizations/create_organization.html", &ctx).unwrap();
+    HttpResponse::Ok().body(rendered)
+}
+
+#[post("/{lang}/create_organization")]
+pub async fn create_organization_post(
+    web::Path(lang): web::Path<String>,
+    _data: web::Data<AppData>,
+    _req: HttpRequest,
+    form: web::Form<OrganizationForm>,
+    id: Identity,
+) -> impl Responder {
+
+    let (_session_user, role) = extract_identity_data(&id);
+
+    if role != "admin".to_string() {
+        let err = CustomError::new(
+            406,
+            "Not authorized".to_string(),
+        );
+        println!("{}", &err);
+        return err.error_response()
+    };
+
+    if !form.is_valid() {
+        return HttpResponse::Found().header("Location", format!("/{}/create_organization", &lang)).finish()
+    };
+
+    let new_organization = NewOrganization::new(
+        form.name_en.trim().to_owned(),
+        form.name_fr.trim().to_owned(),
+        form.acronym_en.trim().to_uppercase(),
+        form.acronym_fr.trim().to_uppercase(),
+        form.org_type.to_owned(),
+    );
+
+    match Organization::create(&new_organization) {
+        Ok(organization) => {
+            println!("Organization {} created", &organization.name_en);
+            HttpResponse::Found().header("Location", format!("/{}/organization/{}", &lang, &organization.id)).finish()
+        },
+        Err(err) => {
+            println!("{:?}", err);
+            HttpResponse::Found().header("Location", "/database_error").finish()
+        },
+    }
+}
+
+#[get("/{lang}/edit_organization/{id}")]
+pub async fn edit_organization(
+    data: web::Data<AppData>,
+    web::Path((lang, organization_id)): web::Path<(String, Uuid)>,
+
+    id: Identity,
+    req: HttpRequest,
+) -> impl Responder {
+
+    let (mut ctx, _session_user, role, _lang) = generate_basic_context(id, &lang, req.uri().path());
+
+    if role != "admin".to_string() {
+        let err = CustomError::new(
+            406,
+            "Not authorized".to_string(),
+        );
+        println!("{}", &err);
+        return err.error_response()
+    };
+
+    let organization = match find_organization(&organization_id.to_string()) {
+        Ok(o) => o,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    ctx.insert("organization", &organization);
+    ctx.insert("org_types", &ORG_TYPES);
+
+    let rendered = data.tmpl.render("organizations/edit_organization.html", &ctx).unwrap();
+    HttpResponse::Ok().body(rendered)
+}
+
+#[post("/{lang}/edit_organization/{id}")]
+pub async fn edit_organization_post(
+    web::Path((lang, organization_id)): web::Path<(String, Uuid)>,
+    _data: web::Data<AppData>,
+    _req: HttpRequest,
+    form: web::Form<OrganizationForm>,
+    id: Identity,
+) -> impl Responder {
+
+    let (_session_user, role) = extract_identity_data(&id);
+
+    if role != "admin".to_string() {
+        let err = CustomError::new(
+            406,
+            "Not authorized".to_string(),
+        );
+        println!("{}", &err);
+        return err.error_response()
+    };
+
+    if !form.is_valid() {
+        return HttpResponse::Found().header("Location", format!("/{}/edit_organization/{}", &lang, &organization_id)).finish()
+    };
+
+    let mut organization = match find_organization(&organization_id.to_string()) {
+        Ok(o) => o,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    organization.name_en = form.name_en.trim().to_owned();
+    organization.name_fr = form.name_fr.trim().to_owned();
+    organization.acronym_en = form.acronym_en.trim().to_uppercase();
+    organization.acronym_fr = form.acronym_fr.trim().to_uppercase();
+    organization.org_type = form.org_type.to_owned();
+    organization.updated_at = Utc::now().naive_utc();
+
+    match organization.update() {
+        Ok(organization) => {
+            println!("Organization {} updated", &organization.name_en);
+            HttpResponse::Found().header("Location", format!("/{}/organization/{}", &lang, &organization.id)).finish()
+        },
+        Err(err) => {
+            println!("{:?}", err);
+            HttpResponse::Found().header("Location", "/database_error").finish()
+        },
+    }
+}
+
+#[get("/{lang}/org_tier/{id}")]
+pub async fn org_tier_page(
+    data: web::Data<AppData>,
+    web::Path((lang, org_tier_id)): web::Path<(String, Uuid)>,
+
+    id: Identity,
+    req: HttpRequest,
+) -> impl Responder {
+
+    let (mut ctx, _session_user, role, _lang) = generate_basic_context(id, &lang, req.uri().path());
+
+    if role != "admin".to_string() {
+        let err = CustomError::new(
+            406,
+            "Not authorized".to_string(),
+        );
+        println!("{}", &err);
+        return err.error_response()
+    };
+
+    let org_tier = match OrgTier::find(org_tier_id) {
+        Ok(t) => t,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    let organization = match find_organization(&org_tier.organization_id.to_string()) {
+        Ok(o) => o,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    let parent = org_tier.parent_tier.and_then(|p| OrgTier::find(p).ok());
+    let children = org_tier.find_children().unwrap_or_default();
+    let teams = Team::find_by_org_tier(org_tier.id).unwrap_or_default();
+    let owners = OrgOwnership::find_current_by_org_tier(org_tier.id, Utc::now().naive_utc()).unwrap_or_default();
+
+    ctx.insert("organization", &organization);
+    ctx.insert("org_tier", &org_tier);
+    ctx.insert("parent", &parent);
+    ctx.insert("children", &children);
+    ctx.insert("teams", &teams);
+    ctx.insert("owners", &owners);
+
+    let rendered = data.tmpl.render("organizations/org_tier_page.html", &ctx).unwrap();
+    HttpResponse::Ok().body(rendered)
+}
+
+#[get("/{lang}/create_org_tier/{organization_id}")]
+pub async fn create_org_tier(
+    data: web::Data<AppData>,
+    web::Path((lang, organization_id)): web::Path<(String, Uuid)>,
+
+    id: Identity,
+    req: HttpRequest,
+) -> impl Responder {
+
+    let (mut ctx, _session_user, role, _lang) = generate_basic_context(id, &lang, req.uri().path());
+
+    if role != "admin".to_string() {
+        let err = CustomError::new(
+            406,
+            "Not authorized".to_string(),
+        );
+        println!("{}", &err);
+        return err.error_response()
+    };
+
+    let organization = match find_organization(&organization_id.to_string()) {
+        Ok(o) => o,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    let org_tiers = OrgTier::find_by_organization(organization.id).unwrap_or_default();
+
+    ctx.insert("organization", &organization);
+    ctx.insert("org_tiers", &org_tiers);
+
+    let rendered = data.tmpl.render("organizations/create_org_tier.html", &ctx).unwrap();
+    HttpResponse::Ok().body(rendered)
+}
+
+#[post("/{lang}/create_org_tier/{organization_id}")]
+pub async fn create_org_tier_post(
+    web::Path((lang, organization_id)): web::Path<(String, Uuid)>,
+    _data: web::Data<AppData>,
+    _req: HttpRequest,
+    form: web::Form<OrgTierForm>,
+    id: Identity,
+) -> impl Responder {
+
+    let (_session_user, role) = extract_identity_data(&id);
+
+    if role != "admin".to_string() {
+        let err = CustomError::new(
+            406,
+            "Not authorized".to_string(),
+        );
+        println!("{}", &err);
+        return err.error_response()
+    };
+
+    if form.name_en.trim().is_empty() || form.name_fr.trim().is_empty() {
+        return HttpResponse::Found().header("Location", format!("/{}/create_org_tier/{}", &lang, &organization_id)).finish()
+    };
+
+    let (parent_tier, tier_level) = match form.parent_and_level() {
+        Ok(p) => p,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    let new_org_tier = NewOrgTier::new(
+        organization_id,
+        tier_level,
+        form.name_en.trim().to_owned(),
+        form.name_fr.trim().to_owned(),
+        parent_tier,
+    );
+
+    match OrgTier::create(&new_org_tier) {
+        Ok(org_tier) => {
+            println!("OrgTier {} created", &org_tier.name_en);
+            HttpResponse::Found().header("Location", format!("/{}/org_tier/{}", &lang, &org_tier.id)).finish()
+        },
+        Err(err) => {
+            println!("{:?}", err);
+            HttpResponse::Found().header("Location", "/database_error").finish()
+        },
+    }
+}
+
+#[get("/{lang}/edit_org_tier/{id}")]
+pub async fn edit_org_tier(
+    data: web::Data<AppData>,
+    web::Path((lang, org_tier_id)): web::Path<(String, Uuid)>,
+
+    id: Identity,
+    req: HttpRequest,
+) -> impl Responder {
+
+    let (mut ctx, _session_user, role, _lang) = generate_basic_context(id, &lang, req.uri().path());
+
+    if role != "admin".to_string() {
+        let err = CustomError::new(
+            406,
+            "Not authorized".to_string(),
+        );
+        println!("{}", &err);
+        return err.error_response()
+    };
+
+    let org_tier = match OrgTier::find(org_tier_id) {
+        Ok(t) => t,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    // A tier can't be its own parent
+    let org_tiers: Vec<OrgTier> = OrgTier::find_by_organization(org_tier.organization_id)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|t| t.id != org_tier.id)
+        .collect();
+
+    ctx.insert("org_tier", &org_tier);
+    ctx.insert("org_tiers", &org_tiers);
+
+    let rendered = data.tmpl.render("organizations/edit_org_tier.html", &ctx).unwrap();
+    HttpResponse::Ok().body(rendered)
+}
+
+#[post("/{lang}/edit_org_tier/{id}")]
+pub async fn edit_org_tier_post(
+    web::Path((lang, org_tier_id)): web::Path<(String, Uuid)>,
+    _data: web::Data<AppData>,
+    _req: HttpRequest,
+    form: web::Form<OrgTierForm>,
+    id: Identity,
+) -> impl Responder {
+
+    let (_session_user, role) = extract_identity_data(&id);
+
+    if role != "admin".to_string() {
+        let err = CustomError::new(
+            406,
+            "Not authorized".to_string(),
+        );
+        println!("{}", &err);
+        return err.error_response()
+    };
+
+    if form.name_en.trim().is_empty() || form.name_fr.trim().is_empty() {
+        return HttpResponse::Found().header("Location", format!("/{}/edit_org_tier/{}", &lang, &org_tier_id)).finish()
+    };
+
+    let mut org_tier = match OrgTier::find(org_tier_id) {
+        Ok(t) => t,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    let (parent_tier, tier_level) = match form.parent_and_level() {
+        Ok(p) => p,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    if let Some(parent_id) = parent_tier {
+        match org_tier.would_create_cycle(parent_id) {
+            Ok(false) => (),
+            _ => return HttpResponse::Found().header("Location", format!("/{}/edit_org_tier/{}", &lang, &org_tier_id)).finish(),
+        };
+    };
+
+    org_tier.name_en = form.name_en.trim().to_owned();
+    org_tier.name_fr = form.name_fr.trim().to_owned();
+    org_tier.parent_tier = parent_tier;
+    org_tier.tier_level = tier_level;
+    org_tier.updated_at = Utc::now().naive_utc();
+
+    match org_tier.update() {
+        Ok(org_tier) => {
+            println!("OrgTier {} updated", &org_tier.name_en);
+            HttpResponse::Found().header("Location", format!("/{}/org_tier/{}", &lang, &org_tier.id)).finish()
+        },
+        Err(err) => {
+            println!("{:?}", err);
+            HttpResponse::Found().header("Location", "/database_error").finish()
+        },
+    }
+}
@@ -0,0 +1,439 @@
+use actix_web::{HttpRequest, HttpResponse, Responder, get, post, web, ResponseError};
+use actix_web::error::BlockingError;
+use actix_identity::{Identity};
+use serde::{Deserialize};
+use uuid::Uuid;
+
+use crate::{AppData, extract_identity_data, generate_basic_context};
+use crate::models::{parse_languages, parse_orcid, Capability, CapabilityLevel, NewValidation, OfficialLanguage,
+    User, Person, Validation};
+use crate::importer::{import_orcid, HttpOrcidClient};
+use crate::database::connection;
+use crate::errors::CustomError;
+
+#[derive(Deserialize, Debug)]
+pub struct PersonForm {
+    /// Only on the form when the names aren't managed by the HRMS sync
+    given_name: Option<String>,
+    family_name: Option<String>,
+    /// ORCID iD, blank to clear
+    orcid: String,
+    /// Comma separated language codes, e.g. "en, fr"
+    languages: String,
+    /// en or fr, blank if not stated
+    first_official_language: String,
+    /// Work location, blank to clear
+    location: String,
+    /// YYYY-MM-DD, only on the admin form
+    retirement_eligible_on: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ValidationForm {
+    level: i32,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct LinkPersonForm {
+    /// Person id to link, or "none" to unlink
+    person_id: String,
+}
+
+/// Admins can edit any person. Users can edit the person linked to their account.
+fn can_edit_person(person: &Person, session_user: &String, role: &String) -> bool {
+    if role == "admin" {
+        return true
+    };
+
+    match (person.user_id, User::find_id_from_slug(session_user)) {
+        (Some(user_id), Ok(session_id)) => user_id == session_id,
+        _ => false,
+    }
+}
+
+/// Names of people in the HRMS extract come from the HRMS sync, so only admins
+/// change them here. Anyone who can edit the person can change other names.
+fn can_edit_names(person: &Person, role: &String) -> bool {
+    role == "admin" || person.employee_number.is_none()
+}
+
+/// The person linked to the signed in user, if any
+fn session_person(session_user: &String) -> Option<Person> {
+    User::find_id_from_slug(session_user)
+        .and_then(Person::find_by_user_id)
+        .ok()
+}
+
+#[get("/{lang}/person/{id}")]
+pub async fn person_page(
+    web::Path((lang, person_id)): web::Path<(String, Uuid)>,
+    data: web::Data<AppData>,
+
+    req: HttpRequest,
+    id: Identity,
+) -> impl Responder {
+
+    let (mut ctx, session_user, role, _lang) = generate_basic_context(id, &lang, req.uri().path());
+
+    if session_user == "" {
+        let err = CustomError::new(
+            406,
+            "Not authorized".to_string(),
+        );
+        println!("{}", &err);
+        return err.error_response()
+    };
+
+    let profile = Person::find(person_id).and_then(|p| p.profile());
+
+    match profile {
+        Ok(profile) => {
+            // Capabilities are validated by other people, never by their holder
+            let can_validate = session_person(&session_user)
+                .map(|p| p.id != profile.person.id)
+                .unwrap_or(false);
+
+            let levels: Vec<(i32, &str)> = (0..=4)
+                .map(|l| (l, CapabilityLevel::from_i32(l).key()))
+                .collect();
+
+            ctx.insert("can_edit", &can_edit_person(&profile.person, &session_user, &role));
+            ctx.insert("can_validate", &can_validate);
+            ctx.insert("levels", &levels);
+            ctx.insert("profile", &profile);
+
+            let rendered = data.tmpl.render("persons/person_page.html", &ctx).unwrap();
+            HttpResponse::Ok().body(rendered)
+        },
+        Err(err) => {
+            println!("{}", &err);
+            err.error_response()
+        },
+    }
+}
+
+#[get("/{lang}/edit_person/{id}")]
+pub async fn edit_person(
+    web::Path((lang, person_id)): web::Path<(String, Uuid)>,
+    data: web::Data<AppData>,
+
+    req: HttpRequest,
+    id: Identity,
+) -> impl Responder {
+
+    let (mut ctx, session_user, role, _lang) = generate_basic_context(id, &lang, req.uri().path());
+
+    match Person::find(person_id) {
+        Ok(person) => {
+            if !can_edit_person(&person, &session_user, &role) {
+                let err = CustomError::new(
+                    406,
+                    "Not authorized".to_string(),
+                );
+                println!("{}", &err);
+                return err.error_response()
+            };
+
+            ctx.insert("can_edit_names", &can_edit_names(&person, &role));
+            ctx.insert("person", &person);
+
+            let rendered = data.tmpl.render("persons/edit_person.html", &ctx).unwrap();
+            HttpResponse::Ok().body(rendered)
+        },
+        Err(err) => {
+            println!("{}", &err);
+            err.error_response()
+        },
+    }
+}
+
+#[post("/{lang}/edit_person/{id}")]
+pub async fn edit_person_post(
+    web::Path((lang, person_id)): web::Path<(String, Uuid)>,
+    _data: web::Data<AppData>,
+    _req: HttpRequest,
+    form: web::Form<PersonForm>,
+    id: Identity,
+) -> impl Responder {
+
+    let (session_user, role) = extract_identity_data(&id);
+
+    let blank = |name: &Option<String>| name.as_ref().map_or(false, |n| n.trim().is_empty());
+
+    if blank(&form.given_name) || blank(&form.family_name) {
+        return HttpResponse::Found().header("Location", format!("/{}/edit_person/{}", &lang, &person_id)).finish()
+    };
+
+    let orcid = match form.orcid.trim() {
+        "" => None,
+        o => match parse_orcid(o) {
+            Ok(o) => Some(o),
+            Err(err) => {
+                println!("{}", &err);
+                return HttpResponse::Found().header("Location", format!("/{}/edit_person/{}", &lang, &person_id)).finish()
+            },
+        },
+    };
+
+    // Absent for non-admins, blank to clear the date
+    let retirement_eligible_on = match form.retirement_eligible_on.as_deref().map(str::trim) {
+        None => None,
+        Some("") => Some(None),
+        Some(d) => match chrono::NaiveDate::parse_from_str(d, "%Y-%m-%d") {
+            Ok(d) => Some(Some(d)),
+            Err(err) => {
+                println!("{}", &err);
+                return HttpResponse::Found().header("Location", format!("/{}/edit_person/{}", &lang, &person_id)).finish()
+            },
+        },
+    };
+
+    match Person::find(person_id) {
+        Ok(mut person) => {
+            if !can_edit_person(&person, &session_user, &role) {
+                let err = CustomError::new(
+                    406,
+                    "Not authorized".to_string(),
+                );
+                println!("{}", &err);
+                return err.error_response()
+            };
+
+            if can_edit_names(&person, &role) {
+                if let (Some(given_name), Some(family_name)) = (&form.given_name, &form.family_name) {
+                    person.given_name = given_name.trim().to_owned();
+                    person.family_name = family_name.trim().to_owned();
+                };
+            };
+            person.orcid = orcid;
+            person.languages = parse_languages(&form.languages);
+            person.first_official_language = form.first_official_language.parse::<OfficialLanguage>()
+                .ok()
+                .map(|l| l.to_string());
+            person.location = Some(form.location.trim().to_owned()).filter(|l| !l.is_empty());
+
+            if role == "admin".to_string() {
+                if let Some(date) = retirement_eligible_on {
+                    person.retirement_eligible_on = date;
+                };
+            };
+            person.updated_at = chrono::Utc::now().naive_utc();
+
+            match person.update() {
+                Ok(person) => {
+                    println!("Person {} updated", &person.id);
+                    HttpResponse::Found().header("Location", format!("/{}/person/{}", &lang, &person.id)).finish()
+                },
+                Err(err) => {
+                    println!("{:?}", err);
+                    HttpResponse::Found().header("Location", "/database_error").finish()
+                },
+            }
+        },
+        Err(err) => {
+            println!("{}", &err);
+            err.error_response()
+        },
+    }
+}
+
+#[post("/{lang}/import_orcid/{id}")]
+pub async fn import_orcid_post(
+    web::Path((lang, person_id)): web::Path<(String, Uuid)>,
+    data: web::Data<AppData>,
+
+    req: HttpRequest,
+    id: Identity,
+) -> impl Responder {
+
+    let (mut ctx, session_user, role, _lang) = generate_basic_context(id, &lang, req.uri().path());
+
+    let person = match Person::find(person_id) {
+        Ok(p) => p,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    if !can_edit_person(&person, &session_user, &role) {
+        let err = CustomError::new(
+            406,
+            "Not authorized".to_string(),
+        );
+        println!("{}", &err);
+        return err.error_response()
+    };
+
+    // The ORCID API call blocks, so run the import off the async executor
+    let importing = person.clone();
+    let res = web::block(move || {
+        let conn = connection()?;
+        import_orcid(&conn, &HttpOrcidClient::from_env(), &importing)
+    }).await;
+
+    match res {
+        Ok(report) => {
+            ctx.insert("person", &person);
+            ctx.insert("report", &report);
+
+            let rendered = data.tmpl.render("persons/orcid_import.html", &ctx).unwrap();
+            HttpResponse::Ok().body(rendered)
+        },
+        Err(BlockingError::Error(err)) => {
+            println!("{}", &err);
+            err.error_response()
+        },
+        Err(BlockingError::Canceled) => {
+            println!("ORCID import for {} canceled", &person.id);
+            HttpResponse::Found().header("Location", "/internal_server_error").finish()
+        },
+    }
+}
+
+#[get("/{lang}/admin_link_person/{slug}")]
+pub async fn admin_link_person(
+    web::Path((lang, slug)): web::Path<(String, String)>,
+    data: web::Data<AppData>,
+
+    req: HttpRequest,
+    id: Identity,
+) -> impl Responder {
+
+    let (mut ctx, _session_user, role, _lang) = generate_basic_context(id, &lang, req.uri().path());
+
+    if &role != "admin" {
+        let err = CustomError::new(
+            406,
+            "Not authorized".to_string(),
+        );
+        println!("{}", &err);
+        return err.error_response()
+    };
+
+    let user = match User::find_from_slug(&slug) {
+        Ok(u) => u,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    let linked_person = Person::find_by_user_id(user.id).ok();
+
+    let unlinked_persons = match Person::find_unlinked() {
+        Ok(p) => p,
+        Err(e) => {
+            println!("{:?}", e);
+            Vec::new()
+        }
+    };
+
+    ctx.insert("user", &user);
+    ctx.insert("linked_person", &linked_person);
+    ctx.insert("unlinked_persons", &unlinked_persons);
+
+    let rendered = data.tmpl.render("persons/admin_link_person.html", &ctx).unwrap();
+    HttpResponse::Ok().body(rendered)
+}
+
+#[post("/{lang}/admin_link_person/{slug}")]
+pub async fn admin_link_person_post(
+    web::Path((lang, slug)): web::Path<(String, String)>,
+    _data: web::Data<AppData>,
+    _req: HttpRequest,
+    form: web::Form<LinkPersonForm>,
+    id: Identity,
+) -> impl Responder {
+
+    let (_session_user, role) = extract_identity_data(&id);
+
+    if &role != "admin" {
+        let err = CustomError::new(
+            406,
+            "Not authorized".to_string(),
+        );
+        println!("{}", &err);
+        return err.error_response()
+    };
+
+    let user = match User::find_from_slug(&slug) {
+        Ok(u) => u,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    // Unlink any person currently linked to the user
+    if let Ok(person) = Person::find_by_user_id(user.id) {
+        if let Err(err) = person.set_user(None) {
+            println!("{}", &err);
+            return err.error_response()
+        };
+    };
+
+    if form.person_id != "none" {
+        let person = Uuid::parse_str(&form.person_id)
+            .map_err(|e| CustomError::new(400, format!("Invalid person id: {}", e)))
+            .and_then(Person::find)
+            .and_then(|p| p.set_user(Some(user.id)));
+
+        if let Err(err) = person {
+            println!("{}", &err);
+            return err.error_response()
+        };
+    };
+
+    HttpResponse::Found().header("Location", format!("/{}/user/{}", &lang, &user.slug)).finish()
+}
+
+#[post("/{lang}/validate_capability/{id}")]
+pub async fn validate_capability_post(
+    web::Path((lang, capability_id)): web::Path<(String, Uuid)>,
+    _data: web::Data<AppData>,
+    _req: HttpRequest,
+    form: web::Form<ValidationForm>,
+    id: Identity,
+) -> impl Responder {
+
+    let (session_user, _role) = extract_identity_data(&id);
+
+    let capability = match Capability::find(capability_id) {
+        Ok(c) => c,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    let validator = match session_person(&session_user) {
+        Some(p) if p.id != capability.person_id => p,
+        _ => {
+            let err = CustomError::new(
+                406,
+                "Not authorized".to_string(),
+            );
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    let conn = match connection() {
+        Ok(c) => c,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    let validation = NewValidation::new(validator.id, capability.id, CapabilityLevel::from_i32(form.level));
+
+    match Validation::create_or_update(&conn, &validation) {
+        Ok(_) => HttpResponse::Found().header("Location", format!("/{}/person/{}", &lang, &capability.person_id)).finish(),
+        Err(err) => {
+            println!("{:?}", err);
+            HttpResponse::Found().header("Location", "/database_error").finish()
+        },
+    }
+}
@@ -0,0 +1,424 @@
+use std::str::FromStr;
+
+use actix_web::{HttpRequest, HttpResponse, Responder, get, post, web, ResponseError};
+use actix_web::error::BlockingError;
+use actix_identity::{Identity};
+use actix_multipart::Multipart;
+use chrono::{Datelike, Utc};
+use futures::{StreamExt, TryStreamExt};
+use serde::{Deserialize};
+use uuid::Uuid;
+
+use crate::{AppData, extract_identity_data, generate_basic_context};
+use crate::handlers::find_organization;
+use crate::importer::{import_publications, parse_publications, HttpCrossrefClient, PublicationFormat};
+use crate::models::{AuthorMatch, Publication, PublicationFilter, PublicationOutput, PublicationType};
+use crate::database::connection;
+use crate::errors::CustomError;
+
+#[derive(Deserialize, Debug)]
+/// Blank form fields arrive as empty strings, so values are parsed here
+pub struct PublicationQuery {
+    pub topic: Option<String>,
+    pub year: Option<String>,
+    pub publication_type: Option<String>,
+}
+
+impl PublicationQuery {
+    pub fn filter(&self) -> PublicationFilter {
+        let non_empty = |v: &Option<String>| v.as_ref()
+            .map(|s| s.trim().to_owned())
+            .filter(|s| !s.is_empty());
+
+        PublicationFilter {
+            topic: non_empty(&self.topic),
+            year: non_empty(&self.year).and_then(|y| y.parse().ok()),
+            publication_type: non_empty(&self.publication_type),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct OutputQuery {
+    pub from_year: Option<i32>,
+    pub to_year: Option<i32>,
+}
+
+/// Fields collected from the multipart publication import form
+#[derive(Default)]
+struct PublicationUpload {
+    format: String,
+    dois: String,
+    dry_run: bool,
+    file: Vec<u8>,
+}
+
+impl PublicationUpload {
+    async fn from_multipart(mut payload: Multipart) -> Result<Self, CustomError> {
+        let mut upload = PublicationUpload::default();
+
+        while let Some(mut field) = payload.try_next().await
+            .map_err(|e| CustomError::new(400, format!("Invalid upload: {}", e)))? {
+
+            let name = field.content_disposition()
+                .and_then(|cd| cd.get_name().map(|n| n.to_owned()))
+                .unwrap_or_default();
+
+            let mut bytes = Vec::new();
+            while let Some(chunk) = field.next().await {
+                let chunk = chunk.map_err(|e| CustomError::new(400, format!("Invalid upload: {}", e)))?;
+                bytes.extend_from_slice(&chunk);
+            };
+
+            match name.as_str() {
+                "format" => upload.format = String::from_utf8_lossy(&bytes).trim().to_owned(),
+                "dois" => upload.dois = String::from_utf8_lossy(&bytes).to_string(),
+                "dry_run" => upload.dry_run = true,
+                "file" => upload.file = bytes,
+                _ => (),
+            };
+        };
+
+        Ok(upload)
+    }
+
+    /// Pasted DOIs are used when no file was chosen
+    fn input(&self) -> String {
+        if self.file.is_empty() {
+            self.dois.clone()
+        } else {
+            String::from_utf8_lossy(&self.file).to_string()
+        }
+    }
+}
+
+#[get("/{lang}/publications")]
+pub async fn publication_index(
+    data: web::Data<AppData>,
+    web::Path(lang): web::Path<String>,
+    query: web::Query<PublicationQuery>,
+
+    id: Identity,
+    req: HttpRequest,
+) -> impl Responder {
+
+    let (mut ctx, session_user, _role, _lang) = generate_basic_context(id, &lang, req.uri().path());
+
+    if session_user == "" {
+        let err = CustomError::new(
+            406,
+            "Not authorized".to_string(),
+        );
+        println!("{}", &err);
+        return err.error_response()
+    };
+
+    let conn = match connection() {
+        Ok(c) => c,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    let filter = query.filter();
+
+    let publications = match Publication::find_filtered(&conn, &filter) {
+        Ok(p) => p,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    let publication_types: Vec<String> = PublicationType::ALL.iter().map(|t| t.to_string()).collect();
+
+    ctx.insert("publications", &publications);
+    ctx.insert("publication_types", &publication_types);
+    ctx.insert("topic", &filter.topic);
+    ctx.insert("year", &filter.year);
+    ctx.insert("publication_type", &filter.publication_type);
+
+    let rendered = data.tmpl.render("publications/publication_index.html", &ctx).unwrap();
+    HttpResponse::Ok().body(rendered)
+}
+
+#[get("/{lang}/publication/{id}")]
+pub async fn publication_page(
+    data: web::Data<AppData>,
+    web::Path((lang, publication_id)): web::Path<(String, Uuid)>,
+
+    id: Identity,
+    req: HttpRequest,
+) -> impl Responder {
+
+    let (mut ctx, session_user, _role, _lang) = generate_basic_context(id, &lang, req.uri().path());
+
+    if session_user == "" {
+        let err = CustomError::new(
+            406,
+            "Not authorized".to_string(),
+        );
+        println!("{}", &err);
+        return err.error_response()
+    };
+
+    let conn = match connection() {
+        Ok(c) => c,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    let publication = Publication::find(&conn, publication_id)
+        .and_then(|p| p.with_authors(&conn));
+
+    match publication {
+        Ok(publication) => {
+            ctx.insert("publication", &publication);
+
+            let rendered = data.tmpl.render("publications/publication_page.html", &ctx).unwrap();
+            HttpResponse::Ok().body(rendered)
+        },
+        Err(err) => {
+            println!("{}", &err);
+            err.error_response()
+        },
+    }
+}
+
+#[get("/{lang}/publication_output/{organization}")]
+pub async fn publication_output(
+    data: web::Data<AppData>,
+    web::Path((lang, organization)): web::Path<(String, String)>,
+    query: web::Query<OutputQuery>,
+
+    id: Identity,
+    req: HttpRequest,
+) -> impl Responder {
+
+    let (mut ctx, session_user, _role, _lang) = generate_basic_context(id, &lang, req.uri().path());
+
+    if session_user == "" {
+        let err = CustomError::new(
+            406,
+            "Not authorized".to_string(),
+        );
+        println!("{}", &err);
+        return err.error_response()
+    };
+
+    let organization = match find_organization(&organization) {
+        Ok(o) => o,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    let conn = match connection() {
+        Ok(c) => c,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    // Default to the last five years, and cap the range at twenty
+    let to_year = query.to_year.unwrap_or_else(|| Utc::now().year());
+    let from_year = query.from_year.unwrap_or(to_year - 4).max(to_year - 19).min(to_year);
+
+    let output = match PublicationOutput::build(&conn, organization.id, from_year, to_year) {
+        Ok(o) => o,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    ctx.insert("organization", &organization);
+    ctx.insert("output", &output);
+    ctx.insert("from_year", &from_year);
+    ctx.insert("to_year", &to_year);
+
+    let rendered = data.tmpl.render("publications/publication_output.html", &ctx).unwrap();
+    HttpResponse::Ok().body(rendered)
+}
+
+#[get("/{lang}/publication_import")]
+pub async fn publication_import_page(
+    data: web::Data<AppData>,
+    web::Path(lang): web::Path<String>,
+
+    id: Identity,
+    req: HttpRequest,
+) -> impl Responder {
+
+    let (mut ctx, _session_user, role, _lang) = generate_basic_context(id, &lang, req.uri().path());
+
+    if role != "admin".to_string() {
+        let err = CustomError::new(
+            406,
+            "Not authorized".to_string(),
+        );
+        println!("{}", &err);
+        return err.error_response()
+    };
+
+    let formats: Vec<String> = PublicationFormat::ALL.iter().map(|f| f.to_string()).collect();
+
+    ctx.insert("formats", &formats);
+
+    let rendered = data.tmpl.render("publications/publication_import.html", &ctx).unwrap();
+    HttpResponse::Ok().body(rendered)
+}
+
+#[post("/{lang}/publication_import")]
+pub async fn publication_import_post(
+    data: web::Data<AppData>,
+    web::Path(lang): web::Path<String>,
+    payload: Multipart,
+
+    id: Identity,
+    req: HttpRequest,
+) -> impl Responder {
+
+    let (mut ctx, _session_user, role, _lang) = generate_basic_context(id, &lang, req.uri().path());
+
+    if role != "admin".to_string() {
+        let err = CustomError::new(
+            406,
+            "Not authorized".to_string(),
+        );
+        println!("{}", &err);
+        return err.error_response()
+    };
+
+    let upload = match PublicationUpload::from_multipart(payload).await {
+        Ok(u) => u,
+        Err(err) => {
+            println!("{}", &err);
+            return HttpResponse::Found().header("Location", format!("/{}/publication_import", &lang)).finish()
+        },
+    };
+
+    // DOI lists are looked up on Crossref, which blocks
+    let res = web::block(move || {
+        let format = PublicationFormat::from_str(&upload.format)?;
+        let entries = parse_publications(format, &upload.input(), &HttpCrossrefClient::from_env())?;
+        let conn = connection()?;
+        import_publications(&conn, format, entries, upload.dry_run)
+    }).await;
+
+    match res {
+        Ok(report) => ctx.insert("report", &report),
+        Err(BlockingError::Error(err)) => {
+            println!("{}", &err);
+            ctx.insert("import_error", &err.error_message);
+        },
+        Err(BlockingError::Canceled) => {
+            println!("Publication import canceled");
+            return HttpResponse::Found().header("Location", "/internal_server_error").finish()
+        },
+    };
+
+    let rendered = data.tmpl.render("publications/publication_import_report.html", &ctx).unwrap();
+    HttpResponse::Ok().body(rendered)
+}
+
+#[get("/{lang}/author_matches")]
+pub async fn author_match_index(
+    data: web::Data<AppData>,
+    web::Path(lang): web::Path<String>,
+
+    id: Identity,
+    req: HttpRequest,
+) -> impl Responder {
+
+    let (mut ctx, _session_user, role, _lang) = generate_basic_context(id, &lang, req.uri().path());
+
+    if role != "admin".to_string() {
+        let err = CustomError::new(
+            406,
+            "Not authorized".to_string(),
+        );
+        println!("{}", &err);
+        return err.error_response()
+    };
+
+    let conn = match connection() {
+        Ok(c) => c,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    match AuthorMatch::find_pending(&conn) {
+        Ok(matches) => {
+            ctx.insert("matches", &matches);
+
+            let rendered = data.tmpl.render("publications/author_matches.html", &ctx).unwrap();
+            HttpResponse::Ok().body(rendered)
+        },
+        Err(err) => {
+            println!("{}", &err);
+            err.error_response()
+        },
+    }
+}
+
+/// decision is "accept" (link the author to the person) or "reject"
+#[post("/{lang}/author_match/{id}/{decision}")]
+pub async fn author_match_resolve(
+    web::Path((lang, match_id, decision)): web::Path<(String, Uuid, String)>,
+
+    id: Identity,
+    _req: HttpRequest,
+) -> impl Responder {
+
+    let (_session_user, role) = extract_identity_data(&id);
+
+    if role != "admin".to_string() {
+        let err = CustomError::new(
+            406,
+            "Not authorized".to_string(),
+        );
+        println!("{}", &err);
+        return err.error_response()
+    };
+
+    let conn = match connection() {
+        Ok(c) => c,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    let author_match = match AuthorMatch::find(&conn, match_id) {
+        Ok(m) => m,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    let res = match decision.as_str() {
+        "accept" => author_match.accept(&conn),
+        "reject" => author_match.reject(&conn),
+        _ => return HttpResponse::Found().header("Location", "/not_found").finish(),
+    };
+
+    match res {
+        Ok(author_match) => {
+            println!("Author match {} resolved as {}", &author_match.id, &author_match.status);
+            HttpResponse::Found().header("Location", format!("/{}/author_matches", &lang)).finish()
+        },
+        Err(err) => {
+            println!("{}", &err);
+            err.error_response()
+        },
+    }
+}
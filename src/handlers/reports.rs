@@ -0,0 +1,116 @@
+use actix_web::{HttpRequest, HttpResponse, Responder, get, web, ResponseError};
+use actix_identity::{Identity};
+use chrono::Utc;
+use serde::{Deserialize};
+use uuid::Uuid;
+
+use crate::{AppData, generate_basic_context};
+use crate::models::{TeamOwnership, OrgOwnership, Organization, SuccessionReport, DEFAULT_RETIREMENT_HORIZON_MONTHS};
+use crate::database::connection;
+use crate::errors::CustomError;
+
+#[derive(Deserialize, Debug)]
+pub struct SuccessionQuery {
+    pub organization: Option<String>,
+    /// Months ahead to look for retirement eligibility
+    pub months: Option<u32>,
+}
+
+#[get("/{lang}/ownership_report")]
+pub async fn ownership_report(
+    data: web::Data<AppData>,
+    web::Path(lang): web::Path<String>,
+    
+    id: Identity,
+    req:HttpRequest) -> impl Responder {
+
+    let (mut ctx, _session_user, role, _lang) = generate_basic_context(id, &lang, req.uri().path());
+
+    if role != "admin".to_string() {
+        let err = CustomError::new(
+            406,
+            "Not authorized".to_string(),
+        );
+        println!("{}", &err);
+        return err.error_response()
+    };
+
+    let conn = match connection() {
+        Ok(c) => c,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    let now = Utc::now().naive_utc();
+
+    let team_issues = match TeamOwnership::validate_ownership(&conn, now) {
+        Ok(i) => i,
+        Err(e) => {
+            println!("{:?}", e);
+            Vec::new()
+        }
+    };
+
+    let org_tier_issues = match OrgOwnership::validate_ownership(now) {
+        Ok(i) => i,
+        Err(e) => {
+            println!("{:?}", e);
+            Vec::new()
+        }
+    };
+
+    ctx.insert("team_issues", &team_issues);
+    ctx.insert("org_tier_issues", &org_tier_issues);
+
+    let rendered = data.tmpl.render("reports/ownership_report.html", &ctx).unwrap();
+    HttpResponse::Ok().body(rendered)
+}
+
+#[get("/{lang}/succession_report")]
+pub async fn succession_report(
+    data: web::Data<AppData>,
+    web::Path(lang): web::Path<String>,
+    query: web::Query<SuccessionQuery>,
+
+    id: Identity,
+    req: HttpRequest,
+) -> impl Responder {
+
+    let (mut ctx, _session_user, role, _lang) = generate_basic_context(id, &lang, req.uri().path());
+
+    if role != "admin".to_string() {
+        let err = CustomError::new(
+            406,
+            "Not authorized".to_string(),
+        );
+        println!("{}", &err);
+        return err.error_response()
+    };
+
+    let conn = match connection() {
+        Ok(c) => c,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    let organization_id = query.organization.as_ref().and_then(|o| Uuid::parse_str(o).ok());
+    let months = query.months.unwrap_or(DEFAULT_RETIREMENT_HORIZON_MONTHS);
+
+    let report = match SuccessionReport::build(&conn, organization_id, months, Utc::now().naive_utc()) {
+        Ok(r) => r,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    ctx.insert("report", &report);
+    ctx.insert("organizations", &Organization::find_all().unwrap_or_default());
+
+    let rendered = data.tmpl.render("reports/succession_report.html", &ctx).unwrap();
+    HttpResponse::Ok().body(rendered)
+}
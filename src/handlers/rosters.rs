@@ -0,0 +1,578 @@
+use std::str::FromStr;
+
+use actix_web::{HttpRequest, HttpResponse, Responder, get, post, web, ResponseError};
+use actix_identity::{Identity};
+use chrono::{NaiveDate, NaiveDateTime, Utc};
+use diesel::PgConnection;
+use serde::{Deserialize};
+use uuid::Uuid;
+
+use crate::{AppData, extract_identity_data, generate_basic_context};
+use crate::models::{CapabilityLevel, NewRole, NewRoster, NewRosterMember, NewRosterSkill, Organization, Person,
+    Roster, RosterMember, RosterSearch, RosterSkill, RoleType, Skill, Team, TeamOwnership, User};
+use crate::database::connection;
+use crate::errors::CustomError;
+
+#[derive(Deserialize, Debug)]
+pub struct RosterForm {
+    name_en: String,
+    name_fr: String,
+    description_en: String,
+    description_fr: String,
+    organization_id: Uuid,
+    /// e.g. EC-05, blank for a skill set roster
+    classification: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct RosterSkillForm {
+    skill_id: Uuid,
+    min_level: i32,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct RosterMemberForm {
+    person_id: Uuid,
+    qualified_at: String,
+    /// Blank if the qualification doesn't expire
+    expires_at: String,
+    note: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct PlacementForm {
+    team_id: Uuid,
+    title_en: String,
+    title_fr: String,
+    effort: f64,
+    /// substantive, acting or secondment
+    role_type: String,
+    start_date: String,
+    /// Required for acting and secondment
+    end_date: String,
+    note: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct RosterSearchQuery {
+    pub roster: Option<String>,
+    pub classification: Option<String>,
+    pub skill: Option<String>,
+    pub min_level: Option<i32>,
+    pub language: Option<String>,
+    pub location: Option<String>,
+    pub include_expired: Option<String>,
+}
+
+impl RosterSearchQuery {
+    pub fn search(&self) -> RosterSearch {
+        let text = |value: &Option<String>| value.as_ref()
+            .map(|v| v.trim().to_owned())
+            .filter(|v| !v.is_empty());
+
+        RosterSearch {
+            roster_id: self.roster.as_ref().and_then(|r| Uuid::parse_str(r).ok()),
+            classification: text(&self.classification),
+            skill_id: self.skill.as_ref().and_then(|s| Uuid::parse_str(s).ok()),
+            min_level: self.min_level.unwrap_or(0),
+            language: text(&self.language),
+            location: text(&self.location),
+            include_expired: self.include_expired.is_some(),
+        }
+    }
+}
+
+/// Start of the day of a YYYY-MM-DD form date
+fn parse_date(date: &str) -> Option<NaiveDateTime> {
+    NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d")
+        .ok()
+        .map(|d| d.and_hms(0, 0, 0))
+}
+
+/// Admins can place people on any team, managers on teams they own
+fn can_place(conn: &PgConnection, team_id: Uuid, session_user: &String, role: &String) -> bool {
+    if role == "admin" {
+        return true
+    };
+
+    let person = match User::find_id_from_slug(session_user).and_then(Person::find_by_user_id) {
+        Ok(p) => p,
+        Err(_) => return false,
+    };
+
+    TeamOwnership::find_current_by_team(conn, team_id, Utc::now().naive_utc())
+        .map(|owners| owners.iter().any(|o| o.person_id == person.id))
+        .unwrap_or(false)
+}
+
+#[get("/{lang}/rosters")]
+pub async fn roster_index(
+    data: web::Data<AppData>,
+    web::Path(lang): web::Path<String>,
+
+    id: Identity,
+    req: HttpRequest,
+) -> impl Responder {
+
+    let (mut ctx, session_user, _role, _lang) = generate_basic_context(id, &lang, req.uri().path());
+
+    if session_user == "" {
+        let err = CustomError::new(
+            406,
+            "Not authorized".to_string(),
+        );
+        println!("{}", &err);
+        return err.error_response()
+    };
+
+    let conn = match connection() {
+        Ok(c) => c,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    let rosters = match Roster::find_active(&conn) {
+        Ok(r) => r,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    let levels: Vec<(i32, &str)> = (0..=4)
+        .map(|l| (l, CapabilityLevel::from_i32(l).key()))
+        .collect();
+
+    ctx.insert("rosters", &rosters);
+    ctx.insert("search", &RosterSearch::default());
+    ctx.insert("organizations", &Organization::find_all().unwrap_or_default());
+    ctx.insert("skills", &Skill::find_all(&conn).unwrap_or_default());
+    ctx.insert("levels", &levels);
+
+    let rendered = data.tmpl.render("rosters/roster_index.html", &ctx).unwrap();
+    HttpResponse::Ok().body(rendered)
+}
+
+#[post("/{lang}/create_roster")]
+pub async fn create_roster_post(
+    web::Path(lang): web::Path<String>,
+    _data: web::Data<AppData>,
+    _req: HttpRequest,
+    form: web::Form<RosterForm>,
+    id: Identity,
+) -> impl Responder {
+
+    let (_session_user, role) = extract_identity_data(&id);
+
+    if role != "admin".to_string() {
+        let err = CustomError::new(
+            406,
+            "Not authorized".to_string(),
+        );
+        println!("{}", &err);
+        return err.error_response()
+    };
+
+    if form.name_en.trim().is_empty() || form.name_fr.trim().is_empty() {
+        return HttpResponse::Found().header("Location", format!("/{}/rosters", &lang)).finish()
+    };
+
+    let conn = match connection() {
+        Ok(c) => c,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    let new_roster = NewRoster::new(
+        form.name_en.trim().to_owned(),
+        form.name_fr.trim().to_owned(),
+        form.description_en.trim().to_owned(),
+        form.description_fr.trim().to_owned(),
+        form.organization_id,
+        Some(form.classification.clone()),
+    );
+
+    match Roster::create(&conn, &new_roster) {
+        Ok(roster) => {
+            println!("Roster {} created", &roster.name_en);
+            HttpResponse::Found().header("Location", format!("/{}/roster/{}", &lang, &roster.id)).finish()
+        },
+        Err(err) => {
+            println!("{:?}", err);
+            HttpResponse::Found().header("Location", "/database_error").finish()
+        },
+    }
+}
+
+#[get("/{lang}/roster/{id}")]
+pub async fn roster_page(
+    data: web::Data<AppData>,
+    web::Path((lang, roster_id)): web::Path<(String, Uuid)>,
+
+    id: Identity,
+    req: HttpRequest,
+) -> impl Responder {
+
+    let (mut ctx, session_user, role, _lang) = generate_basic_context(id, &lang, req.uri().path());
+
+    if session_user == "" {
+        let err = CustomError::new(
+            406,
+            "Not authorized".to_string(),
+        );
+        println!("{}", &err);
+        return err.error_response()
+    };
+
+    let conn = match connection() {
+        Ok(c) => c,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    let now = Utc::now().naive_utc();
+
+    let profile = match Roster::find(&conn, roster_id).and_then(|r| r.profile(&conn, now)) {
+        Ok(p) => p,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    if role == "admin" {
+        let levels: Vec<(i32, &str)> = (0..=4)
+            .map(|l| (l, CapabilityLevel::from_i32(l).key()))
+            .collect();
+
+        ctx.insert("persons", &Person::find_all().unwrap_or_default());
+        ctx.insert("skills", &Skill::find_all(&conn).unwrap_or_default());
+        ctx.insert("levels", &levels);
+    };
+
+    // Managers only see the teams they can place people on
+    let teams: Vec<Team> = Team::find_all()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|t| t.retired_at.is_none() && can_place(&conn, t.id, &session_user, &role))
+        .collect();
+
+    ctx.insert("profile", &profile);
+    ctx.insert("teams", &teams);
+    ctx.insert("today", &now.format("%Y-%m-%d").to_string());
+
+    let rendered = data.tmpl.render("rosters/roster_page.html", &ctx).unwrap();
+    HttpResponse::Ok().body(rendered)
+}
+
+#[post("/{lang}/roster_skill/{id}")]
+pub async fn roster_skill_post(
+    web::Path((lang, roster_id)): web::Path<(String, Uuid)>,
+    _data: web::Data<AppData>,
+    _req: HttpRequest,
+    form: web::Form<RosterSkillForm>,
+    id: Identity,
+) -> impl Responder {
+
+    let (_session_user, role) = extract_identity_data(&id);
+
+    if role != "admin".to_string() {
+        let err = CustomError::new(
+            406,
+            "Not authorized".to_string(),
+        );
+        println!("{}", &err);
+        return err.error_response()
+    };
+
+    let conn = match connection() {
+        Ok(c) => c,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    let roster_skill = NewRosterSkill::new(roster_id, form.skill_id, CapabilityLevel::from_i32(form.min_level));
+
+    match RosterSkill::create_or_update(&conn, &roster_skill) {
+        Ok(_) => HttpResponse::Found().header("Location", format!("/{}/roster/{}", &lang, &roster_id)).finish(),
+        Err(err) => {
+            println!("{:?}", err);
+            HttpResponse::Found().header("Location", "/database_error").finish()
+        },
+    }
+}
+
+#[post("/{lang}/delete_roster_skill/{id}")]
+pub async fn delete_roster_skill_post(
+    web::Path((lang, roster_skill_id)): web::Path<(String, Uuid)>,
+
+    id: Identity,
+    _req: HttpRequest,
+) -> impl Responder {
+
+    let (_session_user, role) = extract_identity_data(&id);
+
+    if role != "admin".to_string() {
+        let err = CustomError::new(
+            406,
+            "Not authorized".to_string(),
+        );
+        println!("{}", &err);
+        return err.error_response()
+    };
+
+    let conn = match connection() {
+        Ok(c) => c,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    match RosterSkill::delete(&conn, roster_skill_id) {
+        Ok(roster_skill) => HttpResponse::Found()
+            .header("Location", format!("/{}/roster/{}", &lang, &roster_skill.roster_id))
+            .finish(),
+        Err(err) => {
+            println!("{}", &err);
+            err.error_response()
+        },
+    }
+}
+
+#[post("/{lang}/roster_member/{id}")]
+pub async fn roster_member_post(
+    web::Path((lang, roster_id)): web::Path<(String, Uuid)>,
+    _data: web::Data<AppData>,
+    _req: HttpRequest,
+    form: web::Form<RosterMemberForm>,
+    id: Identity,
+) -> impl Responder {
+
+    let (_session_user, role) = extract_identity_data(&id);
+
+    if role != "admin".to_string() {
+        let err = CustomError::new(
+            406,
+            "Not authorized".to_string(),
+        );
+        println!("{}", &err);
+        return err.error_response()
+    };
+
+    let qualified_at = match parse_date(&form.qualified_at) {
+        Some(d) => d,
+        None => return HttpResponse::Found().header("Location", format!("/{}/roster/{}", &lang, &roster_id)).finish(),
+    };
+
+    let expires_at = parse_date(&form.expires_at);
+
+    if expires_at.map_or(false, |e| e <= qualified_at) {
+        return HttpResponse::Found().header("Location", format!("/{}/roster/{}", &lang, &roster_id)).finish()
+    };
+
+    let conn = match connection() {
+        Ok(c) => c,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    let member = NewRosterMember::new(roster_id, form.person_id, qualified_at, expires_at, form.note.trim().to_owned());
+
+    match RosterMember::qualify(&conn, &member) {
+        Ok(_) => HttpResponse::Found().header("Location", format!("/{}/roster/{}", &lang, &roster_id)).finish(),
+        Err(err) => {
+            println!("{:?}", err);
+            HttpResponse::Found().header("Location", "/database_error").finish()
+        },
+    }
+}
+
+#[post("/{lang}/remove_roster_member/{id}")]
+pub async fn remove_roster_member_post(
+    web::Path((lang, member_id)): web::Path<(String, Uuid)>,
+
+    id: Identity,
+    _req: HttpRequest,
+) -> impl Responder {
+
+    let (_session_user, role) = extract_identity_data(&id);
+
+    if role != "admin".to_string() {
+        let err = CustomError::new(
+            406,
+            "Not authorized".to_string(),
+        );
+        println!("{}", &err);
+        return err.error_response()
+    };
+
+    let conn = match connection() {
+        Ok(c) => c,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    let member = match RosterMember::find(&conn, member_id) {
+        Ok(m) => m,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    match member.remove(&conn) {
+        Ok(member) => HttpResponse::Found().header("Location", format!("/{}/roster/{}", &lang, &member.roster_id)).finish(),
+        Err(err) => {
+            println!("{:?}", err);
+            HttpResponse::Found().header("Location", "/database_error").finish()
+        },
+    }
+}
+
+#[get("/{lang}/roster_search")]
+pub async fn roster_search(
+    data: web::Data<AppData>,
+    web::Path(lang): web::Path<String>,
+    query: web::Query<RosterSearchQuery>,
+
+    id: Identity,
+    req: HttpRequest,
+) -> impl Responder {
+
+    let (mut ctx, session_user, _role, _lang) = generate_basic_context(id, &lang, req.uri().path());
+
+    if session_user == "" {
+        let err = CustomError::new(
+            406,
+            "Not authorized".to_string(),
+        );
+        println!("{}", &err);
+        return err.error_response()
+    };
+
+    let conn = match connection() {
+        Ok(c) => c,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    let search = query.search();
+
+    let matches = match Roster::search(&conn, &search, Utc::now().naive_utc()) {
+        Ok(m) => m,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    let levels: Vec<(i32, &str)> = (0..=4)
+        .map(|l| (l, CapabilityLevel::from_i32(l).key()))
+        .collect();
+
+    ctx.insert("matches", &matches);
+    ctx.insert("search", &search);
+    ctx.insert("rosters", &Roster::find_active(&conn).unwrap_or_default());
+    ctx.insert("skills", &Skill::find_all(&conn).unwrap_or_default());
+    ctx.insert("levels", &levels);
+
+    let rendered = data.tmpl.render("rosters/roster_search.html", &ctx).unwrap();
+    HttpResponse::Ok().body(rendered)
+}
+
+#[post("/{lang}/roster_place/{id}")]
+pub async fn roster_place_post(
+    web::Path((lang, member_id)): web::Path<(String, Uuid)>,
+    _data: web::Data<AppData>,
+    _req: HttpRequest,
+    form: web::Form<PlacementForm>,
+    id: Identity,
+) -> impl Responder {
+
+    let (session_user, role) = extract_identity_data(&id);
+
+    let conn = match connection() {
+        Ok(c) => c,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    if !can_place(&conn, form.team_id, &session_user, &role) {
+        let err = CustomError::new(
+            406,
+            "Not authorized".to_string(),
+        );
+        println!("{}", &err);
+        return err.error_response()
+    };
+
+    let (member, roster) = match RosterMember::find(&conn, member_id)
+        .and_then(|m| Roster::find(&conn, m.roster_id).map(|r| (m, r))) {
+        Ok(mr) => mr,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    let role_type = match RoleType::from_str(&form.role_type) {
+        Ok(t) => t,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    let start_date = match parse_date(&form.start_date) {
+        Some(d) if form.effort > 0.0 && form.effort <= 1.0 && !form.title_en.trim().is_empty() => d,
+        _ => return HttpResponse::Found().header("Location", format!("/{}/roster/{}", &lang, &roster.id)).finish(),
+    };
+
+    let title_en = form.title_en.trim().to_owned();
+    let title_fr = match form.title_fr.trim() {
+        "" => title_en.clone(),
+        t => t.to_owned(),
+    };
+
+    let new_role = NewRole::new(
+        Uuid::new_v4(),
+        member.person_id,
+        form.team_id,
+        title_en,
+        title_fr,
+        form.effort,
+        true,
+        start_date,
+        parse_date(&form.end_date),
+        role_type,
+        None,
+    );
+
+    match member.place(&conn, &roster, &new_role, role_type, form.note.trim()) {
+        Ok(placement) => {
+            println!("Person {} placed from roster {}", &placement.person_id, &roster.name_en);
+            HttpResponse::Found().header("Location", format!("/{}/roster/{}", &lang, &roster.id)).finish()
+        },
+        Err(err) => {
+            println!("{:?}", err);
+            HttpResponse::Found().header("Location", "/database_error").finish()
+        },
+    }
+}
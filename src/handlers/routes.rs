@@ -48,6 +48,125 @@ use crate::handlers::{
     delete_user,
     delete_user_handler,
 
+    // reports
+    ownership_report,
+    succession_report,
+
+    // organizations
+    org_chart,
+    organization_index,
+    organization_page,
+    create_organization,
+    create_organization_post,
+    edit_organization,
+    edit_organization_post,
+    org_tier_page,
+    create_org_tier,
+    create_org_tier_post,
+    edit_org_tier,
+    edit_org_tier_post,
+
+    // persons
+    person_page,
+    edit_person,
+    edit_person_post,
+    import_orcid_post,
+    admin_link_person,
+    admin_link_person_post,
+    validate_capability_post,
+
+    // teams
+    team_page,
+    create_team,
+    create_team_post,
+    edit_team,
+    edit_team_post,
+
+    // imports
+    import_page,
+    import_post,
+
+    // exports
+    export_report,
+
+    // hr sync
+    hr_sync_index,
+    hr_sync_run_page,
+    hr_sync_resolve,
+
+    // publications
+    publication_index,
+    publication_page,
+    publication_output,
+    publication_import_page,
+    publication_import_post,
+    author_match_index,
+    author_match_resolve,
+
+    // collaborations
+    collaboration_page,
+    collaborators_page,
+    collaboration_graph_export,
+
+    // surge
+    surge_index,
+    create_surge_event_post,
+    surge_event_page,
+    add_surge_requirement_post,
+    delete_surge_requirement_post,
+    surge_deploy_post,
+    surge_release_post,
+    close_surge_event_post,
+    deployment_fatigue_page,
+
+    // languages
+    language_evaluation_post,
+    delete_language_evaluation_post,
+    role_language_requirement_post,
+    language_report,
+
+    // rosters
+    roster_index,
+    create_roster_post,
+    roster_page,
+    roster_skill_post,
+    delete_roster_skill_post,
+    roster_member_post,
+    remove_roster_member_post,
+    roster_search,
+    roster_place_post,
+
+    // skill gaps
+    skill_gap_report,
+    work_skill_requirement_post,
+    delete_work_skill_requirement_post,
+    classification_skills_page,
+    classification_skill_post,
+    delete_classification_skill_post,
+
+    // workforce analytics
+    workforce_analytics,
+    refresh_workforce_metrics_post,
+
+    // graphql
+    graphql_post,
+    graphql_playground,
+
+    // search
+    search_page,
+
+    // expert finder
+    expert_finder,
+
+    // api tokens
+    api_tokens_page,
+    create_api_token_post,
+    revoke_api_token_post,
+
+    // single sign-on
+    oidc_login,
+    oidc_callback,
+
 };
 
 pub fn configure_services(config: &mut web::ServiceConfig) {
@@ -94,4 +213,123 @@ pub fn configure_services(config: &mut web::ServiceConfig) {
      config.service(edit_user_post);
      config.service(delete_user);
      config.service(delete_user_handler);
+
+     // reports
+     config.service(ownership_report);
+     config.service(succession_report);
+
+     // organizations
+     config.service(org_chart);
+     config.service(organization_index);
+     config.service(organization_page);
+     config.service(create_organization);
+     config.service(create_organization_post);
+     config.service(edit_organization);
+     config.service(edit_organization_post);
+     config.service(org_tier_page);
+     config.service(create_org_tier);
+     config.service(create_org_tier_post);
+     config.service(edit_org_tier);
+     config.service(edit_org_tier_post);
+
+     // persons
+     config.service(person_page);
+     config.service(edit_person);
+     config.service(edit_person_post);
+     config.service(import_orcid_post);
+     config.service(admin_link_person);
+     config.service(admin_link_person_post);
+     config.service(validate_capability_post);
+
+     // teams
+     config.service(team_page);
+     config.service(create_team);
+     config.service(create_team_post);
+     config.service(edit_team);
+     config.service(edit_team_post);
+
+    // imports
+    config.service(import_page);
+    config.service(import_post);
+
+    // exports
+    config.service(export_report);
+
+    // hr sync
+    config.service(hr_sync_index);
+    config.service(hr_sync_run_page);
+    config.service(hr_sync_resolve);
+
+    // publications
+    config.service(publication_index);
+    config.service(publication_page);
+    config.service(publication_output);
+    config.service(publication_import_page);
+    config.service(publication_import_post);
+    config.service(author_match_index);
+    config.service(author_match_resolve);
+
+    // collaborations
+    config.service(collaboration_page);
+    config.service(collaborators_page);
+    config.service(collaboration_graph_export);
+
+    // surge
+    config.service(surge_index);
+    config.service(create_surge_event_post);
+    config.service(surge_event_page);
+    config.service(add_surge_requirement_post);
+    config.service(delete_surge_requirement_post);
+    config.service(surge_deploy_post);
+    config.service(surge_release_post);
+    config.service(close_surge_event_post);
+    config.service(deployment_fatigue_page);
+
+    // languages
+    config.service(language_evaluation_post);
+    config.service(delete_language_evaluation_post);
+    config.service(role_language_requirement_post);
+    config.service(language_report);
+
+    // rosters
+    config.service(roster_index);
+    config.service(create_roster_post);
+    config.service(roster_page);
+    config.service(roster_skill_post);
+    config.service(delete_roster_skill_post);
+    config.service(roster_member_post);
+    config.service(remove_roster_member_post);
+    config.service(roster_search);
+    config.service(roster_place_post);
+
+    // skill gaps
+    config.service(skill_gap_report);
+    config.service(work_skill_requirement_post);
+    config.service(delete_work_skill_requirement_post);
+    config.service(classification_skills_page);
+    config.service(classification_skill_post);
+    config.service(delete_classification_skill_post);
+
+    // workforce analytics
+    config.service(workforce_analytics);
+    config.service(refresh_workforce_metrics_post);
+
+    // graphql
+    config.service(graphql_post);
+    config.service(graphql_playground);
+
+    // search
+    config.service(search_page);
+
+    // expert finder
+    config.service(expert_finder);
+
+    // api tokens
+    config.service(api_tokens_page);
+    config.service(create_api_token_post);
+    config.service(revoke_api_token_post);
+
+    // single sign-on
+    config.service(oidc_login);
+    config.service(oidc_callback);
 }
@@ -0,0 +1,84 @@
+use actix_web::{HttpRequest, HttpResponse, Responder, get, web, ResponseError};
+use actix_identity::{Identity};
+use serde::{Deserialize};
+use uuid::Uuid;
+
+use crate::{AppData, generate_basic_context};
+use crate::models::{SearchFilters, SearchResults};
+use crate::database::connection;
+use crate::errors::CustomError;
+
+#[derive(Deserialize, Debug)]
+pub struct SearchQuery {
+    pub q: Option<String>,
+    pub kind: Option<String>,
+    pub organization: Option<String>,
+    pub org_tier: Option<String>,
+    pub classification: Option<String>,
+    pub level: Option<String>, // CapabilityLevel
+}
+
+impl SearchQuery {
+    /// Blank form fields mean no filter
+    pub fn filters(&self) -> SearchFilters {
+        let present = |s: &Option<String>| s.as_ref()
+            .map(|s| s.trim().to_owned())
+            .filter(|s| !s.is_empty());
+
+        SearchFilters {
+            kind: present(&self.kind),
+            organization_id: self.organization.as_ref().and_then(|o| Uuid::parse_str(o).ok()),
+            org_tier_id: self.org_tier.as_ref().and_then(|t| Uuid::parse_str(t).ok()),
+            classification: present(&self.classification),
+            skill_level: self.level.as_ref().and_then(|l| l.parse::<i32>().ok()),
+        }
+    }
+}
+
+#[get("/{lang}/search")]
+pub async fn search_page(
+    data: web::Data<AppData>,
+    web::Path(lang): web::Path<String>,
+    query: web::Query<SearchQuery>,
+
+    id: Identity,
+    req: HttpRequest,
+) -> impl Responder {
+
+    let (mut ctx, session_user, _role, _lang) = generate_basic_context(id, &lang, req.uri().path());
+
+    if session_user == "" {
+        let err = CustomError::new(
+            406,
+            "Not authorized".to_string(),
+        );
+        println!("{}", &err);
+        return err.error_response()
+    };
+
+    let q = query.q.clone().unwrap_or_default();
+    let filters = query.filters();
+
+    let conn = match connection() {
+        Ok(c) => c,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    let results = match SearchResults::search(&conn, &q, &filters) {
+        Ok(r) => r,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    ctx.insert("q", &q);
+    ctx.insert("filters", &filters);
+    ctx.insert("results", &results);
+
+    let rendered = data.tmpl.render("search/search.html", &ctx).unwrap();
+    HttpResponse::Ok().body(rendered)
+}
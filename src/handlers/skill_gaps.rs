@@ -0,0 +1,306 @@
+use actix_web::{HttpRequest, HttpResponse, Responder, get, post, web, ResponseError};
+use actix_identity::{Identity};
+use serde::{Deserialize};
+use uuid::Uuid;
+
+use crate::{AppData, extract_identity_data, generate_basic_context};
+use crate::models::{CapabilityLevel, ClassificationSkill, NewClassificationSkill, NewWorkSkillRequirement,
+    OrgTier, Skill, SkillGapReport, Team, Work, WorkSkillRequirement};
+use crate::handlers::AsOfQuery;
+use crate::database::connection;
+use crate::errors::CustomError;
+
+#[derive(Deserialize, Debug)]
+pub struct SkillRequirementForm {
+    skill_id: Uuid,
+    level: i32,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ClassificationSkillForm {
+    /// e.g. EC-05
+    classification: String,
+    skill_id: Uuid,
+    level: i32,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct SkillGapQuery {
+    pub team: Option<String>,
+    pub org_tier: Option<String>,
+    pub as_of: Option<String>,
+}
+
+#[get("/{lang}/skill_gaps")]
+pub async fn skill_gap_report(
+    data: web::Data<AppData>,
+    web::Path(lang): web::Path<String>,
+    query: web::Query<SkillGapQuery>,
+
+    id: Identity,
+    req: HttpRequest,
+) -> impl Responder {
+
+    let (mut ctx, _session_user, role, _lang) = generate_basic_context(id, &lang, req.uri().path());
+
+    if role != "admin".to_string() {
+        let err = CustomError::new(
+            406,
+            "Not authorized".to_string(),
+        );
+        println!("{}", &err);
+        return err.error_response()
+    };
+
+    let conn = match connection() {
+        Ok(c) => c,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    let as_of = AsOfQuery { as_of: query.as_of.clone() }.as_of_datetime();
+
+    let team_id = query.team.as_ref().and_then(|t| Uuid::parse_str(t).ok());
+    let org_tier_id = query.org_tier.as_ref().and_then(|t| Uuid::parse_str(t).ok());
+
+    // A team is the narrower scope, so it wins if both are given
+    let report = match (team_id, org_tier_id) {
+        (Some(team_id), _) => Some(Team::find(team_id)
+            .and_then(|t| SkillGapReport::for_team(&conn, &t, as_of))),
+        (None, Some(org_tier_id)) => Some(OrgTier::find(org_tier_id)
+            .and_then(|t| SkillGapReport::for_org_tier(&conn, &t, as_of))),
+        (None, None) => None,
+    };
+
+    let report = match report.transpose() {
+        Ok(r) => r,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    ctx.insert("report", &report);
+    ctx.insert("team_id", &team_id);
+    ctx.insert("org_tier_id", &org_tier_id);
+    ctx.insert("as_of", &as_of.date().format("%Y-%m-%d").to_string());
+    ctx.insert("teams", &Team::find_all().unwrap_or_default());
+    ctx.insert("org_tiers", &OrgTier::find_all().unwrap_or_default());
+
+    let rendered = data.tmpl.render("skill_gaps/skill_gap_report.html", &ctx).unwrap();
+    HttpResponse::Ok().body(rendered)
+}
+
+#[post("/{lang}/work_skill_requirement/{id}")]
+pub async fn work_skill_requirement_post(
+    web::Path((lang, work_id)): web::Path<(String, Uuid)>,
+    _data: web::Data<AppData>,
+    _req: HttpRequest,
+    form: web::Form<SkillRequirementForm>,
+    id: Identity,
+) -> impl Responder {
+
+    let (_session_user, role) = extract_identity_data(&id);
+
+    if role != "admin".to_string() {
+        let err = CustomError::new(
+            406,
+            "Not authorized".to_string(),
+        );
+        println!("{}", &err);
+        return err.error_response()
+    };
+
+    let work = match Work::find(work_id) {
+        Ok(w) => w,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    let conn = match connection() {
+        Ok(c) => c,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    let requirement = NewWorkSkillRequirement::new(work.id, form.skill_id, CapabilityLevel::from_i32(form.level));
+
+    match WorkSkillRequirement::create_or_update(&conn, &requirement) {
+        Ok(_) => HttpResponse::Found().header("Location", format!("/{}/team/{}", &lang, &work.team_id)).finish(),
+        Err(err) => {
+            println!("{:?}", err);
+            HttpResponse::Found().header("Location", "/database_error").finish()
+        },
+    }
+}
+
+#[post("/{lang}/delete_work_skill_requirement/{id}")]
+pub async fn delete_work_skill_requirement_post(
+    web::Path((lang, requirement_id)): web::Path<(String, Uuid)>,
+
+    id: Identity,
+    _req: HttpRequest,
+) -> impl Responder {
+
+    let (_session_user, role) = extract_identity_data(&id);
+
+    if role != "admin".to_string() {
+        let err = CustomError::new(
+            406,
+            "Not authorized".to_string(),
+        );
+        println!("{}", &err);
+        return err.error_response()
+    };
+
+    let conn = match connection() {
+        Ok(c) => c,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    match WorkSkillRequirement::delete(&conn, requirement_id).and_then(|r| Work::find(r.work_id)) {
+        Ok(work) => HttpResponse::Found()
+            .header("Location", format!("/{}/team/{}", &lang, &work.team_id))
+            .finish(),
+        Err(err) => {
+            println!("{}", &err);
+            err.error_response()
+        },
+    }
+}
+
+#[get("/{lang}/classification_skills")]
+pub async fn classification_skills_page(
+    data: web::Data<AppData>,
+    web::Path(lang): web::Path<String>,
+
+    id: Identity,
+    req: HttpRequest,
+) -> impl Responder {
+
+    let (mut ctx, _session_user, role, _lang) = generate_basic_context(id, &lang, req.uri().path());
+
+    if role != "admin".to_string() {
+        let err = CustomError::new(
+            406,
+            "Not authorized".to_string(),
+        );
+        println!("{}", &err);
+        return err.error_response()
+    };
+
+    let conn = match connection() {
+        Ok(c) => c,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    let classifications = match ClassificationSkill::find_all_grouped(&conn) {
+        Ok(c) => c,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    let levels: Vec<(i32, &str)> = (0..=4)
+        .map(|l| (l, CapabilityLevel::from_i32(l).key()))
+        .collect();
+
+    ctx.insert("classifications", &classifications);
+    ctx.insert("skills", &Skill::find_all(&conn).unwrap_or_default());
+    ctx.insert("levels", &levels);
+
+    let rendered = data.tmpl.render("skill_gaps/classification_skills.html", &ctx).unwrap();
+    HttpResponse::Ok().body(rendered)
+}
+
+#[post("/{lang}/classification_skill")]
+pub async fn classification_skill_post(
+    web::Path(lang): web::Path<String>,
+    _data: web::Data<AppData>,
+    _req: HttpRequest,
+    form: web::Form<ClassificationSkillForm>,
+    id: Identity,
+) -> impl Responder {
+
+    let (_session_user, role) = extract_identity_data(&id);
+
+    if role != "admin".to_string() {
+        let err = CustomError::new(
+            406,
+            "Not authorized".to_string(),
+        );
+        println!("{}", &err);
+        return err.error_response()
+    };
+
+    if form.classification.trim().is_empty() {
+        return HttpResponse::Found().header("Location", format!("/{}/classification_skills", &lang)).finish()
+    };
+
+    let conn = match connection() {
+        Ok(c) => c,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    let expectation = NewClassificationSkill::new(&form.classification, form.skill_id, CapabilityLevel::from_i32(form.level));
+
+    match ClassificationSkill::create_or_update(&conn, &expectation) {
+        Ok(_) => HttpResponse::Found().header("Location", format!("/{}/classification_skills", &lang)).finish(),
+        Err(err) => {
+            println!("{:?}", err);
+            HttpResponse::Found().header("Location", "/database_error").finish()
+        },
+    }
+}
+
+#[post("/{lang}/delete_classification_skill/{id}")]
+pub async fn delete_classification_skill_post(
+    web::Path((lang, expectation_id)): web::Path<(String, Uuid)>,
+
+    id: Identity,
+    _req: HttpRequest,
+) -> impl Responder {
+
+    let (_session_user, role) = extract_identity_data(&id);
+
+    if role != "admin".to_string() {
+        let err = CustomError::new(
+            406,
+            "Not authorized".to_string(),
+        );
+        println!("{}", &err);
+        return err.error_response()
+    };
+
+    let conn = match connection() {
+        Ok(c) => c,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    match ClassificationSkill::delete(&conn, expectation_id) {
+        Ok(_) => HttpResponse::Found().header("Location", format!("/{}/classification_skills", &lang)).finish(),
+        Err(err) => {
+            println!("{}", &err);
+            err.error_response()
+        },
+    }
+}
@@ -0,0 +1,151 @@
+use actix_web::{HttpRequest, HttpResponse, Responder, get, web};
+use actix_web::cookie::{Cookie, SameSite};
+use actix_web::error::BlockingError;
+use actix_identity::{Identity};
+use serde::{Deserialize};
+
+use crate::models::{OidcLoginState, UserIdentity, LOGIN_STATE_MINUTES};
+use crate::oidc::{OidcConfig, OidcProvider};
+use crate::database::connection;
+use crate::errors::CustomError;
+
+#[derive(Deserialize, Debug)]
+pub struct OidcCallbackQuery {
+    code: Option<String>,
+    state: Option<String>,
+    /// Set by the identity provider instead of code if sign-in failed or was refused
+    error: Option<String>,
+}
+
+/// Holds the sign-in state in the browser that started it, so a callback
+/// carrying someone else's state is refused (login CSRF)
+const STATE_COOKIE: &str = "oidc-state";
+
+fn state_cookie(state: String) -> Cookie<'static> {
+    Cookie::build(STATE_COOKIE, state)
+        .path("/oidc")
+        .http_only(true)
+        // Sent on the provider's top-level redirect back to the callback
+        .same_site(SameSite::Lax)
+        .max_age(time::Duration::minutes(LOGIN_STATE_MINUTES))
+        .finish()
+}
+
+/// Redirect from the callback, removing the state cookie as the state is used up
+fn callback_redirect(location: String) -> HttpResponse {
+    HttpResponse::Found()
+        .del_cookie(&state_cookie(String::new()))
+        .header("Location", location)
+        .finish()
+}
+
+/// Send the browser to the identity provider
+#[get("/{lang}/oidc/log_in")]
+pub async fn oidc_login(
+    web::Path(lang): web::Path<String>,
+) -> impl Responder {
+
+    let config = match OidcConfig::from_env() {
+        Some(c) => c,
+        None => return HttpResponse::Found().header("Location", format!("/{}/log_in", &lang)).finish(),
+    };
+
+    // Discovery calls the provider and blocks, so run it off the async executor
+    let starting = lang.clone();
+    let res = web::block(move || {
+        let conn = connection()?;
+        let provider = OidcProvider::discover(&config)?;
+        let login_state = OidcLoginState::create(&conn, &starting)?;
+
+        let url = provider.authorization_url(&config, &login_state.state, &login_state.nonce, &login_state.code_verifier)?;
+
+        Ok::<_, CustomError>((url, login_state.state))
+    }).await;
+
+    match res {
+        Ok((url, state)) => HttpResponse::Found()
+            .cookie(state_cookie(state))
+            .header("Location", url)
+            .finish(),
+        Err(BlockingError::Error(err)) => {
+            println!("{}", &err);
+            HttpResponse::Found().header("Location", format!("/{}/log_in", &lang)).finish()
+        },
+        Err(BlockingError::Canceled) => {
+            println!("OIDC sign-in canceled");
+            HttpResponse::Found().header("Location", "/internal_server_error").finish()
+        },
+    }
+}
+
+/// The identity provider's redirect back with an authorization code.
+/// Registered with the provider as OIDC_REDIRECT_URL, so it has no language.
+#[get("/oidc/callback")]
+pub async fn oidc_callback(
+    query: web::Query<OidcCallbackQuery>,
+
+    req: HttpRequest,
+    id: Identity,
+) -> impl Responder {
+
+    let (config, state) = match (OidcConfig::from_env(), query.state.clone()) {
+        (Some(c), Some(s)) => (c, s),
+        _ => return callback_redirect("/en/log_in".to_string()),
+    };
+
+    // The state must come back to the browser that started the sign-in
+    if req.cookie(STATE_COOKIE).map_or(true, |c| c.value() != state) {
+        println!("OIDC sign-in state does not match this browser");
+        return callback_redirect("/en/log_in".to_string())
+    };
+
+    let conn = match connection() {
+        Ok(c) => c,
+        Err(err) => {
+            println!("{}", &err);
+            return callback_redirect("/internal_server_error".to_string())
+        },
+    };
+
+    let login_state = match OidcLoginState::take(&conn, &state) {
+        Ok(s) => s,
+        Err(err) => {
+            println!("{}", &err);
+            return callback_redirect("/en/log_in".to_string())
+        },
+    };
+
+    let lang = login_state.lang.clone();
+
+    let code = match (query.code.clone(), query.error.as_ref()) {
+        (Some(code), None) => code,
+        (_, error) => {
+            println!("OIDC sign-in refused: {:?}", error);
+            return callback_redirect(format!("/{}/log_in", &lang))
+        },
+    };
+
+    let res = web::block(move || {
+        let provider = OidcProvider::discover(&config)?;
+        let claims = provider.sign_in(&config, &code, &login_state.code_verifier, &login_state.nonce)?;
+
+        let conn = connection()?;
+        UserIdentity::sign_in(&conn, &config.issuer, &claims)
+    }).await;
+
+    match res {
+        Ok(user) => {
+            id.remember(user.slug.to_owned());
+
+            callback_redirect(format!("/{}/user/{}", &lang, user.slug))
+        },
+        Err(BlockingError::Error(err)) => {
+            println!("{}", &err);
+            callback_redirect(format!("/{}/not_authorized", &lang))
+        },
+        Err(BlockingError::Canceled) => {
+            println!("OIDC sign-in canceled");
+            callback_redirect("/internal_server_error".to_string())
+        },
+    }
+}
@@ -0,0 +1,512 @@
+use actix_web::{HttpRequest, HttpResponse, Responder, get, post, web, ResponseError};
+use actix_identity::{Identity};
+use chrono::{NaiveDate, NaiveDateTime, Utc};
+use serde::{Deserialize};
+use uuid::Uuid;
+
+use crate::{AppData, extract_identity_data, generate_basic_context};
+use crate::models::{parse_languages, CapabilityLevel, DeploymentFatigue, DEFAULT_FATIGUE_WINDOW_MONTHS, NewSurgeEvent, NewSurgeRequirement, Organization, Person,
+    Skill, SurgeDeployment, SurgeEvent, SurgeRequirement, SurgeSearch, Team};
+use crate::database::connection;
+use crate::errors::CustomError;
+
+#[derive(Deserialize, Debug)]
+pub struct SurgeEventForm {
+    name_en: String,
+    name_fr: String,
+    description_en: String,
+    description_fr: String,
+    team_id: Uuid,
+    headcount: i32,
+    deployment_effort: f64,
+    languages: String,
+    start_date: String,
+    end_date: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct SurgeRequirementForm {
+    /// Skill id, or "none" for a credential requirement
+    skill_id: String,
+    min_level: i32,
+    credential: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct DeployForm {
+    effort: f64,
+    start_date: String,
+    end_date: String,
+    note: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct CandidateQuery {
+    pub organization: Option<String>,
+    pub include_unavailable: Option<String>,
+    pub months: Option<u32>,
+}
+
+impl CandidateQuery {
+    pub fn search(&self) -> SurgeSearch {
+        SurgeSearch {
+            organization_id: self.organization.as_ref().and_then(|o| Uuid::parse_str(o).ok()),
+            include_unavailable: self.include_unavailable.is_some(),
+            window_months: self.months.filter(|m| *m > 0).unwrap_or(DEFAULT_FATIGUE_WINDOW_MONTHS),
+        }
+    }
+}
+
+/// Start of the day of a YYYY-MM-DD form date
+fn parse_date(date: &str) -> Option<NaiveDateTime> {
+    NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d")
+        .ok()
+        .map(|d| d.and_hms(0, 0, 0))
+}
+
+#[get("/{lang}/surge_events")]
+pub async fn surge_index(
+    data: web::Data<AppData>,
+    web::Path(lang): web::Path<String>,
+
+    id: Identity,
+    req: HttpRequest,
+) -> impl Responder {
+
+    let (mut ctx, session_user, _role, _lang) = generate_basic_context(id, &lang, req.uri().path());
+
+    if session_user == "" {
+        let err = CustomError::new(
+            406,
+            "Not authorized".to_string(),
+        );
+        println!("{}", &err);
+        return err.error_response()
+    };
+
+    let conn = match connection() {
+        Ok(c) => c,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    let events = match SurgeEvent::find_all(&conn) {
+        Ok(e) => e,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    let teams = Team::find_all().unwrap_or_default();
+
+    ctx.insert("events", &events);
+    ctx.insert("teams", &teams);
+    ctx.insert("today", &Utc::now().format("%Y-%m-%d").to_string());
+
+    let rendered = data.tmpl.render("surge/surge_index.html", &ctx).unwrap();
+    HttpResponse::Ok().body(rendered)
+}
+
+#[post("/{lang}/create_surge_event")]
+pub async fn create_surge_event_post(
+    web::Path(lang): web::Path<String>,
+    _data: web::Data<AppData>,
+    _req: HttpRequest,
+    form: web::Form<SurgeEventForm>,
+    id: Identity,
+) -> impl Responder {
+
+    let (_session_user, role) = extract_identity_data(&id);
+
+    if role != "admin".to_string() {
+        let err = CustomError::new(
+            406,
+            "Not authorized".to_string(),
+        );
+        println!("{}", &err);
+        return err.error_response()
+    };
+
+    let start_date = parse_date(&form.start_date);
+
+    if form.name_en.trim().is_empty() || form.name_fr.trim().is_empty() || start_date.is_none()
+        || form.headcount < 1 || !(0.0..=1.0).contains(&form.deployment_effort) || form.deployment_effort <= 0.0 {
+        return HttpResponse::Found().header("Location", format!("/{}/surge_events", &lang)).finish()
+    };
+
+    let conn = match connection() {
+        Ok(c) => c,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    let new_event = NewSurgeEvent::new(
+        form.name_en.trim().to_owned(),
+        form.name_fr.trim().to_owned(),
+        form.description_en.trim().to_owned(),
+        form.description_fr.trim().to_owned(),
+        form.team_id,
+        form.headcount,
+        form.deployment_effort,
+        parse_languages(&form.languages),
+        start_date.unwrap_or_else(|| Utc::now().naive_utc()),
+        parse_date(&form.end_date),
+    );
+
+    match SurgeEvent::create(&conn, &new_event) {
+        Ok(event) => {
+            println!("Surge event {} created", &event.name_en);
+            HttpResponse::Found().header("Location", format!("/{}/surge_event/{}", &lang, &event.id)).finish()
+        },
+        Err(err) => {
+            println!("{:?}", err);
+            HttpResponse::Found().header("Location", "/database_error").finish()
+        },
+    }
+}
+
+#[get("/{lang}/surge_event/{id}")]
+pub async fn surge_event_page(
+    data: web::Data<AppData>,
+    web::Path((lang, event_id)): web::Path<(String, Uuid)>,
+    query: web::Query<CandidateQuery>,
+
+    id: Identity,
+    req: HttpRequest,
+) -> impl Responder {
+
+    let (mut ctx, session_user, role, _lang) = generate_basic_context(id, &lang, req.uri().path());
+
+    if session_user == "" {
+        let err = CustomError::new(
+            406,
+            "Not authorized".to_string(),
+        );
+        println!("{}", &err);
+        return err.error_response()
+    };
+
+    let conn = match connection() {
+        Ok(c) => c,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    let profile = match SurgeEvent::find(&conn, event_id).and_then(|e| e.profile(&conn)) {
+        Ok(p) => p,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    // Only admins mobilize people, so only they see candidates
+    if role == "admin" && profile.event.is_active() {
+        let search = query.search();
+
+        match profile.event.find_candidates(&conn, &search) {
+            Ok(candidates) => ctx.insert("candidates", &candidates),
+            Err(err) => {
+                println!("{}", &err);
+                return err.error_response()
+            },
+        };
+
+        ctx.insert("search", &search);
+        ctx.insert("organizations", &Organization::find_all().unwrap_or_default());
+        ctx.insert("skills", &Skill::find_all(&conn).unwrap_or_default());
+        ctx.insert("today", &Utc::now().format("%Y-%m-%d").to_string());
+    };
+
+    let levels: Vec<(i32, &str)> = (0..=4)
+        .map(|l| (l, CapabilityLevel::from_i32(l).key()))
+        .collect();
+
+    ctx.insert("profile", &profile);
+    ctx.insert("levels", &levels);
+
+    let rendered = data.tmpl.render("surge/surge_event_page.html", &ctx).unwrap();
+    HttpResponse::Ok().body(rendered)
+}
+
+#[post("/{lang}/surge_requirement/{id}")]
+pub async fn add_surge_requirement_post(
+    web::Path((lang, event_id)): web::Path<(String, Uuid)>,
+    _data: web::Data<AppData>,
+    _req: HttpRequest,
+    form: web::Form<SurgeRequirementForm>,
+    id: Identity,
+) -> impl Responder {
+
+    let (_session_user, role) = extract_identity_data(&id);
+
+    if role != "admin".to_string() {
+        let err = CustomError::new(
+            406,
+            "Not authorized".to_string(),
+        );
+        println!("{}", &err);
+        return err.error_response()
+    };
+
+    let requirement = match (Uuid::parse_str(&form.skill_id), form.credential.trim()) {
+        (Ok(skill_id), _) => NewSurgeRequirement::skill(event_id, skill_id, CapabilityLevel::from_i32(form.min_level)),
+        (Err(_), credential) if !credential.is_empty() => NewSurgeRequirement::credential(event_id, credential.to_owned()),
+        _ => return HttpResponse::Found().header("Location", format!("/{}/surge_event/{}", &lang, &event_id)).finish(),
+    };
+
+    let conn = match connection() {
+        Ok(c) => c,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    match SurgeRequirement::create(&conn, &requirement) {
+        Ok(_) => HttpResponse::Found().header("Location", format!("/{}/surge_event/{}", &lang, &event_id)).finish(),
+        Err(err) => {
+            println!("{:?}", err);
+            HttpResponse::Found().header("Location", "/database_error").finish()
+        },
+    }
+}
+
+#[post("/{lang}/delete_surge_requirement/{id}")]
+pub async fn delete_surge_requirement_post(
+    web::Path((lang, requirement_id)): web::Path<(String, Uuid)>,
+
+    id: Identity,
+    _req: HttpRequest,
+) -> impl Responder {
+
+    let (_session_user, role) = extract_identity_data(&id);
+
+    if role != "admin".to_string() {
+        let err = CustomError::new(
+            406,
+            "Not authorized".to_string(),
+        );
+        println!("{}", &err);
+        return err.error_response()
+    };
+
+    let conn = match connection() {
+        Ok(c) => c,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    match SurgeRequirement::delete(&conn, requirement_id) {
+        Ok(requirement) => HttpResponse::Found()
+            .header("Location", format!("/{}/surge_event/{}", &lang, &requirement.surge_event_id))
+            .finish(),
+        Err(err) => {
+            println!("{}", &err);
+            err.error_response()
+        },
+    }
+}
+
+#[post("/{lang}/surge_deploy/{event_id}/{person_id}")]
+pub async fn surge_deploy_post(
+    web::Path((lang, event_id, person_id)): web::Path<(String, Uuid, Uuid)>,
+    _data: web::Data<AppData>,
+    _req: HttpRequest,
+    form: web::Form<DeployForm>,
+    id: Identity,
+) -> impl Responder {
+
+    let (_session_user, role) = extract_identity_data(&id);
+
+    if role != "admin".to_string() {
+        let err = CustomError::new(
+            406,
+            "Not authorized".to_string(),
+        );
+        println!("{}", &err);
+        return err.error_response()
+    };
+
+    let (start_date, end_date) = match (parse_date(&form.start_date), parse_date(&form.end_date)) {
+        (Some(s), Some(e)) if e > s && form.effort > 0.0 && form.effort <= 1.0 => (s, e),
+        _ => return HttpResponse::Found().header("Location", format!("/{}/surge_event/{}", &lang, &event_id)).finish(),
+    };
+
+    let conn = match connection() {
+        Ok(c) => c,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    let (event, person) = match (SurgeEvent::find(&conn, event_id), Person::find(person_id)) {
+        (Ok(e), Ok(p)) => (e, p),
+        (Err(err), _) | (_, Err(err)) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    match event.deploy(&conn, &person, form.effort, start_date, end_date, form.note.trim()) {
+        Ok(deployment) => {
+            println!("Person {} deployed to surge event {}", &deployment.person_id, &deployment.surge_event_id);
+            HttpResponse::Found().header("Location", format!("/{}/surge_event/{}", &lang, &event_id)).finish()
+        },
+        Err(err) => {
+            println!("{:?}", err);
+            HttpResponse::Found().header("Location", "/database_error").finish()
+        },
+    }
+}
+
+#[post("/{lang}/surge_release/{id}")]
+pub async fn surge_release_post(
+    web::Path((lang, deployment_id)): web::Path<(String, Uuid)>,
+
+    id: Identity,
+    _req: HttpRequest,
+) -> impl Responder {
+
+    let (_session_user, role) = extract_identity_data(&id);
+
+    if role != "admin".to_string() {
+        let err = CustomError::new(
+            406,
+            "Not authorized".to_string(),
+        );
+        println!("{}", &err);
+        return err.error_response()
+    };
+
+    let conn = match connection() {
+        Ok(c) => c,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    let deployment = match SurgeDeployment::find(&conn, deployment_id) {
+        Ok(d) => d,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    match deployment.release(&conn, Utc::now().naive_utc(), "Released from surge deployment") {
+        Ok(deployment) => {
+            println!("Surge deployment {} released", &deployment.id);
+            HttpResponse::Found().header("Location", format!("/{}/surge_event/{}", &lang, &deployment.surge_event_id)).finish()
+        },
+        Err(err) => {
+            println!("{:?}", err);
+            HttpResponse::Found().header("Location", "/database_error").finish()
+        },
+    }
+}
+
+#[post("/{lang}/close_surge_event/{id}")]
+pub async fn close_surge_event_post(
+    web::Path((lang, event_id)): web::Path<(String, Uuid)>,
+
+    id: Identity,
+    _req: HttpRequest,
+) -> impl Responder {
+
+    let (_session_user, role) = extract_identity_data(&id);
+
+    if role != "admin".to_string() {
+        let err = CustomError::new(
+            406,
+            "Not authorized".to_string(),
+        );
+        println!("{}", &err);
+        return err.error_response()
+    };
+
+    let conn = match connection() {
+        Ok(c) => c,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    let event = match SurgeEvent::find(&conn, event_id) {
+        Ok(e) => e,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    match event.close(&conn, Utc::now().naive_utc()) {
+        Ok(event) => {
+            println!("Surge event {} closed", &event.name_en);
+            HttpResponse::Found().header("Location", format!("/{}/surge_event/{}", &lang, &event.id)).finish()
+        },
+        Err(err) => {
+            println!("{:?}", err);
+            HttpResponse::Found().header("Location", "/database_error").finish()
+        },
+    }
+}
+
+#[get("/{lang}/deployment_fatigue")]
+pub async fn deployment_fatigue_page(
+    data: web::Data<AppData>,
+    web::Path(lang): web::Path<String>,
+    query: web::Query<CandidateQuery>,
+
+    id: Identity,
+    req: HttpRequest,
+) -> impl Responder {
+
+    let (mut ctx, _session_user, role, _lang) = generate_basic_context(id, &lang, req.uri().path());
+
+    if role != "admin".to_string() {
+        let err = CustomError::new(
+            406,
+            "Not authorized".to_string(),
+        );
+        println!("{}", &err);
+        return err.error_response()
+    };
+
+    let conn = match connection() {
+        Ok(c) => c,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    let search = query.search();
+
+    let rows = match DeploymentFatigue::find_deployed(&conn, search.organization_id, search.window_months, Utc::now().naive_utc()) {
+        Ok(r) => r,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    ctx.insert("rows", &rows);
+    ctx.insert("search", &search);
+    ctx.insert("organizations", &Organization::find_all().unwrap_or_default());
+
+    let rendered = data.tmpl.render("surge/deployment_fatigue.html", &ctx).unwrap();
+    HttpResponse::Ok().body(rendered)
+}
@@ -0,0 +1,271 @@
+use actix_web::{HttpRequest, HttpResponse, Responder, get, post, web, ResponseError};
+use actix_identity::{Identity};
+use serde::{Deserialize};
+use uuid::Uuid;
+
+use crate::{AppData, extract_identity_data, generate_basic_context};
+use crate::models::{CapabilityLevel, Team, NewTeam, OrgTier, Skill};
+use crate::handlers::AsOfQuery;
+use crate::database::connection;
+use crate::errors::CustomError;
+
+#[derive(Deserialize, Debug)]
+pub struct TeamForm {
+    name_en: String,
+    name_fr: String,
+    description_en: String,
+    description_fr: String,
+    org_tier_id: Uuid,
+}
+
+#[get("/{lang}/team/{id}")]
+pub async fn team_page(
+    web::Path((lang, team_id)): web::Path<(String, Uuid)>,
+    query: web::Query<AsOfQuery>,
+    data: web::Data<AppData>,
+
+    req: HttpRequest,
+    id: Identity,
+) -> impl Responder {
+
+    let (mut ctx, session_user, role, _lang) = generate_basic_context(id, &lang, req.uri().path());
+
+    if session_user == "" {
+        let err = CustomError::new(
+            406,
+            "Not authorized".to_string(),
+        );
+        println!("{}", &err);
+        return err.error_response()
+    };
+
+    let as_of = query.as_of_datetime();
+
+    let profile = Team::find(team_id).and_then(|t| t.profile(as_of));
+
+    match profile {
+        Ok(profile) => {
+            ctx.insert("profile", &profile);
+            ctx.insert("as_of", &as_of.date().format("%Y-%m-%d").to_string());
+
+            if role == "admin".to_string() {
+                let levels: Vec<(i32, &str)> = (0..=4)
+                    .map(|l| (l, CapabilityLevel::from_i32(l).key()))
+                    .collect();
+
+                let skills = connection().ok()
+                    .and_then(|conn| Skill::find_all(&conn).ok())
+                    .unwrap_or_default();
+
+                ctx.insert("skills", &skills);
+                ctx.insert("levels", &levels);
+            };
+
+            let rendered = data.tmpl.render("teams/team_page.html", &ctx).unwrap();
+            HttpResponse::Ok().body(rendered)
+        },
+        Err(err) => {
+            println!("{}", &err);
+            err.error_response()
+        },
+    }
+}
+
+#[get("/{lang}/create_team")]
+pub async fn create_team(
+    web::Path(lang): web::Path<String>,
+    data: web::Data<AppData>,
+
+    req: HttpRequest,
+    id: Identity,
+) -> impl Responder {
+
+    let (mut ctx, _session_user, role, _lang) = generate_basic_context(id, &lang, req.uri().path());
+
+    if &role != "admin" {
+        let err = CustomError::new(
+            406,
+            "Not authorized".to_string(),
+        );
+        println!("{}", &err);
+        return err.error_response()
+    };
+
+    let org_tiers = match OrgTier::find_all() {
+        Ok(t) => t,
+        Err(e) => {
+            println!("{:?}", e);
+            Vec::new()
+        }
+    };
+
+    ctx.insert("org_tiers", &org_tiers);
+
+    let rendered = data.tmpl.render("teams/create_team.html", &ctx).unwrap();
+    HttpResponse::Ok().body(rendered)
+}
+
+#[post("/{lang}/create_team")]
+pub async fn create_team_post(
+    web::Path(lang): web::Path<String>,
+    _data: web::Data<AppData>,
+    _req: HttpRequest,
+    form: web::Form<TeamForm>,
+    id: Identity,
+) -> impl Responder {
+
+    let (_session_user, role) = extract_identity_data(&id);
+
+    if &role != "admin" {
+        let err = CustomError::new(
+            406,
+            "Not authorized".to_string(),
+        );
+        println!("{}", &err);
+        return err.error_response()
+    };
+
+    if form.name_en.trim().is_empty() || form.name_fr.trim().is_empty() {
+        return HttpResponse::Found().header("Location", format!("/{}/create_team", &lang)).finish()
+    };
+
+    // Team belongs to the same organization as its tier
+    let org_tier = match OrgTier::find(form.org_tier_id) {
+        Ok(t) => t,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    let new_team = NewTeam::new(
+        form.name_en.trim().to_owned(),
+        form.name_fr.trim().to_owned(),
+        org_tier.organization_id,
+        org_tier.id,
+        form.description_en.trim().to_owned(),
+        form.description_fr.trim().to_owned(),
+    );
+
+    let conn = match connection() {
+        Ok(c) => c,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    match Team::create(&conn, &new_team) {
+        Ok(team) => {
+            println!("Team {} created", &team.name_en);
+            HttpResponse::Found().header("Location", format!("/{}/team/{}", &lang, &team.id)).finish()
+        },
+        Err(err) => {
+            println!("{:?}", err);
+            HttpResponse::Found().header("Location", "/database_error").finish()
+        },
+    }
+}
+
+#[get("/{lang}/edit_team/{id}")]
+pub async fn edit_team(
+    web::Path((lang, team_id)): web::Path<(String, Uuid)>,
+    data: web::Data<AppData>,
+
+    req: HttpRequest,
+    id: Identity,
+) -> impl Responder {
+
+    let (mut ctx, _session_user, role, _lang) = generate_basic_context(id, &lang, req.uri().path());
+
+    if &role != "admin" {
+        let err = CustomError::new(
+            406,
+            "Not authorized".to_string(),
+        );
+        println!("{}", &err);
+        return err.error_response()
+    };
+
+    let team = match Team::find(team_id) {
+        Ok(t) => t,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    let org_tiers = match OrgTier::find_all() {
+        Ok(t) => t,
+        Err(e) => {
+            println!("{:?}", e);
+            Vec::new()
+        }
+    };
+
+    ctx.insert("team", &team);
+    ctx.insert("org_tiers", &org_tiers);
+
+    let rendered = data.tmpl.render("teams/edit_team.html", &ctx).unwrap();
+    HttpResponse::Ok().body(rendered)
+}
+
+#[post("/{lang}/edit_team/{id}")]
+pub async fn edit_team_post(
+    web::Path((lang, team_id)): web::Path<(String, Uuid)>,
+    _data: web::Data<AppData>,
+    _req: HttpRequest,
+    form: web::Form<TeamForm>,
+    id: Identity,
+) -> impl Responder {
+
+    let (_session_user, role) = extract_identity_data(&id);
+
+    if &role != "admin" {
+        let err = CustomError::new(
+            406,
+            "Not authorized".to_string(),
+        );
+        println!("{}", &err);
+        return err.error_response()
+    };
+
+    if form.name_en.trim().is_empty() || form.name_fr.trim().is_empty() {
+        return HttpResponse::Found().header("Location", format!("/{}/edit_team/{}", &lang, &team_id)).finish()
+    };
+
+    let (mut team, org_tier) = match Team::find(team_id).and_then(|t| Ok((t, OrgTier::find(form.org_tier_id)?))) {
+        Ok(r) => r,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    team.name_en = form.name_en.trim().to_owned();
+    team.name_fr = form.name_fr.trim().to_owned();
+    team.description_en = form.description_en.trim().to_owned();
+    team.description_fr = form.description_fr.trim().to_owned();
+    team.organization_id = org_tier.organization_id;
+    team.org_tier_id = org_tier.id;
+    team.updated_at = chrono::Utc::now().naive_utc();
+
+    let conn = match connection() {
+        Ok(c) => c,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    match team.update(&conn) {
+        Ok(team) => {
+            println!("Team {} updated", &team.name_en);
+            HttpResponse::Found().header("Location", format!("/{}/team/{}", &lang, &team.id)).finish()
+        },
+        Err(err) => {
+            println!("{:?}", err);
+            HttpResponse::Found().header("Location", "/database_error").finish()
+        },
+    }
+}
@@ -6,7 +6,7 @@ use inflector::Inflector;
 use serde::{Deserialize};
 
 use crate::{AppData, extract_identity_data, generate_basic_context};
-use crate::models::{User};
+use crate::models::{User, Person};
 use crate::handlers::DeleteForm;
 use crate::errors::CustomError;
 
@@ -85,9 +85,15 @@ pub async fn user_page_handler(
 
         match user_select {
             Ok(user) => {
+                // Add linked Person profile if one exists
+                let profile = Person::find_by_user_id(user.id).and_then(|p| p.profile());
+
+                match profile {
+                    Ok(profile) => ctx.insert("profile", &profile),
+                    Err(e) => println!("No person profile for {}: {}", &user.slug, e),
+                };
+
                 ctx.insert("user", &user);
-        
-                // Add Additional User Data Here
             
                 let rendered = data.tmpl.render("users/user_page.html", &ctx).unwrap();
                 HttpResponse::Ok().body(rendered)
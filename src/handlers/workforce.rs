@@ -0,0 +1,139 @@
+use actix_web::{HttpRequest, HttpResponse, Responder, get, post, web, ResponseError};
+use actix_identity::{Identity};
+use chrono::Utc;
+use serde::{Deserialize};
+use uuid::Uuid;
+
+use crate::{AppData, extract_identity_data, generate_basic_context};
+use crate::models::{Organization, WorkforceMetric, WorkforceScope};
+use crate::database::connection;
+use crate::errors::CustomError;
+
+#[derive(Deserialize, Debug)]
+pub struct WorkforceQuery {
+    pub organization: Option<String>,
+    pub team: Option<String>,
+    pub org_tier: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct WorkforceRefreshForm {
+    organization_id: Uuid,
+}
+
+#[get("/{lang}/workforce_analytics")]
+pub async fn workforce_analytics(
+    data: web::Data<AppData>,
+    web::Path(lang): web::Path<String>,
+    query: web::Query<WorkforceQuery>,
+
+    id: Identity,
+    req: HttpRequest,
+) -> impl Responder {
+
+    let (mut ctx, _session_user, role, _lang) = generate_basic_context(id, &lang, req.uri().path());
+
+    if role != "admin".to_string() {
+        let err = CustomError::new(
+            406,
+            "Not authorized".to_string(),
+        );
+        println!("{}", &err);
+        return err.error_response()
+    };
+
+    let conn = match connection() {
+        Ok(c) => c,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    let organization_id = query.organization.as_ref().and_then(|o| Uuid::parse_str(o).ok());
+    let team_id = query.team.as_ref().and_then(|t| Uuid::parse_str(t).ok());
+    let org_tier_id = query.org_tier.as_ref().and_then(|t| Uuid::parse_str(t).ok());
+
+    // A team is the narrower scope, so it wins if both are given
+    let selected = match (team_id, org_tier_id) {
+        (Some(team_id), _) => Some((WorkforceScope::Team, team_id)),
+        (None, Some(org_tier_id)) => Some((WorkforceScope::OrgTier, org_tier_id)),
+        (None, None) => None,
+    };
+
+    let series = match selected {
+        Some((scope, scope_id)) => WorkforceMetric::find_series(&conn, scope, scope_id, None, None),
+        None => Ok(Vec::new()),
+    };
+
+    let series = match series {
+        Ok(s) => s,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    // The series decides the organization when a unit is picked from a link
+    let organization_id = organization_id.or_else(|| series.first().map(|m| m.organization_id));
+
+    let last_refreshed = match organization_id {
+        Some(organization_id) => WorkforceMetric::last_refreshed(&conn, organization_id).unwrap_or_default(),
+        None => None,
+    };
+
+    let units = match organization_id {
+        Some(organization_id) => WorkforceMetric::find_units(&conn, Some(organization_id)).unwrap_or_default(),
+        None => Vec::new(),
+    };
+
+    ctx.insert("organizations", &Organization::find_all().unwrap_or_default());
+    ctx.insert("organization_id", &organization_id);
+    ctx.insert("units", &units);
+    ctx.insert("selected_id", &selected.map(|(_, id)| id));
+    ctx.insert("selected_type", &selected.map(|(scope, _)| scope.to_string()));
+    ctx.insert("series", &series);
+    ctx.insert("last_refreshed", &last_refreshed);
+
+    let rendered = data.tmpl.render("reports/workforce_analytics.html", &ctx).unwrap();
+    HttpResponse::Ok().body(rendered)
+}
+
+#[post("/{lang}/refresh_workforce_metrics")]
+pub async fn refresh_workforce_metrics_post(
+    web::Path(lang): web::Path<String>,
+    _data: web::Data<AppData>,
+    _req: HttpRequest,
+    form: web::Form<WorkforceRefreshForm>,
+    id: Identity,
+) -> impl Responder {
+
+    let (_session_user, role) = extract_identity_data(&id);
+
+    if role != "admin".to_string() {
+        let err = CustomError::new(
+            406,
+            "Not authorized".to_string(),
+        );
+        println!("{}", &err);
+        return err.error_response()
+    };
+
+    let conn = match connection() {
+        Ok(c) => c,
+        Err(err) => {
+            println!("{}", &err);
+            return err.error_response()
+        },
+    };
+
+    match WorkforceMetric::refresh(&conn, form.organization_id, Utc::now().naive_utc().date()) {
+        Ok(_) => HttpResponse::Found()
+            .header("Location", format!("/{}/workforce_analytics?organization={}", &lang, &form.organization_id))
+            .finish(),
+        Err(err) => {
+            println!("{}", &err);
+            err.error_response()
+        },
+    }
+}
@@ -0,0 +1,152 @@
+use std::fs::{self, File};
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::database::connection;
+use crate::errors::CustomError;
+use crate::importer::{import_csv, import_orcid, import_publications, parse_publications, sync_hr_extract,
+    ColumnMapping, CrossrefClient, FileCrossrefClient, FileOrcidClient, HrSyncReport, HttpCrossrefClient,
+    HttpOrcidClient, ImportKind, ImportReport, OrcidClient, OrcidDocument, OrcidImportReport,
+    PublicationFormat, PublicationImportReport};
+use crate::models::{parse_orcid, Organization, Person};
+
+pub const IMPORT_USAGE: &str = "Usage: people_data import <organizations|org_tiers|teams|persons|roles> <file.csv> [--dry-run] [--map field=Header,...]";
+pub const IMPORT_ORCID_USAGE: &str = "Usage: people_data import_orcid <ORCID iD> [--file record.json|record.xml] [--dir saved_records]";
+pub const IMPORT_PUBLICATIONS_USAGE: &str = "Usage: people_data import_publications <crossref|ris|bibtex|dois> <file> [--dry-run] [--dir saved_crossref_records]";
+pub const SYNC_HRMS_USAGE: &str = "Usage: people_data sync_hrms <organization acronym> <extract file> [--source peoplesoft] [--delimiter ,] [--map field=Header,...]";
+
+/// Run the `import` subcommand from the arguments that follow it
+pub fn run_import_command(args: &[String]) -> Result<ImportReport, CustomError> {
+    let usage = || CustomError::new(400, IMPORT_USAGE.to_string());
+
+    let kind = ImportKind::from_str(args.get(0).ok_or_else(usage)?)?;
+    let path = args.get(1).ok_or_else(usage)?;
+
+    let mut dry_run = false;
+    let mut mapping = ColumnMapping::default();
+
+    let mut rest = args[2..].iter();
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "--dry-run" => dry_run = true,
+            "--map" => mapping = ColumnMapping::parse(rest.next().ok_or_else(usage)?)?,
+            _ => return Err(usage()),
+        };
+    };
+
+    let file = File::open(path)
+        .map_err(|e| CustomError::new(400, format!("Unable to open {}: {}", path, e)))?;
+
+    let conn = connection()?;
+
+    import_csv(&conn, kind, file, &mapping, dry_run)
+}
+
+/// Run the `sync_hrms` subcommand from the arguments that follow it
+pub fn run_sync_hrms_command(args: &[String]) -> Result<HrSyncReport, CustomError> {
+    let usage = || CustomError::new(400, SYNC_HRMS_USAGE.to_string());
+
+    let organization = Organization::get_by_acronym(args.get(0).ok_or_else(usage)?)
+        .map_err(|e| CustomError::new(408, format!("Organization not found: {}", e.message)))?;
+    let path = args.get(1).ok_or_else(usage)?;
+
+    let mut source = "peoplesoft".to_string();
+    let mut delimiter = b',';
+    let mut mapping = ColumnMapping::default();
+
+    let mut rest = args[2..].iter();
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "--source" => source = rest.next().ok_or_else(usage)?.to_owned(),
+            "--delimiter" => {
+                let d = rest.next().ok_or_else(usage)?;
+                if d.len() != 1 {
+                    return Err(usage())
+                };
+                delimiter = d.as_bytes()[0];
+            },
+            "--map" => mapping = ColumnMapping::parse(rest.next().ok_or_else(usage)?)?,
+            _ => return Err(usage()),
+        };
+    };
+
+    let file = File::open(path)
+        .map_err(|e| CustomError::new(400, format!("Unable to open {}: {}", path, e)))?;
+
+    let file_name = Path::new(path)
+        .file_name()
+        .map(|f| f.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_owned());
+
+    let conn = connection()?;
+
+    sync_hr_extract(&conn, file, &source, &file_name, organization.id, delimiter, &mapping)
+}
+
+/// Saved record file passed to `import_orcid --file`
+struct SingleFileClient(OrcidDocument);
+
+impl OrcidClient for SingleFileClient {
+    fn fetch_record(&self, _orcid: &str) -> Result<OrcidDocument, CustomError> {
+        Ok(self.0.clone())
+    }
+}
+
+/// Run the `import_orcid` subcommand from the arguments that follow it.
+/// The record is read from the ORCID public API unless a file or directory is given.
+pub fn run_import_orcid_command(args: &[String]) -> Result<OrcidImportReport, CustomError> {
+    let usage = || CustomError::new(400, IMPORT_ORCID_USAGE.to_string());
+
+    let orcid = parse_orcid(args.get(0).ok_or_else(usage)?)?;
+
+    let mut client: Box<dyn OrcidClient> = Box::new(HttpOrcidClient::from_env());
+
+    let mut rest = args[1..].iter();
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "--file" => {
+                let path = Path::new(rest.next().ok_or_else(usage)?);
+                client = Box::new(SingleFileClient(OrcidDocument::from_file(path)?));
+            },
+            "--dir" => client = Box::new(FileOrcidClient::new(Path::new(rest.next().ok_or_else(usage)?))),
+            _ => return Err(usage()),
+        };
+    };
+
+    let conn = connection()?;
+
+    let person = Person::find_by_orcid(&conn, &orcid)
+        .map_err(|e| CustomError::new(408, format!("No person with ORCID iD {}: {}", orcid, e.message)))?;
+
+    import_orcid(&conn, client.as_ref(), &person)
+}
+
+/// Run the `import_publications` subcommand from the arguments that follow it.
+/// DOIs are looked up on Crossref unless a directory of saved responses is given.
+pub fn run_import_publications_command(args: &[String]) -> Result<PublicationImportReport, CustomError> {
+    let usage = || CustomError::new(400, IMPORT_PUBLICATIONS_USAGE.to_string());
+
+    let format = PublicationFormat::from_str(args.get(0).ok_or_else(usage)?)?;
+    let path = args.get(1).ok_or_else(usage)?;
+
+    let mut dry_run = false;
+    let mut client: Box<dyn CrossrefClient> = Box::new(HttpCrossrefClient::from_env());
+
+    let mut rest = args[2..].iter();
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "--dry-run" => dry_run = true,
+            "--dir" => client = Box::new(FileCrossrefClient::new(Path::new(rest.next().ok_or_else(usage)?))),
+            _ => return Err(usage()),
+        };
+    };
+
+    let input = fs::read_to_string(path)
+        .map_err(|e| CustomError::new(400, format!("Unable to read {}: {}", path, e)))?;
+
+    let entries = parse_publications(format, &input, client.as_ref())?;
+
+    let conn = connection()?;
+
+    import_publications(&conn, format, entries, dry_run)
+}
@@ -0,0 +1,898 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::io::Read;
+use std::str::FromStr;
+
+use async_graphql::FieldError;
+use chrono::{NaiveDate, NaiveDateTime, Utc};
+use diesel::prelude::*;
+use diesel::PgConnection;
+use diesel::result::{DatabaseErrorKind, Error as DieselError};
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::errors::CustomError;
+use crate::models::{NewOrgTier, NewOrganization, NewPerson, NewRole, NewTeam, OrgTier,
+    Organization, Person, Role, RoleType, Team, ORG_TYPES};
+use crate::schema::*;
+
+/// RoleEvent note for roles loaded from a CSV file
+const IMPORT_NOTE: &str = "CSV import";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+/// The entity a CSV file describes. Each file holds a single kind,
+/// and files are expected to be loaded in the order listed here.
+pub enum ImportKind {
+    Organizations,
+    OrgTiers,
+    Teams,
+    Persons,
+    Roles,
+}
+
+impl ImportKind {
+    pub const ALL: [ImportKind; 5] = [
+        ImportKind::Organizations,
+        ImportKind::OrgTiers,
+        ImportKind::Teams,
+        ImportKind::Persons,
+        ImportKind::Roles,
+    ];
+
+    pub fn required_fields(&self) -> &'static [&'static str] {
+        match self {
+            ImportKind::Organizations => &["name_en", "name_fr", "acronym_en", "acronym_fr", "org_type"],
+            ImportKind::OrgTiers => &["organization", "name_en", "name_fr"],
+            ImportKind::Teams => &["organization", "org_tier", "name_en", "name_fr"],
+            ImportKind::Persons => &["employee_number", "family_name", "given_name", "organization"],
+            ImportKind::Roles => &["employee_number", "organization", "team", "title_en", "title_fr", "start_date"],
+        }
+    }
+
+    pub fn optional_fields(&self) -> &'static [&'static str] {
+        match self {
+            ImportKind::Organizations => &[],
+            ImportKind::OrgTiers => &["parent_tier"],
+            ImportKind::Teams => &["description_en", "description_fr"],
+            ImportKind::Persons => &[],
+            ImportKind::Roles => &["effort", "end_date", "role_type"],
+        }
+    }
+
+    fn is_field(&self, field: &str) -> bool {
+        self.required_fields().contains(&field) || self.optional_fields().contains(&field)
+    }
+}
+
+impl fmt::Display for ImportKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            ImportKind::Organizations => "organizations",
+            ImportKind::OrgTiers => "org_tiers",
+            ImportKind::Teams => "teams",
+            ImportKind::Persons => "persons",
+            ImportKind::Roles => "roles",
+        };
+        f.write_str(s)
+    }
+}
+
+impl FromStr for ImportKind {
+    type Err = CustomError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "organizations" => Ok(ImportKind::Organizations),
+            "org_tiers" => Ok(ImportKind::OrgTiers),
+            "teams" => Ok(ImportKind::Teams),
+            "persons" => Ok(ImportKind::Persons),
+            "roles" => Ok(ImportKind::Roles),
+            _ => Err(CustomError::new(400, format!("Unknown import kind: {}", s))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+/// Maps import fields to the CSV headers that hold them.
+/// Fields without a mapping are read from a header of the same name.
+pub struct ColumnMapping {
+    columns: HashMap<String, String>,
+}
+
+impl ColumnMapping {
+    /// Parse `field=Header` pairs separated by commas or new lines
+    pub fn parse(s: &str) -> Result<Self, CustomError> {
+        let mut columns = HashMap::new();
+
+        for pair in s.split(|c| c == ',' || c == '\n').map(str::trim).filter(|p| !p.is_empty()) {
+            let (field, header) = pair.split_once('=')
+                .ok_or_else(|| CustomError::new(400, format!("Invalid column mapping: {}", pair)))?;
+
+            columns.insert(field.trim().to_lowercase(), header.trim().to_lowercase());
+        };
+
+        Ok(ColumnMapping { columns })
+    }
+
+    pub(crate) fn header_for(&self, field: &str) -> String {
+        match self.columns.get(field) {
+            Some(header) => header.to_owned(),
+            None => field.to_owned(),
+        }
+    }
+}
+
+/// A CSV record read through the column mapping
+pub(crate) struct MappedRow<'a> {
+    pub(crate) headers: &'a HashMap<String, usize>,
+    pub(crate) mapping: &'a ColumnMapping,
+    pub(crate) record: &'a csv::StringRecord,
+}
+
+impl<'a> MappedRow<'a> {
+    /// Trimmed value for a field, or None if the cell is empty
+    pub(crate) fn get(&self, field: &str) -> Option<&'a str> {
+        self.headers.get(&self.mapping.header_for(field))
+            .and_then(|i| self.record.get(*i))
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+    }
+
+    fn required(&self, field: &str) -> Result<&'a str, RowError> {
+        self.get(field)
+            .ok_or_else(|| RowError::Invalid(format!("Missing value for {}", field)))
+    }
+
+    fn date(&self, field: &str) -> Result<Option<NaiveDateTime>, RowError> {
+        match self.get(field) {
+            Some(v) => NaiveDate::parse_from_str(v, "%Y-%m-%d")
+                .map(|d| Some(d.and_hms(0, 0, 0)))
+                .map_err(|_| RowError::Invalid(format!("Invalid date for {}: {} (expected YYYY-MM-DD)", field, v))),
+            None => Ok(None),
+        }
+    }
+
+    /// Natural key used to spot the same entity twice in one file
+    fn natural_key(&self, kind: ImportKind) -> String {
+        let fields: &[&str] = match kind {
+            ImportKind::Organizations => &["acronym_en"],
+            ImportKind::OrgTiers => &["organization", "name_en"],
+            ImportKind::Teams => &["organization", "name_en"],
+            ImportKind::Persons => &["employee_number"],
+            ImportKind::Roles => &["employee_number", "organization", "team", "start_date", "role_type", "title_en"],
+        };
+
+        fields.iter()
+            .map(|f| self.get(f).unwrap_or("").to_uppercase())
+            .collect::<Vec<String>>()
+            .join(" / ")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RowAction {
+    Create,
+    Update,
+    Unchanged,
+    Conflict,
+    Error,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RowReport {
+    pub line: u64,
+    pub key: String,
+    pub action: RowAction,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+/// Outcome of an import. Rows are only saved when `committed` is true,
+/// which requires a non dry-run with no conflicts or errors.
+pub struct ImportReport {
+    pub kind: ImportKind,
+    pub dry_run: bool,
+    pub committed: bool,
+    pub rows: Vec<RowReport>,
+    pub created: usize,
+    pub updated: usize,
+    pub unchanged: usize,
+    pub conflicts: usize,
+    pub errors: usize,
+}
+
+impl ImportReport {
+    fn new(kind: ImportKind, dry_run: bool) -> Self {
+        ImportReport {
+            kind,
+            dry_run,
+            committed: false,
+            rows: Vec::new(),
+            created: 0,
+            updated: 0,
+            unchanged: 0,
+            conflicts: 0,
+            errors: 0,
+        }
+    }
+
+    fn push(&mut self, line: u64, key: String, action: RowAction, message: String) {
+        match action {
+            RowAction::Create => self.created += 1,
+            RowAction::Update => self.updated += 1,
+            RowAction::Unchanged => self.unchanged += 1,
+            RowAction::Conflict => self.conflicts += 1,
+            RowAction::Error => self.errors += 1,
+        };
+
+        self.rows.push(RowReport { line, key, action, message });
+    }
+
+    pub fn has_failures(&self) -> bool {
+        self.conflicts > 0 || self.errors > 0
+    }
+}
+
+impl fmt::Display for ImportReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for row in &self.rows {
+            writeln!(f, "line {:>5}  {:<9}  {}  {}", row.line, format!("{:?}", row.action).to_lowercase(), row.key, row.message)?;
+        };
+
+        writeln!(f, "{}: {} create, {} update, {} unchanged, {} conflict, {} error",
+            self.kind, self.created, self.updated, self.unchanged, self.conflicts, self.errors)?;
+
+        let status = if self.committed {
+            "Committed"
+        } else if self.dry_run {
+            "Dry run - nothing saved"
+        } else {
+            "Rolled back - nothing saved"
+        };
+
+        write!(f, "{}", status)
+    }
+}
+
+enum RowOutcome {
+    Created,
+    Updated(Vec<&'static str>),
+    Unchanged,
+}
+
+enum RowError {
+    Invalid(String),
+    Conflict(String),
+    Database(DieselError),
+}
+
+/// Unique and foreign key violations mean the row clashes with stored data
+fn conflict_message(error: &DieselError) -> Option<String> {
+    match error {
+        DieselError::DatabaseError(DatabaseErrorKind::UniqueViolation, info)
+        | DieselError::DatabaseError(DatabaseErrorKind::ForeignKeyViolation, info) => Some(info.message().to_string()),
+        _ => None,
+    }
+}
+
+/// Errors from the model's get_or_create and role lifecycle methods. Database
+/// errors are kept as the FieldError's source, so conflicts are still reported as such.
+impl From<FieldError> for RowError {
+    fn from(error: FieldError) -> RowError {
+        match error.source::<DieselError>().and_then(conflict_message) {
+            Some(message) => RowError::Conflict(message),
+            None => RowError::Invalid(error.message),
+        }
+    }
+}
+
+impl From<DieselError> for RowError {
+    fn from(error: DieselError) -> RowError {
+        match conflict_message(&error) {
+            Some(message) => RowError::Conflict(message),
+            None => RowError::Database(error),
+        }
+    }
+}
+
+/// Import a CSV file of a single kind inside one transaction.
+/// Each row runs in its own savepoint so one bad row is reported without
+/// hiding problems further down the file. The transaction is rolled back
+/// on a dry run or if any row conflicts or fails, so nothing is saved
+/// unless every row succeeds.
+pub fn import_csv<R: Read>(
+    conn: &PgConnection,
+    kind: ImportKind,
+    input: R,
+    mapping: &ColumnMapping,
+    dry_run: bool,
+) -> Result<ImportReport, CustomError> {
+
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(input);
+
+    let headers: HashMap<String, usize> = reader.headers()
+        .map_err(|e| CustomError::new(400, format!("Unable to read CSV headers: {}", e)))?
+        .iter()
+        .enumerate()
+        .map(|(i, h)| (h.to_lowercase(), i))
+        .collect();
+
+    for field in mapping.columns.keys() {
+        if !kind.is_field(field) {
+            return Err(CustomError::new(400, format!("Unknown field for {}: {}", kind, field)))
+        };
+    };
+
+    for field in kind.required_fields() {
+        let header = mapping.header_for(field);
+        if !headers.contains_key(&header) {
+            return Err(CustomError::new(400, format!("Missing column {} for field {}", header, field)))
+        };
+    };
+
+    let mut report = ImportReport::new(kind, dry_run);
+    let mut seen: HashMap<String, u64> = HashMap::new();
+
+    let res = conn.transaction::<(), DieselError, _>(|| {
+        let started = transaction_start(conn)?;
+
+        for (i, record) in reader.records().enumerate() {
+            let fallback_line = i as u64 + 2;
+
+            let record = match record {
+                Ok(r) => r,
+                Err(e) => {
+                    report.push(fallback_line, String::new(), RowAction::Error, format!("Unreadable row: {}", e));
+                    continue
+                },
+            };
+
+            let line = record.position().map(|p| p.line()).unwrap_or(fallback_line);
+            let row = MappedRow { headers: &headers, mapping, record: &record };
+            let key = row.natural_key(kind);
+
+            if let Some(first_line) = seen.get(&key) {
+                report.push(line, key, RowAction::Conflict, format!("Duplicate of line {}", first_line));
+                continue
+            };
+            seen.insert(key.clone(), line);
+
+            let res = conn.transaction::<RowOutcome, RowError, _>(|| import_row(conn, kind, &row, started));
+
+            match res {
+                Ok(RowOutcome::Created) => report.push(line, key, RowAction::Create, String::new()),
+                Ok(RowOutcome::Updated(fields)) => report.push(line, key, RowAction::Update, fields.join(", ")),
+                Ok(RowOutcome::Unchanged) => report.push(line, key, RowAction::Unchanged, String::new()),
+                Err(RowError::Conflict(m)) => report.push(line, key, RowAction::Conflict, m),
+                Err(RowError::Invalid(m)) => report.push(line, key, RowAction::Error, m),
+                Err(RowError::Database(e)) => report.push(line, key, RowAction::Error, e.to_string()),
+            };
+        };
+
+        if dry_run || report.has_failures() {
+            Err(DieselError::RollbackTransaction)
+        } else {
+            Ok(())
+        }
+    });
+
+    match res {
+        Ok(()) => report.committed = true,
+        Err(DieselError::RollbackTransaction) => (),
+        Err(e) => return Err(CustomError::from(e)),
+    };
+
+    Ok(report)
+}
+
+fn import_row(conn: &PgConnection, kind: ImportKind, row: &MappedRow, started: NaiveDateTime) -> Result<RowOutcome, RowError> {
+    match kind {
+        ImportKind::Organizations => import_organization(conn, row, started),
+        ImportKind::OrgTiers => import_org_tier(conn, row, started),
+        ImportKind::Teams => import_team(conn, row, started),
+        ImportKind::Persons => import_person(conn, row, started),
+        ImportKind::Roles => import_role(conn, row),
+    }
+}
+
+/// Start of the import transaction. New rows are stamped with it, as NOW() is the
+/// transaction start, so a row get_or_create returns with this created_at is new.
+/// Rows from earlier lines have other natural keys, so are never returned.
+fn transaction_start(conn: &PgConnection) -> Result<NaiveDateTime, DieselError> {
+    diesel::select(diesel::dsl::now).get_result::<NaiveDateTime>(conn)
+}
+
+/// Overwrite `current` with `value` and record the field name if they differ
+fn set_if_changed<T: PartialEq + Clone>(current: &mut T, value: &T, name: &'static str, changed: &mut Vec<&'static str>) {
+    if current != value {
+        *current = value.clone();
+        changed.push(name);
+    };
+}
+
+fn find_organization(conn: &PgConnection, acronym: &str) -> Result<Organization, RowError> {
+    let acronym = acronym.to_uppercase();
+
+    organizations::table
+        .filter(organizations::acronym_en.eq(&acronym).or(organizations::acronym_fr.eq(&acronym)))
+        .first::<Organization>(conn)
+        .optional()?
+        .ok_or_else(|| RowError::Invalid(format!("Unknown organization: {}", acronym)))
+}
+
+fn find_org_tier(conn: &PgConnection, organization_id: Uuid, name_en: &str) -> Result<Option<OrgTier>, RowError> {
+    let tier = org_tiers::table
+        .filter(org_tiers::organization_id.eq(organization_id))
+        .filter(org_tiers::name_en.eq(name_en))
+        .first::<OrgTier>(conn)
+        .optional()?;
+
+    Ok(tier)
+}
+
+fn find_team(conn: &PgConnection, organization_id: Uuid, name_en: &str) -> Result<Option<Team>, RowError> {
+    let team = teams::table
+        .filter(teams::organization_id.eq(organization_id))
+        .filter(teams::name_en.eq(name_en))
+        .first::<Team>(conn)
+        .optional()?;
+
+    Ok(team)
+}
+
+fn import_organization(conn: &PgConnection, row: &MappedRow, started: NaiveDateTime) -> Result<RowOutcome, RowError> {
+    let org_type = row.required("org_type")?.to_lowercase();
+
+    if !ORG_TYPES.contains(&org_type.as_str()) {
+        return Err(RowError::Invalid(format!("Unknown org_type: {}", org_type)))
+    };
+
+    let new_organization = NewOrganization::new(
+        row.required("name_en")?.to_owned(),
+        row.required("name_fr")?.to_owned(),
+        row.required("acronym_en")?.to_uppercase(),
+        row.required("acronym_fr")?.to_uppercase(),
+        org_type,
+    );
+
+    let mut organization = Organization::get_or_create(conn, &new_organization)?;
+
+    if organization.created_at >= started {
+        return Ok(RowOutcome::Created)
+    };
+
+    let mut changed = Vec::new();
+    set_if_changed(&mut organization.name_en, &new_organization.name_en, "name_en", &mut changed);
+    set_if_changed(&mut organization.name_fr, &new_organization.name_fr, "name_fr", &mut changed);
+    set_if_changed(&mut organization.acronym_fr, &new_organization.acronym_fr, "acronym_fr", &mut changed);
+    set_if_changed(&mut organization.org_type, &new_organization.org_type, "org_type", &mut changed);
+
+    if changed.is_empty() {
+        return Ok(RowOutcome::Unchanged)
+    };
+
+    organization.updated_at = Utc::now().naive_utc();
+
+    diesel::update(organizations::table.find(organization.id))
+        .set(&organization)
+        .execute(conn)?;
+
+    Ok(RowOutcome::Updated(changed))
+}
+
+fn import_org_tier(conn: &PgConnection, row: &MappedRow, started: NaiveDateTime) -> Result<RowOutcome, RowError> {
+    let organization = find_organization(conn, row.required("organization")?)?;
+    let name_en = row.required("name_en")?;
+    let name_fr = row.required("name_fr")?;
+
+    let existing = find_org_tier(conn, organization.id, name_en)?;
+
+    let (parent_tier, tier_level) = match row.get("parent_tier") {
+        None => (None, 1),
+        Some(parent_name) => {
+            let parent = find_org_tier(conn, organization.id, parent_name)?
+                .ok_or_else(|| RowError::Invalid(format!("Unknown parent tier: {}", parent_name)))?;
+
+            if let Some(tier) = &existing {
+                if creates_cycle(conn, tier.id, &parent)? {
+                    return Err(RowError::Conflict(format!("{} cannot be placed under {}", name_en, parent_name)))
+                };
+            };
+
+            (Some(parent.id), parent.tier_level + 1)
+        },
+    };
+
+    let new_tier = NewOrgTier::new(
+        organization.id,
+        tier_level,
+        name_en.to_owned(),
+        name_fr.to_owned(),
+        parent_tier,
+    );
+
+    let mut tier = OrgTier::get_or_create(conn, &new_tier)?;
+
+    if tier.created_at >= started {
+        return Ok(RowOutcome::Created)
+    };
+
+    let mut changed = Vec::new();
+    set_if_changed(&mut tier.name_fr, &name_fr.to_owned(), "name_fr", &mut changed);
+    set_if_changed(&mut tier.parent_tier, &parent_tier, "parent_tier", &mut changed);
+    set_if_changed(&mut tier.tier_level, &tier_level, "tier_level", &mut changed);
+
+    if changed.is_empty() {
+        return Ok(RowOutcome::Unchanged)
+    };
+
+    tier.updated_at = Utc::now().naive_utc();
+
+    diesel::update(org_tiers::table.find(tier.id))
+        .set(&tier)
+        .execute(conn)?;
+
+    Ok(RowOutcome::Updated(changed))
+}
+
+/// True if `tier_id` is `parent` or one of its ancestors. Reads through
+/// the caller's transaction so tiers created earlier in the file are seen.
+pub(crate) fn creates_cycle(conn: &PgConnection, tier_id: Uuid, parent: &OrgTier) -> Result<bool, DieselError> {
+    let mut current = Some(parent.id);
+
+    while let Some(id) = current {
+        if id == tier_id {
+            return Ok(true)
+        };
+
+        current = org_tiers::table
+            .find(id)
+            .select(org_tiers::parent_tier)
+            .first::<Option<Uuid>>(conn)?;
+    };
+
+    Ok(false)
+}
+
+fn import_team(conn: &PgConnection, row: &MappedRow, started: NaiveDateTime) -> Result<RowOutcome, RowError> {
+    let organization = find_organization(conn, row.required("organization")?)?;
+    let tier_name = row.required("org_tier")?;
+    let name_en = row.required("name_en")?;
+    let name_fr = row.required("name_fr")?;
+
+    let tier = find_org_tier(conn, organization.id, tier_name)?
+        .ok_or_else(|| RowError::Invalid(format!("Unknown org tier: {}", tier_name)))?;
+
+    let new_team = NewTeam::new(
+        name_en.to_owned(),
+        name_fr.to_owned(),
+        organization.id,
+        tier.id,
+        row.get("description_en").unwrap_or("").to_owned(),
+        row.get("description_fr").unwrap_or("").to_owned(),
+    );
+
+    let mut team = Team::get_or_create(conn, &new_team)?;
+
+    if team.created_at >= started {
+        return Ok(RowOutcome::Created)
+    };
+
+    let mut changed = Vec::new();
+    set_if_changed(&mut team.name_fr, &name_fr.to_owned(), "name_fr", &mut changed);
+    set_if_changed(&mut team.org_tier_id, &tier.id, "org_tier", &mut changed);
+
+    // Descriptions are only overwritten when the file provides them
+    if let Some(description_en) = row.get("description_en") {
+        set_if_changed(&mut team.description_en, &description_en.to_owned(), "description_en", &mut changed);
+    };
+    if let Some(description_fr) = row.get("description_fr") {
+        set_if_changed(&mut team.description_fr, &description_fr.to_owned(), "description_fr", &mut changed);
+    };
+
+    if changed.is_empty() {
+        return Ok(RowOutcome::Unchanged)
+    };
+
+    team.updated_at = Utc::now().naive_utc();
+
+    diesel::update(teams::table.find(team.id))
+        .set(&team)
+        .execute(conn)?;
+
+    Ok(RowOutcome::Updated(changed))
+}
+
+fn import_person(conn: &PgConnection, row: &MappedRow, started: NaiveDateTime) -> Result<RowOutcome, RowError> {
+    let employee_number = row.required("employee_number")?;
+    let family_name = row.required("family_name")?.to_owned();
+    let given_name = row.required("given_name")?.to_owned();
+    let organization = find_organization(conn, row.required("organization")?)?;
+
+    let new_person = NewPerson::new(
+        None,
+        family_name.clone(),
+        given_name.clone(),
+        organization.id,
+        Some(employee_number.to_owned()),
+    );
+
+    let mut person = Person::get_or_create(conn, &new_person)?;
+
+    if person.created_at >= started {
+        return Ok(RowOutcome::Created)
+    };
+
+    let mut changed = Vec::new();
+    set_if_changed(&mut person.family_name, &family_name, "family_name", &mut changed);
+    set_if_changed(&mut person.given_name, &given_name, "given_name", &mut changed);
+    set_if_changed(&mut person.organization_id, &organization.id, "organization", &mut changed);
+
+    if changed.is_empty() {
+        return Ok(RowOutcome::Unchanged)
+    };
+
+    person.updated_at = Utc::now().naive_utc();
+
+    diesel::update(persons::table.find(person.id))
+        .set(&person)
+        .execute(conn)?;
+
+    Ok(RowOutcome::Updated(changed))
+}
+
+/// Roles are matched on (person, team, start date, role type, English title) and go
+/// through the role lifecycle, so each change is recorded as a RoleEvent. A current
+/// substantive role is a transfer, closing the person's substantive role and any acting
+/// assignment or secondment. A current temporary role suspends the substantive role
+/// like an acting assignment or secondment does. Roles that have already ended are
+/// recorded as past roles without touching current ones.
+fn import_role(conn: &PgConnection, row: &MappedRow) -> Result<RowOutcome, RowError> {
+    let employee_number = row.required("employee_number")?;
+    let organization = find_organization(conn, row.required("organization")?)?;
+    let team_name = row.required("team")?;
+    let title_en = row.required("title_en")?.to_owned();
+    let title_fr = row.required("title_fr")?.to_owned();
+
+    let start_datestamp = row.date("start_date")?
+        .ok_or_else(|| RowError::Invalid("Missing value for start_date".to_string()))?;
+    let end_date = row.date("end_date")?;
+
+    if let Some(end) = end_date {
+        if end < start_datestamp {
+            return Err(RowError::Invalid("end_date is before start_date".to_string()))
+        };
+    };
+
+    let effort = match row.get("effort") {
+        Some(v) => v.parse::<f64>()
+            .ok()
+            .filter(|e| (0.0..=1.0).contains(e))
+            .ok_or_else(|| RowError::Invalid(format!("Invalid effort: {} (expected 0.0 to 1.0)", v)))?,
+        None => 1.0,
+    };
+
+    let role_type = match row.get("role_type") {
+        Some(v) => RoleType::from_str(&v.to_lowercase())
+            .map_err(|e| RowError::Invalid(e.error_message))?,
+        None => RoleType::Substantive,
+    };
+
+    if role_type.is_temporary() && end_date.is_none() {
+        return Err(RowError::Invalid(format!("A {} role requires an end_date", role_type)))
+    };
+
+    let person = persons::table
+        .filter(persons::employee_number.eq(employee_number))
+        .first::<Person>(conn)
+        .optional()?
+        .ok_or_else(|| RowError::Invalid(format!("Unknown employee number: {}", employee_number)))?;
+
+    let team = find_team(conn, organization.id, team_name)?
+        .ok_or_else(|| RowError::Invalid(format!("Unknown team: {}", team_name)))?;
+
+    let now = Utc::now().naive_utc();
+
+    let new_role = NewRole::new(
+        Uuid::new_v4(),
+        person.id,
+        team.id,
+        title_en,
+        title_fr,
+        effort,
+        true,
+        start_datestamp,
+        end_date,
+        role_type,
+        None,
+    );
+
+    let mut role = match Role::find_by_natural_key(conn, &new_role)? {
+        Some(r) => r,
+        None => {
+            match end_date {
+                Some(end) if end <= now => Role::record_past(conn, &new_role, IMPORT_NOTE)?,
+                _ if role_type.is_temporary() => Role::assign_temporary(conn, &new_role, role_type, IMPORT_NOTE)?,
+                _ => Role::transfer(conn, &new_role, IMPORT_NOTE)?,
+            };
+            return Ok(RowOutcome::Created)
+        },
+    };
+
+    let mut changed = Vec::new();
+
+    // Ending a role goes through the lifecycle first, as it reloads the role
+    let ended = !role.active && role.end_date.is_some();
+
+    match end_date {
+        None if ended => {
+            return Err(RowError::Invalid("An ended role can't be reopened by import; add a new role instead".to_string()))
+        },
+        Some(end) if end <= now && !ended => {
+            role = role.end_role(conn, end, IMPORT_NOTE)?;
+            changed.push("end_date");
+        },
+        _ => set_if_changed(&mut role.end_date, &end_date, "end_date", &mut changed),
+    };
+
+    set_if_changed(&mut role.title_fr, &new_role.title_fr, "title_fr", &mut changed);
+    set_if_changed(&mut role.effort, &effort, "effort", &mut changed);
+
+    if changed.is_empty() {
+        return Ok(RowOutcome::Unchanged)
+    };
+
+    role.updated_at = Utc::now().naive_utc();
+
+    diesel::update(roles::table.find(role.id))
+        .set(&role)
+        .execute(conn)?;
+
+    Ok(RowOutcome::Updated(changed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    use crate::generate_unique_code;
+    use crate::models::{RoleEvent, RoleEventType};
+
+    fn unique_violation() -> DieselError {
+        DieselError::DatabaseError(DatabaseErrorKind::UniqueViolation, Box::new("duplicate key value".to_string()))
+    }
+
+    #[test]
+    fn column_mapping_reads_pairs() {
+        let mapping = ColumnMapping::parse("employee_number=EMPLID,\n family_name = Last Name").unwrap();
+
+        assert_eq!(mapping.header_for("employee_number"), "emplid");
+        assert_eq!(mapping.header_for("family_name"), "last name");
+        assert_eq!(mapping.header_for("given_name"), "given_name");
+        assert!(ColumnMapping::parse("employee_number").is_err());
+    }
+
+    #[test]
+    fn unique_violations_are_conflicts() {
+        assert!(matches!(RowError::from(unique_violation()), RowError::Conflict(_)));
+        assert!(matches!(RowError::from(DieselError::NotFound), RowError::Database(_)));
+
+        // As returned through graphql_translate by the model upserts
+        assert!(matches!(RowError::from(FieldError::from(unique_violation())), RowError::Conflict(_)));
+        assert!(matches!(RowError::from(FieldError::new("Temporary roles require an end date")), RowError::Invalid(_)));
+    }
+
+    fn import(conn: &PgConnection, kind: ImportKind, csv: &str, dry_run: bool) -> ImportReport {
+        import_csv(conn, kind, csv.as_bytes(), &ColumnMapping::default(), dry_run).unwrap()
+    }
+
+    /// Runs against DATABASE_URL in a transaction that is rolled back:
+    /// cargo test -- --ignored
+    /// Rows created earlier in the test share its transaction start, so a
+    /// second import of them still reports create rather than unchanged.
+    #[test]
+    #[ignore]
+    fn imports_an_organization_through_roles() {
+        let conn = PgConnection::establish(&env::var("DATABASE_URL").expect("DATABASE_URL must be set"))
+            .expect("Unable to connect to DATABASE_URL");
+
+        conn.test_transaction::<_, CustomError, _>(|| {
+            let acronym = format!("CSV{}", generate_unique_code(6, false).to_uppercase());
+            let employee = format!("{}-1", acronym);
+
+            let organizations = format!("name_en,name_fr,acronym_en,acronym_fr,org_type\n\
+                CSV Test,Test CSV,{0},{0},department\n", acronym);
+            let tiers = format!("organization,name_en,name_fr,parent_tier\n\
+                {0},Branch,Direction,\n\
+                {0},Division,Division,Branch\n", acronym);
+            let teams = format!("organization,org_tier,name_en,name_fr\n\
+                {0},Division,Data,Données\n\
+                {0},Division,Policy,Politiques\n", acronym);
+            let persons = format!("employee_number,family_name,given_name,organization\n\
+                {},Carberry,Josiah,{}\n", employee, acronym);
+
+            // A dry run saves nothing
+            let report = import(&conn, ImportKind::Organizations, &organizations, true);
+            assert_eq!((report.created, report.committed), (1, false));
+            assert!(find_organization(&conn, &acronym).is_err());
+
+            for (kind, csv, created) in [
+                (ImportKind::Organizations, &organizations, 1),
+                (ImportKind::OrgTiers, &tiers, 2),
+                (ImportKind::Teams, &teams, 2),
+                (ImportKind::Persons, &persons, 1),
+            ] {
+                let report = import(&conn, kind, csv, false);
+                assert!(report.committed, "{}", report);
+                assert_eq!(report.created, created);
+            };
+
+            let roles = format!("employee_number,organization,team,title_en,title_fr,start_date,end_date,role_type\n\
+                {0},{1},Data,Analyst,Analyste,2019-01-01,,\n\
+                {0},{1},Policy,Advisor,Conseiller,2021-01-01,,\n\
+                {0},{1},Data,Acting manager,Gestionnaire intérimaire,2022-01-01,2022-03-01,acting\n", employee, acronym);
+
+            let report = import(&conn, ImportKind::Roles, &roles, false);
+            assert!(report.committed, "{}", report);
+            assert_eq!(report.created, 3);
+
+            // The second role was a transfer, and the acting assignment is already over
+            let person = persons::table.filter(persons::employee_number.eq(&employee)).first::<Person>(&conn)?;
+            let events: Vec<String> = RoleEvent::timeline(&conn, person.id).unwrap().into_iter().map(|e| e.event_type).collect();
+
+            assert!(events.contains(&RoleEventType::Transferred.to_string()));
+            assert!(events.contains(&RoleEventType::ActingEnded.to_string()));
+
+            let current: Vec<Role> = roles::table
+                .filter(roles::person_id.eq(person.id))
+                .filter(roles::active.eq(true))
+                .load(&conn)?;
+            assert_eq!(current.len(), 1);
+            assert_eq!(current[0].title_en, "Advisor");
+
+            // Reopening the ended analyst role is refused
+            let reopen = format!("employee_number,organization,team,title_en,title_fr,start_date\n\
+                {},{},Data,Analyst,Analyste,2019-01-01\n", employee, acronym);
+            let report = import(&conn, ImportKind::Roles, &reopen, false);
+            assert_eq!((report.errors, report.committed), (1, false));
+
+            Ok(())
+        });
+    }
+
+    #[test]
+    #[ignore]
+    fn bad_rows_roll_back_the_whole_file() {
+        let conn = PgConnection::establish(&env::var("DATABASE_URL").expect("DATABASE_URL must be set"))
+            .expect("Unable to connect to DATABASE_URL");
+
+        conn.test_transaction::<_, CustomError, _>(|| {
+            let acronym = format!("CSV{}", generate_unique_code(6, false).to_uppercase());
+
+            let organizations = format!("name_en,name_fr,acronym_en,acronym_fr,org_type\n\
+                CSV Test,Test CSV,{0},{0},department\n\
+                CSV Test again,Test CSV encore,{0},{0},department\n\
+                CSV Test,Test CSV,X{0},X{0},castle\n", acronym);
+
+            let report = import(&conn, ImportKind::Organizations, &organizations, false);
+
+            assert_eq!(report.rows[1].action, RowAction::Conflict);
+            assert_eq!(report.rows[1].message, "Duplicate of line 2");
+            assert_eq!(report.rows[2].action, RowAction::Error);
+            assert!(report.has_failures());
+            assert!(!report.committed);
+            assert!(find_organization(&conn, &acronym).is_err());
+
+            // Missing required columns are refused before any row is read
+            assert!(import_csv(&conn, ImportKind::Persons, "employee_number,family_name\n1,Carberry\n".as_bytes(),
+                &ColumnMapping::default(), false).is_err());
+
+            Ok(())
+        });
+    }
+}
@@ -0,0 +1,1126 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::io::Read;
+
+use chrono::{NaiveDate, NaiveDateTime, Utc};
+use diesel::prelude::*;
+use diesel::PgConnection;
+use diesel::result::Error as DieselError;
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::errors::CustomError;
+use crate::importer::{creates_cycle, ColumnMapping, MappedRow};
+use crate::models::{HrChangeStatus, HrSyncChange, HrSyncRun, LanguageRequirement, NewHrSyncChange, NewHrSyncRun,
+    NewOrgTier, NewPerson, NewReportingRelationship, NewRole, OrgTier, Person, ReportingRelationship,
+    Role, RoleType, Team};
+use crate::schema::*;
+
+/// Columns read from an HRMS extract. Use a ColumnMapping for extracts
+/// with different headers, e.g. `employee_id=EMPLID`.
+pub const HR_EXTRACT_REQUIRED_FIELDS: [&str; 4] = ["employee_id", "family_name", "given_name", "org_unit"];
+pub const HR_EXTRACT_OPTIONAL_FIELDS: [&str; 11] = [
+    "org_unit_name_en",
+    "org_unit_name_fr",
+    "parent_org_unit",
+    "position_number",
+    "position_title_en",
+    "position_title_fr",
+    "classification",
+    "language_requirement",
+    "reports_to",
+    "effective_date",
+    "retirement_eligible_on",
+];
+
+const ORG_TIER: &str = "org_tier";
+const PERSON: &str = "person";
+const ROLE: &str = "role";
+
+/// One employee line of an extract
+struct ExtractRow {
+    employee_id: String,
+    family_name: String,
+    given_name: String,
+    org_unit: String,
+    position_number: Option<String>,
+    position_title_en: Option<String>,
+    position_title_fr: Option<String>,
+    classification: Option<String>,
+    language_requirement: Option<String>, // LanguageRequirement
+    reports_to: Option<String>,
+    effective_date: NaiveDateTime,
+    retirement_eligible_on: Option<NaiveDate>,
+}
+
+/// An org unit as described by the extract
+struct OrgUnit {
+    code: String,
+    name_en: Option<String>,
+    name_fr: Option<String>,
+    parent: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HrSyncReport {
+    pub run: HrSyncRun,
+    /// Rows or positions that could not be placed and were skipped
+    pub warnings: Vec<String>,
+}
+
+impl fmt::Display for HrSyncReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for warning in &self.warnings {
+            writeln!(f, "warning: {}", warning)?;
+        };
+
+        write!(f, "{} extract {}: {} rows, {} changes applied, {} conflicts flagged",
+            self.run.source, self.run.file_name, self.run.rows_read, self.run.changes_applied, self.run.conflicts)
+    }
+}
+
+enum SyncError {
+    Database(DieselError),
+    Invalid(String),
+}
+
+impl From<DieselError> for SyncError {
+    fn from(error: DieselError) -> SyncError {
+        SyncError::Database(error)
+    }
+}
+
+impl From<SyncError> for CustomError {
+    fn from(error: SyncError) -> CustomError {
+        match error {
+            SyncError::Database(e) => CustomError::from(e),
+            SyncError::Invalid(m) => CustomError::new(400, m),
+        }
+    }
+}
+
+/// Reconciles one extract against the database inside a single transaction
+struct HrSync<'a> {
+    conn: &'a PgConnection,
+    run: HrSyncRun,
+    organization_id: Uuid,
+    applied: i32,
+    conflicts: i32,
+    warnings: Vec<String>,
+}
+
+/// Synchronize persons, org tiers, roles and reporting lines from an HRMS
+/// flat-file extract. Records are matched on employee ID, org unit code and
+/// position number. Every change is recorded in hr_sync_changes with the run
+/// it came from. A field is only overwritten if its current value was set by
+/// a previous sync; values entered in the app are flagged as conflicts for
+/// an admin to accept or keep.
+pub fn sync_hr_extract<R: Read>(
+    conn: &PgConnection,
+    input: R,
+    source: &str,
+    file_name: &str,
+    organization_id: Uuid,
+    delimiter: u8,
+    mapping: &ColumnMapping,
+) -> Result<HrSyncReport, CustomError> {
+
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .trim(csv::Trim::All)
+        .from_reader(input);
+
+    let headers: HashMap<String, usize> = reader.headers()
+        .map_err(|e| CustomError::new(400, format!("Unable to read extract headers: {}", e)))?
+        .iter()
+        .enumerate()
+        .map(|(i, h)| (h.to_lowercase(), i))
+        .collect();
+
+    for field in HR_EXTRACT_REQUIRED_FIELDS.iter() {
+        let header = mapping.header_for(field);
+        if !headers.contains_key(&header) {
+            return Err(CustomError::new(400, format!("Missing column {} for field {}", header, field)))
+        };
+    };
+
+    let mut warnings = Vec::new();
+    let mut rows: Vec<ExtractRow> = Vec::new();
+    let mut units: Vec<OrgUnit> = Vec::new();
+
+    for (i, record) in reader.records().enumerate() {
+        let line = i + 2;
+
+        let record = match record {
+            Ok(r) => r,
+            Err(e) => {
+                warnings.push(format!("line {}: unreadable row: {}", line, e));
+                continue
+            },
+        };
+
+        let row = MappedRow { headers: &headers, mapping, record: &record };
+
+        match parse_row(&row) {
+            Ok((extract_row, unit)) => {
+                match units.iter_mut().find(|u| u.code == unit.code) {
+                    // Fill in names or parent from any row that has them
+                    Some(existing) => {
+                        existing.name_en = existing.name_en.take().or(unit.name_en);
+                        existing.name_fr = existing.name_fr.take().or(unit.name_fr);
+                        existing.parent = existing.parent.take().or(unit.parent);
+                    },
+                    None => units.push(unit),
+                };
+                rows.push(extract_row);
+            },
+            Err(m) => warnings.push(format!("line {}: {}", line, m)),
+        };
+    };
+
+    let rows_read = rows.len() as i32;
+
+    let run = conn.transaction::<HrSyncRun, SyncError, _>(|| {
+        let run = HrSyncRun::create(conn, &NewHrSyncRun::new(
+            source.to_owned(),
+            file_name.to_owned(),
+            organization_id,
+        ))?;
+
+        let mut sync = HrSync {
+            conn,
+            run,
+            organization_id,
+            applied: 0,
+            conflicts: 0,
+            warnings: Vec::new(),
+        };
+
+        let mut tiers: HashMap<String, OrgTier> = HashMap::new();
+        for unit in &units {
+            let tier = sync.sync_org_unit(unit)?;
+            tiers.insert(unit.code.clone(), tier);
+        };
+
+        // Parents are linked once every unit in the extract exists
+        for unit in &units {
+            sync.sync_org_unit_parent(unit, &tiers)?;
+        };
+
+        let mut persons: HashMap<String, Person> = HashMap::new();
+        for row in &rows {
+            if let Some(person) = sync.sync_person(row)? {
+                persons.insert(row.employee_id.clone(), person);
+            };
+        };
+
+        // Rows for people of another organization stop at the person
+        let synced: Vec<&ExtractRow> = rows.iter().filter(|r| persons.contains_key(&r.employee_id)).collect();
+
+        for row in &synced {
+            let person = &persons[&row.employee_id];
+            let tier = &tiers[&row.org_unit];
+            sync.sync_position(row, person, tier)?;
+        };
+
+        for row in &synced {
+            sync.sync_reporting_line(row, &persons)?;
+        };
+
+        warnings.append(&mut sync.warnings);
+
+        let run = sync.run.complete(conn, rows_read, sync.applied, sync.conflicts)?;
+
+        Ok(run)
+    })?;
+
+    Ok(HrSyncReport { run, warnings })
+}
+
+fn parse_row(row: &MappedRow) -> Result<(ExtractRow, OrgUnit), String> {
+    let required = |field: &str| row.get(field)
+        .map(|v| v.to_owned())
+        .ok_or_else(|| format!("missing value for {}", field));
+
+    let optional = |field: &str| row.get(field).map(|v| v.to_owned());
+
+    let effective_date = match row.get("effective_date") {
+        Some(v) => NaiveDate::parse_from_str(v, "%Y-%m-%d")
+            .map(|d| d.and_hms(0, 0, 0))
+            .map_err(|_| format!("invalid effective_date: {} (expected YYYY-MM-DD)", v))?,
+        None => Utc::now().naive_utc(),
+    };
+
+    let language_requirement = match row.get("language_requirement") {
+        Some(v) => Some(v.parse::<LanguageRequirement>()
+            .map_err(|_| format!("invalid language_requirement: {} (expected english_essential, french_essential, either_or or a profile e.g. CBC)", v))?
+            .to_string()),
+        None => None,
+    };
+
+    let retirement_eligible_on = match row.get("retirement_eligible_on") {
+        Some(v) => Some(NaiveDate::parse_from_str(v, "%Y-%m-%d")
+            .map_err(|_| format!("invalid retirement_eligible_on: {} (expected YYYY-MM-DD)", v))?),
+        None => None,
+    };
+
+    let org_unit = required("org_unit")?.to_uppercase();
+
+    let extract_row = ExtractRow {
+        employee_id: required("employee_id")?,
+        family_name: required("family_name")?,
+        given_name: required("given_name")?,
+        org_unit: org_unit.clone(),
+        position_number: optional("position_number"),
+        position_title_en: optional("position_title_en"),
+        position_title_fr: optional("position_title_fr"),
+        classification: optional("classification").map(|c| c.to_uppercase()),
+        language_requirement,
+        reports_to: optional("reports_to"),
+        effective_date,
+        retirement_eligible_on,
+    };
+
+    let unit = OrgUnit {
+        code: org_unit,
+        name_en: optional("org_unit_name_en"),
+        name_fr: optional("org_unit_name_fr"),
+        parent: optional("parent_org_unit").map(|p| p.to_uppercase()),
+    };
+
+    Ok((extract_row, unit))
+}
+
+impl<'a> HrSync<'a> {
+    fn record(&mut self, hr_key: &str, entity_type: &str, entity_id: Uuid, field_name: &str,
+        old_value: Option<String>, new_value: Option<String>, status: HrChangeStatus) -> Result<(), DieselError> {
+
+        HrSyncChange::record(self.conn, &NewHrSyncChange::new(
+            self.run.id,
+            hr_key,
+            entity_type,
+            entity_id,
+            field_name,
+            old_value,
+            new_value,
+            status,
+        ))?;
+
+        match status {
+            HrChangeStatus::Conflict => self.conflicts += 1,
+            _ => self.applied += 1,
+        };
+
+        Ok(())
+    }
+
+    /// Record the initial values of an entity the sync created
+    fn record_created(&mut self, hr_key: &str, entity_type: &str, entity_id: Uuid, fields: &[(&str, Option<String>)]) -> Result<(), DieselError> {
+        for (field_name, value) in fields {
+            self.record(hr_key, entity_type, entity_id, field_name, None, value.clone(), HrChangeStatus::Applied)?;
+        };
+
+        Ok(())
+    }
+
+    /// Decide whether the HRMS value may replace the current one and record
+    /// the outcome. Returns true if the caller should write the new value.
+    fn reconcile(&mut self, hr_key: &str, entity_type: &str, entity_id: Uuid, field_name: &str,
+        current: Option<&str>, incoming: Option<&str>) -> Result<bool, DieselError> {
+
+        if current == incoming {
+            return Ok(false)
+        };
+
+        let last = HrSyncChange::last_settled(self.conn, entity_id, field_name)?;
+
+        let hr_owned = match &last {
+            Some(change) if change.status == HrChangeStatus::Kept.to_string() => {
+                // An admin already chose the app value over this HRMS value
+                if change.new_value.as_deref() == incoming {
+                    return Ok(false)
+                };
+                false
+            },
+            // HRMS set the current value and it has not been edited since
+            Some(change) => change.new_value.as_deref() == current,
+            None => false,
+        };
+
+        if hr_owned {
+            self.record(hr_key, entity_type, entity_id, field_name,
+                current.map(|v| v.to_owned()), incoming.map(|v| v.to_owned()), HrChangeStatus::Applied)?;
+            return Ok(true)
+        };
+
+        if let Some(open) = HrSyncChange::open_conflict(self.conn, entity_id, field_name)? {
+            if open.new_value.as_deref() == incoming {
+                return Ok(false)
+            };
+            open.resolve(self.conn, HrChangeStatus::Superseded)?;
+        };
+
+        self.record(hr_key, entity_type, entity_id, field_name,
+            current.map(|v| v.to_owned()), incoming.map(|v| v.to_owned()), HrChangeStatus::Conflict)?;
+
+        Ok(false)
+    }
+
+    fn sync_org_unit(&mut self, unit: &OrgUnit) -> Result<OrgTier, SyncError> {
+        let existing = org_tiers::table
+            .filter(org_tiers::hr_org_unit.eq(&unit.code))
+            .first::<OrgTier>(self.conn)
+            .optional()?;
+
+        let mut tier = match existing {
+            Some(t) => t,
+            None => {
+                // Adopt an unlinked tier with the same name before creating one
+                let unlinked = match &unit.name_en {
+                    Some(name_en) => org_tiers::table
+                        .filter(org_tiers::organization_id.eq(self.organization_id))
+                        .filter(org_tiers::name_en.eq(name_en))
+                        .filter(org_tiers::hr_org_unit.is_null())
+                        .first::<OrgTier>(self.conn)
+                        .optional()?,
+                    None => None,
+                };
+
+                match unlinked {
+                    Some(t) => {
+                        let t: OrgTier = diesel::update(org_tiers::table.find(t.id))
+                            .set(org_tiers::hr_org_unit.eq(&unit.code))
+                            .get_result(self.conn)?;
+
+                        self.record(&unit.code, ORG_TIER, t.id, "hr_org_unit", None, Some(unit.code.clone()), HrChangeStatus::Applied)?;
+                        t
+                    },
+                    None => {
+                        let name_en = unit.name_en.clone().unwrap_or_else(|| unit.code.clone());
+                        let name_fr = unit.name_fr.clone().unwrap_or_else(|| name_en.clone());
+
+                        let t: OrgTier = diesel::insert_into(org_tiers::table)
+                            .values(&NewOrgTier::new(self.organization_id, 1, name_en.clone(), name_fr.clone(), None))
+                            .get_result(self.conn)?;
+
+                        let t: OrgTier = diesel::update(org_tiers::table.find(t.id))
+                            .set(org_tiers::hr_org_unit.eq(&unit.code))
+                            .get_result(self.conn)?;
+
+                        self.record_created(&unit.code, ORG_TIER, t.id, &[
+                            ("hr_org_unit", Some(unit.code.clone())),
+                            ("name_en", Some(name_en)),
+                            ("name_fr", Some(name_fr)),
+                            ("parent_org_unit", None),
+                        ])?;
+
+                        return Ok(t)
+                    },
+                }
+            },
+        };
+
+        let mut changed = false;
+
+        if let Some(name_en) = &unit.name_en {
+            if self.reconcile(&unit.code, ORG_TIER, tier.id, "name_en", Some(tier.name_en.as_str()), Some(name_en.as_str()))? {
+                tier.name_en = name_en.to_owned();
+                changed = true;
+            };
+        };
+
+        if let Some(name_fr) = &unit.name_fr {
+            if self.reconcile(&unit.code, ORG_TIER, tier.id, "name_fr", Some(tier.name_fr.as_str()), Some(name_fr.as_str()))? {
+                tier.name_fr = name_fr.to_owned();
+                changed = true;
+            };
+        };
+
+        if changed {
+            tier.updated_at = Utc::now().naive_utc();
+            tier = diesel::update(org_tiers::table.find(tier.id))
+                .set(&tier)
+                .get_result(self.conn)?;
+        };
+
+        Ok(tier)
+    }
+
+    fn sync_org_unit_parent(&mut self, unit: &OrgUnit, tiers: &HashMap<String, OrgTier>) -> Result<(), SyncError> {
+        let tier = &tiers[&unit.code];
+
+        let current_parent = match tier.parent_tier {
+            Some(parent_id) => Some(org_tiers::table.find(parent_id).first::<OrgTier>(self.conn)?),
+            None => None,
+        };
+
+        // Parents without an org unit code were placed in the app
+        let current = current_parent.as_ref()
+            .map(|p| p.hr_org_unit.clone().unwrap_or_else(|| p.name_en.clone()));
+
+        if !self.reconcile(&unit.code, ORG_TIER, tier.id, "parent_org_unit", current.as_deref(), unit.parent.as_deref())? {
+            return Ok(())
+        };
+
+        set_tier_parent(self.conn, tier, unit.parent.as_deref())
+    }
+
+    /// People found in another organization are left alone and flagged as an
+    /// organization conflict, so the caller skips their position and manager
+    fn sync_person(&mut self, row: &ExtractRow) -> Result<Option<Person>, SyncError> {
+        let existing = persons::table
+            .filter(persons::employee_number.eq(&row.employee_id))
+            .first::<Person>(self.conn)
+            .optional()?;
+
+        let mut person = match existing {
+            Some(p) if p.organization_id != self.organization_id => {
+                let incoming = self.organization_id.to_string();
+                self.warnings.push(format!("{}: employee number belongs to a person in another organization", row.employee_id));
+
+                if let Some(open) = HrSyncChange::open_conflict(self.conn, p.id, "organization")? {
+                    if open.new_value.as_deref() == Some(incoming.as_str()) {
+                        return Ok(None)
+                    };
+                    open.resolve(self.conn, HrChangeStatus::Superseded)?;
+                };
+
+                self.record(&row.employee_id, PERSON, p.id, "organization", Some(p.organization_id.to_string()), Some(incoming), HrChangeStatus::Conflict)?;
+                return Ok(None)
+            },
+            Some(p) => p,
+            None => {
+                let person: Person = diesel::insert_into(persons::table)
+                    .values(&NewPerson::new(
+                        None,
+                        row.family_name.clone(),
+                        row.given_name.clone(),
+                        self.organization_id,
+                        Some(row.employee_id.clone()),
+                    ))
+                    .get_result(self.conn)?;
+
+                let person: Person = diesel::update(persons::table.find(person.id))
+                    .set(persons::retirement_eligible_on.eq(row.retirement_eligible_on))
+                    .get_result(self.conn)?;
+
+                self.record_created(&row.employee_id, PERSON, person.id, &[
+                    ("family_name", Some(row.family_name.clone())),
+                    ("given_name", Some(row.given_name.clone())),
+                    ("retirement_eligible_on", row.retirement_eligible_on.map(|d| d.to_string())),
+                ])?;
+
+                return Ok(Some(person))
+            },
+        };
+
+        let mut changed = false;
+
+        if self.reconcile(&row.employee_id, PERSON, person.id, "family_name", Some(person.family_name.as_str()), Some(row.family_name.as_str()))? {
+            person.family_name = row.family_name.clone();
+            changed = true;
+        };
+
+        if self.reconcile(&row.employee_id, PERSON, person.id, "given_name", Some(person.given_name.as_str()), Some(row.given_name.as_str()))? {
+            person.given_name = row.given_name.clone();
+            changed = true;
+        };
+
+        let current = person.retirement_eligible_on.map(|d| d.to_string());
+        let incoming = row.retirement_eligible_on.map(|d| d.to_string());
+
+        if incoming.is_some() && self.reconcile(&row.employee_id, PERSON, person.id, "retirement_eligible_on", current.as_deref(), incoming.as_deref())? {
+            person.retirement_eligible_on = row.retirement_eligible_on;
+            changed = true;
+        };
+
+        if changed {
+            person.updated_at = Utc::now().naive_utc();
+            person = diesel::update(persons::table.find(person.id))
+                .set(&person)
+                .get_result(self.conn)?;
+        };
+
+        Ok(Some(person))
+    }
+
+    /// Match the extract position to the person's current substantive role.
+    /// Moves between org units are flagged rather than applied, since team
+    /// placement is managed in the app.
+    fn sync_position(&mut self, row: &ExtractRow, person: &Person, tier: &OrgTier) -> Result<(), SyncError> {
+        let position_number = match &row.position_number {
+            Some(p) => p,
+            None => return Ok(()),
+        };
+
+        let current_roles: Vec<(Role, Team)> = roles::table
+            .inner_join(teams::table)
+            .filter(roles::person_id.eq(person.id))
+            .filter(roles::active.eq(true))
+            .filter(roles::role_type.eq(RoleType::Substantive.to_string()))
+            .load(self.conn)?;
+
+        let matched = current_roles.iter()
+            .find(|(r, _)| r.hr_position_number.as_deref() == Some(position_number.as_str()))
+            .or_else(|| current_roles.iter().find(|(r, _)| r.hr_position_number.is_none()));
+
+        let (mut role, team) = match matched {
+            Some((role, team)) => (role.clone(), team.clone()),
+            None if current_roles.is_empty() => {
+                return self.create_role(row, person, tier, position_number)
+            },
+            None => {
+                // The person holds a different position in the app
+                let (role, _) = &current_roles[0];
+                self.reconcile(&row.employee_id, ROLE, role.id, "hr_position_number",
+                    role.hr_position_number.as_deref(), Some(position_number.as_str()))?;
+                return Ok(())
+            },
+        };
+
+        let mut changed = false;
+
+        if role.hr_position_number.is_none() {
+            role.hr_position_number = Some(position_number.clone());
+            self.record(&row.employee_id, ROLE, role.id, "hr_position_number", None, Some(position_number.clone()), HrChangeStatus::Applied)?;
+            changed = true;
+        };
+
+        if let Some(title_en) = &row.position_title_en {
+            if self.reconcile(&row.employee_id, ROLE, role.id, "title_en", Some(role.title_en.as_str()), Some(title_en.as_str()))? {
+                role.title_en = title_en.clone();
+                changed = true;
+            };
+        };
+
+        if let Some(title_fr) = &row.position_title_fr {
+            if self.reconcile(&row.employee_id, ROLE, role.id, "title_fr", Some(role.title_fr.as_str()), Some(title_fr.as_str()))? {
+                role.title_fr = title_fr.clone();
+                changed = true;
+            };
+        };
+
+        if row.classification.is_some() && self.reconcile(&row.employee_id, ROLE, role.id, "classification",
+            role.classification.as_deref(), row.classification.as_deref())? {
+            role.classification = row.classification.clone();
+            changed = true;
+        };
+
+        if row.language_requirement.is_some() && self.reconcile(&row.employee_id, ROLE, role.id, "language_requirement",
+            role.language_requirement.as_deref(), row.language_requirement.as_deref())? {
+            role.language_requirement = row.language_requirement.clone();
+            changed = true;
+        };
+
+        if changed {
+            role.updated_at = Utc::now().naive_utc();
+            diesel::update(roles::table.find(role.id))
+                .set(&role)
+                .execute(self.conn)?;
+        };
+
+        // Only ever flagged: an accepted move is applied as a role transfer
+        if team.org_tier_id != tier.id {
+            let current_unit = org_tiers::table
+                .find(team.org_tier_id)
+                .select(org_tiers::hr_org_unit)
+                .first::<Option<String>>(self.conn)?;
+
+            if current_unit.as_deref() != Some(row.org_unit.as_str()) {
+                if let Some(open) = HrSyncChange::open_conflict(self.conn, role.id, "org_unit")? {
+                    if open.new_value.as_deref() == Some(row.org_unit.as_str()) {
+                        return Ok(())
+                    };
+                    open.resolve(self.conn, HrChangeStatus::Superseded)?;
+                };
+
+                self.record(&row.employee_id, ROLE, role.id, "org_unit", current_unit, Some(row.org_unit.clone()), HrChangeStatus::Conflict)?;
+            };
+        };
+
+        Ok(())
+    }
+
+    fn create_role(&mut self, row: &ExtractRow, person: &Person, tier: &OrgTier, position_number: &str) -> Result<(), SyncError> {
+        let team = match team_for_tier(self.conn, tier)? {
+            Some(t) => t,
+            None => {
+                self.warnings.push(format!("{}: no single team in org unit {} to place position {}", row.employee_id, row.org_unit, position_number));
+                return Ok(())
+            },
+        };
+
+        let title_en = row.position_title_en.clone().unwrap_or_else(|| position_number.to_owned());
+        let title_fr = row.position_title_fr.clone().unwrap_or_else(|| title_en.clone());
+
+        let new_role = NewRole::new(
+            Uuid::new_v4(),
+            person.id,
+            team.id,
+            title_en.clone(),
+            title_fr.clone(),
+            1.0,
+            true,
+            row.effective_date,
+            None,
+            RoleType::Substantive,
+            None,
+        );
+
+        let role = Role::transfer(self.conn, &new_role, &format!("{} sync", self.run.source))
+            .map_err(|e| SyncError::Invalid(e.message))?;
+
+        diesel::update(roles::table.find(role.id))
+            .set((
+                roles::hr_position_number.eq(position_number),
+                roles::classification.eq(&row.classification),
+                roles::language_requirement.eq(&row.language_requirement),
+            ))
+            .execute(self.conn)?;
+
+        self.record_created(&row.employee_id, ROLE, role.id, &[
+            ("hr_position_number", Some(position_number.to_owned())),
+            ("title_en", Some(title_en)),
+            ("title_fr", Some(title_fr)),
+            ("classification", row.classification.clone()),
+            ("language_requirement", row.language_requirement.clone()),
+        ])?;
+
+        Ok(())
+    }
+
+    fn sync_reporting_line(&mut self, row: &ExtractRow, persons: &HashMap<String, Person>) -> Result<(), SyncError> {
+        let reports_to = match &row.reports_to {
+            Some(r) => r,
+            None => return Ok(()),
+        };
+
+        let reporter = &persons[&row.employee_id];
+
+        let manager = match persons.get(reports_to) {
+            Some(m) => m.clone(),
+            None => match persons::table
+                .filter(persons::employee_number.eq(reports_to))
+                .first::<Person>(self.conn)
+                .optional()? {
+                    Some(m) => m,
+                    None => {
+                        self.warnings.push(format!("{}: unknown manager {}", row.employee_id, reports_to));
+                        return Ok(())
+                    },
+                },
+        };
+
+        let current = ReportingRelationship::find_current_by_reporter(self.conn, reporter.id, Utc::now().naive_utc())
+            .map_err(|e| SyncError::Invalid(e.message))?;
+
+        if current.is_empty() {
+            ReportingRelationship::create(self.conn, &NewReportingRelationship::new(
+                reporter.id,
+                manager.id,
+                String::new(),
+                row.effective_date,
+                None,
+            )).map_err(|e| SyncError::Invalid(e.message))?;
+
+            self.record(&row.employee_id, PERSON, reporter.id, "reports_to", None, Some(reports_to.clone()), HrChangeStatus::Applied)?;
+            return Ok(())
+        };
+
+        let current_manager = persons::table
+            .find(current[0].reporting_to)
+            .select(persons::employee_number)
+            .first::<Option<String>>(self.conn)?;
+
+        if self.reconcile(&row.employee_id, PERSON, reporter.id, "reports_to", current_manager.as_deref(), Some(reports_to.as_str()))? {
+            set_reporting_line(self.conn, reporter.id, manager.id, row.effective_date)?;
+        };
+
+        Ok(())
+    }
+}
+
+/// Team that positions in an org tier are placed on: the tier's only
+/// team, or the team sharing the tier's name
+fn team_for_tier(conn: &PgConnection, tier: &OrgTier) -> Result<Option<Team>, DieselError> {
+    let teams = teams::table
+        .filter(teams::org_tier_id.eq(tier.id))
+        .filter(teams::retired_at.is_null())
+        .load::<Team>(conn)?;
+
+    if teams.len() == 1 {
+        return Ok(teams.into_iter().next())
+    };
+
+    Ok(teams.into_iter().find(|t| t.name_en == tier.name_en))
+}
+
+fn set_tier_parent(conn: &PgConnection, tier: &OrgTier, parent_code: Option<&str>) -> Result<(), SyncError> {
+    let parent = match parent_code {
+        Some(code) => Some(org_tiers::table
+            .filter(org_tiers::hr_org_unit.eq(code))
+            .first::<OrgTier>(conn)
+            .optional()?
+            .ok_or_else(|| SyncError::Invalid(format!("Unknown parent org unit: {}", code)))?),
+        None => None,
+    };
+
+    if let Some(parent) = &parent {
+        if creates_cycle(conn, tier.id, parent)? {
+            return Err(SyncError::Invalid(format!("Org unit {} cannot be placed under {}",
+                tier.hr_org_unit.clone().unwrap_or_default(), parent.name_en)))
+        };
+    };
+
+    diesel::update(org_tiers::table.find(tier.id))
+        .set((
+            org_tiers::parent_tier.eq(parent.as_ref().map(|p| p.id)),
+            org_tiers::tier_level.eq(parent.as_ref().map_or(1, |p| p.tier_level + 1)),
+            org_tiers::updated_at.eq(Utc::now().naive_utc()),
+        ))
+        .execute(conn)?;
+
+    Ok(())
+}
+
+fn set_reporting_line(conn: &PgConnection, reporter: Uuid, manager: Uuid, effective_date: NaiveDateTime) -> Result<(), SyncError> {
+    diesel::update(reporting_relationships::table)
+        .filter(reporting_relationships::reporter.eq(reporter))
+        .filter(reporting_relationships::end_date.is_null())
+        .set((
+            reporting_relationships::end_date.eq(effective_date),
+            reporting_relationships::updated_at.eq(Utc::now().naive_utc()),
+        ))
+        .execute(conn)?;
+
+    ReportingRelationship::create(conn, &NewReportingRelationship::new(
+        reporter,
+        manager,
+        String::new(),
+        effective_date,
+        None,
+    )).map_err(|e| SyncError::Invalid(e.message))?;
+
+    Ok(())
+}
+
+/// Resolve an open conflict. Accepting writes the HRMS value to the record;
+/// keeping leaves the app value and stops the same HRMS value being flagged again.
+pub fn resolve_hr_conflict(conn: &PgConnection, change_id: Uuid, accept: bool) -> Result<HrSyncChange, CustomError> {
+    let change = HrSyncChange::find(conn, change_id)?;
+
+    if change.status != HrChangeStatus::Conflict.to_string() {
+        return Err(CustomError::new(400, "Change is not an open conflict".to_string()))
+    };
+
+    let res = conn.transaction::<HrSyncChange, SyncError, _>(|| {
+        if !accept {
+            return Ok(change.resolve(conn, HrChangeStatus::Kept)?)
+        };
+
+        let value = change.new_value.clone();
+        let now = Utc::now().naive_utc();
+
+        match (change.entity_type.as_str(), change.field_name.as_str()) {
+            (ORG_TIER, "name_en") => {
+                diesel::update(org_tiers::table.find(change.entity_id))
+                    .set((org_tiers::name_en.eq(value.unwrap_or_default()), org_tiers::updated_at.eq(now)))
+                    .execute(conn)?;
+            },
+            (ORG_TIER, "name_fr") => {
+                diesel::update(org_tiers::table.find(change.entity_id))
+                    .set((org_tiers::name_fr.eq(value.unwrap_or_default()), org_tiers::updated_at.eq(now)))
+                    .execute(conn)?;
+            },
+            (ORG_TIER, "parent_org_unit") => {
+                let tier = org_tiers::table.find(change.entity_id).first::<OrgTier>(conn)?;
+                set_tier_parent(conn, &tier, value.as_deref())?;
+            },
+            (PERSON, "family_name") => {
+                diesel::update(persons::table.find(change.entity_id))
+                    .set((persons::family_name.eq(value.unwrap_or_default()), persons::updated_at.eq(now)))
+                    .execute(conn)?;
+            },
+            (PERSON, "given_name") => {
+                diesel::update(persons::table.find(change.entity_id))
+                    .set((persons::given_name.eq(value.unwrap_or_default()), persons::updated_at.eq(now)))
+                    .execute(conn)?;
+            },
+            (PERSON, "retirement_eligible_on") => {
+                let date = value.as_deref()
+                    .map(|v| NaiveDate::parse_from_str(v, "%Y-%m-%d"))
+                    .transpose()
+                    .map_err(|_| SyncError::Invalid(format!("Invalid date: {}", value.clone().unwrap_or_default())))?;
+
+                diesel::update(persons::table.find(change.entity_id))
+                    .set((persons::retirement_eligible_on.eq(date), persons::updated_at.eq(now)))
+                    .execute(conn)?;
+            },
+            (PERSON, "organization") => {
+                let organization_id = value.as_deref()
+                    .and_then(|v| Uuid::parse_str(v).ok())
+                    .ok_or_else(|| SyncError::Invalid(format!("Invalid organization: {}", value.clone().unwrap_or_default())))?;
+
+                diesel::update(persons::table.find(change.entity_id))
+                    .set((persons::organization_id.eq(organization_id), persons::updated_at.eq(now)))
+                    .execute(conn)?;
+            },
+            (PERSON, "reports_to") => {
+                let manager = persons::table
+                    .filter(persons::employee_number.eq(&value))
+                    .first::<Person>(conn)?;
+                set_reporting_line(conn, change.entity_id, manager.id, now)?;
+            },
+            (ROLE, "title_en") => {
+                diesel::update(roles::table.find(change.entity_id))
+                    .set((roles::title_en.eq(value.unwrap_or_default()), roles::updated_at.eq(now)))
+                    .execute(conn)?;
+            },
+            (ROLE, "title_fr") => {
+                diesel::update(roles::table.find(change.entity_id))
+                    .set((roles::title_fr.eq(value.unwrap_or_default()), roles::updated_at.eq(now)))
+                    .execute(conn)?;
+            },
+            (ROLE, "classification") => {
+                diesel::update(roles::table.find(change.entity_id))
+                    .set((roles::classification.eq(value), roles::updated_at.eq(now)))
+                    .execute(conn)?;
+            },
+            (ROLE, "language_requirement") => {
+                diesel::update(roles::table.find(change.entity_id))
+                    .set((roles::language_requirement.eq(value), roles::updated_at.eq(now)))
+                    .execute(conn)?;
+            },
+            (ROLE, "hr_position_number") => {
+                diesel::update(roles::table.find(change.entity_id))
+                    .set((roles::hr_position_number.eq(value), roles::updated_at.eq(now)))
+                    .execute(conn)?;
+            },
+            (ROLE, "org_unit") => {
+                let role = roles::table.find(change.entity_id).first::<Role>(conn)?;
+
+                let tier = org_tiers::table
+                    .filter(org_tiers::hr_org_unit.eq(&value))
+                    .first::<OrgTier>(conn)?;
+
+                let team = team_for_tier(conn, &tier)?
+                    .ok_or_else(|| SyncError::Invalid(format!("No single team in org unit {}", tier.name_en)))?;
+
+                let new_role = NewRole::new(
+                    Uuid::new_v4(),
+                    role.person_id,
+                    team.id,
+                    role.title_en.clone(),
+                    role.title_fr.clone(),
+                    role.effort,
+                    true,
+                    now,
+                    None,
+                    RoleType::Substantive,
+                    None,
+                );
+
+                let new_role = Role::transfer(conn, &new_role, "HRMS org unit change accepted")
+                    .map_err(|e| SyncError::Invalid(e.message))?;
+
+                diesel::update(roles::table.find(new_role.id))
+                    .set((
+                        roles::hr_position_number.eq(&role.hr_position_number),
+                        roles::classification.eq(&role.classification),
+                        roles::language_requirement.eq(&role.language_requirement),
+                    ))
+                    .execute(conn)?;
+            },
+            (entity_type, field_name) => {
+                return Err(SyncError::Invalid(format!("Unsupported change {}.{}", entity_type, field_name)))
+            },
+        };
+
+        Ok(change.resolve(conn, HrChangeStatus::Accepted)?)
+    });
+
+    Ok(res?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    use crate::generate_unique_code;
+    use crate::models::{NewOrganization, Organization};
+
+    const HEADER: &str = "employee_id,family_name,given_name,org_unit,org_unit_name_en,reports_to";
+
+    fn establish() -> PgConnection {
+        PgConnection::establish(&env::var("DATABASE_URL").expect("DATABASE_URL must be set"))
+            .expect("Unable to connect to DATABASE_URL")
+    }
+
+    fn organization(conn: &PgConnection) -> Organization {
+        let acronym = format!("HR{}", generate_unique_code(6, false).to_uppercase());
+
+        diesel::insert_into(organizations::table)
+            .values(&NewOrganization::new(
+                format!("HRMS test {}", acronym),
+                format!("Test SGRH {}", acronym),
+                acronym.clone(),
+                acronym,
+                "department".to_string(),
+            ))
+            .get_result(conn)
+            .unwrap()
+    }
+
+    fn sync(conn: &PgConnection, organization: &Organization, rows: &[String]) -> HrSyncReport {
+        let extract = format!("{}\n{}\n", HEADER, rows.join("\n"));
+
+        sync_hr_extract(conn, extract.as_bytes(), "test", "extract.csv", organization.id, b',', &ColumnMapping::default())
+            .unwrap_or_else(|e| panic!("{}", e.error_message))
+    }
+
+    fn person(conn: &PgConnection, employee_id: &str) -> Person {
+        persons::table.filter(persons::employee_number.eq(employee_id)).first(conn).unwrap()
+    }
+
+    /// Unique employee IDs and org unit code, as both are matched across organizations
+    fn keys() -> (String, String, String) {
+        let code = generate_unique_code(8, false).to_uppercase();
+        (format!("E{}-1", code), format!("E{}-2", code), format!("U{}", code))
+    }
+
+    /// Runs against DATABASE_URL in a transaction that is rolled back:
+    /// cargo test -- --ignored
+    #[test]
+    #[ignore]
+    fn hr_owned_values_are_updated() {
+        let conn = establish();
+
+        conn.test_transaction::<_, CustomError, _>(|| {
+            let organization = organization(&conn);
+            let (manager, employee, unit) = keys();
+
+            let report = sync(&conn, &organization, &[
+                format!("{},Sidle,Sarah,{},Data Branch,", manager, unit),
+                format!("{},Carberry,Josiah,{},,{}", employee, unit, manager),
+            ]);
+            assert_eq!(report.run.conflicts, 0);
+
+            let carberry = person(&conn, &employee);
+            assert_eq!(carberry.organization_id, organization.id);
+
+            let lines = ReportingRelationship::find_current_by_reporter(&conn, carberry.id, Utc::now().naive_utc()).unwrap();
+            assert_eq!(lines[0].reporting_to, person(&conn, &manager).id);
+
+            // The extract set the name, so a new one is applied
+            let report = sync(&conn, &organization, &[
+                format!("{},Carberry-Smith,Josiah,{},,{}", employee, unit, manager),
+            ]);
+            assert_eq!((report.run.changes_applied, report.run.conflicts), (1, 0));
+            assert_eq!(person(&conn, &employee).family_name, "Carberry-Smith");
+
+            Ok(())
+        });
+    }
+
+    #[test]
+    #[ignore]
+    fn app_edits_are_flagged_not_overwritten() {
+        let conn = establish();
+
+        conn.test_transaction::<_, CustomError, _>(|| {
+            let organization = organization(&conn);
+            let (_, employee, unit) = keys();
+
+            sync(&conn, &organization, &[format!("{},Carberry,Josiah,{},Data Branch,", employee, unit)]);
+
+            let carberry = person(&conn, &employee);
+            diesel::update(persons::table.find(carberry.id))
+                .set(persons::family_name.eq("Carberry-Jones"))
+                .execute(&conn)?;
+
+            let rows = [format!("{},Carberry-Smith,Josiah,{},,", employee, unit)];
+
+            let report = sync(&conn, &organization, &rows);
+            assert_eq!(report.run.conflicts, 1);
+            assert_eq!(person(&conn, &employee).family_name, "Carberry-Jones");
+
+            // The same HRMS value isn't flagged twice
+            assert_eq!(sync(&conn, &organization, &rows).run.conflicts, 0);
+
+            let conflict = HrSyncChange::open_conflict(&conn, carberry.id, "family_name")?.expect("No open conflict");
+            resolve_hr_conflict(&conn, conflict.id, true)?;
+            assert_eq!(person(&conn, &employee).family_name, "Carberry-Smith");
+
+            Ok(())
+        });
+    }
+
+    #[test]
+    #[ignore]
+    fn kept_app_value_is_not_flagged_again() {
+        let conn = establish();
+
+        conn.test_transaction::<_, CustomError, _>(|| {
+            let organization = organization(&conn);
+            let (_, employee, unit) = keys();
+
+            sync(&conn, &organization, &[format!("{},Carberry,Josiah,{},Data Branch,", employee, unit)]);
+
+            let carberry = person(&conn, &employee);
+            diesel::update(persons::table.find(carberry.id))
+                .set(persons::given_name.eq("Joe"))
+                .execute(&conn)?;
+
+            let rows = [format!("{},Carberry,Josiah,{},,", employee, unit)];
+
+            assert_eq!(sync(&conn, &organization, &rows).run.conflicts, 1);
+
+            let conflict = HrSyncChange::open_conflict(&conn, carberry.id, "given_name")?.expect("No open conflict");
+            resolve_hr_conflict(&conn, conflict.id, false)?;
+
+            assert_eq!(sync(&conn, &organization, &rows).run.conflicts, 0);
+            assert_eq!(person(&conn, &employee).given_name, "Joe");
+
+            Ok(())
+        });
+    }
+
+    #[test]
+    #[ignore]
+    fn person_of_another_organization_is_flagged() {
+        let conn = establish();
+
+        conn.test_transaction::<_, CustomError, _>(|| {
+            let home = organization(&conn);
+            let other = organization(&conn);
+            let (manager, employee, unit) = keys();
+
+            diesel::insert_into(persons::table)
+                .values(&NewPerson::new(None, "Carberry".to_string(), "Josiah".to_string(), home.id, Some(employee.clone())))
+                .execute(&conn)?;
+
+            let rows = [
+                format!("{},Sidle,Sarah,{},Data Branch,", manager, unit),
+                format!("{},Carberry-Smith,Josiah,{},,{}", employee, unit, manager),
+            ];
+
+            let report = sync(&conn, &other, &rows);
+            assert_eq!(report.run.conflicts, 1);
+            assert!(report.warnings.iter().any(|w| w.starts_with(&employee)));
+
+            let carberry = person(&conn, &employee);
+            assert_eq!((carberry.organization_id, carberry.family_name.as_str()), (home.id, "Carberry"));
+            assert!(ReportingRelationship::find_current_by_reporter(&conn, carberry.id, Utc::now().naive_utc()).unwrap().is_empty());
+
+            // Flagged once until resolved
+            assert_eq!(sync(&conn, &other, &rows).run.conflicts, 0);
+
+            let conflict = HrSyncChange::open_conflict(&conn, carberry.id, "organization")?.expect("No open conflict");
+            resolve_hr_conflict(&conn, conflict.id, true)?;
+            assert_eq!(person(&conn, &employee).organization_id, other.id);
+
+            Ok(())
+        });
+    }
+}
@@ -0,0 +1,13 @@
+//! Bulk loading of people data from external files.
+
+mod csv_import;
+mod hr_sync;
+mod orcid;
+mod publications;
+mod cli;
+
+pub use self::csv_import::*;
+pub use self::hr_sync::*;
+pub use self::orcid::*;
+pub use self::publications::*;
+pub use self::cli::*;
@@ -0,0 +1,695 @@
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::NaiveDate;
+use diesel::prelude::*;
+use diesel::PgConnection;
+use diesel::result::Error as DieselError;
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::errors::CustomError;
+use crate::models::{parse_orcid, Affiliation, Credential, NewAffiliation, NewCredential, NewPublication,
+    OpenAccessStatus, Organization, Person, Publication, PublicationAuthor, PublicationType};
+use crate::schema::*;
+
+pub const ORCID_PUBLIC_API: &str = "https://pub.orcid.org/v3.0";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum OrcidFormat {
+    Json,
+    Xml,
+}
+
+impl OrcidFormat {
+    /// Guess the format from a file extension, defaulting to JSON
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("xml") => OrcidFormat::Xml,
+            _ => OrcidFormat::Json,
+        }
+    }
+}
+
+/// A raw ORCID public record as returned by the API or saved to disk
+#[derive(Debug, Clone)]
+pub struct OrcidDocument {
+    pub format: OrcidFormat,
+    pub body: String,
+}
+
+impl OrcidDocument {
+    pub fn from_file(path: &Path) -> Result<Self, CustomError> {
+        let body = fs::read_to_string(path)
+            .map_err(|e| CustomError::new(400, format!("Unable to read {}: {}", path.display(), e)))?;
+
+        Ok(OrcidDocument {
+            format: OrcidFormat::from_path(path),
+            body,
+        })
+    }
+}
+
+/// Source of ORCID public records. Implemented over HTTP and over a
+/// directory of saved records so imports can run without network access.
+pub trait OrcidClient {
+    fn fetch_record(&self, orcid: &str) -> Result<OrcidDocument, CustomError>;
+}
+
+/// Reads records from the ORCID public API
+pub struct HttpOrcidClient {
+    pub base_url: String,
+}
+
+impl HttpOrcidClient {
+    pub fn new(base_url: &str) -> Self {
+        HttpOrcidClient {
+            base_url: base_url.trim_end_matches('/').to_owned(),
+        }
+    }
+
+    /// Uses ORCID_API_URL if set, otherwise the ORCID public API
+    pub fn from_env() -> Self {
+        let base_url = env::var("ORCID_API_URL").unwrap_or_else(|_| ORCID_PUBLIC_API.to_string());
+        HttpOrcidClient::new(&base_url)
+    }
+}
+
+impl OrcidClient for HttpOrcidClient {
+    fn fetch_record(&self, orcid: &str) -> Result<OrcidDocument, CustomError> {
+        let url = format!("{}/{}/record", self.base_url, orcid);
+
+        let body = ureq::get(&url)
+            .set("Accept", "application/json")
+            .call()
+            .map_err(|e| match e {
+                ureq::Error::Status(404, _) => CustomError::new(408, format!("ORCID record not found: {}", orcid)),
+                e => CustomError::new(502, format!("ORCID request failed: {}", e)),
+            })?
+            .into_string()
+            .map_err(|e| CustomError::new(502, format!("ORCID response unreadable: {}", e)))?;
+
+        Ok(OrcidDocument {
+            format: OrcidFormat::Json,
+            body,
+        })
+    }
+}
+
+/// Reads records saved as `<dir>/<orcid>.json` or `<dir>/<orcid>.xml`
+pub struct FileOrcidClient {
+    pub dir: PathBuf,
+}
+
+impl FileOrcidClient {
+    pub fn new(dir: &Path) -> Self {
+        FileOrcidClient {
+            dir: dir.to_path_buf(),
+        }
+    }
+}
+
+impl OrcidClient for FileOrcidClient {
+    fn fetch_record(&self, orcid: &str) -> Result<OrcidDocument, CustomError> {
+        ["json", "xml"]
+            .iter()
+            .map(|ext| self.dir.join(format!("{}.{}", orcid, ext)))
+            .find(|path| path.is_file())
+            .ok_or_else(|| CustomError::new(408, format!("No saved ORCID record for {} in {}", orcid, self.dir.display())))
+            .and_then(|path| OrcidDocument::from_file(&path))
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+/// Employment or education entry from an ORCID record
+pub struct OrcidAffiliation {
+    pub organization: String,
+    pub role_title: Option<String>,
+    pub department: Option<String>,
+    pub start_date: Option<NaiveDate>,
+    pub end_date: Option<NaiveDate>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OrcidWork {
+    pub title: String,
+    pub work_type: Option<String>, // ORCID work type, e.g. journal-article
+    pub year: Option<i32>,
+    pub doi: Option<String>,
+    pub journal: Option<String>,
+    pub url: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+/// The parts of an ORCID public record used by the importer
+pub struct OrcidRecord {
+    pub orcid: String,
+    pub given_names: Option<String>,
+    pub family_name: Option<String>,
+    pub employments: Vec<OrcidAffiliation>,
+    pub educations: Vec<OrcidAffiliation>,
+    pub works: Vec<OrcidWork>,
+}
+
+impl OrcidRecord {
+    pub fn parse(document: &OrcidDocument) -> Result<Self, CustomError> {
+        let value = match document.format {
+            OrcidFormat::Json => serde_json::from_str(&document.body)
+                .map_err(|e| CustomError::new(400, format!("Invalid ORCID JSON: {}", e)))?,
+            OrcidFormat::Xml => xml_to_value(&document.body)?,
+        };
+
+        OrcidRecord::from_value(&value)
+    }
+
+    /// Reads the v3.0 record layout. XML records are first converted to the
+    /// same shape, with text-only elements becoming strings.
+    fn from_value(record: &Value) -> Result<Self, CustomError> {
+        let orcid = text(record, &["orcid-identifier", "path"])
+            .ok_or_else(|| CustomError::new(400, "ORCID record has no orcid-identifier".to_string()))
+            .and_then(|o| parse_orcid(&o))?;
+
+        let activities = field(record, &["activities-summary"]);
+
+        let works = list(activities.and_then(|a| field(a, &["works", "group"])))
+            .into_iter()
+            // Each group holds the same work from several sources; the first is preferred
+            .filter_map(|group| list(field(group, &["work-summary"])).into_iter().next())
+            .filter_map(parse_work)
+            .collect();
+
+        Ok(OrcidRecord {
+            orcid,
+            given_names: text(record, &["person", "name", "given-names"]),
+            family_name: text(record, &["person", "name", "family-name"]),
+            employments: affiliations(activities, "employments", "employment-summary"),
+            educations: affiliations(activities, "educations", "education-summary"),
+            works,
+        })
+    }
+}
+
+fn affiliations(activities: Option<&Value>, section: &str, summary: &str) -> Vec<OrcidAffiliation> {
+    let mut res = Vec::new();
+
+    for group in list(activities.and_then(|a| field(a, &[section, "affiliation-group"]))) {
+        // JSON wraps summaries in a `summaries` array, XML nests them directly
+        let summaries = match field(group, &["summaries"]) {
+            Some(s) => list(Some(s)).into_iter().filter_map(|s| field(s, &[summary])).collect(),
+            None => list(field(group, &[summary])),
+        };
+
+        for s in summaries {
+            if let Some(organization) = text(s, &["organization", "name"]) {
+                res.push(OrcidAffiliation {
+                    organization,
+                    role_title: text(s, &["role-title"]),
+                    department: text(s, &["department-name"]),
+                    start_date: fuzzy_date(field(s, &["start-date"])),
+                    end_date: fuzzy_date(field(s, &["end-date"])),
+                });
+            };
+        };
+    };
+
+    res
+}
+
+fn parse_work(summary: &Value) -> Option<OrcidWork> {
+    let title = text(summary, &["title", "title"])?;
+
+    let doi = list(field(summary, &["external-ids", "external-id"]))
+        .into_iter()
+        .find(|id| text(id, &["external-id-type"]).map(|t| t.eq_ignore_ascii_case("doi")).unwrap_or(false))
+        .and_then(|id| text(id, &["external-id-value"]));
+
+    Some(OrcidWork {
+        title,
+        work_type: text(summary, &["type"]),
+        year: text(summary, &["publication-date", "year"]).and_then(|y| y.parse().ok()),
+        doi,
+        journal: text(summary, &["journal-title"]),
+        url: text(summary, &["url"]),
+    })
+}
+
+/// ORCID dates may omit the month and day, which default to the first
+fn fuzzy_date(date: Option<&Value>) -> Option<NaiveDate> {
+    let date = date?;
+    let part = |name: &str| text(date, &[name]).and_then(|v| v.parse::<u32>().ok());
+
+    let year = text(date, &["year"])?.parse::<i32>().ok()?;
+
+    NaiveDate::from_ymd_opt(year, part("month").unwrap_or(1), part("day").unwrap_or(1))
+}
+
+fn field<'a>(value: &'a Value, path: &[&str]) -> Option<&'a Value> {
+    path.iter()
+        .try_fold(value, |v, key| v.get(key))
+        .filter(|v| !v.is_null())
+}
+
+/// Leaf values are plain strings in XML and mostly `{"value": ...}` in JSON
+fn text(value: &Value, path: &[&str]) -> Option<String> {
+    let leaf = field(value, path)?;
+
+    let s = match leaf {
+        Value::String(s) => s.trim().to_owned(),
+        Value::Number(n) => n.to_string(),
+        Value::Object(_) => return text(leaf, &["value"]),
+        _ => return None,
+    };
+
+    if s.is_empty() { None } else { Some(s) }
+}
+
+/// A repeated element is an array, but a single XML element is not
+fn list(value: Option<&Value>) -> Vec<&Value> {
+    match value {
+        Some(Value::Array(items)) => items.iter().filter(|v| !v.is_null()).collect(),
+        Some(Value::Null) | None => Vec::new(),
+        Some(v) => vec![v],
+    }
+}
+
+/// Convert an XML document to JSON keyed by element local names,
+/// so `<common:title>` and `"title"` are read the same way
+fn xml_to_value(xml: &str) -> Result<Value, CustomError> {
+    let xml_error = |e: quick_xml::Error| CustomError::new(400, format!("Invalid ORCID XML: {}", e));
+
+    let mut reader = Reader::from_str(xml);
+    reader.trim_text(true);
+
+    // Open elements as (local name, child elements, text)
+    let mut stack: Vec<(String, Map<String, Value>, String)> = Vec::new();
+
+    loop {
+        match reader.read_event().map_err(xml_error)? {
+            Event::Start(e) => {
+                let name = String::from_utf8_lossy(e.local_name().as_ref()).into_owned();
+                stack.push((name, Map::new(), String::new()));
+            },
+            Event::Empty(e) => {
+                if let Some((_, children, _)) = stack.last_mut() {
+                    let name = String::from_utf8_lossy(e.local_name().as_ref()).into_owned();
+                    insert_child(children, name, Value::Null);
+                };
+            },
+            Event::Text(t) => {
+                if let Some((_, _, text)) = stack.last_mut() {
+                    text.push_str(&t.unescape().map_err(xml_error)?);
+                };
+            },
+            Event::CData(t) => {
+                if let Some((_, _, text)) = stack.last_mut() {
+                    text.push_str(&String::from_utf8_lossy(&t.into_inner()));
+                };
+            },
+            Event::End(_) => {
+                let (name, children, text) = stack.pop()
+                    .ok_or_else(|| CustomError::new(400, "Invalid ORCID XML: unbalanced elements".to_string()))?;
+
+                let value = match (children.is_empty(), text.is_empty()) {
+                    (false, _) => Value::Object(children),
+                    (true, false) => Value::String(text),
+                    (true, true) => Value::Null,
+                };
+
+                match stack.last_mut() {
+                    Some((_, parent, _)) => insert_child(parent, name, value),
+                    None => return Ok(value),
+                };
+            },
+            Event::Eof => return Err(CustomError::new(400, "Invalid ORCID XML: no record element".to_string())),
+            _ => (),
+        };
+    };
+}
+
+fn insert_child(children: &mut Map<String, Value>, name: String, value: Value) {
+    match children.get_mut(&name) {
+        Some(Value::Array(items)) => items.push(value),
+        Some(existing) => {
+            let first = existing.take();
+            *existing = Value::Array(vec![first, value]);
+        },
+        None => {
+            children.insert(name, value);
+        },
+    };
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OrcidImportReport {
+    pub orcid: String,
+    pub record_name: String,
+    pub affiliations: usize,
+    pub credentials: usize,
+    /// Employers not found among Organizations, by name
+    pub unmatched_organizations: Vec<String>,
+    /// Entries that could not be imported, with the reason
+    pub skipped: Vec<String>,
+    /// Works found or added as publications, with the person as an author
+    pub publications: Vec<Publication>,
+}
+
+impl fmt::Display for OrcidImportReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for name in &self.unmatched_organizations {
+            writeln!(f, "unmatched organization: {}", name)?;
+        };
+
+        for skipped in &self.skipped {
+            writeln!(f, "skipped: {}", skipped)?;
+        };
+
+        write!(f, "ORCID {} ({}): {} affiliations, {} credentials, {} publications",
+            self.orcid, self.record_name, self.affiliations, self.credentials, self.publications.len())
+    }
+}
+
+/// Fetch the record for a person's ORCID iD and import it
+pub fn import_orcid(conn: &PgConnection, client: &dyn OrcidClient, person: &Person) -> Result<OrcidImportReport, CustomError> {
+    let orcid = person.orcid.as_ref()
+        .ok_or_else(|| CustomError::new(400, format!("{} {} has no ORCID iD", person.given_name, person.family_name)))?;
+
+    let record = OrcidRecord::parse(&client.fetch_record(orcid)?)?;
+
+    import_orcid_record(conn, person, &record)
+}
+
+/// Add employments as affiliations, educations as credentials and works
+/// as publications authored by the person. Re-importing a record leaves existing entries unchanged.
+pub fn import_orcid_record(conn: &PgConnection, person: &Person, record: &OrcidRecord) -> Result<OrcidImportReport, CustomError> {
+    if person.orcid.as_ref() != Some(&record.orcid) {
+        return Err(CustomError::new(400, format!("ORCID record {} does not belong to {} {}",
+            record.orcid, person.given_name, person.family_name)))
+    };
+
+    let record_name = [&record.given_names, &record.family_name]
+        .iter()
+        .filter_map(|n| n.as_deref())
+        .collect::<Vec<&str>>()
+        .join(" ");
+
+    let mut report = OrcidImportReport {
+        orcid: record.orcid.clone(),
+        record_name,
+        affiliations: 0,
+        credentials: 0,
+        unmatched_organizations: Vec::new(),
+        skipped: Vec::new(),
+        publications: Vec::new(),
+    };
+
+    let organizations = organizations::table
+        .filter(organizations::retired_at.is_null())
+        .load::<Organization>(conn)?;
+
+    // FieldErrors from the model upserts are carried out of the transaction here
+    let mut failure = None;
+
+    let res = conn.transaction::<(), DieselError, _>(|| {
+        for employment in &record.employments {
+            let organization = match find_organization(&organizations, &employment.organization) {
+                Some(o) => o,
+                None => {
+                    if !report.unmatched_organizations.contains(&employment.organization) {
+                        report.unmatched_organizations.push(employment.organization.clone());
+                    };
+                    continue
+                },
+            };
+
+            // Employment with the person's own organization is covered by their roles
+            if organization.id == person.organization_id {
+                continue
+            };
+
+            let start_date = match employment.start_date {
+                Some(d) => d,
+                None => {
+                    report.skipped.push(format!("{}: employment has no start date", employment.organization));
+                    continue
+                },
+            };
+
+            let role = employment.role_title.clone()
+                .or_else(|| employment.department.clone())
+                .unwrap_or_else(|| "Affiliate".to_string());
+
+            Affiliation::get_or_create(conn, &NewAffiliation::new(
+                person.id,
+                organization.id,
+                role,
+                start_date,
+                employment.end_date,
+            )).map_err(|e| {
+                failure = Some(e.message);
+                DieselError::RollbackTransaction
+            })?;
+
+            report.affiliations += 1;
+        };
+
+        for education in &record.educations {
+            // Credentials are dated by completion, or by start if still in progress
+            let received_date = match education.end_date.or(education.start_date) {
+                Some(d) => d,
+                None => {
+                    report.skipped.push(format!("{}: education has no date", education.organization));
+                    continue
+                },
+            };
+
+            let description = match education.role_title.clone().or_else(|| education.department.clone()) {
+                Some(d) => d,
+                None => {
+                    report.skipped.push(format!("{}: education has no degree or department", education.organization));
+                    continue
+                },
+            };
+
+            Credential::get_or_create(conn, &NewCredential::new(
+                person.id,
+                education.organization.clone(),
+                description,
+                received_date,
+                false, // Self-asserted on ORCID
+            )).map_err(|e| {
+                failure = Some(e.message);
+                DieselError::RollbackTransaction
+            })?;
+
+            report.credentials += 1;
+        };
+
+        for work in &record.works {
+            // ORCID work summaries don't list co-authors, so author order is unknown
+            let publication = Publication::get_or_create(conn, &NewPublication::new(
+                work.title.clone(),
+                work.doi.clone(),
+                work.journal.clone(),
+                work.year,
+                work.work_type.as_deref().map(PublicationType::from_orcid).unwrap_or(PublicationType::Other),
+                OpenAccessStatus::Unknown,
+                Vec::new(),
+                work.url.clone(),
+            )).and_then(|p| PublicationAuthor::link_person(conn, p.id, person, None).map(|_| p))
+            .map_err(|e| {
+                failure = Some(e.message);
+                DieselError::RollbackTransaction
+            })?;
+
+            report.publications.push(publication);
+        };
+
+        Ok(())
+    });
+
+    match res {
+        Ok(()) => Ok(report),
+        Err(DieselError::RollbackTransaction) => Err(CustomError::new(409, format!("Unable to save ORCID record {}: {}",
+            record.orcid, failure.unwrap_or_default()))),
+        Err(e) => Err(CustomError::from(e)),
+    }
+}
+
+fn find_organization<'a>(organizations: &'a [Organization], name: &str) -> Option<&'a Organization> {
+    let name = name.trim().to_lowercase();
+
+    organizations.iter().find(|o| {
+        [&o.name_en, &o.name_fr, &o.acronym_en, &o.acronym_fr]
+            .iter()
+            .any(|n| n.to_lowercase() == name)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{NewOrganization, NewPerson};
+
+    const ORCID: &str = "0000-0002-1825-0097";
+
+    const RECORD: &str = r#"{
+        "orcid-identifier": { "path": "0000-0002-1825-0097" },
+        "person": { "name": { "given-names": { "value": "Josiah" }, "family-name": { "value": "Carberry" } } },
+        "activities-summary": {
+            "employments": { "affiliation-group": [
+                { "summaries": [ { "employment-summary": {
+                    "organization": { "name": "Test University" },
+                    "role-title": "Visiting researcher",
+                    "start-date": { "year": { "value": "2019" }, "month": { "value": "09" } }
+                } } ] },
+                { "summaries": [ { "employment-summary": {
+                    "organization": { "name": "Unknown Institute" },
+                    "start-date": { "year": { "value": "2015" } }
+                } } ] }
+            ] },
+            "educations": { "affiliation-group": [
+                { "summaries": [ { "education-summary": {
+                    "organization": { "name": "Brown University" },
+                    "role-title": "PhD",
+                    "end-date": { "year": { "value": "2014" } }
+                } } ] }
+            ] },
+            "works": { "group": [
+                { "work-summary": [ {
+                    "title": { "title": { "value": "Psychoceramics revisited" } },
+                    "type": "journal-article",
+                    "publication-date": { "year": { "value": "2020" } },
+                    "journal-title": { "value": "Journal of Psychoceramics" },
+                    "external-ids": { "external-id": [
+                        { "external-id-type": "doi", "external-id-value": "10.5555/orcid-import-test" }
+                    ] }
+                } ] }
+            ] }
+        }
+    }"#;
+
+    /// Serves the record above without network access
+    struct StubOrcidClient;
+
+    impl OrcidClient for StubOrcidClient {
+        fn fetch_record(&self, orcid: &str) -> Result<OrcidDocument, CustomError> {
+            if orcid != ORCID {
+                return Err(CustomError::new(408, format!("ORCID record not found: {}", orcid)))
+            };
+
+            Ok(OrcidDocument {
+                format: OrcidFormat::Json,
+                body: RECORD.to_string(),
+            })
+        }
+    }
+
+    #[test]
+    fn parses_stub_record() {
+        let record = OrcidRecord::parse(&StubOrcidClient.fetch_record(ORCID).unwrap()).unwrap();
+
+        assert_eq!(record.orcid, ORCID);
+        assert_eq!(record.family_name.as_deref(), Some("Carberry"));
+        assert_eq!(record.employments.len(), 2);
+        assert_eq!(record.employments[0].start_date, NaiveDate::from_ymd_opt(2019, 9, 1));
+        assert_eq!(record.educations[0].end_date, NaiveDate::from_ymd_opt(2014, 1, 1));
+        assert_eq!(record.works[0].doi.as_deref(), Some("10.5555/orcid-import-test"));
+        assert_eq!(record.works[0].year, Some(2020));
+    }
+
+    #[test]
+    fn parses_xml_record() {
+        let xml = r#"<record:record xmlns:record="http://www.orcid.org/ns/record" xmlns:common="http://www.orcid.org/ns/common">
+            <common:orcid-identifier><common:path>0000-0002-1825-0097</common:path></common:orcid-identifier>
+            <activities:activities-summary xmlns:activities="http://www.orcid.org/ns/activities">
+                <activities:works><activities:group><work:work-summary xmlns:work="http://www.orcid.org/ns/work">
+                    <work:title><common:title>Psychoceramics revisited</common:title></work:title>
+                    <common:publication-date><common:year>2020</common:year></common:publication-date>
+                </work:work-summary></activities:group></activities:works>
+            </activities:activities-summary>
+        </record:record>"#;
+
+        let record = OrcidRecord::parse(&OrcidDocument { format: OrcidFormat::Xml, body: xml.to_string() }).unwrap();
+
+        assert_eq!(record.works.len(), 1);
+        assert_eq!(record.works[0].title, "Psychoceramics revisited");
+        assert_eq!(record.works[0].year, Some(2020));
+    }
+
+    #[test]
+    fn rejects_record_with_invalid_orcid() {
+        let body = RECORD.replace(ORCID, "0000-0002-1825-0098");
+
+        assert!(OrcidRecord::parse(&OrcidDocument { format: OrcidFormat::Json, body }).is_err());
+    }
+
+    /// Runs against DATABASE_URL in a transaction that is rolled back:
+    /// cargo test -- --ignored
+    #[test]
+    #[ignore]
+    fn imports_stub_record_once() {
+        let conn = PgConnection::establish(&env::var("DATABASE_URL").expect("DATABASE_URL must be set"))
+            .expect("Unable to connect to DATABASE_URL");
+
+        // The model upserts return FieldErrors
+        let fail = |e: async_graphql::FieldError| CustomError::new(500, e.message);
+
+        conn.test_transaction::<_, CustomError, _>(|| {
+            let home = Organization::get_or_create(&conn, &NewOrganization::new(
+                "ORCID Test Department".to_string(),
+                "Ministère test ORCID".to_string(),
+                "OTD".to_string(),
+                "MTO".to_string(),
+                "department".to_string(),
+            )).map_err(fail)?;
+
+            Organization::get_or_create(&conn, &NewOrganization::new(
+                "Test University".to_string(),
+                "Université test".to_string(),
+                "TU".to_string(),
+                "UT".to_string(),
+                "agency".to_string(),
+            )).map_err(fail)?;
+
+            let person = Person::get_or_create(&conn, &NewPerson::new(
+                None,
+                "Carberry".to_string(),
+                "Josiah".to_string(),
+                home.id,
+                Some("ORCID-TEST-1".to_string()),
+            )).map_err(fail)?;
+
+            let person = diesel::update(persons::table.find(person.id))
+                .set(persons::orcid.eq(Some(ORCID)))
+                .get_result::<Person>(&conn)?;
+
+            let report = import_orcid(&conn, &StubOrcidClient, &person)?;
+
+            assert_eq!(report.affiliations, 1);
+            assert_eq!(report.credentials, 1);
+            assert_eq!(report.publications.len(), 1);
+            assert_eq!(report.unmatched_organizations, vec!["Unknown Institute".to_string()]);
+
+            // Re-importing adds nothing
+            let again = import_orcid(&conn, &StubOrcidClient, &person)?;
+            assert_eq!(again.publications[0].id, report.publications[0].id);
+
+            let affiliations: i64 = affiliations::table
+                .filter(affiliations::person_id.eq(person.id))
+                .count()
+                .get_result(&conn)?;
+            let credentials: i64 = credentials::table
+                .filter(credentials::person_id.eq(person.id))
+                .count()
+                .get_result(&conn)?;
+
+            assert_eq!((affiliations, credentials), (1, 1));
+
+            Ok(())
+        });
+    }
+}
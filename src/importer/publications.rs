@@ -0,0 +1,1136 @@
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use chrono::Utc;
+use diesel::prelude::*;
+use diesel::PgConnection;
+use diesel::result::Error as DieselError;
+use serde::Serialize;
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::errors::CustomError;
+use crate::importer::{RowAction, RowReport};
+use crate::models::{normalize_doi, parse_orcid, AuthorMatch, AuthorMatchMethod, NewAuthorMatch, NewPublication,
+    NewPublicationAuthor, OpenAccessStatus, Person, Publication, PublicationAuthor, PublicationType};
+use crate::schema::*;
+
+pub const CROSSREF_API: &str = "https://api.crossref.org";
+
+/// Titles at least this similar (Sørensen–Dice on character bigrams)
+/// are treated as the same publication when no DOI settles it
+pub const TITLE_MATCH_THRESHOLD: f64 = 0.9;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PublicationFormat {
+    /// Crossref REST API JSON, a single work or a list of works
+    Crossref,
+    /// RIS, e.g. from Dimensions, Scopus or reference managers
+    Ris,
+    Bibtex,
+    /// One DOI per line, looked up on Crossref
+    Dois,
+}
+
+impl PublicationFormat {
+    pub const ALL: [PublicationFormat; 4] = [
+        PublicationFormat::Crossref,
+        PublicationFormat::Ris,
+        PublicationFormat::Bibtex,
+        PublicationFormat::Dois,
+    ];
+}
+
+impl fmt::Display for PublicationFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            PublicationFormat::Crossref => "crossref",
+            PublicationFormat::Ris => "ris",
+            PublicationFormat::Bibtex => "bibtex",
+            PublicationFormat::Dois => "dois",
+        };
+        f.write_str(s)
+    }
+}
+
+impl FromStr for PublicationFormat {
+    type Err = CustomError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "crossref" | "json" => Ok(PublicationFormat::Crossref),
+            "ris" => Ok(PublicationFormat::Ris),
+            "bibtex" | "bib" => Ok(PublicationFormat::Bibtex),
+            "dois" | "doi" => Ok(PublicationFormat::Dois),
+            _ => Err(CustomError::new(400, format!("Unknown publication format: {}", s))),
+        }
+    }
+}
+
+/// Source of Crossref work metadata by DOI. Implemented over HTTP and over
+/// a directory of saved responses so imports can run without network access.
+pub trait CrossrefClient {
+    /// The Crossref JSON response for a DOI
+    fn fetch_work(&self, doi: &str) -> Result<String, CustomError>;
+}
+
+/// Reads works from the Crossref REST API
+pub struct HttpCrossrefClient {
+    pub base_url: String,
+    /// Contact address sent to Crossref to use its polite pool
+    pub mailto: Option<String>,
+}
+
+impl HttpCrossrefClient {
+    /// Uses CROSSREF_API_URL and CROSSREF_MAILTO if set
+    pub fn from_env() -> Self {
+        HttpCrossrefClient {
+            base_url: env::var("CROSSREF_API_URL")
+                .unwrap_or_else(|_| CROSSREF_API.to_string())
+                .trim_end_matches('/')
+                .to_owned(),
+            mailto: env::var("CROSSREF_MAILTO").ok(),
+        }
+    }
+}
+
+impl CrossrefClient for HttpCrossrefClient {
+    fn fetch_work(&self, doi: &str) -> Result<String, CustomError> {
+        let url = format!("{}/works/{}", self.base_url, doi);
+
+        let mut request = ureq::get(&url);
+
+        if let Some(mailto) = &self.mailto {
+            request = request.set("User-Agent", &format!("people_data (mailto:{})", mailto));
+        };
+
+        request.call()
+            .map_err(|e| match e {
+                ureq::Error::Status(404, _) => CustomError::new(408, format!("DOI not found on Crossref: {}", doi)),
+                e => CustomError::new(502, format!("Crossref request failed: {}", e)),
+            })?
+            .into_string()
+            .map_err(|e| CustomError::new(502, format!("Crossref response unreadable: {}", e)))
+    }
+}
+
+/// Reads responses saved as `<dir>/<doi>.json`, with `/` in the DOI replaced by `_`
+pub struct FileCrossrefClient {
+    pub dir: PathBuf,
+}
+
+impl FileCrossrefClient {
+    pub fn new(dir: &Path) -> Self {
+        FileCrossrefClient {
+            dir: dir.to_path_buf(),
+        }
+    }
+}
+
+impl CrossrefClient for FileCrossrefClient {
+    fn fetch_work(&self, doi: &str) -> Result<String, CustomError> {
+        let path = self.dir.join(format!("{}.json", doi.replace('/', "_")));
+
+        fs::read_to_string(&path)
+            .map_err(|e| CustomError::new(408, format!("No saved Crossref record for {} ({}): {}", doi, path.display(), e)))
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CandidateAuthor {
+    pub given_name: Option<String>,
+    pub family_name: String,
+    pub orcid: Option<String>,
+    pub affiliation: Option<String>,
+}
+
+impl CandidateAuthor {
+    /// Parse "Family, Given" or "Given Family"
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = clean_text(name);
+
+        let (given_name, family_name) = match name.split_once(',') {
+            Some((family, given)) => (Some(given.trim().to_owned()), family.trim().to_owned()),
+            None => match name.rsplit_once(' ') {
+                Some((given, family)) => (Some(given.trim().to_owned()), family.trim().to_owned()),
+                None => (None, name.clone()),
+            },
+        };
+
+        if family_name.is_empty() {
+            return None
+        };
+
+        Some(CandidateAuthor {
+            given_name: given_name.filter(|g| !g.is_empty()),
+            family_name,
+            orcid: None,
+            affiliation: None,
+        })
+    }
+
+    pub fn display_name(&self) -> String {
+        match &self.given_name {
+            Some(given) => format!("{} {}", given, self.family_name),
+            None => self.family_name.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+/// Normalized publication metadata from any supported format
+pub struct CandidatePublication {
+    pub title: String,
+    pub doi: Option<String>,
+    pub venue: Option<String>,
+    pub year: Option<i32>,
+    pub publication_type: PublicationType,
+    pub topics: Vec<String>,
+    pub url: Option<String>,
+    pub authors: Vec<CandidateAuthor>, // In author order
+}
+
+/// Each entry is a candidate, or the reason it could not be read
+pub type ParsedEntries = Vec<Result<CandidatePublication, String>>;
+
+/// Read all entries of an export. Only an unreadable file is an error;
+/// problems with single entries are returned in place of those entries.
+pub fn parse_publications(format: PublicationFormat, input: &str, client: &dyn CrossrefClient) -> Result<ParsedEntries, CustomError> {
+    match format {
+        PublicationFormat::Crossref => parse_crossref(input),
+        PublicationFormat::Ris => Ok(parse_ris(input)),
+        PublicationFormat::Bibtex => Ok(parse_bibtex(input)),
+        PublicationFormat::Dois => Ok(parse_dois(input, client)),
+    }
+}
+
+fn parse_crossref(input: &str) -> Result<ParsedEntries, CustomError> {
+    let value: Value = serde_json::from_str(input)
+        .map_err(|e| CustomError::new(400, format!("Invalid Crossref JSON: {}", e)))?;
+
+    // API responses wrap works in `message`, lists in `message.items`
+    let root = value.get("message").unwrap_or(&value);
+
+    let works = match root {
+        Value::Array(items) => items.iter().collect(),
+        _ => match root.get("items") {
+            Some(Value::Array(items)) => items.iter().collect(),
+            _ => vec![root],
+        },
+    };
+
+    Ok(works.into_iter().map(crossref_work).collect())
+}
+
+fn crossref_work(work: &Value) -> Result<CandidatePublication, String> {
+    let first = |key: &str| {
+        let value = match work.get(key) {
+            Some(Value::Array(items)) => items.iter().find_map(|v| v.as_str()).map(clean_text),
+            Some(Value::String(s)) => Some(clean_text(s)),
+            _ => None,
+        };
+        value.filter(|s| !s.is_empty())
+    };
+
+    let title = first("title").ok_or_else(|| "Entry has no title".to_string())?;
+
+    let year = ["issued", "published", "published-print", "published-online"]
+        .iter()
+        .find_map(|key| work.get(key)?.get("date-parts")?.get(0)?.get(0)?.as_i64())
+        .map(|y| y as i32);
+
+    let publication_type = match work.get("type").and_then(|t| t.as_str()).unwrap_or("") {
+        "journal-article" => PublicationType::JournalArticle,
+        "posted-content" => PublicationType::Preprint,
+        "book" | "monograph" | "edited-book" | "reference-book" => PublicationType::Book,
+        "book-chapter" | "book-section" | "book-part" => PublicationType::BookChapter,
+        "proceedings-article" => PublicationType::ConferencePaper,
+        "report" | "report-component" => PublicationType::Report,
+        "dataset" => PublicationType::Dataset,
+        _ => PublicationType::Other,
+    };
+
+    let topics = match work.get("subject") {
+        Some(Value::Array(items)) => items.iter().filter_map(|v| v.as_str()).map(clean_text).collect(),
+        _ => Vec::new(),
+    };
+
+    let authors = match work.get("author") {
+        Some(Value::Array(items)) => items.iter()
+            .filter_map(|a| {
+                let text = |key: &str| a.get(key).and_then(|v| v.as_str()).map(clean_text).filter(|s| !s.is_empty());
+
+                // Group authors only have a name
+                let family_name = text("family").or_else(|| text("name"))?;
+
+                Some(CandidateAuthor {
+                    given_name: text("given"),
+                    family_name,
+                    orcid: text("ORCID").and_then(|o| parse_orcid(&o).ok()),
+                    affiliation: a.get("affiliation")
+                        .and_then(|aff| aff.get(0))
+                        .and_then(|aff| aff.get("name"))
+                        .and_then(|n| n.as_str())
+                        .map(clean_text),
+                })
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    Ok(CandidatePublication {
+        title,
+        doi: first("DOI").and_then(|d| normalize_doi(&d)),
+        venue: first("container-title").or_else(|| first("publisher")),
+        year,
+        publication_type,
+        topics,
+        url: first("URL"),
+        authors,
+    })
+}
+
+fn parse_ris(input: &str) -> ParsedEntries {
+    let mut entries = Vec::new();
+    let mut fields: Vec<(String, String)> = Vec::new();
+
+    for line in input.lines() {
+        // Tags are two characters, two spaces, a hyphen and a space: `TI  - Title`
+        let line = line.trim_start_matches('\u{feff}').trim_end();
+        if line.get(2..5) != Some("  -") {
+            continue
+        };
+
+        let tag = line[..2].to_uppercase();
+        let value = line[5..].trim().to_owned();
+
+        match tag.as_str() {
+            "TY" => {
+                fields.clear();
+                fields.push((tag, value));
+            },
+            "ER" => {
+                entries.push(ris_entry(&fields));
+                fields.clear();
+            },
+            _ => fields.push((tag, value)),
+        };
+    };
+
+    entries
+}
+
+fn ris_entry(fields: &[(String, String)]) -> Result<CandidatePublication, String> {
+    let first = |tags: &[&str]| tags.iter()
+        .find_map(|tag| fields.iter().find(|(t, v)| t.as_str() == *tag && !v.is_empty()))
+        .map(|(_, v)| clean_text(v));
+
+    let all = |tags: &[&str]| fields.iter()
+        .filter(|(t, v)| tags.contains(&t.as_str()) && !v.is_empty())
+        .map(|(_, v)| v.as_str())
+        .collect::<Vec<&str>>();
+
+    let title = first(&["TI", "T1", "CT"]).ok_or_else(|| "Entry has no title".to_string())?;
+
+    let publication_type = match first(&["TY"]).unwrap_or_default().as_str() {
+        "JOUR" | "JFULL" | "EJOUR" | "MGZN" => PublicationType::JournalArticle,
+        "BOOK" | "EBOOK" | "EDBOOK" => PublicationType::Book,
+        "CHAP" | "ECHAP" => PublicationType::BookChapter,
+        "CONF" | "CPAPER" => PublicationType::ConferencePaper,
+        "RPRT" | "GOVDOC" => PublicationType::Report,
+        "DATA" | "DBASE" => PublicationType::Dataset,
+        "UNPB" | "INPR" => PublicationType::Preprint,
+        _ => PublicationType::Other,
+    };
+
+    Ok(CandidatePublication {
+        title,
+        doi: first(&["DO"]).and_then(|d| normalize_doi(&d)),
+        venue: first(&["JF", "JO", "T2", "JA", "BT", "PB"]),
+        year: first(&["PY", "Y1", "DA"]).and_then(|y| parse_year(&y)),
+        publication_type,
+        topics: all(&["KW"]).into_iter().map(clean_text).collect(),
+        url: first(&["UR"]),
+        authors: all(&["AU", "A1"]).into_iter().filter_map(CandidateAuthor::from_name).collect(),
+    })
+}
+
+fn parse_bibtex(input: &str) -> ParsedEntries {
+    let chars: Vec<char> = input.chars().collect();
+    let mut entries = Vec::new();
+    let mut i = 0;
+
+    while let Some(at) = chars[i..].iter().position(|c| *c == '@') {
+        i += at + 1;
+
+        let entry_type: String = chars[i..].iter()
+            .take_while(|c| c.is_alphanumeric())
+            .collect::<String>()
+            .to_lowercase();
+        i += entry_type.chars().count();
+
+        while i < chars.len() && chars[i].is_whitespace() {
+            i += 1;
+        };
+
+        if i >= chars.len() || chars[i] != '{' {
+            continue
+        };
+
+        let end = matching_brace(&chars, i);
+        let body: String = chars[i + 1..end].iter().collect();
+        i = (end + 1).min(chars.len());
+
+        if ["comment", "preamble", "string"].contains(&entry_type.as_str()) {
+            continue
+        };
+
+        entries.push(bibtex_entry(&entry_type, &body));
+    };
+
+    entries
+}
+
+/// Index of the brace closing the one at `open`, or the end of input
+fn matching_brace(chars: &[char], open: usize) -> usize {
+    let mut depth = 0;
+
+    for (offset, c) in chars[open..].iter().enumerate() {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return open + offset
+                };
+            },
+            _ => (),
+        };
+    };
+
+    chars.len()
+}
+
+/// Parse `key, field = {value}, field = "value", field = 2020`
+fn bibtex_fields(body: &str) -> HashMap<String, String> {
+    let chars: Vec<char> = body.chars().collect();
+    let mut fields = HashMap::new();
+
+    // Skip the citation key
+    let mut i = chars.iter().position(|c| *c == ',').map(|p| p + 1).unwrap_or(chars.len());
+
+    while i < chars.len() {
+        let eq = match chars[i..].iter().position(|c| *c == '=') {
+            Some(p) => i + p,
+            None => break,
+        };
+
+        let name: String = chars[i..eq].iter().collect::<String>().trim().trim_start_matches(',').trim().to_lowercase();
+        i = eq + 1;
+
+        while i < chars.len() && chars[i].is_whitespace() {
+            i += 1;
+        };
+
+        if i >= chars.len() {
+            break
+        };
+
+        let value: String = match chars[i] {
+            '{' => {
+                let end = matching_brace(&chars, i);
+                let v = chars[i + 1..end.min(chars.len())].iter().collect();
+                i = end + 1;
+                v
+            },
+            '"' => {
+                let mut depth = 0;
+                let mut end = i + 1;
+                while end < chars.len() && !(chars[end] == '"' && depth == 0) {
+                    match chars[end] {
+                        '{' => depth += 1,
+                        '}' => depth -= 1,
+                        _ => (),
+                    };
+                    end += 1;
+                };
+                let v = chars[i + 1..end.min(chars.len())].iter().collect();
+                i = end + 1;
+                v
+            },
+            _ => {
+                let end = chars[i..].iter().position(|c| *c == ',').map(|p| i + p).unwrap_or(chars.len());
+                let v = chars[i..end].iter().collect();
+                i = end;
+                v
+            },
+        };
+
+        // Skip to the comma before the next field
+        while i < chars.len() && chars[i] != ',' {
+            i += 1;
+        };
+
+        if !name.is_empty() {
+            fields.insert(name, value);
+        };
+    };
+
+    fields
+}
+
+fn bibtex_entry(entry_type: &str, body: &str) -> Result<CandidatePublication, String> {
+    let fields = bibtex_fields(body);
+    let field = |name: &str| fields.get(name).map(|v| clean_text(&strip_latex(v))).filter(|v| !v.is_empty());
+
+    let title = field("title").ok_or_else(|| "Entry has no title".to_string())?;
+
+    let publication_type = match entry_type {
+        "article" => PublicationType::JournalArticle,
+        "book" | "proceedings" => PublicationType::Book,
+        "inbook" | "incollection" => PublicationType::BookChapter,
+        "inproceedings" | "conference" => PublicationType::ConferencePaper,
+        "techreport" | "report" => PublicationType::Report,
+        "dataset" => PublicationType::Dataset,
+        "unpublished" => PublicationType::Preprint,
+        _ => PublicationType::Other,
+    };
+
+    let topics = field("keywords")
+        .map(|k| k.split(|c| c == ',' || c == ';').map(|t| t.trim().to_owned()).filter(|t| !t.is_empty()).collect())
+        .unwrap_or_default();
+
+    let authors = field("author")
+        .map(|a| a.split(" and ").filter_map(CandidateAuthor::from_name).collect())
+        .unwrap_or_default();
+
+    Ok(CandidatePublication {
+        title,
+        doi: field("doi").and_then(|d| normalize_doi(&d)),
+        venue: field("journal").or_else(|| field("booktitle")).or_else(|| field("publisher")),
+        year: field("year").and_then(|y| parse_year(&y)),
+        publication_type,
+        topics,
+        url: field("url"),
+        authors,
+    })
+}
+
+fn parse_dois(input: &str, client: &dyn CrossrefClient) -> ParsedEntries {
+    input.lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let doi = normalize_doi(line).ok_or_else(|| format!("Not a DOI: {}", line))?;
+
+            let body = client.fetch_work(&doi).map_err(|e| e.error_message)?;
+
+            let value: Value = serde_json::from_str(&body)
+                .map_err(|e| format!("Invalid Crossref JSON for {}: {}", doi, e))?;
+
+            crossref_work(value.get("message").unwrap_or(&value))
+        })
+        .collect()
+}
+
+fn parse_year(s: &str) -> Option<i32> {
+    let digits: String = s.chars().skip_while(|c| !c.is_ascii_digit()).take(4).collect();
+
+    if digits.len() == 4 { digits.parse().ok() } else { None }
+}
+
+/// Drop markup such as Crossref's <i>..</i> and collapse whitespace
+fn clean_text(s: &str) -> String {
+    let mut res = String::with_capacity(s.len());
+    let mut in_tag = false;
+
+    for c in s.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            c if !in_tag => res.push(c),
+            _ => (),
+        };
+    };
+
+    res.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+/// Remove BibTeX braces and the most common escapes
+fn strip_latex(s: &str) -> String {
+    s.replace("\\&", "&")
+        .replace("\\%", "%")
+        .replace("\\_", "_")
+        .replace(|c: char| c == '{' || c == '}', "")
+}
+
+/// Lower case ASCII letters and digits separated by single spaces,
+/// used to compare titles and names
+fn match_key(s: &str) -> String {
+    let folded: String = s.to_lowercase()
+        .chars()
+        .map(|c| match c {
+            'à' | 'á' | 'â' | 'ä' | 'ã' | 'å' => 'a',
+            'ç' => 'c',
+            'è' | 'é' | 'ê' | 'ë' => 'e',
+            'ì' | 'í' | 'î' | 'ï' => 'i',
+            'ñ' => 'n',
+            'ò' | 'ó' | 'ô' | 'ö' | 'õ' | 'ø' => 'o',
+            'ù' | 'ú' | 'û' | 'ü' => 'u',
+            'ý' | 'ÿ' => 'y',
+            c if c.is_alphanumeric() => c,
+            _ => ' ',
+        })
+        .collect();
+
+    folded.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+/// Sørensen–Dice coefficient of the character bigrams of two match keys
+fn title_similarity(a: &str, b: &str) -> f64 {
+    let bigrams = |s: &str| {
+        let chars: Vec<char> = s.chars().filter(|c| *c != ' ').collect();
+        let mut counts: HashMap<(char, char), usize> = HashMap::new();
+        for pair in chars.windows(2) {
+            *counts.entry((pair[0], pair[1])).or_insert(0) += 1;
+        };
+        counts
+    };
+
+    let (a, b) = (bigrams(a), bigrams(b));
+    let total: usize = a.values().sum::<usize>() + b.values().sum::<usize>();
+
+    if total == 0 {
+        return 0.0
+    };
+
+    let shared: usize = a.iter()
+        .map(|(pair, n)| (*n).min(*b.get(pair).unwrap_or(&0)))
+        .sum();
+
+    2.0 * shared as f64 / total as f64
+}
+
+/// Publication identity fields kept in memory for de-duplication
+struct KnownPublication {
+    id: Uuid,
+    title_key: String,
+    year: Option<i32>,
+    doi: Option<String>,
+}
+
+impl KnownPublication {
+    fn new(publication: &Publication) -> Self {
+        KnownPublication {
+            id: publication.id,
+            title_key: match_key(&publication.title),
+            year: publication.publication_year,
+            doi: publication.doi.clone(),
+        }
+    }
+}
+
+/// Find the same publication by DOI, or by a near-identical title in the same year
+fn find_known(known: &[KnownPublication], candidate: &CandidatePublication) -> Option<Uuid> {
+    if let Some(doi) = &candidate.doi {
+        if let Some(k) = known.iter().find(|k| k.doi.as_ref() == Some(doi)) {
+            return Some(k.id)
+        };
+    };
+
+    let title_key = match_key(&candidate.title);
+
+    known.iter()
+        .filter(|k| match (k.year, candidate.year) {
+            (Some(a), Some(b)) => a == b,
+            _ => true,
+        })
+        // Different DOIs are different publications, however similar the titles
+        .filter(|k| match (&k.doi, &candidate.doi) {
+            (Some(a), Some(b)) => a == b,
+            _ => true,
+        })
+        .map(|k| (k.id, title_similarity(&k.title_key, &title_key)))
+        .filter(|(_, score)| *score >= TITLE_MATCH_THRESHOLD)
+        .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal))
+        .map(|(id, _)| id)
+}
+
+/// Persons indexed for author matching
+struct PersonMatcher {
+    by_orcid: HashMap<String, Uuid>,
+    by_family: HashMap<String, Vec<(Uuid, String)>>, // family name key -> (person, given name key)
+}
+
+impl PersonMatcher {
+    fn new(persons: &[Person]) -> Self {
+        let mut by_family: HashMap<String, Vec<(Uuid, String)>> = HashMap::new();
+
+        for p in persons {
+            by_family
+                .entry(match_key(&p.family_name))
+                .or_insert_with(Vec::new)
+                .push((p.id, match_key(&p.given_name)));
+        };
+
+        PersonMatcher {
+            by_orcid: persons.iter()
+                .filter_map(|p| p.orcid.clone().map(|o| (o, p.id)))
+                .collect(),
+            by_family,
+        }
+    }
+
+    /// An ORCID match is definitive. Otherwise propose persons with the
+    /// same family name whose first given name or initial agrees.
+    fn propose(&self, author: &CandidateAuthor) -> Vec<(Uuid, AuthorMatchMethod)> {
+        if let Some(id) = author.orcid.as_ref().and_then(|o| self.by_orcid.get(o)) {
+            return vec![(*id, AuthorMatchMethod::Orcid)]
+        };
+
+        let given = author.given_name.as_deref().map(match_key).unwrap_or_default();
+        let first_given = given.split(' ').next().unwrap_or("");
+
+        self.by_family.get(&match_key(&author.family_name))
+            .into_iter()
+            .flatten()
+            .filter_map(|(id, person_given)| {
+                let person_first = person_given.split(' ').next().unwrap_or("");
+
+                if first_given.len() > 1 && first_given == person_first {
+                    Some((*id, AuthorMatchMethod::Name))
+                } else if !first_given.is_empty() && first_given.chars().next() == person_first.chars().next() {
+                    Some((*id, AuthorMatchMethod::Initials))
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+/// Outcome of a publication import. Entries are only saved when `committed`
+/// is true, which requires a non dry-run with no errors.
+pub struct PublicationImportReport {
+    pub format: PublicationFormat,
+    pub dry_run: bool,
+    pub committed: bool,
+    pub rows: Vec<RowReport>, // line is the entry number in the file
+    pub created: usize,
+    pub updated: usize,
+    pub unchanged: usize,
+    pub errors: usize,
+    /// Author to person matches waiting for confirmation
+    pub proposals: usize,
+}
+
+impl PublicationImportReport {
+    fn new(format: PublicationFormat, dry_run: bool) -> Self {
+        PublicationImportReport {
+            format,
+            dry_run,
+            committed: false,
+            rows: Vec::new(),
+            created: 0,
+            updated: 0,
+            unchanged: 0,
+            errors: 0,
+            proposals: 0,
+        }
+    }
+
+    fn push(&mut self, line: u64, key: String, action: RowAction, message: String) {
+        match action {
+            RowAction::Create => self.created += 1,
+            RowAction::Update => self.updated += 1,
+            RowAction::Unchanged => self.unchanged += 1,
+            RowAction::Conflict | RowAction::Error => self.errors += 1,
+        };
+
+        self.rows.push(RowReport { line, key, action, message });
+    }
+
+    pub fn has_failures(&self) -> bool {
+        self.errors > 0
+    }
+}
+
+impl fmt::Display for PublicationImportReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for row in &self.rows {
+            writeln!(f, "entry {:>4}  {:<9}  {}  {}", row.line, format!("{:?}", row.action).to_lowercase(), row.key, row.message)?;
+        };
+
+        writeln!(f, "{}: {} create, {} update, {} unchanged, {} error, {} author matches to review",
+            self.format, self.created, self.updated, self.unchanged, self.errors, self.proposals)?;
+
+        let status = if self.committed {
+            "Committed"
+        } else if self.dry_run {
+            "Dry run - nothing saved"
+        } else {
+            "Rolled back - nothing saved"
+        };
+
+        write!(f, "{}", status)
+    }
+}
+
+enum EntryOutcome {
+    Created(Uuid),
+    Updated(Uuid, Vec<&'static str>),
+    Unchanged(Uuid),
+}
+
+/// Create or merge publications, add their authors and propose author
+/// matches. Runs in one transaction with a savepoint per entry.
+pub fn import_publications(
+    conn: &PgConnection,
+    format: PublicationFormat,
+    entries: ParsedEntries,
+    dry_run: bool,
+) -> Result<PublicationImportReport, CustomError> {
+
+    let persons = persons::table
+        .filter(persons::retired_at.is_null())
+        .load::<Person>(conn)?;
+
+    let matcher = PersonMatcher::new(&persons);
+
+    let mut report = PublicationImportReport::new(format, dry_run);
+
+    let res = conn.transaction::<(), DieselError, _>(|| {
+        let mut known: Vec<KnownPublication> = publications::table
+            .load::<Publication>(conn)?
+            .iter()
+            .map(KnownPublication::new)
+            .collect();
+
+        let mut seen: HashMap<Uuid, u64> = HashMap::new();
+
+        for (i, entry) in entries.into_iter().enumerate() {
+            let line = i as u64 + 1;
+
+            let candidate = match entry {
+                Ok(c) => c,
+                Err(message) => {
+                    report.push(line, String::new(), RowAction::Error, message);
+                    continue
+                },
+            };
+
+            let key = candidate.doi.clone().unwrap_or_else(|| candidate.title.clone());
+            let mut proposals = 0;
+
+            let res = conn.transaction::<EntryOutcome, DieselError, _>(|| {
+                import_entry(conn, &candidate, &mut known, &matcher, &mut proposals)
+            });
+
+            match res {
+                Ok(outcome) => {
+                    let (id, action, mut message) = match outcome {
+                        EntryOutcome::Created(id) => (id, RowAction::Create, String::new()),
+                        EntryOutcome::Updated(id, fields) => (id, RowAction::Update, fields.join(", ")),
+                        EntryOutcome::Unchanged(id) => (id, RowAction::Unchanged, String::new()),
+                    };
+
+                    if let Some(first) = seen.get(&id) {
+                        message = format!("Same publication as entry {} {}", first, message).trim().to_owned();
+                    } else {
+                        seen.insert(id, line);
+                    };
+
+                    report.proposals += proposals;
+                    report.push(line, key, action, message);
+                },
+                Err(e) => report.push(line, key, RowAction::Error, e.to_string()),
+            };
+        };
+
+        if dry_run || report.has_failures() {
+            Err(DieselError::RollbackTransaction)
+        } else {
+            Ok(())
+        }
+    });
+
+    match res {
+        Ok(()) => {
+            report.committed = true;
+            Ok(report)
+        },
+        Err(DieselError::RollbackTransaction) => Ok(report),
+        Err(e) => Err(CustomError::from(e)),
+    }
+}
+
+fn import_entry(
+    conn: &PgConnection,
+    candidate: &CandidatePublication,
+    known: &mut Vec<KnownPublication>,
+    matcher: &PersonMatcher,
+    proposals: &mut usize,
+) -> Result<EntryOutcome, DieselError> {
+
+    let (publication, mut changed, created) = match find_known(known, candidate) {
+        Some(id) => {
+            let mut publication = publications::table.find(id).first::<Publication>(conn)?;
+            let changed = merge_publication(&mut publication, candidate);
+
+            if !changed.is_empty() {
+                publication.updated_at = Utc::now().naive_utc();
+                publication = diesel::update(publications::table.find(id))
+                    .set(&publication)
+                    .get_result(conn)?;
+
+                // Keep the in-memory key current, e.g. a DOI found for a title match
+                if let Some(k) = known.iter_mut().find(|k| k.id == id) {
+                    *k = KnownPublication::new(&publication);
+                };
+            };
+
+            (publication, changed, false)
+        },
+        None => {
+            let publication: Publication = diesel::insert_into(publications::table)
+                .values(&NewPublication::new(
+                    candidate.title.clone(),
+                    candidate.doi.clone(),
+                    candidate.venue.clone(),
+                    candidate.year,
+                    candidate.publication_type,
+                    OpenAccessStatus::Unknown,
+                    candidate.topics.clone(),
+                    candidate.url.clone(),
+                ))
+                .get_result(conn)?;
+
+            known.push(KnownPublication::new(&publication));
+
+            (publication, Vec::new(), true)
+        },
+    };
+
+    let taken_orders: HashSet<i32> = publication_authors::table
+        .filter(publication_authors::publication_id.eq(publication.id))
+        .select(publication_authors::author_order)
+        .load::<Option<i32>>(conn)?
+        .into_iter()
+        .flatten()
+        .collect();
+
+    let mut authors_added = false;
+
+    for (i, author) in candidate.authors.iter().enumerate() {
+        let order = i as i32 + 1;
+
+        if taken_orders.contains(&order) {
+            continue
+        };
+
+        let added: PublicationAuthor = diesel::insert_into(publication_authors::table)
+            .values(&NewPublicationAuthor::new(
+                publication.id,
+                None,
+                author.display_name(),
+                Some(order),
+                author.affiliation.clone(),
+                author.orcid.clone(),
+            ))
+            .get_result(conn)?;
+
+        authors_added = true;
+
+        for (person_id, method) in matcher.propose(author) {
+            *proposals += AuthorMatch::propose(conn, &NewAuthorMatch::new(added.id, person_id, method))?;
+        };
+    };
+
+    if authors_added && !created {
+        changed.push("authors");
+    };
+
+    Ok(match (created, changed.is_empty()) {
+        (true, _) => EntryOutcome::Created(publication.id),
+        (false, false) => EntryOutcome::Updated(publication.id, changed),
+        (false, true) => EntryOutcome::Unchanged(publication.id),
+    })
+}
+
+/// Fill fields the stored publication is missing. Existing values win.
+fn merge_publication(publication: &mut Publication, candidate: &CandidatePublication) -> Vec<&'static str> {
+    let mut changed = Vec::new();
+
+    fn fill<T: Clone>(current: &mut Option<T>, value: &Option<T>, name: &'static str, changed: &mut Vec<&'static str>) {
+        if current.is_none() && value.is_some() {
+            *current = value.clone();
+            changed.push(name);
+        };
+    }
+
+    fill(&mut publication.doi, &candidate.doi, "doi", &mut changed);
+    fill(&mut publication.venue, &candidate.venue, "venue", &mut changed);
+    fill(&mut publication.publication_year, &candidate.year, "publication_year", &mut changed);
+    fill(&mut publication.url, &candidate.url, "url", &mut changed);
+
+    if publication.publication_type == PublicationType::Other.to_string() && candidate.publication_type != PublicationType::Other {
+        publication.publication_type = candidate.publication_type.to_string();
+        changed.push("publication_type");
+    };
+
+    let new_topics: Vec<String> = candidate.topics.iter()
+        .filter(|t| !publication.topics.contains(t))
+        .cloned()
+        .collect();
+
+    if !new_topics.is_empty() {
+        publication.topics.extend(new_topics);
+        changed.push("topics");
+    };
+
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn similarity(a: &str, b: &str) -> f64 {
+        title_similarity(&match_key(a), &match_key(b))
+    }
+
+    #[test]
+    fn reads_bibtex_entries_and_authors() {
+        let input = r#"
+            @comment{Exported from a reference manager}
+            @Article{carberry2020,
+                title = {Psychoceramics {R}evisited: Cracks \& Crazing},
+                author = {Carberry, Josiah and Jane Q. Public and {van der Berg}, Anna},
+                journal = "Journal of Psychoceramics",
+                year = 2020,
+                doi = {https://doi.org/10.5555/ABC.123},
+                keywords = {ceramics; cracks, crazing}
+            }
+        "#;
+
+        let entries = parse_bibtex(input);
+        assert_eq!(entries.len(), 1);
+
+        let entry = entries[0].as_ref().unwrap();
+        assert_eq!(entry.title, "Psychoceramics Revisited: Cracks & Crazing");
+        assert_eq!(entry.publication_type, PublicationType::JournalArticle);
+        assert_eq!(entry.venue.as_deref(), Some("Journal of Psychoceramics"));
+        assert_eq!(entry.year, Some(2020));
+        assert_eq!(entry.doi.as_deref(), Some("10.5555/abc.123"));
+        assert_eq!(entry.topics, vec!["ceramics", "cracks", "crazing"]);
+
+        let authors: Vec<(Option<&str>, &str)> = entry.authors.iter()
+            .map(|a| (a.given_name.as_deref(), a.family_name.as_str()))
+            .collect();
+        assert_eq!(authors, vec![
+            (Some("Josiah"), "Carberry"),
+            (Some("Jane Q."), "Public"),
+            (Some("Anna"), "van der Berg"),
+        ]);
+    }
+
+    #[test]
+    fn reports_malformed_bibtex_entries_in_place() {
+        let input = r#"
+            @misc{notitle, author = {Carberry, Josiah}, year = {2019}}
+            @article without a body
+            @inproceedings{ok, title = {Second entry}, booktitle = {Proceedings}}
+            @book{unclosed, title = {Never closed}
+        "#;
+
+        let entries = parse_bibtex(input);
+        assert_eq!(entries.len(), 3);
+
+        assert_eq!(entries[0].as_ref().unwrap_err(), "Entry has no title");
+
+        let second = entries[1].as_ref().unwrap();
+        assert_eq!(second.title, "Second entry");
+        assert_eq!(second.publication_type, PublicationType::ConferencePaper);
+        assert_eq!(second.venue.as_deref(), Some("Proceedings"));
+
+        // An unclosed entry runs to the end of the input
+        assert_eq!(entries[2].as_ref().unwrap().title, "Never closed");
+    }
+
+    #[test]
+    fn reads_ris_entries_and_authors() {
+        let input = "\u{feff}TY  - JOUR\r\n\
+            TI  - Psychoceramics revisited\r\n\
+            AU  - Carberry, Josiah\r\n\
+            AU  - Public, Jane Q.\r\n\
+            A1  - Jane Doe\r\n\
+            JO  - Journal of Psychoceramics\r\n\
+            PY  - 2020///\r\n\
+            DO  - 10.5555/XYZ\r\n\
+            KW  - ceramics\r\n\
+            KW  - cracks\r\n\
+            ER  - \r\n";
+
+        let entries = parse_ris(input);
+        assert_eq!(entries.len(), 1);
+
+        let entry = entries[0].as_ref().unwrap();
+        assert_eq!(entry.title, "Psychoceramics revisited");
+        assert_eq!(entry.publication_type, PublicationType::JournalArticle);
+        assert_eq!(entry.year, Some(2020));
+        assert_eq!(entry.doi.as_deref(), Some("10.5555/xyz"));
+        assert_eq!(entry.topics, vec!["ceramics", "cracks"]);
+
+        let families: Vec<&str> = entry.authors.iter().map(|a| a.family_name.as_str()).collect();
+        assert_eq!(families, vec!["Carberry", "Public", "Doe"]);
+    }
+
+    #[test]
+    fn reports_malformed_ris_entries_in_place() {
+        let input = "TY  - RPRT\n\
+            AU  - Carberry, Josiah\n\
+            ER  - \n\
+            not a tag line\n\
+            TY  - BOOK\n\
+            T1  - A book\n\
+            ER  - \n\
+            TY  - JOUR\n\
+            TI  - Never ended\n";
+
+        let entries = parse_ris(input);
+
+        // The last entry has no ER line, so is never read
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].as_ref().unwrap_err(), "Entry has no title");
+        assert_eq!(entries[1].as_ref().unwrap().publication_type, PublicationType::Book);
+    }
+
+    #[test]
+    fn identical_titles_ignore_case_and_punctuation() {
+        assert_eq!(similarity("Climate change impacts on boreal forest productivity",
+            "Climate-Change Impacts on Boreal Forest Productivity."), 1.0);
+        assert_eq!(similarity("Écologie des forêts", "Ecologie des forets"), 1.0);
+    }
+
+    #[test]
+    fn near_identical_titles_meet_the_threshold() {
+        assert!(similarity("Climate change impacts on boreal forest productivity",
+            "Climate change impacts on boreal forests productivity") >= TITLE_MATCH_THRESHOLD);
+    }
+
+    #[test]
+    fn different_titles_fall_below_the_threshold() {
+        assert!(similarity("Climate change impacts on boreal forest productivity",
+            "Climate change impacts on Arctic tundra productivity") < TITLE_MATCH_THRESHOLD);
+        // A shortened title is not the same publication
+        assert!(similarity("Climate change impacts on boreal forest productivity",
+            "Climate change impacts on boreal forest") < TITLE_MATCH_THRESHOLD);
+        assert_eq!(similarity("", ""), 0.0);
+    }
+}
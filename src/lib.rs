@@ -4,10 +4,16 @@ pub mod database;
 pub mod errors;
 pub mod schema;
 pub mod graphql;
+pub mod importer;
+pub mod export;
+pub mod oidc;
+#[cfg(feature = "mock-idp")]
+pub mod mock_idp;
 
 use tera::{Tera, Context};
 use actix_identity::Identity;
 use actix_session::Session;
+use actix_web_httpauth::extractors::bearer::BearerAuth;
 
 use rand::{thread_rng, Rng};
 use rand::distributions::Alphanumeric;
@@ -96,6 +102,24 @@ pub fn extract_identity_data(id: &Identity) -> (String, String) {
     (session_user, role)
 }
 
+/// Session user and role for API endpoints. A bearer API token with the scope
+/// acts as its user, otherwise the identity cookie is used.
+pub fn extract_api_identity(
+    bearer: Option<BearerAuth>,
+    id: &Identity,
+    scope: models::ApiScope,
+) -> Result<(String, String), errors::CustomError> {
+
+    match bearer {
+        Some(bearer) => {
+            let conn = database::connection()?;
+            let user = models::ApiToken::authenticate(&conn, bearer.token(), scope)?;
+            Ok((user.slug, user.role))
+        },
+        None => Ok(extract_identity_data(id)),
+    }
+}
+
 /// Generate context, session_user and role from id and lang
 pub fn generate_email_context(
     id: Identity,
@@ -1,15 +1,22 @@
-use actix_web::{HttpServer, App, middleware};
+use actix_web::{HttpServer, App, middleware, web};
 use dotenv::dotenv;
 use std::env;
+use std::fmt;
+use std::time::Duration;
 use tera::{Tera};
 use tera_text_filters::snake_case;
 use actix_identity::{IdentityService, CookieIdentityPolicy};
 use actix_web_static_files;
+use chrono::Utc;
 use sendgrid::SGClient;
 
 use people_data::handlers;
 use people_data::AppData;
 use people_data::database;
+use people_data::errors::CustomError;
+use people_data::importer;
+use people_data::graphql;
+use people_data::models::WorkforceMetric;
 
 use fluent_templates::{FluentLoader, static_loader};
 // https://lib.rs/crates/fluent-templates
@@ -25,6 +32,27 @@ static_loader! {
     };
 }
 
+/// Run a CLI subcommand against the database, print its report and exit:
+/// 1 if the report has failures, 2 if the command couldn't run
+fn run_command<R, F>(command: F, has_failures: fn(&R) -> bool) -> !
+where
+    R: fmt::Display,
+    F: FnOnce() -> Result<R, CustomError>,
+{
+    database::init();
+
+    match command() {
+        Ok(report) => {
+            println!("{}", report);
+            std::process::exit(if has_failures(&report) { 1 } else { 0 });
+        },
+        Err(e) => {
+            eprintln!("{}", e.error_message);
+            std::process::exit(2);
+        },
+    };
+}
+
 #[actix_rt::main]
 async fn main() -> std::io::Result<()> {
 
@@ -32,6 +60,22 @@ async fn main() -> std::io::Result<()> {
     std::env::set_var("RUST_LOG", "actix_web=info");
     env_logger::init();
 
+    // CLI subcommands run against the database and exit without serving
+    let args: Vec<String> = env::args().collect();
+
+    match args.get(1).map(String::as_str) {
+        Some("import") => run_command(|| importer::run_import_command(&args[2..]), |r| r.has_failures()),
+        Some("sync_hrms") => run_command(|| importer::run_sync_hrms_command(&args[2..]), |_| false),
+        Some("import_orcid") => run_command(|| importer::run_import_orcid_command(&args[2..]), |_| false),
+        Some("import_publications") => run_command(|| importer::run_import_publications_command(&args[2..]), |r| r.has_failures()),
+        Some("refresh_workforce") => run_command(|| {
+            database::connection()
+                .and_then(|conn| WorkforceMetric::refresh_all(&conn, Utc::now().naive_utc().date()))
+                .map(|rows| format!("Refreshed {} workforce metric rows", rows))
+        }, |_| false),
+        _ => {},
+    };
+
     let environment = env::var("ENVIRONMENT");
 
     let environment = match environment {
@@ -49,6 +93,24 @@ async fn main() -> std::io::Result<()> {
 
     database::init();
 
+    // Acting assignments, secondments and deployments end on their end date while we serve
+    actix_rt::spawn(async {
+        let mut interval = actix_rt::time::interval(Duration::from_secs(database::ROLE_EXPIRY_INTERVAL_SECS));
+
+        // The first tick completes at once, and init has just expired roles
+        interval.tick().await;
+
+        loop {
+            interval.tick().await;
+
+            match web::block(database::expire_temporary_roles).await {
+                Ok(expired) if expired > 0 => println!("Expired {} temporary roles", expired),
+                Ok(_) => {},
+                Err(e) => println!("Unable to expire temporary roles: {:?}", e),
+            };
+        };
+    });
+
     // SendGrid email API
     let sendgrid_var = env::var("SENDGRID_API_KEY");
     let sendgrid_key: String;
@@ -58,6 +120,8 @@ async fn main() -> std::io::Result<()> {
         Err(err) => panic!("Must supply API key in env variables to use: {}", err),
     };
 
+    let schema = graphql::create_schema();
+
     println!("Serving on: {}:{}", &host, &port);
 
     HttpServer::new(move || {
@@ -82,6 +146,7 @@ async fn main() -> std::io::Result<()> {
             .wrap(middleware::Logger::default())
             .configure(handlers::configure_services)
             .data(data.clone())
+            .data(schema.clone())
             .service(actix_web_static_files::ResourceFiles::new(
                 "/static", generated,
             ))
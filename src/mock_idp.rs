@@ -0,0 +1,237 @@
+// OpenID Connect provider for trying single sign-on without a real one, served by
+// the mock_idp binary and used by the sign-in tests. Only built with the mock-idp feature.
+// Anyone can sign in as anyone with any email, so never expose it.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use actix_web::{get, post, web, HttpResponse, Responder};
+use actix_web_httpauth::extractors::bearer::BearerAuth;
+use chrono::Utc;
+use serde::Deserialize;
+use serde_json::json;
+use url::Url;
+
+use crate::generate_unique_code;
+use crate::oidc::{pkce_challenge, OidcClaims};
+
+/// An authorization code waiting to be exchanged
+struct Grant {
+    client_id: String,
+    redirect_uri: String,
+    code_challenge: String,
+    nonce: Option<String>,
+    claims: OidcClaims,
+}
+
+pub struct MockIdp {
+    pub issuer: String,
+    grants: Mutex<HashMap<String, Grant>>, // By code
+    access_tokens: Mutex<HashMap<String, OidcClaims>>,
+}
+
+impl MockIdp {
+    pub fn new(issuer: &str) -> Self {
+        MockIdp {
+            issuer: issuer.trim_end_matches('/').to_owned(),
+            grants: Mutex::new(HashMap::new()),
+            access_tokens: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+/// Register the provider's endpoints. The app needs a web::Data<MockIdp>.
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(discovery);
+    cfg.service(authorize);
+    cfg.service(authorize_post);
+    cfg.service(token);
+    cfg.service(userinfo);
+}
+
+#[derive(Deserialize, Debug)]
+struct AuthorizeQuery {
+    response_type: String,
+    client_id: String,
+    redirect_uri: String,
+    state: String,
+    nonce: Option<String>,
+    code_challenge: Option<String>,
+    code_challenge_method: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct AuthorizeForm {
+    client_id: String,
+    redirect_uri: String,
+    state: String,
+    nonce: Option<String>,
+    code_challenge: String,
+    email: String,
+    name: String,
+    /// Checkbox, "on" when ticked
+    email_verified: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct TokenForm {
+    grant_type: String,
+    code: String,
+    redirect_uri: String,
+    client_id: String,
+    code_verifier: String,
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn base64_json(value: &serde_json::Value) -> String {
+    base64::encode_config(value.to_string(), base64::URL_SAFE_NO_PAD)
+}
+
+fn token_error(error: &str) -> HttpResponse {
+    HttpResponse::BadRequest().json(json!({ "error": error }))
+}
+
+#[get("/.well-known/openid-configuration")]
+async fn discovery(idp: web::Data<MockIdp>) -> impl Responder {
+    HttpResponse::Ok().json(json!({
+        "issuer": idp.issuer,
+        "authorization_endpoint": format!("{}/authorize", idp.issuer),
+        "token_endpoint": format!("{}/token", idp.issuer),
+        "userinfo_endpoint": format!("{}/userinfo", idp.issuer),
+        "response_types_supported": ["code"],
+        "subject_types_supported": ["public"],
+        "id_token_signing_alg_values_supported": ["none"],
+        "code_challenge_methods_supported": ["S256"],
+    }))
+}
+
+/// Sign-in form standing in for the provider's login page
+#[get("/authorize")]
+async fn authorize(query: web::Query<AuthorizeQuery>) -> impl Responder {
+    let code_challenge = match (query.code_challenge.as_ref(), query.code_challenge_method.as_deref()) {
+        (Some(challenge), Some("S256")) if query.response_type == "code" => challenge,
+        _ => return HttpResponse::BadRequest().body("Only the code flow with S256 PKCE is supported"),
+    };
+
+    let hidden = |name: &str, value: &str| format!(r#"<input type="hidden" name="{}" value="{}">"#, name, escape(value));
+
+    let body = format!(
+        r#"<!DOCTYPE html>
+<html><head><title>Mock identity provider</title></head>
+<body>
+<h1>Mock identity provider</h1>
+<p>Sign in to {client_id} as anyone.</p>
+<form action="/authorize" method="POST">
+{fields}
+<p><label>Email <input type="email" name="email" required></label></p>
+<p><label>Name <input type="text" name="name" required></label></p>
+<p><label><input type="checkbox" name="email_verified" checked> Email verified</label></p>
+<button type="submit">Sign in</button>
+</form>
+</body></html>"#,
+        client_id = escape(&query.client_id),
+        fields = [
+            hidden("client_id", &query.client_id),
+            hidden("redirect_uri", &query.redirect_uri),
+            hidden("state", &query.state),
+            hidden("nonce", query.nonce.as_deref().unwrap_or("")),
+            hidden("code_challenge", code_challenge),
+        ].join("\n"),
+    );
+
+    HttpResponse::Ok().content_type("text/html; charset=utf-8").body(body)
+}
+
+#[post("/authorize")]
+async fn authorize_post(idp: web::Data<MockIdp>, form: web::Form<AuthorizeForm>) -> impl Responder {
+    let form = form.into_inner();
+    let email = form.email.trim().to_lowercase();
+
+    let code = generate_unique_code(32, false);
+
+    let redirect = match Url::parse_with_params(&form.redirect_uri, &[("code", code.as_str()), ("state", form.state.as_str())]) {
+        Ok(url) => url,
+        Err(_) => return HttpResponse::BadRequest().body("Invalid redirect_uri"),
+    };
+
+    idp.grants.lock().unwrap().insert(code, Grant {
+        client_id: form.client_id,
+        redirect_uri: form.redirect_uri,
+        code_challenge: form.code_challenge,
+        nonce: form.nonce.filter(|n| !n.is_empty()),
+        claims: OidcClaims {
+            // Stable per email, as a real provider's subject is per account
+            sub: format!("mock|{}", email),
+            email: Some(email),
+            email_verified: form.email_verified.is_some(),
+            name: Some(form.name.trim().to_owned()),
+            preferred_username: None,
+        },
+    });
+
+    HttpResponse::Found().header("Location", redirect.to_string()).finish()
+}
+
+#[post("/token")]
+async fn token(idp: web::Data<MockIdp>, form: web::Form<TokenForm>) -> impl Responder {
+    if form.grant_type != "authorization_code" {
+        return token_error("unsupported_grant_type")
+    };
+
+    // Codes are single use
+    let grant = match idp.grants.lock().unwrap().remove(&form.code) {
+        Some(g) => g,
+        None => return token_error("invalid_grant"),
+    };
+
+    if grant.client_id != form.client_id || grant.redirect_uri != form.redirect_uri {
+        return token_error("invalid_grant")
+    };
+
+    if pkce_challenge(&form.code_verifier) != grant.code_challenge {
+        return token_error("invalid_grant")
+    };
+
+    let now = Utc::now().timestamp();
+
+    // Unsigned, which the app accepts as the token comes straight from this endpoint
+    let id_token = format!(
+        "{}.{}.",
+        base64_json(&json!({ "alg": "none", "typ": "JWT" })),
+        base64_json(&json!({
+            "iss": idp.issuer,
+            "aud": grant.client_id,
+            "sub": grant.claims.sub,
+            "iat": now,
+            "exp": now + 300,
+            "nonce": grant.nonce,
+            "email": grant.claims.email,
+            "email_verified": grant.claims.email_verified,
+            "name": grant.claims.name,
+        })),
+    );
+
+    let access_token = generate_unique_code(32, false);
+    idp.access_tokens.lock().unwrap().insert(access_token.clone(), grant.claims);
+
+    HttpResponse::Ok().json(json!({
+        "access_token": access_token,
+        "token_type": "Bearer",
+        "expires_in": 300,
+        "id_token": id_token,
+    }))
+}
+
+#[get("/userinfo")]
+async fn userinfo(idp: web::Data<MockIdp>, bearer: BearerAuth) -> impl Responder {
+    match idp.access_tokens.lock().unwrap().get(bearer.token()) {
+        Some(claims) => HttpResponse::Ok().json(claims),
+        None => HttpResponse::Unauthorized().finish(),
+    }
+}
@@ -56,13 +56,3 @@ pub struct Assessment {
     pub created_at: NaiveDate,
     pub updated_at: NaiveDate,
 }
-
-/// Other people's validations of an individuals Capability
-pub struct Validations {
-    pub id: Uuid,
-    pub validator_id: Uuid, // Person
-    pub capability_id: Uuid, // Capability
-    pub validated_level: u32,
-    pub created_at: NaiveDate,
-    pub updated_at: NaiveDate,
-}
\ No newline at end of file
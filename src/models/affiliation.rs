@@ -3,25 +3,38 @@ use std::fmt::Debug;
 use chrono::{prelude::*};
 use serde::{Deserialize, Serialize};
 use diesel::{self, Insertable, PgConnection, Queryable, ExpressionMethods};
+use diesel::upsert::excluded;
 use diesel::{RunQueryDsl, QueryDsl};
 use uuid::Uuid;
 use async_graphql::*;
-use rand::{Rng, thread_rng};
 
 use crate::graphql::graphql_translate;
 
 use crate::schema::*;
+use crate::errors::CustomError;
+use crate::database::connection;
+use crate::models::Organization;
 
 #[derive(Debug, Clone, Deserialize, Serialize, Queryable, Insertable, AsChangeset)]
 #[table_name = "affiliations"]
+/// A Person's relationship with an external Organization
+/// e.g. adjunct professor, visiting scientist
 pub struct Affiliation {
     pub id: Uuid,
+    pub person_id: Uuid,
     pub organization_id: Uuid,
     pub role: String,
     pub start_date: NaiveDate,
-    pub end_date: NaiveDate,
-    pub created_at: NaiveDate,
-    pub updated_at: NaiveDate,
+    pub end_date: Option<NaiveDate>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+/// Affiliation with its Organization for display
+pub struct AffiliationWithOrganization {
+    pub affiliation: Affiliation,
+    pub organization: Organization,
 }
 
 // Non Graphql
@@ -34,35 +47,42 @@ impl Affiliation {
         graphql_translate(res)
     }
     
+    /// Natural key: (person_id, organization_id, role, start_date)
+    /// Returns the existing affiliation unchanged if the key matches.
     pub fn get_or_create(conn: &PgConnection, affiliation: &NewAffiliation) -> FieldResult<Affiliation> {
-        let res = affiliations::table
-        .filter(affiliations::family_name.eq(&affiliation.family_name))
-        .distinct()
-        .first(conn);
-        
-        let affiliation = match res {
-            Ok(p) => p,
-            Err(e) => {
-                // Affiliation not found
-                println!("{:?}", e);
-                let p = Affiliation::create(conn, affiliation).expect("Unable to create affiliation");
-                p
-            }
-        };
-        Ok(affiliation)
+        let res = diesel::insert_into(affiliations::table)
+            .values(affiliation)
+            .on_conflict((affiliations::person_id, affiliations::organization_id, affiliations::role, affiliations::start_date))
+            .do_update()
+            .set(affiliations::person_id.eq(excluded(affiliations::person_id)))
+            .get_result(conn);
+
+        graphql_translate(res)
     }
 
     pub fn find_all() -> Result<Vec<Self>, CustomError> {
-        let conn = database::connection()?;
+        let conn = connection()?;
         let affiliations = affiliations::table.load::<Affiliation>(&conn)?;
         Ok(affiliations)
     }
 
     pub fn find(id: Uuid) -> Result<Self, CustomError> {
-        let conn = database::connection()?;
+        let conn = connection()?;
         let affiliation = affiliations::table.filter(affiliations::id.eq(id)).first(&conn)?;
         Ok(affiliation)
     }
+
+    pub fn find_by_person_with_organizations(conn: &PgConnection, person_id: Uuid) -> Result<Vec<AffiliationWithOrganization>, CustomError> {
+        let res = affiliations::table
+            .inner_join(organizations::table)
+            .filter(affiliations::person_id.eq(person_id))
+            .order(affiliations::start_date.desc())
+            .load::<(Affiliation, Organization)>(conn)?;
+
+        Ok(res.into_iter()
+            .map(|(affiliation, organization)| AffiliationWithOrganization { affiliation, organization })
+            .collect())
+    }
     
     pub fn update(&self, conn: &PgConnection) -> FieldResult<Self> {
         let res = diesel::update(affiliations::table)
@@ -77,31 +97,28 @@ impl Affiliation {
 #[derive(Debug, Clone, Deserialize, Serialize, Insertable, SimpleObject)]
 #[table_name = "affiliations"]
 pub struct NewAffiliation {
+    pub person_id: Uuid,
     pub organization_id: Uuid,
     pub role: String,
     pub start_date: NaiveDate,
-    pub end_date: NaiveDate,
-    pub created_at: NaiveDate,
-    pub updated_at: NaiveDate,
+    pub end_date: Option<NaiveDate>,
 }
 
 impl NewAffiliation {
 
     pub fn new(
+        person_id: Uuid,
         organization_id: Uuid,
         role: String,
         start_date: NaiveDate,
-        end_date: NaiveDate,
-        created_at: NaiveDate,
-        updated_at: NaiveDate,
+        end_date: Option<NaiveDate>,
     ) -> Self {
         NewAffiliation {
+            person_id,
             organization_id,
             role,
             start_date,
             end_date,
-            created_at,
-            updated_at,
         }
     }
 }
@@ -0,0 +1,193 @@
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::{Duration, prelude::*};
+use serde::{Deserialize, Serialize};
+use diesel::{self, Insertable, PgConnection, Queryable, ExpressionMethods};
+use diesel::{RunQueryDsl, QueryDsl};
+use uuid::Uuid;
+
+use crate::schema::*;
+use crate::errors::CustomError;
+use crate::generate_unique_code;
+use crate::models::{make_hash, make_salt, User};
+
+/// Marks our tokens so they can be recognized, e.g. by secret scanners
+const TOKEN_MARKER: &str = "pdt";
+
+const PREFIX_LENGTH: usize = 12;
+const SECRET_LENGTH: usize = 40;
+
+/// Longest a token can be issued for
+pub const MAX_TOKEN_DAYS: i64 = 365;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+/// What a token may be used for
+pub enum ApiScope {
+    Graphql, // POST /api/graphql
+    Export, // GET /{lang}/export/{report}/{format}
+}
+
+impl ApiScope {
+    pub fn all() -> Vec<ApiScope> {
+        vec![ApiScope::Graphql, ApiScope::Export]
+    }
+}
+
+impl fmt::Display for ApiScope {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            ApiScope::Graphql => "graphql",
+            ApiScope::Export => "export",
+        };
+        f.write_str(s)
+    }
+}
+
+impl FromStr for ApiScope {
+    type Err = CustomError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "graphql" => Ok(ApiScope::Graphql),
+            "export" => Ok(ApiScope::Export),
+            _ => Err(CustomError::new(400, format!("Unknown API scope: {}", s))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, Queryable)]
+/// Personal token for scripts calling the API as its user.
+/// The full token is only shown once, when issued: pdt_<prefix>_<secret>
+pub struct ApiToken {
+    pub id: Uuid,
+    pub user_id: Uuid, // User
+    pub name: String,
+    pub token_prefix: String,
+    #[serde(skip_serializing)]
+    pub token_hash: String, // argon2 hash of the secret
+    pub scopes: Vec<String>, // ApiScope
+    pub expires_at: NaiveDateTime,
+    pub last_used_at: Option<NaiveDateTime>,
+    pub revoked_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Insertable)]
+#[table_name = "api_tokens"]
+struct NewApiToken {
+    user_id: Uuid,
+    name: String,
+    token_prefix: String,
+    token_hash: String,
+    scopes: Vec<String>,
+    expires_at: NaiveDateTime,
+}
+
+impl ApiToken {
+    /// Issue a token for the user. Returns the stored token and the full token,
+    /// which can't be recovered later.
+    pub fn issue(
+        conn: &PgConnection,
+        user_id: Uuid,
+        name: &str,
+        scopes: &[ApiScope],
+        days: i64,
+    ) -> Result<(ApiToken, String), CustomError> {
+        if name.trim().is_empty() || scopes.is_empty() {
+            return Err(CustomError::new(400, "API tokens need a name and at least one scope".to_string()))
+        };
+
+        let prefix = generate_unique_code(PREFIX_LENGTH, false);
+        let secret = generate_unique_code(SECRET_LENGTH, false);
+
+        let new_token = NewApiToken {
+            user_id,
+            name: name.trim().to_owned(),
+            token_prefix: prefix.clone(),
+            token_hash: make_hash(&secret, &make_salt()),
+            scopes: scopes.iter().map(|s| s.to_string()).collect(),
+            expires_at: Utc::now().naive_utc() + Duration::days(days.max(1).min(MAX_TOKEN_DAYS)),
+        };
+
+        let token = diesel::insert_into(api_tokens::table)
+            .values(&new_token)
+            .get_result(conn)?;
+
+        Ok((token, format!("{}_{}_{}", TOKEN_MARKER, prefix, secret)))
+    }
+
+    /// Tokens issued to the user, newest first
+    pub fn find_by_user(conn: &PgConnection, user_id: Uuid) -> Result<Vec<Self>, CustomError> {
+        let tokens = api_tokens::table
+            .filter(api_tokens::user_id.eq(user_id))
+            .order(api_tokens::created_at.desc())
+            .load::<ApiToken>(conn)?;
+
+        Ok(tokens)
+    }
+
+    /// Revoke one of the user's tokens. Revoked tokens are kept so their use stays on record.
+    pub fn revoke(conn: &PgConnection, id: Uuid, user_id: Uuid) -> Result<Self, CustomError> {
+        let now = Utc::now().naive_utc();
+
+        let token = diesel::update(api_tokens::table)
+            .filter(api_tokens::id.eq(id))
+            .filter(api_tokens::user_id.eq(user_id))
+            .set((
+                api_tokens::revoked_at.eq(now),
+                api_tokens::updated_at.eq(now),
+            ))
+            .get_result(conn)?;
+
+        Ok(token)
+    }
+
+    pub fn is_active(&self, as_of: NaiveDateTime) -> bool {
+        self.revoked_at.is_none() && self.expires_at > as_of
+    }
+
+    pub fn allows(&self, scope: ApiScope) -> bool {
+        self.scopes.iter().any(|s| ApiScope::from_str(s).map_or(false, |s| s == scope))
+    }
+
+    /// The user a bearer token acts for, if it is active and carries the scope.
+    /// Records the time of use.
+    pub fn authenticate(conn: &PgConnection, bearer: &str, scope: ApiScope) -> Result<User, CustomError> {
+        let invalid = || CustomError::new(401, "Invalid or expired API token".to_string());
+
+        let mut parts = bearer.trim().splitn(3, '_');
+
+        let (prefix, secret) = match (parts.next(), parts.next(), parts.next()) {
+            (Some(TOKEN_MARKER), Some(prefix), Some(secret)) => (prefix, secret),
+            _ => return Err(invalid()),
+        };
+
+        let token = api_tokens::table
+            .filter(api_tokens::token_prefix.eq(prefix))
+            .first::<ApiToken>(conn)
+            .map_err(|_| invalid())?;
+
+        let now = Utc::now().naive_utc();
+
+        if !token.is_active(now) || !argon2::verify_encoded(&token.token_hash, secret.as_bytes()).unwrap_or(false) {
+            return Err(invalid())
+        };
+
+        if !token.allows(scope) {
+            return Err(CustomError::new(403, format!("API token lacks the {} scope", scope)))
+        };
+
+        diesel::update(api_tokens::table)
+            .filter(api_tokens::id.eq(token.id))
+            .set(api_tokens::last_used_at.eq(now))
+            .execute(conn)?;
+
+        let user = users::table
+            .filter(users::id.eq(token.user_id))
+            .first::<User>(conn)?;
+
+        Ok(user)
+    }
+}
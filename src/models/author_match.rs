@@ -0,0 +1,236 @@
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::{prelude::*};
+use serde::{Deserialize, Serialize};
+use diesel::{self, Insertable, PgConnection, Queryable, ExpressionMethods};
+use diesel::{Connection, RunQueryDsl, QueryDsl, OptionalExtension};
+use diesel::result::Error as DieselError;
+use uuid::Uuid;
+use async_graphql::*;
+
+use crate::schema::*;
+use crate::errors::CustomError;
+use crate::models::{Person, Publication, PublicationAuthor};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+/// How an author was matched to a person, strongest first
+pub enum AuthorMatchMethod {
+    Orcid,
+    Name,
+    Initials,
+}
+
+impl AuthorMatchMethod {
+    pub fn score(&self) -> f64 {
+        match self {
+            AuthorMatchMethod::Orcid => 1.0,
+            AuthorMatchMethod::Name => 0.8,
+            AuthorMatchMethod::Initials => 0.5,
+        }
+    }
+}
+
+impl fmt::Display for AuthorMatchMethod {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            AuthorMatchMethod::Orcid => "orcid",
+            AuthorMatchMethod::Name => "name",
+            AuthorMatchMethod::Initials => "initials",
+        };
+        f.write_str(s)
+    }
+}
+
+impl FromStr for AuthorMatchMethod {
+    type Err = CustomError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "orcid" => Ok(AuthorMatchMethod::Orcid),
+            "name" => Ok(AuthorMatchMethod::Name),
+            "initials" => Ok(AuthorMatchMethod::Initials),
+            _ => Err(CustomError::new(400, format!("Unknown author match method: {}", s))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum AuthorMatchStatus {
+    Pending,
+    Accepted,
+    Rejected,
+}
+
+impl fmt::Display for AuthorMatchStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            AuthorMatchStatus::Pending => "pending",
+            AuthorMatchStatus::Accepted => "accepted",
+            AuthorMatchStatus::Rejected => "rejected",
+        };
+        f.write_str(s)
+    }
+}
+
+impl FromStr for AuthorMatchStatus {
+    type Err = CustomError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pending" => Ok(AuthorMatchStatus::Pending),
+            "accepted" => Ok(AuthorMatchStatus::Accepted),
+            "rejected" => Ok(AuthorMatchStatus::Rejected),
+            _ => Err(CustomError::new(400, format!("Unknown author match status: {}", s))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, Queryable, Identifiable, SimpleObject)]
+#[table_name = "author_matches"]
+/// Proposed link from an external PublicationAuthor to a Person,
+/// waiting for an admin to confirm
+pub struct AuthorMatch {
+    pub id: Uuid,
+    pub publication_author_id: Uuid, // PublicationAuthor
+    pub person_id: Uuid, // Person
+    pub method: String, // AuthorMatchMethod
+    pub score: f64,
+    pub status: String, // AuthorMatchStatus
+    pub created_at: NaiveDateTime,
+    pub resolved_at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+/// Pending match with what an admin needs to judge it
+pub struct AuthorMatchReview {
+    pub author_match: AuthorMatch,
+    pub author: PublicationAuthor,
+    pub publication: Publication,
+    pub person: Person,
+}
+
+impl AuthorMatch {
+    /// Keeps an existing proposal for the same author and person
+    pub fn propose(conn: &PgConnection, author_match: &NewAuthorMatch) -> Result<usize, DieselError> {
+        diesel::insert_into(author_matches::table)
+            .values(author_match)
+            .on_conflict((author_matches::publication_author_id, author_matches::person_id))
+            .do_nothing()
+            .execute(conn)
+    }
+
+    pub fn find(conn: &PgConnection, id: Uuid) -> Result<Self, CustomError> {
+        let author_match = author_matches::table.find(id).first(conn)?;
+        Ok(author_match)
+    }
+
+    /// Pending proposals, strongest first
+    pub fn find_pending(conn: &PgConnection) -> Result<Vec<AuthorMatchReview>, CustomError> {
+        let res = author_matches::table
+            .inner_join(publication_authors::table.inner_join(publications::table))
+            .inner_join(persons::table)
+            .filter(author_matches::status.eq(AuthorMatchStatus::Pending.to_string()))
+            .order((author_matches::score.desc(), publications::title.asc(), publication_authors::author_order.asc()))
+            .load::<(AuthorMatch, (PublicationAuthor, Publication), Person)>(conn)?;
+
+        Ok(res.into_iter()
+            .map(|(author_match, (author, publication), person)| AuthorMatchReview {
+                author_match,
+                author,
+                publication,
+                person,
+            })
+            .collect())
+    }
+
+    /// Link the author to the person. Any unordered authorship the person
+    /// already has on the publication, e.g. from an ORCID import, is folded in.
+    pub fn accept(&self, conn: &PgConnection) -> Result<AuthorMatch, CustomError> {
+        conn.transaction::<AuthorMatch, CustomError, _>(|| {
+            let author = publication_authors::table
+                .find(self.publication_author_id)
+                .first::<PublicationAuthor>(conn)?;
+
+            if author.person_id.is_some() {
+                return Err(CustomError::new(409, format!("{} is already linked to a person", author.author_name)))
+            };
+
+            let existing = publication_authors::table
+                .filter(publication_authors::publication_id.eq(author.publication_id))
+                .filter(publication_authors::person_id.eq(self.person_id))
+                .first::<PublicationAuthor>(conn)
+                .optional()?;
+
+            if let Some(existing) = existing {
+                if existing.author_order.is_some() {
+                    return Err(CustomError::new(409, format!("Person is already author {} of this publication",
+                        existing.author_order.unwrap_or_default())))
+                };
+
+                diesel::delete(publication_authors::table.find(existing.id)).execute(conn)?;
+            };
+
+            let now = Utc::now().naive_utc();
+
+            diesel::update(publication_authors::table.find(author.id))
+                .set((
+                    publication_authors::person_id.eq(self.person_id),
+                    publication_authors::updated_at.eq(now),
+                ))
+                .execute(conn)?;
+
+            // Other candidates for the same author no longer apply
+            diesel::update(author_matches::table)
+                .filter(author_matches::publication_author_id.eq(author.id))
+                .filter(author_matches::id.ne(self.id))
+                .filter(author_matches::status.eq(AuthorMatchStatus::Pending.to_string()))
+                .set((
+                    author_matches::status.eq(AuthorMatchStatus::Rejected.to_string()),
+                    author_matches::resolved_at.eq(now),
+                ))
+                .execute(conn)?;
+
+            self.resolve(conn, AuthorMatchStatus::Accepted)
+        })
+    }
+
+    pub fn reject(&self, conn: &PgConnection) -> Result<AuthorMatch, CustomError> {
+        self.resolve(conn, AuthorMatchStatus::Rejected)
+    }
+
+    fn resolve(&self, conn: &PgConnection, status: AuthorMatchStatus) -> Result<AuthorMatch, CustomError> {
+        let res = diesel::update(author_matches::table.find(self.id))
+            .set((
+                author_matches::status.eq(status.to_string()),
+                author_matches::resolved_at.eq(Utc::now().naive_utc()),
+            ))
+            .get_result(conn)?;
+
+        Ok(res)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, Insertable)]
+#[table_name = "author_matches"]
+pub struct NewAuthorMatch {
+    pub publication_author_id: Uuid,
+    pub person_id: Uuid,
+    pub method: String,
+    pub score: f64,
+}
+
+impl NewAuthorMatch {
+    pub fn new(
+        publication_author_id: Uuid,
+        person_id: Uuid,
+        method: AuthorMatchMethod,
+    ) -> Self {
+        NewAuthorMatch {
+            publication_author_id,
+            person_id,
+            method: method.to_string(),
+            score: method.score(),
+        }
+    }
+}
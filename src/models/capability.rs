@@ -3,14 +3,17 @@ use std::fmt::Debug;
 use chrono::{prelude::*};
 use serde::{Deserialize, Serialize};
 use diesel::{self, Insertable, PgConnection, Queryable, ExpressionMethods};
+use diesel::upsert::excluded;
 use diesel::{RunQueryDsl, QueryDsl};
 use uuid::Uuid;
 use async_graphql::*;
-use rand::{Rng, thread_rng};
 
 use crate::graphql::graphql_translate;
 
 use crate::schema::*;
+use crate::errors::CustomError;
+use crate::database::connection;
+use crate::models::{Skill, Validation, ValidationConsensus};
 
 #[derive(Debug, Clone, Deserialize, Serialize, Queryable, Insertable, AsChangeset)]
 #[table_name = "capabilities"]
@@ -18,11 +21,12 @@ pub struct Capability {
     pub id: Uuid,
     pub person_id: Uuid, // Person
     pub skill_id: Uuid, // Skill
-    pub self_identified_level: u32,
-    pub created_at: NaiveDate,
-    pub updated_at: NaiveDate,
+    pub self_identified_level: i32, // CapabilityLevel
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
 // Enums for Capability -> shift to 0 - 4
 pub enum CapabilityLevel {
     Desired,
@@ -32,6 +36,53 @@ pub enum CapabilityLevel {
     Specialist,
 }
 
+impl CapabilityLevel {
+    pub fn from_i32(level: i32) -> Self {
+        match level {
+            i32::MIN..=0 => CapabilityLevel::Desired,
+            1 => CapabilityLevel::Novice,
+            2 => CapabilityLevel::Experienced,
+            3 => CapabilityLevel::Expert,
+            _ => CapabilityLevel::Specialist,
+        }
+    }
+
+    pub fn as_i32(&self) -> i32 {
+        *self as i32
+    }
+
+    /// Fluent key for the level name
+    pub fn key(&self) -> &'static str {
+        match self {
+            CapabilityLevel::Desired => "level-desired",
+            CapabilityLevel::Novice => "level-novice",
+            CapabilityLevel::Experienced => "level-experienced",
+            CapabilityLevel::Expert => "level-expert",
+            CapabilityLevel::Specialist => "level-specialist",
+        }
+    }
+
+    /// English and French level names, for output without Fluent
+    pub fn names(&self) -> (&'static str, &'static str) {
+        match self {
+            CapabilityLevel::Desired => ("Desired", "Souhaité"),
+            CapabilityLevel::Novice => ("Novice", "Débutant"),
+            CapabilityLevel::Experienced => ("Experienced", "Expérimenté"),
+            CapabilityLevel::Expert => ("Expert", "Expert"),
+            CapabilityLevel::Specialist => ("Specialist", "Spécialiste"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+/// Capability with its Skill and validations for display
+pub struct CapabilityWithSkill {
+    pub capability: Capability,
+    pub skill: Skill,
+    pub level_key: String,
+    pub consensus: ValidationConsensus,
+}
+
 // Non Graphql
 impl Capability {
     pub fn create(conn: &PgConnection, capability: &NewCapability) -> FieldResult<Capability> {
@@ -42,35 +93,53 @@ impl Capability {
         graphql_translate(res)
     }
     
+    /// Natural key: (person_id, skill_id)
+    /// Returns the existing capability unchanged if the key matches.
     pub fn get_or_create(conn: &PgConnection, capability: &NewCapability) -> FieldResult<Capability> {
-        let res = capabilities::table
-        .filter(capabilities::family_name.eq(&capability.family_name))
-        .distinct()
-        .first(conn);
-        
-        let capability = match res {
-            Ok(p) => p,
-            Err(e) => {
-                // Capability not found
-                println!("{:?}", e);
-                let p = Capability::create(conn, capability).expect("Unable to create capability");
-                p
-            }
-        };
-        Ok(capability)
+        let res = diesel::insert_into(capabilities::table)
+            .values(capability)
+            .on_conflict((capabilities::person_id, capabilities::skill_id))
+            .do_update()
+            .set(capabilities::person_id.eq(excluded(capabilities::person_id)))
+            .get_result(conn);
+
+        graphql_translate(res)
     }
 
     pub fn find_all() -> Result<Vec<Self>, CustomError> {
-        let conn = database::connection()?;
+        let conn = connection()?;
         let capabilities = capabilities::table.load::<Capability>(&conn)?;
         Ok(capabilities)
     }
 
     pub fn find(id: Uuid) -> Result<Self, CustomError> {
-        let conn = database::connection()?;
+        let conn = connection()?;
         let capability = capabilities::table.filter(capabilities::id.eq(id)).first(&conn)?;
         Ok(capability)
     }
+
+    pub fn find_by_person_with_skills(conn: &PgConnection, person_id: Uuid) -> Result<Vec<CapabilityWithSkill>, CustomError> {
+        let res = capabilities::table
+            .inner_join(skills::table)
+            .filter(capabilities::person_id.eq(person_id))
+            .order(capabilities::self_identified_level.desc())
+            .load::<(Capability, Skill)>(conn)?;
+
+        let capabilities: Vec<Capability> = res.iter().map(|(c, _)| c.clone()).collect();
+        let mut consensus = Validation::find_consensus(conn, &capabilities)?;
+
+        Ok(res.into_iter()
+            .map(|(capability, skill)| {
+                let level_key = capability.get_level().key().to_string();
+                let consensus = consensus.remove(&capability.id).unwrap_or_default();
+                CapabilityWithSkill { capability, skill, level_key, consensus }
+            })
+            .collect())
+    }
+
+    pub fn get_level(&self) -> CapabilityLevel {
+        CapabilityLevel::from_i32(self.self_identified_level)
+    }
     
     pub fn update(&self, conn: &PgConnection) -> FieldResult<Self> {
         let res = diesel::update(capabilities::table)
@@ -87,9 +156,7 @@ impl Capability {
 pub struct NewCapability {
     pub person_id: Uuid, // Person
     pub skill_id: Uuid, // Skill
-    pub self_identified_level: u32,
-    pub created_at: NaiveDate,
-    pub updated_at: NaiveDate,
+    pub self_identified_level: i32,
 }
 
 impl NewCapability {
@@ -97,16 +164,12 @@ impl NewCapability {
     pub fn new(
         person_id: Uuid, // Person
         skill_id: Uuid, // Skill
-        self_identified_level: u32,
-        created_at: NaiveDate,
-        updated_at: NaiveDate,
+        self_identified_level: CapabilityLevel,
     ) -> Self {
         NewCapability {
             person_id,
             skill_id,
-            self_identified_level,
-            created_at,
-            updated_at,
+            self_identified_level: self_identified_level.as_i32(),
         }
     }
 }
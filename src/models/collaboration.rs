@@ -0,0 +1,549 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use diesel::{PgConnection, ExpressionMethods};
+use diesel::{RunQueryDsl, QueryDsl};
+use quick_xml::escape::escape;
+use uuid::Uuid;
+
+use crate::schema::*;
+use crate::errors::CustomError;
+use crate::models::{Affiliation, Organization, Person, PublicationAuthor, Role, Team};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CollaborationNodeKind {
+    Person,
+    Team,
+    /// Any organization other than the one the graph is built for,
+    /// including co-author affiliations that match no Organization
+    ExternalOrganization,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CollaborationEdgeKind {
+    /// Person to team, from any role held on it
+    Membership,
+    /// Person to person, weighted by teams where their roles overlapped
+    SharedTeam,
+    /// Person to person or external organization, weighted by publications
+    CoAuthorship,
+    /// Person to external organization
+    Affiliation,
+}
+
+impl fmt::Display for CollaborationEdgeKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            CollaborationEdgeKind::Membership => "membership",
+            CollaborationEdgeKind::SharedTeam => "shared_team",
+            CollaborationEdgeKind::CoAuthorship => "co_authorship",
+            CollaborationEdgeKind::Affiliation => "affiliation",
+        };
+        f.write_str(s)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GraphFormat {
+    Graphml,
+    Json,
+}
+
+impl GraphFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            GraphFormat::Graphml => "application/graphml+xml",
+            GraphFormat::Json => "application/json",
+        }
+    }
+}
+
+impl fmt::Display for GraphFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            GraphFormat::Graphml => "graphml",
+            GraphFormat::Json => "json",
+        };
+        f.write_str(s)
+    }
+}
+
+impl FromStr for GraphFormat {
+    type Err = CustomError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "graphml" => Ok(GraphFormat::Graphml),
+            "json" => Ok(GraphFormat::Json),
+            _ => Err(CustomError::new(400, format!("Unknown graph format: {}", s))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CollaborationNode {
+    pub id: String, // e.g. person:<uuid>, stable across exports
+    pub kind: CollaborationNodeKind,
+    pub label: String,
+    pub entity_id: Option<Uuid>, // None for unmatched external affiliations
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CollaborationEdge {
+    pub source: String,
+    pub target: String,
+    pub kind: CollaborationEdgeKind,
+    pub weight: u32,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+/// Who works with whom in an organization, from shared teams,
+/// co-authorship and affiliations
+pub struct CollaborationGraph {
+    pub organization_id: Uuid,
+    pub nodes: Vec<CollaborationNode>,
+    pub edges: Vec<CollaborationEdge>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Collaborator {
+    pub node: CollaborationNode,
+    pub shared_teams: u32,
+    pub co_publications: u32,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+/// Link between two teams through people who held roles on both
+/// and co-authorship between their members
+pub struct TeamLink {
+    pub team_a: CollaborationNode,
+    pub team_b: CollaborationNode,
+    pub shared_people: u32,
+    pub co_publications: u32,
+    pub weight: u32,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ExternalOrganizationLink {
+    pub node: CollaborationNode,
+    pub people: usize, // Distinct people connected to it
+    pub co_publications: u32,
+    pub affiliations: u32,
+}
+
+/// Accumulates nodes and weighted edges while the graph is built
+#[derive(Default)]
+struct GraphBuilder {
+    nodes: BTreeMap<String, CollaborationNode>,
+    edges: BTreeMap<(String, String, CollaborationEdgeKind), u32>,
+}
+
+impl GraphBuilder {
+    fn node(&mut self, kind: CollaborationNodeKind, key: &str, label: &str, entity_id: Option<Uuid>) -> String {
+        let prefix = match kind {
+            CollaborationNodeKind::Person => "person",
+            CollaborationNodeKind::Team => "team",
+            CollaborationNodeKind::ExternalOrganization => "organization",
+        };
+
+        let id = format!("{}:{}", prefix, key);
+
+        self.nodes.entry(id.clone()).or_insert_with(|| CollaborationNode {
+            id: id.clone(),
+            kind,
+            label: label.to_owned(),
+            entity_id,
+        });
+
+        id
+    }
+
+    /// Edges between people are undirected, so their ends are kept in order.
+    /// Other edges always run from the person.
+    fn edge(&mut self, source: &str, target: &str, kind: CollaborationEdgeKind, weight: u32) {
+        let (source, target) = if source.starts_with("person:") && target.starts_with("person:") && target < source {
+            (target, source)
+        } else {
+            (source, target)
+        };
+
+        *self.edges.entry((source.to_owned(), target.to_owned(), kind)).or_insert(0) += weight;
+    }
+}
+
+fn person_key(id: Uuid) -> String {
+    format!("person:{}", id)
+}
+
+fn team_key(id: Uuid) -> String {
+    format!("team:{}", id)
+}
+
+/// Lower case with single spaces, for matching affiliation text to Organizations
+fn organization_key(name: &str) -> String {
+    name.to_lowercase().split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+fn ordered<'a>(a: &'a str, b: &'a str) -> (&'a str, &'a str) {
+    if a <= b { (a, b) } else { (b, a) }
+}
+
+fn overlaps(a: &Role, b: &Role) -> bool {
+    b.end_date.map(|end| a.start_datestamp <= end).unwrap_or(true)
+        && a.end_date.map(|end| b.start_datestamp <= end).unwrap_or(true)
+}
+
+impl CollaborationGraph {
+    pub fn build(conn: &PgConnection, organization_id: Uuid) -> Result<CollaborationGraph, CustomError> {
+        let persons = persons::table
+            .filter(persons::organization_id.eq(organization_id))
+            .filter(persons::retired_at.is_null())
+            .load::<Person>(conn)?;
+
+        let teams = teams::table
+            .filter(teams::organization_id.eq(organization_id))
+            .load::<Team>(conn)?;
+
+        let person_ids: Vec<Uuid> = persons.iter().map(|p| p.id).collect();
+        let team_ids: Vec<Uuid> = teams.iter().map(|t| t.id).collect();
+        let members: HashSet<Uuid> = person_ids.iter().copied().collect();
+
+        let organizations = organizations::table
+            .filter(organizations::id.ne(organization_id))
+            .load::<Organization>(conn)?;
+
+        // Affiliation text is matched on names and acronyms in either language
+        let mut organizations_by_key: HashMap<String, &Organization> = HashMap::new();
+        for o in &organizations {
+            for name in &[&o.name_en, &o.name_fr, &o.acronym_en, &o.acronym_fr] {
+                if !name.is_empty() {
+                    organizations_by_key.insert(organization_key(name), o);
+                };
+            };
+        };
+
+        let mut graph = GraphBuilder::default();
+
+        for p in &persons {
+            graph.node(CollaborationNodeKind::Person, &p.id.to_string(), &format!("{} {}", p.given_name, p.family_name), Some(p.id));
+        };
+
+        for t in &teams {
+            graph.node(CollaborationNodeKind::Team, &t.id.to_string(), &t.name_en, Some(t.id));
+        };
+
+        let organization_node = |graph: &mut GraphBuilder, o: &Organization| {
+            graph.node(CollaborationNodeKind::ExternalOrganization, &o.id.to_string(), &o.name_en, Some(o.id))
+        };
+
+        // Memberships and overlapping roles on the same team
+        let roles = roles::table
+            .filter(roles::person_id.eq_any(&person_ids))
+            .filter(roles::team_id.eq_any(&team_ids))
+            .load::<Role>(conn)?;
+
+        let mut roles_by_team: HashMap<Uuid, Vec<&Role>> = HashMap::new();
+
+        for role in &roles {
+            roles_by_team.entry(role.team_id).or_insert_with(Vec::new).push(role);
+        };
+
+        for (team_id, team_roles) in &roles_by_team {
+            let mut people: HashSet<Uuid> = HashSet::new();
+            let mut pairs: HashSet<(Uuid, Uuid)> = HashSet::new();
+
+            for (i, a) in team_roles.iter().enumerate() {
+                people.insert(a.person_id);
+
+                for b in &team_roles[i + 1..] {
+                    if a.person_id != b.person_id && overlaps(a, b) {
+                        pairs.insert((a.person_id.min(b.person_id), a.person_id.max(b.person_id)));
+                    };
+                };
+            };
+
+            for person_id in people {
+                graph.edge(&person_key(person_id), &team_key(*team_id), CollaborationEdgeKind::Membership, 1);
+            };
+
+            for (a, b) in pairs {
+                graph.edge(&person_key(a), &person_key(b), CollaborationEdgeKind::SharedTeam, 1);
+            };
+        };
+
+        // Co-authorship, with co-authors outside the organization
+        // attributed to their organization or stated affiliation
+        let publication_ids: Vec<Uuid> = publication_authors::table
+            .filter(publication_authors::person_id.eq_any(&person_ids))
+            .select(publication_authors::publication_id)
+            .distinct()
+            .load::<Uuid>(conn)?;
+
+        let authors = publication_authors::table
+            .filter(publication_authors::publication_id.eq_any(&publication_ids))
+            .load::<PublicationAuthor>(conn)?;
+
+        let outside_ids: Vec<Uuid> = authors.iter()
+            .filter_map(|a| a.person_id)
+            .filter(|id| !members.contains(id))
+            .collect();
+
+        let outside_organization: HashMap<Uuid, Uuid> = persons::table
+            .filter(persons::id.eq_any(&outside_ids))
+            .select((persons::id, persons::organization_id))
+            .load::<(Uuid, Uuid)>(conn)?
+            .into_iter()
+            .collect();
+
+        let mut authors_by_publication: HashMap<Uuid, Vec<&PublicationAuthor>> = HashMap::new();
+
+        for author in &authors {
+            authors_by_publication.entry(author.publication_id).or_insert_with(Vec::new).push(author);
+        };
+
+        for coauthors in authors_by_publication.values() {
+            let internal: Vec<Uuid> = coauthors.iter()
+                .filter_map(|a| a.person_id)
+                .filter(|id| members.contains(id))
+                .collect::<HashSet<Uuid>>()
+                .into_iter()
+                .collect();
+
+            for (i, a) in internal.iter().enumerate() {
+                for b in &internal[i + 1..] {
+                    graph.edge(&person_key(*a), &person_key(*b), CollaborationEdgeKind::CoAuthorship, 1);
+                };
+            };
+
+            // Each external organization counts once per publication
+            let mut external: HashSet<String> = HashSet::new();
+
+            for author in coauthors {
+                let organization = match author.person_id.and_then(|id| outside_organization.get(&id)) {
+                    Some(org_id) => organizations.iter().find(|o| o.id == *org_id),
+                    None if author.person_id.map(|id| members.contains(&id)).unwrap_or(false) => continue,
+                    None => None,
+                };
+
+                let node = match (organization, &author.external_affiliation) {
+                    (Some(o), _) => organization_node(&mut graph, o),
+                    (None, Some(affiliation)) if !affiliation.trim().is_empty() => {
+                        let key = organization_key(affiliation);
+                        match organizations_by_key.get(&key) {
+                            Some(o) => organization_node(&mut graph, o),
+                            None => graph.node(CollaborationNodeKind::ExternalOrganization, &key, affiliation.trim(), None),
+                        }
+                    },
+                    _ => continue,
+                };
+
+                external.insert(node);
+            };
+
+            for node in &external {
+                for person_id in &internal {
+                    graph.edge(&person_key(*person_id), node, CollaborationEdgeKind::CoAuthorship, 1);
+                };
+            };
+        };
+
+        // Affiliations with other organizations
+        let affiliations = affiliations::table
+            .filter(affiliations::person_id.eq_any(&person_ids))
+            .filter(affiliations::organization_id.ne(organization_id))
+            .load::<Affiliation>(conn)?;
+
+        for affiliation in &affiliations {
+            if let Some(o) = organizations.iter().find(|o| o.id == affiliation.organization_id) {
+                let node = organization_node(&mut graph, o);
+                graph.edge(&person_key(affiliation.person_id), &node, CollaborationEdgeKind::Affiliation, 1);
+            };
+        };
+
+        Ok(CollaborationGraph {
+            organization_id,
+            nodes: graph.nodes.into_iter().map(|(_, n)| n).collect(),
+            edges: graph.edges.into_iter()
+                .map(|((source, target, kind), weight)| CollaborationEdge { source, target, kind, weight })
+                .collect(),
+        })
+    }
+
+    fn node(&self, id: &str) -> Option<&CollaborationNode> {
+        self.nodes.iter().find(|n| n.id == id)
+    }
+
+    /// Teams each person has held a role on
+    fn memberships(&self) -> HashMap<&str, Vec<&str>> {
+        let mut res: HashMap<&str, Vec<&str>> = HashMap::new();
+
+        for e in self.edges.iter().filter(|e| e.kind == CollaborationEdgeKind::Membership) {
+            res.entry(e.source.as_str()).or_insert_with(Vec::new).push(e.target.as_str());
+        };
+
+        res
+    }
+
+    /// People a person shares teams or publications with, strongest first
+    pub fn collaborators(&self, person_id: Uuid) -> Vec<Collaborator> {
+        let key = person_key(person_id);
+        let mut by_person: HashMap<&str, (u32, u32)> = HashMap::new();
+
+        for e in &self.edges {
+            let other = if e.source == key && e.target.starts_with("person:") {
+                e.target.as_str()
+            } else if e.target == key && e.source.starts_with("person:") {
+                e.source.as_str()
+            } else {
+                continue
+            };
+
+            let counts = by_person.entry(other).or_insert((0, 0));
+
+            match e.kind {
+                CollaborationEdgeKind::SharedTeam => counts.0 += e.weight,
+                CollaborationEdgeKind::CoAuthorship => counts.1 += e.weight,
+                _ => (),
+            };
+        };
+
+        let mut res: Vec<Collaborator> = by_person.into_iter()
+            .filter_map(|(id, (shared_teams, co_publications))| Some(Collaborator {
+                node: self.node(id)?.clone(),
+                shared_teams,
+                co_publications,
+            }))
+            .collect();
+
+        res.sort_by(|a, b| (b.shared_teams + b.co_publications).cmp(&(a.shared_teams + a.co_publications))
+            .then_with(|| a.node.label.cmp(&b.node.label)));
+
+        res
+    }
+
+    /// Strongest links between pairs of teams
+    pub fn team_links(&self, limit: usize) -> Vec<TeamLink> {
+        let memberships = self.memberships();
+        let mut links: HashMap<(&str, &str), (u32, u32)> = HashMap::new();
+
+        // People who held roles on several teams
+        for teams in memberships.values() {
+            for (i, a) in teams.iter().enumerate() {
+                for b in &teams[i + 1..] {
+                    links.entry(ordered(a, b)).or_insert((0, 0)).0 += 1;
+                };
+            };
+        };
+
+        // Co-authors on different teams
+        for e in self.edges.iter().filter(|e| e.kind == CollaborationEdgeKind::CoAuthorship && e.target.starts_with("person:")) {
+            let empty = Vec::new();
+            let source_teams = memberships.get(e.source.as_str()).unwrap_or(&empty);
+            let target_teams = memberships.get(e.target.as_str()).unwrap_or(&empty);
+
+            for a in source_teams {
+                for b in target_teams.iter().filter(|b| *b != a) {
+                    links.entry(ordered(a, b)).or_insert((0, 0)).1 += e.weight;
+                };
+            };
+        };
+
+        let mut res: Vec<TeamLink> = links.into_iter()
+            .filter_map(|((a, b), (shared_people, co_publications))| Some(TeamLink {
+                team_a: self.node(a)?.clone(),
+                team_b: self.node(b)?.clone(),
+                shared_people,
+                co_publications,
+                weight: shared_people + co_publications,
+            }))
+            .collect();
+
+        res.sort_by(|a, b| b.weight.cmp(&a.weight)
+            .then_with(|| a.team_a.label.cmp(&b.team_a.label))
+            .then_with(|| a.team_b.label.cmp(&b.team_b.label)));
+        res.truncate(limit);
+
+        res
+    }
+
+    /// External organizations connected to the most people
+    pub fn external_organizations(&self, limit: usize) -> Vec<ExternalOrganizationLink> {
+        let mut by_organization: HashMap<&str, (HashSet<&str>, u32, u32)> = HashMap::new();
+
+        for e in self.edges.iter().filter(|e| e.target.starts_with("organization:")) {
+            let entry = by_organization.entry(e.target.as_str()).or_insert_with(|| (HashSet::new(), 0, 0));
+            entry.0.insert(e.source.as_str());
+
+            match e.kind {
+                CollaborationEdgeKind::CoAuthorship => entry.1 += e.weight,
+                CollaborationEdgeKind::Affiliation => entry.2 += e.weight,
+                _ => (),
+            };
+        };
+
+        let mut res: Vec<ExternalOrganizationLink> = by_organization.into_iter()
+            .filter_map(|(id, (people, co_publications, affiliations))| Some(ExternalOrganizationLink {
+                node: self.node(id)?.clone(),
+                people: people.len(),
+                co_publications,
+                affiliations,
+            }))
+            .collect();
+
+        res.sort_by(|a, b| b.people.cmp(&a.people)
+            .then_with(|| b.co_publications.cmp(&a.co_publications))
+            .then_with(|| a.node.label.cmp(&b.node.label)));
+        res.truncate(limit);
+
+        res
+    }
+
+    pub fn write(&self, format: GraphFormat) -> Result<Vec<u8>, CustomError> {
+        match format {
+            GraphFormat::Graphml => Ok(self.to_graphml().into_bytes()),
+            GraphFormat::Json => serde_json::to_vec_pretty(self)
+                .map_err(|e| CustomError::new(500, format!("Unable to write graph: {}", e))),
+        }
+    }
+
+    /// GraphML for Gephi, Cytoscape, yEd and similar tools
+    pub fn to_graphml(&self) -> String {
+        let mut out = String::from(concat!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+            "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n",
+            "  <key id=\"label\" for=\"node\" attr.name=\"label\" attr.type=\"string\"/>\n",
+            "  <key id=\"kind\" for=\"node\" attr.name=\"kind\" attr.type=\"string\"/>\n",
+            "  <key id=\"edge_kind\" for=\"edge\" attr.name=\"kind\" attr.type=\"string\"/>\n",
+            "  <key id=\"weight\" for=\"edge\" attr.name=\"weight\" attr.type=\"int\"/>\n",
+            "  <graph id=\"collaborations\" edgedefault=\"undirected\">\n",
+        ));
+
+        for n in &self.nodes {
+            let kind = match n.kind {
+                CollaborationNodeKind::Person => "person",
+                CollaborationNodeKind::Team => "team",
+                CollaborationNodeKind::ExternalOrganization => "external_organization",
+            };
+
+            out.push_str(&format!(
+                "    <node id=\"{}\"><data key=\"label\">{}</data><data key=\"kind\">{}</data></node>\n",
+                escape(&n.id), escape(&n.label), kind,
+            ));
+        };
+
+        for e in &self.edges {
+            out.push_str(&format!(
+                "    <edge source=\"{}\" target=\"{}\"><data key=\"edge_kind\">{}</data><data key=\"weight\">{}</data></edge>\n",
+                escape(&e.source), escape(&e.target), e.kind, e.weight,
+            ));
+        };
+
+        out.push_str("  </graph>\n</graphml>\n");
+        out
+    }
+}
@@ -3,14 +3,16 @@ use std::fmt::Debug;
 use chrono::{prelude::*};
 use serde::{Deserialize, Serialize};
 use diesel::{self, Insertable, PgConnection, Queryable, ExpressionMethods};
+use diesel::upsert::excluded;
 use diesel::{RunQueryDsl, QueryDsl};
 use uuid::Uuid;
 use async_graphql::*;
-use rand::{Rng, thread_rng};
 
 use crate::graphql::graphql_translate;
 
 use crate::schema::*;
+use crate::errors::CustomError;
+use crate::database::connection;
 
 #[derive(Debug, Clone, Deserialize, Serialize, Queryable, Insertable, AsChangeset)]
 #[table_name = "credentials"]
@@ -22,8 +24,8 @@ pub struct Credential {
     pub description: String,
     pub received_date: NaiveDate,
     pub validated: bool,
-    pub created_at: NaiveDate,
-    pub updated_at: NaiveDate,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
 }
 
 // Non Graphql
@@ -36,35 +38,38 @@ impl Credential {
         graphql_translate(res)
     }
     
+    /// Natural key: (person_id, provider, description)
+    /// Returns the existing credential unchanged if the key matches.
     pub fn get_or_create(conn: &PgConnection, credential: &NewCredential) -> FieldResult<Credential> {
-        let res = credentials::table
-        .filter(credentials::family_name.eq(&credential.family_name))
-        .distinct()
-        .first(conn);
-        
-        let credential = match res {
-            Ok(p) => p,
-            Err(e) => {
-                // Credential not found
-                println!("{:?}", e);
-                let p = Credential::create(conn, credential).expect("Unable to create credential");
-                p
-            }
-        };
-        Ok(credential)
+        let res = diesel::insert_into(credentials::table)
+            .values(credential)
+            .on_conflict((credentials::person_id, credentials::provider, credentials::description))
+            .do_update()
+            .set(credentials::person_id.eq(excluded(credentials::person_id)))
+            .get_result(conn);
+
+        graphql_translate(res)
     }
 
     pub fn find_all() -> Result<Vec<Self>, CustomError> {
-        let conn = database::connection()?;
+        let conn = connection()?;
         let credentials = credentials::table.load::<Credential>(&conn)?;
         Ok(credentials)
     }
 
     pub fn find(id: Uuid) -> Result<Self, CustomError> {
-        let conn = database::connection()?;
+        let conn = connection()?;
         let credential = credentials::table.filter(credentials::id.eq(id)).first(&conn)?;
         Ok(credential)
     }
+
+    pub fn find_by_person(conn: &PgConnection, person_id: Uuid) -> Result<Vec<Self>, CustomError> {
+        let credentials = credentials::table
+            .filter(credentials::person_id.eq(person_id))
+            .order(credentials::received_date.desc())
+            .load::<Credential>(conn)?;
+        Ok(credentials)
+    }
     
     pub fn update(&self, conn: &PgConnection) -> FieldResult<Self> {
         let res = diesel::update(credentials::table)
@@ -84,8 +89,6 @@ pub struct NewCredential {
     pub description: String,
     pub received_date: NaiveDate,
     pub validated: bool,
-    pub created_at: NaiveDate,
-    pub updated_at: NaiveDate,
 }
 
 impl NewCredential {
@@ -96,8 +99,6 @@ impl NewCredential {
         description: String,
         received_date: NaiveDate,
         validated: bool,
-        created_at: NaiveDate,
-        updated_at: NaiveDate,
     ) -> Self {
         NewCredential {
             person_id,
@@ -105,8 +106,6 @@ impl NewCredential {
             description,
             received_date,
             validated,
-            created_at,
-            updated_at,
         }
     }
 }
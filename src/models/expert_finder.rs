@@ -0,0 +1,481 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use chrono::{prelude::*};
+use serde::{Deserialize, Serialize};
+use diesel::{PgConnection, ExpressionMethods, BoolExpressionMethods};
+use diesel::{RunQueryDsl, QueryDsl};
+use uuid::Uuid;
+use async_graphql::*;
+
+use crate::schema::*;
+use crate::errors::CustomError;
+use crate::models::{Capability, CapabilityLevel, Credential, Person, Publication, PublicationAuthor, Role, Skill,
+    Validation, ValidationConsensus};
+
+/// Most people returned by one query
+pub const EXPERT_FINDER_LIMIT: usize = 50;
+
+/// Validators needed for full confidence in a consensus
+const CONFIDENT_VALIDATORS: i32 = 3;
+
+/// Related credentials and publications needed for full evidence points
+const EVIDENCE_TARGET: f64 = 3.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+/// Parts of an expert's score and the points each is worth.
+/// Language only counts when the query asks for one.
+pub enum ExpertComponent {
+    SkillLevel,
+    PeerValidation,
+    Evidence,
+    Availability,
+    Language,
+}
+
+impl ExpertComponent {
+    pub fn max_points(&self) -> f64 {
+        match self {
+            ExpertComponent::SkillLevel => 40.0,
+            ExpertComponent::PeerValidation => 25.0,
+            ExpertComponent::Evidence => 15.0,
+            ExpertComponent::Availability => 15.0,
+            ExpertComponent::Language => 5.0,
+        }
+    }
+
+    /// English and French names for summaries
+    pub fn names(&self) -> (&'static str, &'static str) {
+        match self {
+            ExpertComponent::SkillLevel => ("skill level", "niveau de compétence"),
+            ExpertComponent::PeerValidation => ("peer validation", "validation par les pairs"),
+            ExpertComponent::Evidence => ("credentials and publications", "titres de compétence et publications"),
+            ExpertComponent::Availability => ("availability", "disponibilité"),
+            ExpertComponent::Language => ("language", "langue"),
+        }
+    }
+}
+
+impl fmt::Display for ExpertComponent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ExpertComponent::SkillLevel => write!(f, "skill_level"),
+            ExpertComponent::PeerValidation => write!(f, "peer_validation"),
+            ExpertComponent::Evidence => write!(f, "evidence"),
+            ExpertComponent::Availability => write!(f, "availability"),
+            ExpertComponent::Language => write!(f, "language"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, InputObject)]
+pub struct SkillCriterion {
+    pub skill_id: Uuid,
+    pub min_level: i32, // CapabilityLevel
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize, InputObject)]
+pub struct ExpertQuery {
+    pub skills: Vec<SkillCriterion>,
+    pub language: Option<String>, // ISO 639-1 code, working language or first official language
+    pub organization_id: Option<Uuid>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, SimpleObject)]
+/// Points earned in one component and why
+pub struct ExpertReason {
+    pub component: String, // ExpertComponent
+    pub points: f64,
+    pub max_points: f64,
+    pub explanation_en: String,
+    pub explanation_fr: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, SimpleObject)]
+/// How a person stands on one requested skill
+pub struct ExpertSkill {
+    pub skill: Skill,
+    pub min_level: i32,
+    pub min_level_key: String,
+    pub self_identified_level: Option<i32>, // None if not held
+    pub level_key: Option<String>,
+    pub consensus: ValidationConsensus,
+    pub meets: bool, // Self-assessed or validated at the minimum or above
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, SimpleObject)]
+pub struct Expert {
+    pub rank: i32,
+    pub person_id: Uuid,
+    pub given_name: String,
+    pub family_name: String,
+    pub organization_id: Uuid,
+    pub score: f64, // Out of 100
+    pub skills_met: i32,
+    pub skills: Vec<ExpertSkill>,
+    pub effort: f64, // Current committed effort
+    pub reasons: Vec<ExpertReason>,
+    pub summary_en: String,
+    pub summary_fr: String,
+    #[serde(skip)]
+    #[graphql(skip)]
+    strongest: Option<ExpertComponent>, // Highest share of its points
+}
+
+/// Evidence found for a person across the requested skills
+#[derive(Default)]
+struct Evidence {
+    credentials: usize,
+    validated_credentials: usize,
+    publications: usize,
+}
+
+fn reason(component: ExpertComponent, share: f64, explanation_en: String, explanation_fr: String) -> (ExpertComponent, ExpertReason) {
+    let max_points = component.max_points();
+
+    (component, ExpertReason {
+        component: component.to_string(),
+        points: (share.max(0.0).min(1.0) * max_points * 10.0).round() / 10.0,
+        max_points,
+        explanation_en,
+        explanation_fr,
+    })
+}
+
+fn mentions(text: &str, skill: &Skill) -> bool {
+    let text = text.to_lowercase();
+
+    [&skill.name_en, &skill.name_fr].iter()
+        .map(|n| n.trim().to_lowercase())
+        .any(|n| !n.is_empty() && text.contains(&n))
+}
+
+impl ExpertQuery {
+    /// Requested skills, keeping the first minimum given for a repeated skill
+    fn criteria(&self) -> Vec<SkillCriterion> {
+        let mut seen = HashSet::new();
+
+        self.skills.iter()
+            .filter(|c| seen.insert(c.skill_id))
+            .cloned()
+            .collect()
+    }
+}
+
+impl Expert {
+    /// People holding at least one requested skill at its minimum level, self-assessed or
+    /// by validator consensus. Ranked by the number of skills met, then by score.
+    pub fn find(conn: &PgConnection, query: &ExpertQuery, as_of: NaiveDateTime) -> Result<Vec<Expert>, CustomError> {
+        let criteria = query.criteria();
+
+        if criteria.is_empty() {
+            return Ok(Vec::new())
+        };
+
+        let skill_ids: Vec<Uuid> = criteria.iter().map(|c| c.skill_id).collect();
+
+        let skills: HashMap<Uuid, Skill> = skills::table
+            .filter(skills::id.eq_any(&skill_ids))
+            .load::<Skill>(conn)?
+            .into_iter()
+            .map(|s| (s.id, s))
+            .collect();
+
+        let mut held_query = capabilities::table
+            .inner_join(persons::table)
+            .filter(capabilities::skill_id.eq_any(&skill_ids))
+            .filter(persons::retired_at.is_null())
+            .into_boxed();
+
+        if let Some(organization_id) = query.organization_id {
+            held_query = held_query.filter(persons::organization_id.eq(organization_id));
+        };
+
+        let held = held_query.load::<(Capability, Person)>(conn)?;
+
+        let capabilities: Vec<Capability> = held.iter().map(|(c, _)| c.clone()).collect();
+        let consensus = Validation::find_consensus(conn, &capabilities)?;
+
+        let min_levels: HashMap<Uuid, i32> = criteria.iter().map(|c| (c.skill_id, c.min_level)).collect();
+
+        let meets = |capability: &Capability| -> bool {
+            let min_level = min_levels.get(&capability.skill_id).cloned().unwrap_or(0);
+            let validated = consensus.get(&capability.id)
+                .and_then(|c| c.average_level)
+                .map_or(false, |l| l.round() as i32 >= min_level);
+
+            capability.self_identified_level >= min_level || validated
+        };
+
+        let mut persons: HashMap<Uuid, Person> = HashMap::new();
+        let mut held_by_person: HashMap<Uuid, HashMap<Uuid, Capability>> = HashMap::new();
+
+        for (capability, person) in &held {
+            held_by_person.entry(person.id).or_insert_with(HashMap::new).insert(capability.skill_id, capability.clone());
+            persons.entry(person.id).or_insert_with(|| person.clone());
+        };
+
+        // Candidates meet at least one requested skill
+        held_by_person.retain(|_, held| held.values().any(|c| meets(c)));
+
+        let person_ids: Vec<Uuid> = held_by_person.keys().cloned().collect();
+
+        let mut evidence: HashMap<Uuid, Evidence> = HashMap::new();
+
+        for credential in credentials::table
+            .filter(credentials::person_id.eq_any(&person_ids))
+            .load::<Credential>(conn)? {
+            let text = format!("{} {}", credential.description, credential.provider);
+
+            if skills.values().any(|s| mentions(&text, s)) {
+                let e = evidence.entry(credential.person_id).or_insert_with(Evidence::default);
+                e.credentials += 1;
+                if credential.validated {
+                    e.validated_credentials += 1;
+                };
+            };
+        };
+
+        for (author, publication) in publication_authors::table
+            .inner_join(publications::table)
+            .filter(publication_authors::person_id.eq_any(&person_ids))
+            .load::<(PublicationAuthor, Publication)>(conn)? {
+            let text = format!("{} {}", publication.title, publication.topics.join(" "));
+
+            if let Some(person_id) = author.person_id.filter(|_| skills.values().any(|s| mentions(&text, s))) {
+                evidence.entry(person_id).or_insert_with(Evidence::default).publications += 1;
+            };
+        };
+
+        let mut effort: HashMap<Uuid, f64> = HashMap::new();
+
+        // Suspended home roles are inactive, so only the effort actually committed counts
+        for role in roles::table
+            .filter(roles::person_id.eq_any(&person_ids))
+            .filter(roles::active.eq(true))
+            .filter(roles::start_datestamp.le(as_of))
+            .filter(roles::end_date.is_null().or(roles::end_date.gt(as_of)))
+            .load::<Role>(conn)? {
+            *effort.entry(role.person_id).or_insert(0.0) += role.effort;
+        };
+
+        let language = query.language.as_ref()
+            .map(|l| l.trim().to_lowercase())
+            .filter(|l| !l.is_empty());
+
+        let mut experts: Vec<Expert> = held_by_person.iter()
+            .filter_map(|(person_id, held)| {
+                let person = persons.get(person_id)?;
+
+                let expert_skills: Vec<ExpertSkill> = criteria.iter()
+                    .filter_map(|criterion| {
+                        let skill = skills.get(&criterion.skill_id)?.clone();
+                        let capability = held.get(&criterion.skill_id);
+
+                        Some(ExpertSkill {
+                            skill,
+                            min_level: criterion.min_level,
+                            min_level_key: CapabilityLevel::from_i32(criterion.min_level).key().to_string(),
+                            self_identified_level: capability.map(|c| c.self_identified_level),
+                            level_key: capability.map(|c| c.get_level().key().to_string()),
+                            consensus: capability.and_then(|c| consensus.get(&c.id)).cloned().unwrap_or_default(),
+                            meets: capability.map_or(false, |c| meets(c)),
+                        })
+                    })
+                    .collect();
+
+                let scored = Expert::reasons(
+                    &expert_skills,
+                    evidence.get(person_id).unwrap_or(&Evidence::default()),
+                    effort.get(person_id).cloned().unwrap_or(0.0),
+                    person,
+                    language.as_deref(),
+                );
+
+                let strongest = scored.iter()
+                    .max_by(|(_, a), (_, b)| (a.points / a.max_points).partial_cmp(&(b.points / b.max_points))
+                        .unwrap_or(std::cmp::Ordering::Equal))
+                    .map(|(c, _)| *c);
+
+                let reasons: Vec<ExpertReason> = scored.into_iter().map(|(_, r)| r).collect();
+
+                let max_points: f64 = reasons.iter().map(|r| r.max_points).sum();
+                let points: f64 = reasons.iter().map(|r| r.points).sum();
+
+                Some(Expert {
+                    rank: 0,
+                    person_id: person.id,
+                    given_name: person.given_name.clone(),
+                    family_name: person.family_name.clone(),
+                    organization_id: person.organization_id,
+                    score: (points / max_points * 1000.0).round() / 10.0,
+                    skills_met: expert_skills.iter().filter(|s| s.meets).count() as i32,
+                    skills: expert_skills,
+                    effort: effort.get(person_id).cloned().unwrap_or(0.0),
+                    reasons,
+                    summary_en: String::new(),
+                    summary_fr: String::new(),
+                    strongest,
+                })
+            })
+            .collect();
+
+        experts.sort_by(|a, b| b.skills_met.cmp(&a.skills_met)
+            .then_with(|| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal))
+            .then_with(|| a.family_name.cmp(&b.family_name)));
+
+        experts.truncate(EXPERT_FINDER_LIMIT);
+
+        let requested = criteria.len();
+
+        for (i, expert) in experts.iter_mut().enumerate() {
+            expert.rank = i as i32 + 1;
+
+            let (strongest_en, strongest_fr) = expert.strongest.map(|c| c.names()).unwrap_or(("", ""));
+
+            expert.summary_en = format!(
+                "Ranked {} for meeting {} of {} skills with a score of {:.0}; strongest on {}",
+                expert.rank, expert.skills_met, requested, expert.score, strongest_en,
+            );
+            expert.summary_fr = format!(
+                "Classé {} pour {} compétences sur {} satisfaites avec une note de {:.0}; point fort : {}",
+                expert.rank, expert.skills_met, requested, expert.score, strongest_fr,
+            );
+        };
+
+        Ok(experts)
+    }
+
+    fn reasons(
+        skills: &[ExpertSkill],
+        evidence: &Evidence,
+        effort: f64,
+        person: &Person,
+        language: Option<&str>,
+    ) -> Vec<(ExpertComponent, ExpertReason)> {
+        let requested = skills.len().max(1) as f64;
+
+        // Meeting the minimum earns most of a skill's share, the rest scales up to Specialist.
+        // A skill held below the minimum earns a little, one not held earns nothing.
+        let level_share: f64 = skills.iter()
+            .map(|s| match s.self_identified_level {
+                Some(level) if level >= s.min_level => {
+                    let top = CapabilityLevel::Specialist.as_i32();
+                    if s.min_level >= top {
+                        1.0
+                    } else {
+                        0.6 + 0.4 * (level - s.min_level) as f64 / (top - s.min_level) as f64
+                    }
+                },
+                Some(level) => 0.3 * level as f64 / s.min_level.max(1) as f64,
+                None => 0.0,
+            })
+            .sum::<f64>() / requested;
+
+        let (level_en, level_fr): (Vec<String>, Vec<String>) = skills.iter()
+            .map(|s| {
+                let (min_en, min_fr) = CapabilityLevel::from_i32(s.min_level).names();
+                match s.self_identified_level {
+                    Some(level) => {
+                        let (en, fr) = CapabilityLevel::from_i32(level).names();
+                        (
+                            format!("self-assessed {} in {} (needs {})", en, s.skill.name_en, min_en),
+                            format!("autoévaluation {} en {} (requis : {})", fr, s.skill.name_fr, min_fr),
+                        )
+                    },
+                    None => (
+                        format!("does not list {}", s.skill.name_en),
+                        format!("ne déclare pas {}", s.skill.name_fr),
+                    ),
+                }
+            })
+            .unzip();
+
+        // Confidence grows with the number of validators, agreement is the share confirming the self-assessment
+        let validation_share: f64 = skills.iter()
+            .map(|s| s.consensus.validators.min(CONFIDENT_VALIDATORS) as f64 / CONFIDENT_VALIDATORS as f64
+                * s.consensus.agreement())
+            .sum::<f64>() / requested;
+
+        let validated: Vec<&ExpertSkill> = skills.iter().filter(|s| s.consensus.validators > 0).collect();
+
+        let (validation_en, validation_fr) = if validated.is_empty() {
+            ("No peer validations".to_string(), "Aucune validation par les pairs".to_string())
+        } else {
+            let (en, fr): (Vec<String>, Vec<String>) = validated.iter()
+                .map(|s| (
+                    format!("{}: {} of {} validators confirm the self-assessment", s.skill.name_en, s.consensus.confirming, s.consensus.validators),
+                    format!("{} : {} validateurs sur {} confirment l'autoévaluation", s.skill.name_fr, s.consensus.confirming, s.consensus.validators),
+                ))
+                .unzip();
+            (en.join("; "), fr.join("; "))
+        };
+
+        // Unvalidated credentials count for half
+        let evidence_count = evidence.publications as f64
+            + evidence.validated_credentials as f64
+            + 0.5 * (evidence.credentials - evidence.validated_credentials) as f64;
+
+        let (evidence_en, evidence_fr) = if evidence.credentials + evidence.publications == 0 {
+            ("No related credentials or publications".to_string(), "Aucun titre de compétence ni publication connexe".to_string())
+        } else {
+            (
+                format!("{} related credentials ({} validated) and {} related publications",
+                    evidence.credentials, evidence.validated_credentials, evidence.publications),
+                format!("{} titres de compétence connexes ({} validés) et {} publications connexes",
+                    evidence.credentials, evidence.validated_credentials, evidence.publications),
+            )
+        };
+
+        let headroom = (1.0 - effort).max(0.0);
+
+        let mut reasons = vec![
+            reason(
+                ExpertComponent::SkillLevel,
+                level_share,
+                capitalize(&level_en.join("; ")),
+                capitalize(&level_fr.join("; ")),
+            ),
+            reason(ExpertComponent::PeerValidation, validation_share, validation_en, validation_fr),
+            reason(ExpertComponent::Evidence, evidence_count / EVIDENCE_TARGET, evidence_en, evidence_fr),
+            reason(
+                ExpertComponent::Availability,
+                headroom,
+                format!("Committed at {:.0}% effort, {:.0}% headroom", effort * 100.0, headroom * 100.0),
+                format!("Engagé à {:.0} % d'effort, {:.0} % de disponibilité", effort * 100.0, headroom * 100.0),
+            ),
+        ];
+
+        if let Some(language) = language {
+            let works_in = person.languages.iter().any(|l| l == language)
+                || person.first_official_language.as_deref() == Some(language);
+
+            reasons.push(if works_in {
+                reason(
+                    ExpertComponent::Language,
+                    1.0,
+                    format!("Works in {}", language.to_uppercase()),
+                    format!("Travaille en {}", language.to_uppercase()),
+                )
+            } else {
+                reason(
+                    ExpertComponent::Language,
+                    0.0,
+                    format!("Does not list {}", language.to_uppercase()),
+                    format!("Ne déclare pas {}", language.to_uppercase()),
+                )
+            });
+        };
+
+        reasons
+    }
+}
+
+fn capitalize(s: &str) -> String {
+    let mut chars = s.chars();
+
+    match chars.next() {
+        Some(c) => c.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
@@ -0,0 +1,178 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use chrono::{prelude::*, Duration};
+use serde::{Deserialize, Serialize};
+use diesel::{PgConnection, ExpressionMethods};
+use diesel::{RunQueryDsl, QueryDsl};
+use uuid::Uuid;
+
+use crate::schema::*;
+use crate::errors::CustomError;
+use crate::models::{Person, Role, SurgeDeployment};
+
+/// Months of deployment history measured when none are asked for
+pub const DEFAULT_FATIGUE_WINDOW_MONTHS: u32 = 12;
+
+/// People whose last deployment ended more recently than this go to the back of the rotation
+pub const REST_PERIOD_DAYS: i64 = 90;
+
+const DAYS_PER_MONTH: f64 = 365.25 / 12.0;
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+/// Surge deployment load of a person over the last window_months.
+/// Deployments are on top of a person's home role, so all their effort is extra.
+pub struct DeploymentFatigue {
+    pub person_id: Uuid,
+    pub window_months: u32,
+    pub deployments: i64, // Deployments under way at any point in the window
+    pub extra_effort: f64, // Effort-months deployed in the window, e.g. 0.5 effort for 2 months is 1.0
+    pub days_since_last: Option<i64>, // Since the last deployment ended, 0 while deployed, None if never deployed
+    pub committed: bool, // Deployed now or due to start a deployment
+    pub resting: bool, // Committed, or back for less than REST_PERIOD_DAYS
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct PersonFatigue {
+    pub person: Person,
+    pub fatigue: DeploymentFatigue,
+}
+
+impl DeploymentFatigue {
+    fn rested(person_id: Uuid, window_months: u32) -> Self {
+        DeploymentFatigue {
+            person_id,
+            window_months,
+            deployments: 0,
+            extra_effort: 0.0,
+            days_since_last: None,
+            committed: false,
+            resting: false,
+        }
+    }
+
+    /// Rotation order: rested people first, then those deployed least often,
+    /// then those who gave the least extra effort, then the longest since deployed.
+    pub fn rotation_cmp(&self, other: &Self) -> Ordering {
+        self.resting.cmp(&other.resting)
+            .then_with(|| self.deployments.cmp(&other.deployments))
+            .then_with(|| self.extra_effort.partial_cmp(&other.extra_effort).unwrap_or(Ordering::Equal))
+            .then_with(|| match (self.days_since_last, other.days_since_last) {
+                (None, None) => Ordering::Equal,
+                (None, Some(_)) => Ordering::Less,
+                (Some(_), None) => Ordering::Greater,
+                (Some(a), Some(b)) => b.cmp(&a),
+            })
+    }
+
+    /// Fatigue of each person as of a date. People never deployed are included, fully rested.
+    pub fn find_by_persons(
+        conn: &PgConnection,
+        person_ids: &[Uuid],
+        window_months: u32,
+        as_of: NaiveDateTime,
+    ) -> Result<HashMap<Uuid, DeploymentFatigue>, CustomError> {
+        let window_start = as_of - Duration::days((f64::from(window_months) * DAYS_PER_MONTH) as i64);
+
+        let mut fatigue: HashMap<Uuid, DeploymentFatigue> = person_ids.iter()
+            .map(|id| (*id, DeploymentFatigue::rested(*id, window_months)))
+            .collect();
+
+        let rows = surge_deployments::table
+            .inner_join(roles::table)
+            .filter(surge_deployments::person_id.eq_any(person_ids))
+            .load::<(SurgeDeployment, Role)>(conn)?;
+
+        for (deployment, role) in rows {
+            let f = match fatigue.get_mut(&deployment.person_id) {
+                Some(f) => f,
+                None => continue,
+            };
+
+            let start = role.start_datestamp;
+
+            // Whichever came first, the role's end or the release
+            let end = match (role.end_date, deployment.released_at) {
+                (Some(e), Some(r)) => Some(e.min(r)),
+                (e, r) => e.or(r),
+            };
+
+            if start > as_of {
+                f.committed = true;
+                continue
+            };
+
+            if end.map_or(true, |e| e > as_of) {
+                f.committed = true;
+                f.days_since_last = Some(0);
+            } else if let Some(e) = end {
+                let days = (as_of - e).num_days();
+                f.days_since_last = Some(f.days_since_last.map_or(days, |d| d.min(days)));
+            };
+
+            let overlap_end = end.map_or(as_of, |e| e.min(as_of));
+            let overlap_start = start.max(window_start);
+
+            if overlap_end > overlap_start {
+                f.deployments += 1;
+                f.extra_effort += role.effort * (overlap_end - overlap_start).num_days() as f64 / DAYS_PER_MONTH;
+            };
+        };
+
+        for f in fatigue.values_mut() {
+            f.resting = f.committed || f.days_since_last.map_or(false, |d| d < REST_PERIOD_DAYS);
+        };
+
+        Ok(fatigue)
+    }
+
+    pub fn find_by_person(
+        conn: &PgConnection,
+        person_id: Uuid,
+        window_months: u32,
+        as_of: NaiveDateTime,
+    ) -> Result<DeploymentFatigue, CustomError> {
+        let mut fatigue = DeploymentFatigue::find_by_persons(conn, &[person_id], window_months, as_of)?;
+
+        Ok(fatigue.remove(&person_id).unwrap_or_else(|| DeploymentFatigue::rested(person_id, window_months)))
+    }
+
+    /// Everyone deployed in the window, optionally in one organization,
+    /// most deployed first
+    pub fn find_deployed(
+        conn: &PgConnection,
+        organization_id: Option<Uuid>,
+        window_months: u32,
+        as_of: NaiveDateTime,
+    ) -> Result<Vec<PersonFatigue>, CustomError> {
+        let mut query = persons::table
+            .filter(persons::id.eq_any(
+                surge_deployments::table.select(surge_deployments::person_id)
+            ))
+            .into_boxed();
+
+        if let Some(organization_id) = organization_id {
+            query = query.filter(persons::organization_id.eq(organization_id));
+        };
+
+        let persons = query.load::<Person>(conn)?;
+        let person_ids: Vec<Uuid> = persons.iter().map(|p| p.id).collect();
+
+        let mut fatigue = DeploymentFatigue::find_by_persons(conn, &person_ids, window_months, as_of)?;
+
+        let mut rows: Vec<PersonFatigue> = persons.into_iter()
+            .filter_map(|person| {
+                let fatigue = fatigue.remove(&person.id)?;
+                if fatigue.deployments == 0 && !fatigue.committed {
+                    return None
+                };
+                Some(PersonFatigue { person, fatigue })
+            })
+            .collect();
+
+        rows.sort_by(|a, b| b.fatigue.rotation_cmp(&a.fatigue)
+            .then_with(|| a.person.family_name.cmp(&b.person.family_name)));
+
+        Ok(rows)
+    }
+}
@@ -0,0 +1,244 @@
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::{prelude::*};
+use serde::{Deserialize, Serialize};
+use diesel::{self, Insertable, PgConnection, Queryable, ExpressionMethods};
+use diesel::{RunQueryDsl, QueryDsl, OptionalExtension};
+use uuid::Uuid;
+use async_graphql::*;
+
+use crate::graphql::graphql_translate;
+
+use crate::schema::*;
+use crate::errors::CustomError;
+
+#[derive(Debug, Clone, Deserialize, Serialize, Queryable, Identifiable, SimpleObject)]
+#[table_name = "hr_sync_runs"]
+/// One HRMS extract processed by the sync
+pub struct HrSyncRun {
+    pub id: Uuid,
+    pub source: String, // e.g. peoplesoft
+    pub file_name: String,
+    pub organization_id: Uuid, // Organization
+    pub rows_read: i32,
+    pub changes_applied: i32,
+    pub conflicts: i32,
+    pub started_at: NaiveDateTime,
+    pub completed_at: Option<NaiveDateTime>,
+}
+
+impl HrSyncRun {
+    pub fn create(conn: &PgConnection, run: &NewHrSyncRun) -> Result<HrSyncRun, diesel::result::Error> {
+        diesel::insert_into(hr_sync_runs::table)
+            .values(run)
+            .get_result(conn)
+    }
+
+    pub fn complete(&self, conn: &PgConnection, rows_read: i32, changes_applied: i32, conflicts: i32) -> Result<HrSyncRun, diesel::result::Error> {
+        diesel::update(hr_sync_runs::table.find(self.id))
+            .set((
+                hr_sync_runs::rows_read.eq(rows_read),
+                hr_sync_runs::changes_applied.eq(changes_applied),
+                hr_sync_runs::conflicts.eq(conflicts),
+                hr_sync_runs::completed_at.eq(Utc::now().naive_utc()),
+            ))
+            .get_result(conn)
+    }
+
+    pub fn find_recent(conn: &PgConnection, limit: i64) -> FieldResult<Vec<HrSyncRun>> {
+        let res = hr_sync_runs::table
+            .order(hr_sync_runs::started_at.desc())
+            .limit(limit)
+            .load::<HrSyncRun>(conn);
+
+        graphql_translate(res)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, Insertable)]
+#[table_name = "hr_sync_runs"]
+pub struct NewHrSyncRun {
+    pub source: String,
+    pub file_name: String,
+    pub organization_id: Uuid,
+}
+
+impl NewHrSyncRun {
+    pub fn new(
+        source: String,
+        file_name: String,
+        organization_id: Uuid,
+    ) -> Self {
+        NewHrSyncRun {
+            source,
+            file_name,
+            organization_id,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+/// Applied and Accepted changes hold the value HRMS last set.
+/// Kept changes record an HRMS value an admin chose not to use.
+/// Superseded conflicts were replaced by a newer extract value.
+pub enum HrChangeStatus {
+    Applied,
+    Conflict,
+    Accepted,
+    Kept,
+    Superseded,
+}
+
+impl fmt::Display for HrChangeStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            HrChangeStatus::Applied => "applied",
+            HrChangeStatus::Conflict => "conflict",
+            HrChangeStatus::Accepted => "accepted",
+            HrChangeStatus::Kept => "kept",
+            HrChangeStatus::Superseded => "superseded",
+        };
+        f.write_str(s)
+    }
+}
+
+impl FromStr for HrChangeStatus {
+    type Err = CustomError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "applied" => Ok(HrChangeStatus::Applied),
+            "conflict" => Ok(HrChangeStatus::Conflict),
+            "accepted" => Ok(HrChangeStatus::Accepted),
+            "kept" => Ok(HrChangeStatus::Kept),
+            "superseded" => Ok(HrChangeStatus::Superseded),
+            _ => Err(CustomError::new(400, format!("Unknown HR change status: {}", s))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, Queryable, Identifiable, SimpleObject)]
+#[table_name = "hr_sync_changes"]
+/// Field-level change proposed by an HRMS extract, kept as provenance
+pub struct HrSyncChange {
+    pub id: Uuid,
+    pub sync_run_id: Uuid, // HrSyncRun
+    pub hr_key: String, // Employee number or org unit code
+    pub entity_type: String, // org_tier, person or role
+    pub entity_id: Uuid,
+    pub field_name: String,
+    pub old_value: Option<String>,
+    pub new_value: Option<String>,
+    pub status: String, // HrChangeStatus
+    pub created_at: NaiveDateTime,
+    pub resolved_at: Option<NaiveDateTime>,
+}
+
+impl HrSyncChange {
+    /// Called inside the sync transaction, so returns the raw diesel error
+    pub fn record(conn: &PgConnection, change: &NewHrSyncChange) -> Result<HrSyncChange, diesel::result::Error> {
+        diesel::insert_into(hr_sync_changes::table)
+            .values(change)
+            .get_result(conn)
+    }
+
+    /// Most recent settled change (applied, accepted or kept) for a field
+    pub fn last_settled(conn: &PgConnection, entity_id: Uuid, field_name: &str) -> Result<Option<HrSyncChange>, diesel::result::Error> {
+        let settled = [HrChangeStatus::Applied, HrChangeStatus::Accepted, HrChangeStatus::Kept]
+            .iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<String>>();
+
+        hr_sync_changes::table
+            .filter(hr_sync_changes::entity_id.eq(entity_id))
+            .filter(hr_sync_changes::field_name.eq(field_name))
+            .filter(hr_sync_changes::status.eq_any(settled))
+            .order(hr_sync_changes::created_at.desc())
+            .first(conn)
+            .optional()
+    }
+
+    /// Unresolved conflict for a field, if one is already open
+    pub fn open_conflict(conn: &PgConnection, entity_id: Uuid, field_name: &str) -> Result<Option<HrSyncChange>, diesel::result::Error> {
+        hr_sync_changes::table
+            .filter(hr_sync_changes::entity_id.eq(entity_id))
+            .filter(hr_sync_changes::field_name.eq(field_name))
+            .filter(hr_sync_changes::status.eq(HrChangeStatus::Conflict.to_string()))
+            .order(hr_sync_changes::created_at.desc())
+            .first(conn)
+            .optional()
+    }
+
+    pub fn find(conn: &PgConnection, id: Uuid) -> Result<Self, CustomError> {
+        let change = hr_sync_changes::table.find(id).first(conn)?;
+        Ok(change)
+    }
+
+    pub fn find_conflicts(conn: &PgConnection) -> FieldResult<Vec<HrSyncChange>> {
+        let res = hr_sync_changes::table
+            .filter(hr_sync_changes::status.eq(HrChangeStatus::Conflict.to_string()))
+            .order((hr_sync_changes::hr_key.asc(), hr_sync_changes::created_at.asc()))
+            .load::<HrSyncChange>(conn);
+
+        graphql_translate(res)
+    }
+
+    pub fn find_by_run(conn: &PgConnection, sync_run_id: Uuid) -> FieldResult<Vec<HrSyncChange>> {
+        let res = hr_sync_changes::table
+            .filter(hr_sync_changes::sync_run_id.eq(sync_run_id))
+            .order((hr_sync_changes::hr_key.asc(), hr_sync_changes::created_at.asc()))
+            .load::<HrSyncChange>(conn);
+
+        graphql_translate(res)
+    }
+
+    /// Close a conflict as accepted, kept or superseded
+    pub fn resolve(&self, conn: &PgConnection, status: HrChangeStatus) -> Result<HrSyncChange, diesel::result::Error> {
+        diesel::update(hr_sync_changes::table.find(self.id))
+            .set((
+                hr_sync_changes::status.eq(status.to_string()),
+                hr_sync_changes::resolved_at.eq(Utc::now().naive_utc()),
+            ))
+            .get_result(conn)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, Insertable)]
+#[table_name = "hr_sync_changes"]
+pub struct NewHrSyncChange {
+    pub sync_run_id: Uuid,
+    pub hr_key: String,
+    pub entity_type: String,
+    pub entity_id: Uuid,
+    pub field_name: String,
+    pub old_value: Option<String>,
+    pub new_value: Option<String>,
+    pub status: String,
+    pub created_at: NaiveDateTime, // Set per change, as NOW() is fixed for the whole sync transaction
+}
+
+impl NewHrSyncChange {
+    pub fn new(
+        sync_run_id: Uuid,
+        hr_key: &str,
+        entity_type: &str,
+        entity_id: Uuid,
+        field_name: &str,
+        old_value: Option<String>,
+        new_value: Option<String>,
+        status: HrChangeStatus,
+    ) -> Self {
+        NewHrSyncChange {
+            sync_run_id,
+            hr_key: hr_key.to_owned(),
+            entity_type: entity_type.to_owned(),
+            entity_id,
+            field_name: field_name.to_owned(),
+            old_value,
+            new_value,
+            status: status.to_string(),
+            created_at: Utc::now().naive_utc(),
+        }
+    }
+}
@@ -8,6 +8,31 @@ mod org_ownership;
 mod org_tier;
 mod team_ownership;
 mod role;
+mod role_event;
+mod org_chart;
+mod skill;
+mod capability;
+mod credential;
+mod affiliation;
+mod work;
+mod reporting_relationship;
+mod hr_sync;
+mod publication;
+mod author_match;
+mod collaboration;
+mod surge;
+mod fatigue;
+mod official_language;
+mod roster;
+mod skill_requirement;
+mod skill_gap;
+mod succession;
+mod workforce_metric;
+mod search;
+mod validation;
+mod expert_finder;
+mod api_token;
+mod user_identity;
 
 
 pub use user::*;
@@ -20,3 +45,28 @@ pub use org_ownership::*;
 pub use org_tier::*;
 pub use team_ownership::*;
 pub use role::*;
+pub use role_event::*;
+pub use org_chart::*;
+pub use skill::*;
+pub use capability::*;
+pub use credential::*;
+pub use affiliation::*;
+pub use work::*;
+pub use reporting_relationship::*;
+pub use hr_sync::*;
+pub use publication::*;
+pub use author_match::*;
+pub use collaboration::*;
+pub use surge::*;
+pub use fatigue::*;
+pub use official_language::*;
+pub use roster::*;
+pub use skill_requirement::*;
+pub use skill_gap::*;
+pub use succession::*;
+pub use workforce_metric::*;
+pub use search::*;
+pub use validation::*;
+pub use expert_finder::*;
+pub use api_token::*;
+pub use user_identity::*;
@@ -0,0 +1,419 @@
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::{prelude::*, Duration};
+use serde::{Deserialize, Serialize};
+use diesel::{self, Insertable, PgConnection, Queryable, ExpressionMethods, BoolExpressionMethods};
+use diesel::{RunQueryDsl, QueryDsl};
+use uuid::Uuid;
+use async_graphql::*;
+
+use crate::graphql::graphql_translate;
+
+use crate::schema::*;
+use crate::errors::CustomError;
+use crate::models::{Person, Role, Team};
+
+/// Second language evaluation results other than E are valid for five years
+pub const EVALUATION_VALID_YEARS: i64 = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum OfficialLanguage {
+    English,
+    French,
+}
+
+impl OfficialLanguage {
+    pub fn other(&self) -> OfficialLanguage {
+        match self {
+            OfficialLanguage::English => OfficialLanguage::French,
+            OfficialLanguage::French => OfficialLanguage::English,
+        }
+    }
+}
+
+impl fmt::Display for OfficialLanguage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            OfficialLanguage::English => "en",
+            OfficialLanguage::French => "fr",
+        };
+        f.write_str(s)
+    }
+}
+
+impl FromStr for OfficialLanguage {
+    type Err = CustomError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "en" => Ok(OfficialLanguage::English),
+            "fr" => Ok(OfficialLanguage::French),
+            _ => Err(CustomError::new(400, format!("Unknown official language: {}", s))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+/// Second language evaluation result, lowest first. X is below A.
+pub enum ProficiencyLevel {
+    X,
+    A,
+    B,
+    C,
+    E, // Exempt from further testing
+}
+
+impl fmt::Display for ProficiencyLevel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            ProficiencyLevel::X => "X",
+            ProficiencyLevel::A => "A",
+            ProficiencyLevel::B => "B",
+            ProficiencyLevel::C => "C",
+            ProficiencyLevel::E => "E",
+        };
+        f.write_str(s)
+    }
+}
+
+impl FromStr for ProficiencyLevel {
+    type Err = CustomError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_uppercase().as_str() {
+            "X" => Ok(ProficiencyLevel::X),
+            "A" => Ok(ProficiencyLevel::A),
+            "B" => Ok(ProficiencyLevel::B),
+            "C" => Ok(ProficiencyLevel::C),
+            "E" => Ok(ProficiencyLevel::E),
+            _ => Err(CustomError::new(400, format!("Unknown proficiency level: {}", s))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+/// Reading, writing and oral levels, written e.g. CBC
+pub struct LanguageProfile {
+    pub reading: ProficiencyLevel,
+    pub writing: ProficiencyLevel,
+    pub oral: ProficiencyLevel,
+}
+
+impl fmt::Display for LanguageProfile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}{}", self.reading, self.writing, self.oral)
+    }
+}
+
+impl FromStr for LanguageProfile {
+    type Err = CustomError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let levels: Vec<String> = s.trim().chars().map(|c| c.to_string()).collect();
+
+        match levels.as_slice() {
+            [reading, writing, oral] => Ok(LanguageProfile {
+                reading: reading.parse()?,
+                writing: writing.parse()?,
+                oral: oral.parse()?,
+            }),
+            _ => Err(CustomError::new(400, format!("Language profile must be three levels, e.g. CBC: {}", s))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+/// Language requirement of a position
+pub enum LanguageRequirement {
+    EnglishEssential,
+    FrenchEssential,
+    EitherOr, // English or French, met by anyone
+    Bilingual(LanguageProfile), // Profile required in the incumbent's second official language
+}
+
+impl fmt::Display for LanguageRequirement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LanguageRequirement::EnglishEssential => f.write_str("english_essential"),
+            LanguageRequirement::FrenchEssential => f.write_str("french_essential"),
+            LanguageRequirement::EitherOr => f.write_str("either_or"),
+            LanguageRequirement::Bilingual(profile) => write!(f, "{}", profile),
+        }
+    }
+}
+
+impl FromStr for LanguageRequirement {
+    type Err = CustomError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "english_essential" => Ok(LanguageRequirement::EnglishEssential),
+            "french_essential" => Ok(LanguageRequirement::FrenchEssential),
+            "either_or" => Ok(LanguageRequirement::EitherOr),
+            _ => s.parse::<LanguageProfile>()
+                .map(LanguageRequirement::Bilingual)
+                .map_err(|_| CustomError::new(400, format!("Unknown language requirement: {}", s))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum LanguageGapReason {
+    NoFirstOfficialLanguage, // Needed to know which language is the second
+    NoEvaluation,
+    Expired, // Results would meet the requirement but have lapsed
+    BelowProfile,
+}
+
+impl fmt::Display for LanguageGapReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            LanguageGapReason::NoFirstOfficialLanguage => "no_first_official_language",
+            LanguageGapReason::NoEvaluation => "no_evaluation",
+            LanguageGapReason::Expired => "expired",
+            LanguageGapReason::BelowProfile => "below_profile",
+        };
+        f.write_str(s)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, Queryable, Insertable, AsChangeset, SimpleObject)]
+#[table_name = "language_evaluations"]
+/// Second language evaluation result in one official language
+pub struct LanguageEvaluation {
+    pub id: Uuid,
+    pub person_id: Uuid,
+    pub language: String, // OfficialLanguage
+    pub reading: Option<String>, // ProficiencyLevel
+    pub writing: Option<String>,
+    pub oral: Option<String>,
+    pub tested_at: NaiveDateTime,
+    pub expires_at: Option<NaiveDateTime>, // Doesn't apply to E results
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+/// Current role of a person who doesn't meet its language requirement
+pub struct LanguageGap {
+    pub role: Role,
+    pub team: Team,
+    pub person: Person,
+    pub requirement: String, // LanguageRequirement
+    pub reason: String, // LanguageGapReason
+    pub shortfall: Vec<String>, // e.g. "reading B/C"
+}
+
+impl LanguageEvaluation {
+    pub fn create(conn: &PgConnection, evaluation: &NewLanguageEvaluation) -> FieldResult<LanguageEvaluation> {
+        let res = diesel::insert_into(language_evaluations::table)
+            .values(evaluation)
+            .get_result(conn);
+
+        graphql_translate(res)
+    }
+
+    pub fn delete(conn: &PgConnection, id: Uuid) -> Result<LanguageEvaluation, CustomError> {
+        let res = diesel::delete(language_evaluations::table.find(id)).get_result(conn)?;
+        Ok(res)
+    }
+
+    /// Most recent first
+    pub fn find_by_person(conn: &PgConnection, person_id: Uuid) -> Result<Vec<Self>, CustomError> {
+        let res = language_evaluations::table
+            .filter(language_evaluations::person_id.eq(person_id))
+            .order(language_evaluations::tested_at.desc())
+            .load::<LanguageEvaluation>(conn)?;
+
+        Ok(res)
+    }
+
+    fn level(value: &Option<String>) -> Option<ProficiencyLevel> {
+        value.as_deref().and_then(|v| v.parse().ok())
+    }
+
+    /// E results are permanent, the others hold until expires_at
+    fn is_valid(&self, level: ProficiencyLevel, as_of: NaiveDateTime) -> bool {
+        level == ProficiencyLevel::E || self.expires_at.map_or(true, |e| e > as_of)
+    }
+
+    /// Best reading, writing and oral results in a language, ignoring lapsed
+    /// results unless include_expired
+    pub fn best_levels(
+        evaluations: &[LanguageEvaluation],
+        language: OfficialLanguage,
+        as_of: NaiveDateTime,
+        include_expired: bool,
+    ) -> [Option<ProficiencyLevel>; 3] {
+        let mut best = [None, None, None];
+
+        for evaluation in evaluations.iter().filter(|e| e.language == language.to_string() && e.tested_at <= as_of) {
+            let levels = [&evaluation.reading, &evaluation.writing, &evaluation.oral];
+
+            for (i, value) in levels.iter().enumerate() {
+                if let Some(level) = LanguageEvaluation::level(value) {
+                    if (include_expired || evaluation.is_valid(level, as_of)) && best[i].map_or(true, |b| level > b) {
+                        best[i] = Some(level);
+                    };
+                };
+            };
+        };
+
+        best
+    }
+}
+
+impl LanguageRequirement {
+    /// Why a person doesn't meet the requirement, with the skills that fall
+    /// short, or None when they do. Essential requirements are met by people
+    /// whose first official language it is, or who hold at least B in every skill.
+    pub fn check(
+        &self,
+        person: &Person,
+        evaluations: &[LanguageEvaluation],
+        as_of: NaiveDateTime,
+    ) -> Option<(LanguageGapReason, Vec<String>)> {
+        let first = person.first_official_language.as_deref().and_then(|l| l.parse::<OfficialLanguage>().ok());
+
+        let (language, profile) = match self {
+            LanguageRequirement::EitherOr => return None,
+            LanguageRequirement::EnglishEssential | LanguageRequirement::FrenchEssential => {
+                let language = match self {
+                    LanguageRequirement::EnglishEssential => OfficialLanguage::English,
+                    _ => OfficialLanguage::French,
+                };
+
+                if first == Some(language) {
+                    return None
+                };
+
+                (language, LanguageProfile {
+                    reading: ProficiencyLevel::B,
+                    writing: ProficiencyLevel::B,
+                    oral: ProficiencyLevel::B,
+                })
+            },
+            LanguageRequirement::Bilingual(profile) => match first {
+                Some(first) => (first.other(), *profile),
+                None => return Some((LanguageGapReason::NoFirstOfficialLanguage, Vec::new())),
+            },
+        };
+
+        let required = [("reading", profile.reading), ("writing", profile.writing), ("oral", profile.oral)];
+
+        let shortfall = |levels: [Option<ProficiencyLevel>; 3]| -> Vec<String> {
+            required.iter()
+                .zip(levels.iter())
+                .filter(|((_, wanted), held)| held.map_or(true, |h| h < *wanted))
+                .map(|((skill, wanted), held)| format!("{} {}/{}",
+                    skill, held.map_or("-".to_string(), |h| h.to_string()), wanted))
+                .collect()
+        };
+
+        let current = shortfall(LanguageEvaluation::best_levels(evaluations, language, as_of, false));
+
+        if current.is_empty() {
+            return None
+        };
+
+        if !evaluations.iter().any(|e| e.language == language.to_string()) {
+            return Some((LanguageGapReason::NoEvaluation, current))
+        };
+
+        if shortfall(LanguageEvaluation::best_levels(evaluations, language, as_of, true)).is_empty() {
+            return Some((LanguageGapReason::Expired, current))
+        };
+
+        Some((LanguageGapReason::BelowProfile, current))
+    }
+}
+
+impl LanguageGap {
+    /// Current roles with a language requirement their incumbent doesn't meet,
+    /// optionally in one organization
+    pub fn find_all(conn: &PgConnection, organization_id: Option<Uuid>, as_of: NaiveDateTime) -> Result<Vec<LanguageGap>, CustomError> {
+        let mut query = roles::table
+            .inner_join(teams::table)
+            .inner_join(persons::table)
+            .filter(roles::language_requirement.is_not_null())
+            .filter(roles::active.eq(true))
+            .filter(roles::start_datestamp.le(as_of))
+            .filter(roles::end_date.is_null().or(roles::end_date.gt(as_of)))
+            .into_boxed();
+
+        if let Some(organization_id) = organization_id {
+            query = query.filter(teams::organization_id.eq(organization_id));
+        };
+
+        let rows = query.load::<(Role, Team, Person)>(conn)?;
+
+        let person_ids: Vec<Uuid> = rows.iter().map(|(_, _, p)| p.id).collect();
+
+        let evaluations = language_evaluations::table
+            .filter(language_evaluations::person_id.eq_any(&person_ids))
+            .load::<LanguageEvaluation>(conn)?;
+
+        let mut gaps: Vec<LanguageGap> = rows.into_iter()
+            .filter_map(|(role, team, person)| {
+                let requirement = role.language_requirement.as_deref()?.parse::<LanguageRequirement>().ok()?;
+
+                let held: Vec<LanguageEvaluation> = evaluations.iter()
+                    .filter(|e| e.person_id == person.id)
+                    .cloned()
+                    .collect();
+
+                let (reason, shortfall) = requirement.check(&person, &held, as_of)?;
+
+                Some(LanguageGap {
+                    requirement: requirement.to_string(),
+                    reason: reason.to_string(),
+                    shortfall,
+                    role,
+                    team,
+                    person,
+                })
+            })
+            .collect();
+
+        gaps.sort_by(|a, b| a.team.name_en.cmp(&b.team.name_en)
+            .then_with(|| a.person.family_name.cmp(&b.person.family_name)));
+
+        Ok(gaps)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, Insertable)]
+#[table_name = "language_evaluations"]
+pub struct NewLanguageEvaluation {
+    pub person_id: Uuid,
+    pub language: String,
+    pub reading: Option<String>,
+    pub writing: Option<String>,
+    pub oral: Option<String>,
+    pub tested_at: NaiveDateTime,
+    pub expires_at: Option<NaiveDateTime>,
+}
+
+impl NewLanguageEvaluation {
+    /// Results expire EVALUATION_VALID_YEARS after the test unless another expiry is given
+    pub fn new(
+        person_id: Uuid,
+        language: OfficialLanguage,
+        reading: Option<ProficiencyLevel>,
+        writing: Option<ProficiencyLevel>,
+        oral: Option<ProficiencyLevel>,
+        tested_at: NaiveDateTime,
+        expires_at: Option<NaiveDateTime>,
+    ) -> Self {
+        NewLanguageEvaluation {
+            person_id,
+            language: language.to_string(),
+            reading: reading.map(|l| l.to_string()),
+            writing: writing.map(|l| l.to_string()),
+            oral: oral.map(|l| l.to_string()),
+            tested_at,
+            expires_at: expires_at.or_else(|| Some(tested_at + Duration::days(365 * EVALUATION_VALID_YEARS))),
+        }
+    }
+}
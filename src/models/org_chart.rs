@@ -0,0 +1,139 @@
+use std::collections::HashMap;
+
+use chrono::{prelude::*};
+use serde::{Deserialize, Serialize};
+use diesel::{PgConnection, ExpressionMethods, BoolExpressionMethods};
+use diesel::{RunQueryDsl, QueryDsl};
+use uuid::Uuid;
+
+use crate::schema::*;
+use crate::errors::CustomError;
+use crate::models::{OrgTier, Team, Role, RoleType, Person};
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+/// OrgTier with its Teams and child tiers, as of a date
+pub struct OrgChartNode {
+    pub tier: OrgTier,
+    pub teams: Vec<OrgChartTeam>,
+    pub children: Vec<OrgChartNode>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct OrgChartTeam {
+    pub team: Team,
+    pub members: Vec<OrgChartMember>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+/// Role holder on a Team
+pub struct OrgChartMember {
+    pub role: Role,
+    pub person: Person,
+    pub suspended: bool, // Substantive role held while acting or seconded elsewhere on as_of
+}
+
+impl OrgChartNode {
+    /// Build the tier hierarchy for an organization as it stood on as_of.
+    /// Returns the root tiers (no parent, or parent not in effect on as_of).
+    pub fn build(conn: &PgConnection, organization_id: Uuid, as_of: NaiveDateTime) -> Result<Vec<OrgChartNode>, CustomError> {
+        let tiers = org_tiers::table
+            .filter(org_tiers::organization_id.eq(organization_id))
+            .filter(org_tiers::created_at.le(as_of))
+            .filter(org_tiers::retired_at.is_null().or(org_tiers::retired_at.gt(as_of)))
+            .order((org_tiers::tier_level.asc(), org_tiers::name_en.asc()))
+            .load::<OrgTier>(conn)?;
+
+        let teams = teams::table
+            .filter(teams::organization_id.eq(organization_id))
+            .filter(teams::created_at.le(as_of))
+            .filter(teams::retired_at.is_null().or(teams::retired_at.gt(as_of)))
+            .order(teams::name_en.asc())
+            .load::<Team>(conn)?;
+
+        let team_ids: Vec<Uuid> = teams.iter().map(|t| t.id).collect();
+
+        let roles_and_persons = roles::table
+            .inner_join(persons::table)
+            .filter(roles::team_id.eq_any(&team_ids))
+            .filter(roles::start_datestamp.le(as_of))
+            .filter(roles::end_date.is_null().or(roles::end_date.gt(as_of)))
+            .order((roles::effort.desc(), persons::family_name.asc()))
+            .load::<(Role, Person)>(conn)?;
+
+        let role_ids: Vec<Uuid> = roles_and_persons.iter().map(|(r, _)| r.id).collect();
+
+        // Home roles suspended on as_of by an acting assignment or secondment, which may be in another organization
+        let suspended: Vec<Uuid> = roles::table
+            .select(roles::home_role_id)
+            .filter(roles::home_role_id.eq_any(&role_ids))
+            .filter(roles::role_type.eq_any(vec![
+                RoleType::Acting.to_string(),
+                RoleType::Secondment.to_string(),
+            ]))
+            .filter(roles::start_datestamp.le(as_of))
+            .filter(roles::end_date.is_null().or(roles::end_date.gt(as_of)))
+            .load::<Option<Uuid>>(conn)?
+            .into_iter()
+            .flatten()
+            .collect();
+
+        let mut members_by_team: HashMap<Uuid, Vec<OrgChartMember>> = HashMap::new();
+
+        for (role, person) in roles_and_persons {
+            let suspended = suspended.contains(&role.id);
+
+            members_by_team
+                .entry(role.team_id)
+                .or_insert_with(Vec::new)
+                .push(OrgChartMember { role, person, suspended });
+        };
+
+        let mut teams_by_tier: HashMap<Uuid, Vec<OrgChartTeam>> = HashMap::new();
+
+        for team in teams {
+            let members = members_by_team.remove(&team.id).unwrap_or_default();
+
+            teams_by_tier
+                .entry(team.org_tier_id)
+                .or_insert_with(Vec::new)
+                .push(OrgChartTeam { team, members });
+        };
+
+        let tier_ids: Vec<Uuid> = tiers.iter().map(|t| t.id).collect();
+
+        let mut children_by_parent: HashMap<Option<Uuid>, Vec<OrgTier>> = HashMap::new();
+
+        for tier in tiers {
+            // Treat tiers whose parent isn't in effect as roots
+            let parent = tier.parent_tier.filter(|p| tier_ids.contains(p));
+
+            children_by_parent
+                .entry(parent)
+                .or_insert_with(Vec::new)
+                .push(tier);
+        };
+
+        Ok(assemble(None, &mut children_by_parent, &mut teams_by_tier))
+    }
+}
+
+fn assemble(
+    parent: Option<Uuid>,
+    children_by_parent: &mut HashMap<Option<Uuid>, Vec<OrgTier>>,
+    teams_by_tier: &mut HashMap<Uuid, Vec<OrgChartTeam>>,
+) -> Vec<OrgChartNode> {
+    let tiers = children_by_parent.remove(&parent).unwrap_or_default();
+
+    tiers.into_iter()
+        .map(|tier| {
+            let children = assemble(Some(tier.id), children_by_parent, teams_by_tier);
+            let teams = teams_by_tier.remove(&tier.id).unwrap_or_default();
+
+            OrgChartNode {
+                tier,
+                teams,
+                children,
+            }
+        })
+        .collect()
+}
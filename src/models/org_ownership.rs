@@ -1,9 +1,10 @@
 use std::fmt::Debug;
+use std::str::FromStr;
 
 use chrono::{prelude::*};
 use serde::{Deserialize, Serialize};
-use diesel::{self, Insertable, PgConnection, Queryable, ExpressionMethods};
-use diesel::{RunQueryDsl, QueryDsl};
+use diesel::{self, Insertable, PgConnection, Queryable, ExpressionMethods, BoolExpressionMethods};
+use diesel::{RunQueryDsl, QueryDsl, Connection, OptionalExtension};
 use uuid::Uuid;
 use async_graphql::*;
 use rand::{Rng, thread_rng};
@@ -13,6 +14,7 @@ use crate::graphql::graphql_translate;
 use crate::database::connection;
 use crate::errors::CustomError;
 use crate::schema::*;
+use crate::models::{OrgTier, OwnershipIssue, OwnershipType};
 
 #[derive(Debug, Clone, Deserialize, Serialize, Queryable, Insertable, AsChangeset)]
 #[table_name = "org_tier_ownerships"]
@@ -23,39 +25,71 @@ pub struct OrgOwnership {
 
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
-    pub retired_at: Option<NaiveDateTime>,
+    pub retired_at: Option<NaiveDateTime>, // End of ownership
+
+    pub ownership_type: String, // OwnershipType
+    pub start_datestamp: NaiveDateTime,
 }
 
 // Non Graphql
 impl OrgOwnership {
+    /// Primary owners can't overlap, so an org tier has at most one at any time
     pub fn create(org_tier_ownership: &NewOrgOwnership) -> FieldResult<OrgOwnership> {
         let conn = connection()?;
 
-        let res = diesel::insert_into(org_tier_ownerships::table)
-            .values(org_tier_ownership)
-            .get_result(&conn);
-        
-        graphql_translate(res)
+        OrgOwnership::insert_checked(&conn, org_tier_ownership)
+    }
+
+    /// Insert after checking the end date and, for primary owners, overlaps
+    fn insert_checked(conn: &PgConnection, org_tier_ownership: &NewOrgOwnership) -> FieldResult<OrgOwnership> {
+        if org_tier_ownership.ownership_type == OwnershipType::Acting.to_string() && org_tier_ownership.retired_at.is_none() {
+            return Err(FieldError::new("Acting ownership requires an end date"));
+        };
+
+        if org_tier_ownership.retired_at.map_or(false, |e| e <= org_tier_ownership.start_datestamp) {
+            return Err(FieldError::new("Ownership must end after it starts"));
+        };
+
+        conn.transaction::<OrgOwnership, FieldError, _>(|| {
+            if org_tier_ownership.ownership_type == OwnershipType::Owner.to_string() {
+                lock_org_tier(conn, org_tier_ownership.org_tier_id)?;
+
+                let overlapping = find_overlapping_owners(
+                    conn,
+                    org_tier_ownership.org_tier_id,
+                    org_tier_ownership.start_datestamp,
+                    org_tier_ownership.retired_at,
+                )?;
+
+                if let Some(owner) = overlapping.first() {
+                    return Err(FieldError::new(format!(
+                        "The org tier already has a primary owner from {}", owner.start_datestamp.date())));
+                };
+            };
+
+            graphql_translate(diesel::insert_into(org_tier_ownerships::table)
+                .values(org_tier_ownership)
+                .get_result(conn))
+        })
     }
     
-    pub fn get_or_create(org_tier_ownership: &NewOrgOwnership) -> FieldResult<OrgOwnership> {
-        let conn = connection()?;
+    /// Natural key: (owner_id, org_tier_id, start_datestamp)
+    /// Returns the existing org_tier_ownership unchanged if the key matches,
+    /// otherwise creates it with the same checks as create.
+    pub fn get_or_create(conn: &PgConnection, org_tier_ownership: &NewOrgOwnership) -> FieldResult<OrgOwnership> {
+        conn.transaction::<OrgOwnership, FieldError, _>(|| {
+            let existing = graphql_translate(org_tier_ownerships::table
+                .filter(org_tier_ownerships::owner_id.eq(org_tier_ownership.owner_id))
+                .filter(org_tier_ownerships::org_tier_id.eq(org_tier_ownership.org_tier_id))
+                .filter(org_tier_ownerships::start_datestamp.eq(org_tier_ownership.start_datestamp))
+                .first::<OrgOwnership>(conn)
+                .optional())?;
 
-        let res = org_tier_ownerships::table
-            .filter(org_tier_ownerships::org_tier_id.eq(&org_tier_ownership.org_tier_id))
-            .distinct()
-            .first(&conn);
-        
-        let org_tier_ownership = match res {
-            Ok(p) => p,
-            Err(e) => {
-                // OrgOwnership not found
-                println!("{:?}", e);
-                let p = OrgOwnership::create(org_tier_ownership).expect("Unable to create org_tier_ownership");
-                p
+            match existing {
+                Some(o) => Ok(o),
+                None => OrgOwnership::insert_checked(conn, org_tier_ownership),
             }
-        };
-        Ok(org_tier_ownership)
+        })
     }
 
     pub fn find_all() -> Result<Vec<Self>, CustomError> {
@@ -80,6 +114,142 @@ impl OrgOwnership {
         
         Ok(res)
     }
+
+    /// Ownerships of any type in effect for an org tier on as_of
+    pub fn find_current_by_org_tier(org_tier_id: Uuid, as_of: NaiveDateTime) -> FieldResult<Vec<OrgOwnership>> {
+        let conn = connection()?;
+
+        let res = org_tier_ownerships::table
+            .filter(org_tier_ownerships::org_tier_id.eq(org_tier_id))
+            .filter(org_tier_ownerships::start_datestamp.le(as_of))
+            .filter(org_tier_ownerships::retired_at.is_null().or(org_tier_ownerships::retired_at.gt(as_of)))
+            .load::<OrgOwnership>(&conn);
+
+        graphql_translate(res)
+    }
+
+    pub fn get_ownership_type(&self) -> OwnershipType {
+        OwnershipType::from_str(&self.ownership_type).unwrap_or(OwnershipType::Delegate)
+    }
+
+    /// Hand primary ownership of an org tier to a new owner, retiring the
+    /// current owner's term on start_datestamp in the same transaction.
+    /// The current term must have started before start_datestamp.
+    pub fn succeed(org_tier_id: Uuid, new_owner_id: Uuid, start_datestamp: NaiveDateTime) -> FieldResult<OrgOwnership> {
+        let conn = connection()?;
+
+        let new_ownership = NewOrgOwnership::new(
+            new_owner_id,
+            org_tier_id,
+            OwnershipType::Owner,
+            start_datestamp,
+            None,
+        );
+
+        conn.transaction::<OrgOwnership, FieldError, _>(|| {
+            lock_org_tier(&conn, org_tier_id)?;
+
+            for current in find_overlapping_owners(&conn, org_tier_id, start_datestamp, None)? {
+                if let Some(retired_at) = current.retired_at {
+                    return Err(FieldError::new(format!(
+                        "The org tier already has a primary owner until {}", retired_at.date())));
+                };
+
+                if start_datestamp <= current.start_datestamp {
+                    return Err(FieldError::new(format!(
+                        "The successor must start after the current owner, who started {}", current.start_datestamp.date())));
+                };
+
+                graphql_translate(diesel::update(org_tier_ownerships::table)
+                    .filter(org_tier_ownerships::id.eq(current.id))
+                    .set((
+                        org_tier_ownerships::retired_at.eq(Some(start_datestamp)),
+                        org_tier_ownerships::updated_at.eq(Utc::now().naive_utc()),
+                    ))
+                    .execute(&conn))?;
+            };
+
+            graphql_translate(diesel::insert_into(org_tier_ownerships::table)
+                .values(&new_ownership)
+                .get_result(&conn))
+        })
+    }
+
+    /// Active org tiers without exactly one current primary owner on as_of
+    pub fn validate_ownership(as_of: NaiveDateTime) -> FieldResult<Vec<OwnershipIssue>> {
+        let conn = connection()?;
+
+        let org_tiers = graphql_translate(org_tiers::table
+            .filter(org_tiers::retired_at.is_null())
+            .load::<OrgTier>(&conn))?;
+
+        let current = graphql_translate(org_tier_ownerships::table
+            .filter(org_tier_ownerships::start_datestamp.le(as_of))
+            .filter(org_tier_ownerships::retired_at.is_null().or(org_tier_ownerships::retired_at.gt(as_of)))
+            .load::<OrgOwnership>(&conn))?;
+
+        let mut issues = Vec::new();
+
+        for org_tier in org_tiers {
+            let owner_count = current.iter()
+                .filter(|o| o.org_tier_id == org_tier.id && o.get_ownership_type() == OwnershipType::Owner)
+                .count();
+
+            if owner_count == 1 {
+                continue;
+            };
+
+            let acting_owner_ids = current.iter()
+                .filter(|o| o.org_tier_id == org_tier.id && o.get_ownership_type() == OwnershipType::Acting)
+                .map(|o| o.owner_id)
+                .collect();
+
+            issues.push(OwnershipIssue {
+                entity_type: "org_tier".to_string(),
+                entity_id: org_tier.id,
+                name_en: org_tier.name_en,
+                name_fr: org_tier.name_fr,
+                owner_count,
+                acting_owner_ids,
+            });
+        };
+
+        Ok(issues)
+    }
+
+    /// Active org tiers with no current primary owner
+    pub fn find_orphaned_org_tiers(as_of: NaiveDateTime) -> FieldResult<Vec<OwnershipIssue>> {
+        let issues = OrgOwnership::validate_ownership(as_of)?;
+
+        Ok(issues.into_iter().filter(|i| i.is_orphaned()).collect())
+    }
+}
+
+/// Hold the org tier row until the transaction ends, so primary owner
+/// changes to one org tier are checked one at a time
+fn lock_org_tier(conn: &PgConnection, org_tier_id: Uuid) -> FieldResult<()> {
+    graphql_translate(org_tiers::table
+        .select(org_tiers::id)
+        .filter(org_tiers::id.eq(org_tier_id))
+        .for_update()
+        .first::<Uuid>(conn))?;
+
+    Ok(())
+}
+
+/// Primary ownerships of the org tier overlapping start..end, open-ended if end is None
+fn find_overlapping_owners(conn: &PgConnection, org_tier_id: Uuid, start: NaiveDateTime, end: Option<NaiveDateTime>) -> FieldResult<Vec<OrgOwnership>> {
+    let mut query = org_tier_ownerships::table
+        .filter(org_tier_ownerships::org_tier_id.eq(org_tier_id))
+        .filter(org_tier_ownerships::ownership_type.eq(OwnershipType::Owner.to_string()))
+        .filter(org_tier_ownerships::retired_at.is_null().or(org_tier_ownerships::retired_at.gt(start)))
+        .into_boxed();
+
+    if let Some(end) = end {
+        query = query.filter(org_tier_ownerships::start_datestamp.lt(end));
+    };
+
+    graphql_translate(query.load::<OrgOwnership>(conn))
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, Insertable, SimpleObject)]
@@ -87,6 +257,9 @@ impl OrgOwnership {
 pub struct NewOrgOwnership {
     pub owner_id: Uuid,
     pub org_tier_id: Uuid,
+    pub ownership_type: String, // OwnershipType
+    pub start_datestamp: NaiveDateTime,
+    pub retired_at: Option<NaiveDateTime>,
 }
 
 impl NewOrgOwnership {
@@ -94,10 +267,16 @@ impl NewOrgOwnership {
     pub fn new(
         owner_id: Uuid,
         org_tier_id: Uuid,
+        ownership_type: OwnershipType,
+        start_datestamp: NaiveDateTime,
+        retired_at: Option<NaiveDateTime>,
     ) -> Self {
         NewOrgOwnership {
             owner_id,
             org_tier_id,
+            ownership_type: ownership_type.to_string(),
+            start_datestamp,
+            retired_at,
         }
     }
 }
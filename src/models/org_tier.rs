@@ -3,6 +3,7 @@ use std::fmt::Debug;
 use chrono::{prelude::*};
 use serde::{Deserialize, Serialize};
 use diesel::{self, Insertable, PgConnection, Queryable, ExpressionMethods};
+use diesel::upsert::excluded;
 use diesel::{RunQueryDsl, QueryDsl};
 use uuid::Uuid;
 use async_graphql::*;
@@ -16,6 +17,7 @@ use crate::schema::*;
 
 #[derive(Debug, Clone, Deserialize, Serialize, Queryable, Insertable, AsChangeset)]
 #[table_name = "org_tiers"]
+#[changeset_options(treat_none_as_null = "true")]
 pub struct OrgTier {
     pub id: Uuid,
     pub organization_id: Uuid, // Organization
@@ -26,6 +28,7 @@ pub struct OrgTier {
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
     pub retired_at: Option<NaiveDateTime>,
+    pub hr_org_unit: Option<String>, // HRMS org unit code
 }
 
 // Non Graphql
@@ -40,24 +43,17 @@ impl OrgTier {
         graphql_translate(res)
     }
     
-    pub fn get_or_create(org_tier: &NewOrgTier) -> FieldResult<OrgTier> {
-        let conn = connection()?;
+    /// Natural key: (organization_id, name_en)
+    /// Returns the existing org_tier unchanged if the key matches.
+    pub fn get_or_create(conn: &PgConnection, org_tier: &NewOrgTier) -> FieldResult<OrgTier> {
+        let res = diesel::insert_into(org_tiers::table)
+            .values(org_tier)
+            .on_conflict((org_tiers::organization_id, org_tiers::name_en))
+            .do_update()
+            .set(org_tiers::organization_id.eq(excluded(org_tiers::organization_id)))
+            .get_result(conn);
 
-        let res = org_tiers::table
-        .filter(org_tiers::name_en.eq(&org_tier.name_en))
-        .distinct()
-        .first(&conn);
-        
-        let org_tier = match res {
-            Ok(p) => p,
-            Err(e) => {
-                // OrgTier not found
-                println!("{:?}", e);
-                let p = OrgTier::create(org_tier).expect("Unable to create org_tier");
-                p
-            }
-        };
-        Ok(org_tier)
+        graphql_translate(res)
     }
 
     pub fn find_all() -> Result<Vec<Self>, CustomError> {
@@ -72,6 +68,63 @@ impl OrgTier {
         Ok(org_tier)
     }
     
+    pub fn find_by_organization(organization_id: Uuid) -> Result<Vec<Self>, CustomError> {
+        let conn = connection()?;
+        let org_tiers = org_tiers::table
+            .filter(org_tiers::organization_id.eq(organization_id))
+            .order((org_tiers::tier_level.asc(), org_tiers::name_en.asc()))
+            .load::<OrgTier>(&conn)?;
+        Ok(org_tiers)
+    }
+
+    pub fn find_children(&self) -> Result<Vec<Self>, CustomError> {
+        let conn = connection()?;
+        let org_tiers = org_tiers::table
+            .filter(org_tiers::parent_tier.eq(self.id))
+            .order(org_tiers::name_en.asc())
+            .load::<OrgTier>(&conn)?;
+        Ok(org_tiers)
+    }
+
+    /// Ids of this tier and every tier below it, guarding against cycles
+    pub fn find_subtree_ids(&self, conn: &PgConnection) -> Result<Vec<Uuid>, CustomError> {
+        let tiers = org_tiers::table
+            .filter(org_tiers::organization_id.eq(self.organization_id))
+            .select((org_tiers::id, org_tiers::parent_tier))
+            .load::<(Uuid, Option<Uuid>)>(conn)?;
+
+        let mut subtree = vec![self.id];
+        let mut next = 0;
+
+        while next < subtree.len() {
+            let parent = subtree[next];
+            for (tier_id, _) in tiers.iter().filter(|(_, p)| *p == Some(parent)) {
+                if !subtree.contains(tier_id) {
+                    subtree.push(*tier_id);
+                };
+            };
+            next += 1;
+        };
+
+        Ok(subtree)
+    }
+
+    /// True if making parent_id the parent of this tier would create a cycle
+    pub fn would_create_cycle(&self, parent_id: Uuid) -> Result<bool, CustomError> {
+        let mut visited = vec![];
+        let mut current = Some(parent_id);
+
+        while let Some(tier_id) = current {
+            if tier_id == self.id || visited.contains(&tier_id) {
+                return Ok(true)
+            };
+            visited.push(tier_id);
+            current = OrgTier::find(tier_id)?.parent_tier;
+        };
+
+        Ok(false)
+    }
+
     pub fn update(&self) -> FieldResult<Self> {
         let conn = connection()?;
 
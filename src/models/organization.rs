@@ -3,8 +3,9 @@ use std::collections::HashMap;
 use chrono::NaiveDateTime;
 use serde::{Serialize, Deserialize};
 use diesel::prelude::*;
-use diesel::{self, Insertable, Queryable};
+use diesel::{self, Insertable, PgConnection, Queryable};
 use diesel::{RunQueryDsl, QueryDsl};
+use diesel::upsert::excluded;
 //use juniper::{FieldResult};
 use uuid::Uuid;
 
@@ -14,7 +15,18 @@ use crate::database::connection;
 use crate::graphql::graphql_translate;
 use crate::schema::*;
 
-#[derive(Debug, Clone, Deserialize, Serialize, Queryable, Identifiable, SimpleObject)]
+/// Values accepted for Organization.org_type
+pub const ORG_TYPES: [&str; 7] = [
+    "department",
+    "agency",
+    "crown_corporation",
+    "university",
+    "private_sector",
+    "non_profit",
+    "international",
+];
+
+#[derive(Debug, Clone, Deserialize, Serialize, Queryable, Identifiable, AsChangeset, SimpleObject)]
 #[table_name = "organizations"]
 /// Should get this from an API or have standard data
 /// Now pre-loaded as prt of context
@@ -22,7 +34,7 @@ pub struct Organization {
     pub id: Uuid,
     pub name_en: String,
     pub name_fr: String,
-    pub acroynm_en: String,
+    pub acronym_en: String,
     pub acronym_fr: String,
     pub org_type: String,
     pub created_at: NaiveDateTime,
@@ -41,6 +53,19 @@ impl Organization {
         graphql_translate(res)
     }
 
+    /// Natural key: acronym_en
+    /// Returns the existing organization unchanged if the key matches.
+    pub fn get_or_create(conn: &PgConnection, organization: &NewOrganization) -> FieldResult<Organization> {
+        let res = diesel::insert_into(organizations::table)
+            .values(organization)
+            .on_conflict(organizations::acronym_en)
+            .do_update()
+            .set(organizations::acronym_en.eq(excluded(organizations::acronym_en)))
+            .get_result(conn);
+
+        graphql_translate(res)
+    }
+
     pub fn get_by_id(id: &Uuid) -> FieldResult<Organization> {
         let conn = connection()?;
 
@@ -50,19 +75,52 @@ impl Organization {
         graphql_translate(res)
     }
 
-    pub fn load_into_hash() -> HashMap<Uuid, Organization> {
-        let conn = connection().expect("Unable to make connection");
+    pub fn find_all() -> FieldResult<Vec<Organization>> {
+        let conn = connection()?;
+
+        let res = organizations::table
+            .order(organizations::name_en.asc())
+            .load::<Organization>(&conn);
+
+        graphql_translate(res)
+    }
+
+    pub fn update(&self) -> FieldResult<Organization> {
+        let conn = connection()?;
+
+        let res = diesel::update(organizations::table)
+            .filter(organizations::id.eq(&self.id))
+            .set(self)
+            .get_result(&conn);
+
+        graphql_translate(res)
+    }
+
+    /// Find an organization from its English or French acronym
+    pub fn get_by_acronym(acronym: &str) -> FieldResult<Organization> {
+        let conn = connection()?;
+
+        let acronym = acronym.to_uppercase();
 
         let res = organizations::table
-            .load::<Organization>(&conn)
-            .expect("Unable to get organizations");
+            .filter(organizations::acronym_en.eq(&acronym).or(organizations::acronym_fr.eq(&acronym)))
+            .first(&conn);
+
+        graphql_translate(res)
+    }
+
+    pub fn load_into_hash() -> FieldResult<HashMap<Uuid, Organization>> {
+        let conn = connection()?;
+
+        let res = graphql_translate(organizations::table
+            .load::<Organization>(&conn))?;
 
         let mut organizations: HashMap<Uuid, Organization> = HashMap::new();
         for c in res {
             organizations.insert(c.id, c);
         };
 
-        organizations 
+        Ok(organizations)
     }
 }
 
@@ -4,6 +4,7 @@ use chrono::{prelude::*};
 use serde::{Deserialize, Serialize};
 use diesel::{self, Insertable, PgConnection, Queryable, ExpressionMethods};
 use diesel::{RunQueryDsl, QueryDsl};
+use diesel::upsert::excluded;
 use uuid::Uuid;
 use async_graphql::*;
 use rand::{Rng, thread_rng};
@@ -13,14 +14,18 @@ use crate::errors::CustomError;
 
 use crate::database::connection;
 use crate::schema::*;
+use crate::models::{Organization, Role, RoleWithTeam, RoleEvent, RoleEventWithRole, Capability, CapabilityWithSkill,
+    Credential, Affiliation, AffiliationWithOrganization, Publication, DeploymentRecord, SurgeDeployment,
+    DeploymentFatigue, DEFAULT_FATIGUE_WINDOW_MONTHS, LanguageEvaluation};
 
 #[derive(Debug, Clone, Deserialize, Serialize, Queryable, Insertable, AsChangeset)]
 #[table_name = "persons"]
+#[changeset_options(treat_none_as_null = "true")]
 /// Referenced by Team
 /// Referenced by ReportingRelationship
 pub struct Person {
     pub id: Uuid,
-    pub user_id: Uuid,
+    pub user_id: Option<Uuid>, // User account, if linked
     pub family_name: String,
     pub given_name: String,
 
@@ -29,6 +34,31 @@ pub struct Person {
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
     pub retired_at: Option<NaiveDateTime>,
+
+    pub employee_number: Option<String>, // HR system identifier
+    pub orcid: Option<String>, // ORCID iD, e.g. 0000-0002-1825-0097
+    pub languages: Vec<String>, // ISO 639-1 codes of languages they can work in
+    pub first_official_language: Option<String>, // OfficialLanguage
+    pub location: Option<String>, // Work location, e.g. Ottawa, ON
+    pub retirement_eligible_on: Option<NaiveDate>, // From the HRMS
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+/// Person with their roles, role history, capabilities, credentials, affiliations,
+/// publications, surge deployments, deployment fatigue and language evaluations
+pub struct PersonProfile {
+    pub person: Person,
+    pub organization: Organization,
+    pub current_roles: Vec<RoleWithTeam>,
+    pub past_roles: Vec<RoleWithTeam>,
+    pub role_events: Vec<RoleEventWithRole>,
+    pub capabilities: Vec<CapabilityWithSkill>,
+    pub credentials: Vec<Credential>,
+    pub affiliations: Vec<AffiliationWithOrganization>,
+    pub publications: Vec<Publication>,
+    pub deployments: Vec<DeploymentRecord>,
+    pub fatigue: DeploymentFatigue,
+    pub language_evaluations: Vec<LanguageEvaluation>,
 }
 
 
@@ -55,23 +85,43 @@ impl Person {
         Ok(person)
     }
     
-    pub fn get_or_create(person: &NewPerson) -> FieldResult<Person> {
-        let conn = connection()?;
-        let res = persons::table
-        .filter(persons::family_name.eq(&person.family_name))
-        .distinct()
-        .first(&conn);
-        
-        let person = match res {
-            Ok(p) => p,
-            Err(e) => {
-                // Person not found
-                println!("{:?}", e);
-                let p = Person::create(person).expect("Unable to create person");
-                p
-            }
+    /// Natural key: employee_number if present, otherwise user_id.
+    /// Returns the existing person unchanged if the key matches.
+    pub fn get_or_create(conn: &PgConnection, person: &NewPerson) -> FieldResult<Person> {
+        let insert = diesel::insert_into(persons::table)
+            .values(person);
+
+        let res = match (&person.employee_number, &person.user_id) {
+            (Some(_), _) => insert
+                .on_conflict(persons::employee_number)
+                .do_update()
+                .set(persons::employee_number.eq(excluded(persons::employee_number)))
+                .get_result(conn),
+            (None, Some(_)) => insert
+                .on_conflict(persons::user_id)
+                .do_update()
+                .set(persons::user_id.eq(excluded(persons::user_id)))
+                .get_result(conn),
+            (None, None) => return Err(FieldError::new("Person requires an employee number or user id to be matched")),
         };
-        Ok(person)
+
+        graphql_translate(res)
+    }
+
+    pub fn find_by_orcid(conn: &PgConnection, orcid: &str) -> FieldResult<Person> {
+        let res = persons::table
+            .filter(persons::orcid.eq(orcid))
+            .first(conn);
+
+        graphql_translate(res)
+    }
+
+    pub fn find_by_employee_number(conn: &PgConnection, employee_number: &str) -> FieldResult<Person> {
+        let res = persons::table
+            .filter(persons::employee_number.eq(employee_number))
+            .first(conn);
+
+        graphql_translate(res)
     }
     
     pub fn update(&self) -> FieldResult<Self> {
@@ -84,31 +134,183 @@ impl Person {
         
         Ok(res)
     }
+
+    pub fn find_by_user_id(user_id: Uuid) -> Result<Self, CustomError> {
+        let conn = connection()?;
+        let person = persons::table.filter(persons::user_id.eq(user_id)).first(&conn)?;
+        Ok(person)
+    }
+
+    /// Persons not yet linked to a user account
+    pub fn find_unlinked() -> Result<Vec<Self>, CustomError> {
+        let conn = connection()?;
+        let persons = persons::table
+            .filter(persons::user_id.is_null())
+            .filter(persons::retired_at.is_null())
+            .order((persons::family_name.asc(), persons::given_name.asc()))
+            .load::<Person>(&conn)?;
+        Ok(persons)
+    }
+
+    /// Link this person to a user account, or unlink with None
+    pub fn set_user(&self, user_id: Option<Uuid>) -> Result<Self, CustomError> {
+        let conn = connection()?;
+
+        let person = diesel::update(persons::table)
+            .filter(persons::id.eq(&self.id))
+            .set((
+                persons::user_id.eq(user_id),
+                persons::updated_at.eq(Utc::now().naive_utc()),
+            ))
+            .get_result(&conn)?;
+
+        Ok(person)
+    }
+
+    pub fn profile(&self) -> Result<PersonProfile, CustomError> {
+        let conn = connection()?;
+
+        let organization = organizations::table
+            .filter(organizations::id.eq(self.organization_id))
+            .first::<Organization>(&conn)?;
+
+        let now = Utc::now().naive_utc();
+
+        let (current_roles, past_roles): (Vec<RoleWithTeam>, Vec<RoleWithTeam>) = Role::find_by_person_with_teams(&conn, self.id)?
+            .into_iter()
+            .partition(|r| r.role.is_current(now));
+
+        Ok(PersonProfile {
+            person: self.clone(),
+            organization,
+            current_roles,
+            past_roles,
+            role_events: RoleEvent::timeline_with_roles(&conn, self.id)?,
+            capabilities: Capability::find_by_person_with_skills(&conn, self.id)?,
+            credentials: Credential::find_by_person(&conn, self.id)?,
+            affiliations: Affiliation::find_by_person_with_organizations(&conn, self.id)?,
+            publications: Publication::find_by_person(&conn, self.id)?,
+            deployments: SurgeDeployment::find_by_person(&conn, self.id)?,
+            fatigue: DeploymentFatigue::find_by_person(&conn, self.id, DEFAULT_FATIGUE_WINDOW_MONTHS, now)?,
+            language_evaluations: LanguageEvaluation::find_by_person(&conn, self.id)?,
+        })
+    }
+}
+
+/// Language codes from a comma or space separated list, e.g. "EN, fr".
+/// Anything that isn't a two or three letter code is dropped.
+pub fn parse_languages(input: &str) -> Vec<String> {
+    let mut languages: Vec<String> = Vec::new();
+
+    for code in input.split(|c: char| c == ',' || c.is_whitespace()).map(|c| c.trim().to_lowercase()) {
+        if (2..=3).contains(&code.len()) && code.chars().all(|c| c.is_ascii_alphabetic()) && !languages.contains(&code) {
+            languages.push(code);
+        };
+    };
+
+    languages
+}
+
+/// Normalize an ORCID iD to its hyphenated form and verify the
+/// ISO 7064 MOD 11-2 check character.
+/// Accepts bare iDs, iDs without hyphens and https://orcid.org/ URIs.
+pub fn parse_orcid(input: &str) -> Result<String, CustomError> {
+    let invalid = || CustomError::new(400, format!("Invalid ORCID iD: {}", input));
+
+    let trimmed = input.trim();
+    let trimmed = ["https://orcid.org/", "http://orcid.org/", "orcid.org/"]
+        .iter()
+        .find_map(|prefix| trimmed.strip_prefix(prefix))
+        .unwrap_or(trimmed);
+
+    let chars: Vec<char> = trimmed
+        .chars()
+        .filter(|c| *c != '-')
+        .map(|c| c.to_ascii_uppercase())
+        .collect();
+
+    if chars.len() != 16 || !chars[..15].iter().all(|c| c.is_ascii_digit()) {
+        return Err(invalid())
+    };
+
+    let total = chars[..15]
+        .iter()
+        .fold(0, |total, c| (total + c.to_digit(10).unwrap()) * 2);
+
+    let check = match (12 - total % 11) % 11 {
+        10 => 'X',
+        n => std::char::from_digit(n, 10).unwrap(),
+    };
+
+    if chars[15] != check {
+        return Err(invalid())
+    };
+
+    let digits: String = chars.into_iter().collect();
+
+    Ok(format!("{}-{}-{}-{}", &digits[0..4], &digits[4..8], &digits[8..12], &digits[12..16]))
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, Insertable, SimpleObject)]
 /// Referenced by Roles, TeamOwnership, OrgOwnership
 #[table_name = "persons"]
 pub struct NewPerson {
-    pub user_id: Uuid,
+    pub user_id: Option<Uuid>,
     pub family_name: String,
     pub given_name: String,
     pub organization_id: Uuid, // Organization
+    pub employee_number: Option<String>,
 }
 
 impl NewPerson {
 
     pub fn new(
-        user_id: Uuid,
+        user_id: Option<Uuid>,
         family_name: String,
         given_name: String,
-        organization_id: Uuid, // Organizatio
+        organization_id: Uuid, // Organization
+        employee_number: Option<String>,
     ) -> Self {
         NewPerson {
             user_id,
             family_name,
             given_name,
             organization_id,
+            employee_number,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_orcid_accepts_valid_check_digit() {
+        assert_eq!(parse_orcid("0000-0002-1825-0097").unwrap(), "0000-0002-1825-0097");
+    }
+
+    #[test]
+    fn parse_orcid_rejects_bad_check_digit() {
+        assert!(parse_orcid("0000-0002-1825-0098").is_err());
+    }
+
+    #[test]
+    fn parse_orcid_accepts_x_check_digit() {
+        assert_eq!(parse_orcid("0000-0002-1694-233X").unwrap(), "0000-0002-1694-233X");
+        assert_eq!(parse_orcid("0000-0002-1694-233x").unwrap(), "0000-0002-1694-233X");
+    }
+
+    #[test]
+    fn parse_orcid_strips_uri_and_adds_hyphens() {
+        assert_eq!(parse_orcid("https://orcid.org/0000-0002-1825-0097").unwrap(), "0000-0002-1825-0097");
+        assert_eq!(parse_orcid(" orcid.org/0000000218250097 ").unwrap(), "0000-0002-1825-0097");
+    }
+
+    #[test]
+    fn parse_orcid_rejects_wrong_length_and_letters() {
+        assert!(parse_orcid("0000-0002-1825-009").is_err());
+        assert!(parse_orcid("0000-000X-1825-0097").is_err());
+        assert!(parse_orcid("https://example.org/0000-0002-1825-0097").is_err());
+    }
+}
@@ -0,0 +1,603 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::{prelude::*};
+use serde::{Deserialize, Serialize};
+use diesel::{self, Insertable, PgConnection, Queryable, ExpressionMethods, BoolExpressionMethods};
+use diesel::{RunQueryDsl, QueryDsl, OptionalExtension, PgArrayExpressionMethods, PgExpressionMethods};
+use diesel::upsert::excluded;
+use uuid::Uuid;
+use async_graphql::*;
+
+use crate::graphql::graphql_translate;
+
+use crate::schema::*;
+use crate::errors::CustomError;
+use crate::models::{OrgTier, Person, Role, Team};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum PublicationType {
+    JournalArticle,
+    Preprint,
+    Book,
+    BookChapter,
+    ConferencePaper,
+    Report,
+    Dataset,
+    Other,
+}
+
+impl PublicationType {
+    pub const ALL: [PublicationType; 8] = [
+        PublicationType::JournalArticle,
+        PublicationType::Preprint,
+        PublicationType::Book,
+        PublicationType::BookChapter,
+        PublicationType::ConferencePaper,
+        PublicationType::Report,
+        PublicationType::Dataset,
+        PublicationType::Other,
+    ];
+
+    /// Map an ORCID work type, e.g. journal-article
+    pub fn from_orcid(work_type: &str) -> Self {
+        match work_type {
+            "journal-article" | "review" => PublicationType::JournalArticle,
+            "preprint" | "working-paper" => PublicationType::Preprint,
+            "book" | "edited-book" => PublicationType::Book,
+            "book-chapter" => PublicationType::BookChapter,
+            "conference-paper" | "conference-abstract" | "conference-poster" => PublicationType::ConferencePaper,
+            "report" => PublicationType::Report,
+            "data-set" => PublicationType::Dataset,
+            _ => PublicationType::Other,
+        }
+    }
+}
+
+impl fmt::Display for PublicationType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            PublicationType::JournalArticle => "journal_article",
+            PublicationType::Preprint => "preprint",
+            PublicationType::Book => "book",
+            PublicationType::BookChapter => "book_chapter",
+            PublicationType::ConferencePaper => "conference_paper",
+            PublicationType::Report => "report",
+            PublicationType::Dataset => "dataset",
+            PublicationType::Other => "other",
+        };
+        f.write_str(s)
+    }
+}
+
+impl FromStr for PublicationType {
+    type Err = CustomError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        PublicationType::ALL
+            .iter()
+            .find(|t| t.to_string() == s)
+            .copied()
+            .ok_or_else(|| CustomError::new(400, format!("Unknown publication type: {}", s)))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+/// Unpaywall-style open access categories
+pub enum OpenAccessStatus {
+    Gold,
+    Green,
+    Hybrid,
+    Bronze,
+    Closed,
+    Unknown,
+}
+
+impl OpenAccessStatus {
+    pub fn is_open(&self) -> bool {
+        !matches!(self, OpenAccessStatus::Closed | OpenAccessStatus::Unknown)
+    }
+}
+
+impl fmt::Display for OpenAccessStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            OpenAccessStatus::Gold => "gold",
+            OpenAccessStatus::Green => "green",
+            OpenAccessStatus::Hybrid => "hybrid",
+            OpenAccessStatus::Bronze => "bronze",
+            OpenAccessStatus::Closed => "closed",
+            OpenAccessStatus::Unknown => "unknown",
+        };
+        f.write_str(s)
+    }
+}
+
+impl FromStr for OpenAccessStatus {
+    type Err = CustomError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "gold" => Ok(OpenAccessStatus::Gold),
+            "green" => Ok(OpenAccessStatus::Green),
+            "hybrid" => Ok(OpenAccessStatus::Hybrid),
+            "bronze" => Ok(OpenAccessStatus::Bronze),
+            "closed" => Ok(OpenAccessStatus::Closed),
+            "unknown" => Ok(OpenAccessStatus::Unknown),
+            _ => Err(CustomError::new(400, format!("Unknown open access status: {}", s))),
+        }
+    }
+}
+
+/// Lower case DOI without its resolver prefix, or None if it isn't a DOI
+pub fn normalize_doi(input: &str) -> Option<String> {
+    let trimmed = input.trim();
+    let lower = trimmed.to_lowercase();
+
+    let doi = ["https://doi.org/", "http://doi.org/", "https://dx.doi.org/", "http://dx.doi.org/", "doi:"]
+        .iter()
+        .find_map(|prefix| lower.strip_prefix(prefix))
+        .unwrap_or(&lower)
+        .trim();
+
+    if doi.starts_with("10.") && doi.contains('/') {
+        Some(doi.to_owned())
+    } else {
+        None
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, Queryable, Insertable, AsChangeset, SimpleObject)]
+#[table_name = "publications"]
+/// Referenced by PublicationAuthor
+pub struct Publication {
+    pub id: Uuid,
+    pub title: String,
+    pub doi: Option<String>, // See normalize_doi
+    pub venue: Option<String>, // Journal, conference or publisher
+    pub publication_year: Option<i32>,
+    pub publication_type: String, // PublicationType
+    pub open_access: String, // OpenAccessStatus
+    pub topics: Vec<String>,
+    pub url: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+/// Publication with its authors in author order
+pub struct PublicationWithAuthors {
+    pub publication: Publication,
+    pub authors: Vec<AuthorWithPerson>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PublicationFilter {
+    pub topic: Option<String>,
+    pub year: Option<i32>,
+    pub publication_type: Option<String>,
+}
+
+// Non Graphql
+impl Publication {
+    pub fn create(conn: &PgConnection, publication: &NewPublication) -> FieldResult<Publication> {
+        let res = diesel::insert_into(publications::table)
+            .values(publication)
+            .get_result(conn);
+
+        graphql_translate(res)
+    }
+
+    /// Natural key: doi if present, otherwise title and year.
+    /// Returns the existing publication unchanged if the key matches.
+    pub fn get_or_create(conn: &PgConnection, publication: &NewPublication) -> FieldResult<Publication> {
+        if publication.doi.is_some() {
+            let res = diesel::insert_into(publications::table)
+                .values(publication)
+                .on_conflict(publications::doi)
+                .do_update()
+                .set(publications::doi.eq(excluded(publications::doi)))
+                .get_result(conn);
+
+            return graphql_translate(res)
+        };
+
+        let existing = publications::table
+            .filter(publications::title.eq(&publication.title))
+            .filter(publications::publication_year.is_not_distinct_from(publication.publication_year))
+            .first::<Publication>(conn)
+            .optional()?;
+
+        match existing {
+            Some(p) => Ok(p),
+            None => Publication::create(conn, publication),
+        }
+    }
+
+    pub fn find(conn: &PgConnection, id: Uuid) -> Result<Self, CustomError> {
+        let publication = publications::table.find(id).first(conn)?;
+        Ok(publication)
+    }
+
+    pub fn find_by_doi(conn: &PgConnection, doi: &str) -> Result<Option<Self>, CustomError> {
+        let publication = publications::table
+            .filter(publications::doi.eq(doi))
+            .first(conn)
+            .optional()?;
+        Ok(publication)
+    }
+
+    /// Most recent first
+    pub fn find_filtered(conn: &PgConnection, filter: &PublicationFilter) -> Result<Vec<Self>, CustomError> {
+        let mut query = publications::table.into_boxed();
+
+        if let Some(topic) = &filter.topic {
+            query = query.filter(publications::topics.contains(vec![topic.to_owned()]));
+        };
+
+        if let Some(year) = filter.year {
+            query = query.filter(publications::publication_year.eq(year));
+        };
+
+        if let Some(publication_type) = &filter.publication_type {
+            query = query.filter(publications::publication_type.eq(publication_type));
+        };
+
+        let res = query
+            .order((publications::publication_year.is_null(), publications::publication_year.desc(), publications::title.asc()))
+            .load::<Publication>(conn)?;
+
+        Ok(res)
+    }
+
+    pub fn find_by_person(conn: &PgConnection, person_id: Uuid) -> Result<Vec<Self>, CustomError> {
+        let res = publications::table
+            .inner_join(publication_authors::table)
+            .filter(publication_authors::person_id.eq(person_id))
+            .select(publications::all_columns)
+            .order((publications::publication_year.is_null(), publications::publication_year.desc(), publications::title.asc()))
+            .load::<Publication>(conn)?;
+
+        Ok(res)
+    }
+
+    pub fn with_authors(self, conn: &PgConnection) -> Result<PublicationWithAuthors, CustomError> {
+        let authors = PublicationAuthor::find_by_publication(conn, self.id)?;
+
+        Ok(PublicationWithAuthors {
+            publication: self,
+            authors,
+        })
+    }
+
+    pub fn update(&self, conn: &PgConnection) -> FieldResult<Self> {
+        let res = diesel::update(publications::table)
+            .filter(publications::id.eq(&self.id))
+            .set(self)
+            .get_result(conn)?;
+
+        Ok(res)
+    }
+
+    pub fn is_open_access(&self) -> bool {
+        OpenAccessStatus::from_str(&self.open_access)
+            .map(|s| s.is_open())
+            .unwrap_or(false)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, Insertable)]
+#[table_name = "publications"]
+pub struct NewPublication {
+    pub title: String,
+    pub doi: Option<String>,
+    pub venue: Option<String>,
+    pub publication_year: Option<i32>,
+    pub publication_type: String,
+    pub open_access: String,
+    pub topics: Vec<String>,
+    pub url: Option<String>,
+}
+
+impl NewPublication {
+    pub fn new(
+        title: String,
+        doi: Option<String>,
+        venue: Option<String>,
+        publication_year: Option<i32>,
+        publication_type: PublicationType,
+        open_access: OpenAccessStatus,
+        topics: Vec<String>,
+        url: Option<String>,
+    ) -> Self {
+        NewPublication {
+            title,
+            doi: doi.as_deref().and_then(normalize_doi),
+            venue,
+            publication_year,
+            publication_type: publication_type.to_string(),
+            open_access: open_access.to_string(),
+            topics,
+            url,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, Queryable, Insertable, AsChangeset, SimpleObject)]
+#[table_name = "publication_authors"]
+/// Authorship of a Publication by a Person, or by an external co-author
+/// when person_id is None
+pub struct PublicationAuthor {
+    pub id: Uuid,
+    pub publication_id: Uuid,
+    pub person_id: Option<Uuid>, // Person
+    pub author_name: String, // As printed on the publication
+    pub author_order: Option<i32>, // 1 for first author, None if unknown
+    pub external_affiliation: Option<String>,
+    pub external_orcid: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct AuthorWithPerson {
+    pub author: PublicationAuthor,
+    pub person: Option<Person>,
+}
+
+impl PublicationAuthor {
+    pub fn create(conn: &PgConnection, author: &NewPublicationAuthor) -> FieldResult<PublicationAuthor> {
+        let res = diesel::insert_into(publication_authors::table)
+            .values(author)
+            .get_result(conn);
+
+        graphql_translate(res)
+    }
+
+    /// Link a Person as an author, keeping an existing link unchanged.
+    /// An external author at the same position is matched to the person.
+    pub fn link_person(conn: &PgConnection, publication_id: Uuid, person: &Person, author_order: Option<i32>) -> FieldResult<PublicationAuthor> {
+        let existing = publication_authors::table
+            .filter(publication_authors::publication_id.eq(publication_id))
+            .filter(publication_authors::person_id.eq(person.id))
+            .first::<PublicationAuthor>(conn)
+            .optional()?;
+
+        if let Some(author) = existing {
+            return Ok(author)
+        };
+
+        if let Some(order) = author_order {
+            let res = diesel::update(publication_authors::table)
+                .filter(publication_authors::publication_id.eq(publication_id))
+                .filter(publication_authors::author_order.eq(order))
+                .filter(publication_authors::person_id.is_null())
+                .set((
+                    publication_authors::person_id.eq(person.id),
+                    publication_authors::updated_at.eq(Utc::now().naive_utc()),
+                ))
+                .get_result::<PublicationAuthor>(conn)
+                .optional()?;
+
+            if let Some(author) = res {
+                return Ok(author)
+            };
+        };
+
+        PublicationAuthor::create(conn, &NewPublicationAuthor::new(
+            publication_id,
+            Some(person.id),
+            format!("{} {}", person.given_name, person.family_name),
+            author_order,
+            None,
+            None,
+        ))
+    }
+
+    /// Authors in order. Postgres sorts authors of unknown position last.
+    pub fn find_by_publication(conn: &PgConnection, publication_id: Uuid) -> Result<Vec<AuthorWithPerson>, CustomError> {
+        let res = publication_authors::table
+            .left_join(persons::table)
+            .filter(publication_authors::publication_id.eq(publication_id))
+            .order((publication_authors::author_order.asc(), publication_authors::author_name.asc()))
+            .load::<(PublicationAuthor, Option<Person>)>(conn)?;
+
+        Ok(res.into_iter()
+            .map(|(author, person)| AuthorWithPerson { author, person })
+            .collect())
+    }
+
+    pub fn update(&self, conn: &PgConnection) -> FieldResult<Self> {
+        let res = diesel::update(publication_authors::table)
+            .filter(publication_authors::id.eq(&self.id))
+            .set(self)
+            .get_result(conn)?;
+
+        Ok(res)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, Insertable)]
+#[table_name = "publication_authors"]
+pub struct NewPublicationAuthor {
+    pub publication_id: Uuid,
+    pub person_id: Option<Uuid>,
+    pub author_name: String,
+    pub author_order: Option<i32>,
+    pub external_affiliation: Option<String>,
+    pub external_orcid: Option<String>,
+}
+
+impl NewPublicationAuthor {
+    pub fn new(
+        publication_id: Uuid,
+        person_id: Option<Uuid>,
+        author_name: String,
+        author_order: Option<i32>,
+        external_affiliation: Option<String>,
+        external_orcid: Option<String>,
+    ) -> Self {
+        NewPublicationAuthor {
+            publication_id,
+            person_id,
+            author_name,
+            author_order,
+            external_affiliation,
+            external_orcid,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct YearOutput {
+    pub year: i32,
+    pub publications: usize,
+    pub open_access: usize,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+/// Publication counts for a Team or OrgTier, one entry per year
+pub struct UnitOutput {
+    pub id: Uuid,
+    pub name_en: String,
+    pub name_fr: String,
+    pub years: Vec<YearOutput>,
+    pub total: usize,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+/// Distinct publications by members of each team and org tier per year.
+/// A publication counts for a team if an author held a role on it during
+/// the publication year, and for that team's tier and every tier above it.
+pub struct PublicationOutput {
+    pub organization_id: Uuid,
+    pub years: Vec<i32>,
+    pub teams: Vec<UnitOutput>,
+    pub org_tiers: Vec<UnitOutput>,
+}
+
+impl PublicationOutput {
+    pub fn build(conn: &PgConnection, organization_id: Uuid, from_year: i32, to_year: i32) -> Result<PublicationOutput, CustomError> {
+        let teams = teams::table
+            .filter(teams::organization_id.eq(organization_id))
+            .order(teams::name_en.asc())
+            .load::<Team>(conn)?;
+
+        let tiers = org_tiers::table
+            .filter(org_tiers::organization_id.eq(organization_id))
+            .order((org_tiers::tier_level.asc(), org_tiers::name_en.asc()))
+            .load::<OrgTier>(conn)?;
+
+        let team_ids: Vec<Uuid> = teams.iter().map(|t| t.id).collect();
+
+        let authored = publication_authors::table
+            .inner_join(publications::table)
+            .filter(publications::publication_year.between(from_year, to_year))
+            .filter(publication_authors::person_id.is_not_null())
+            .select((publication_authors::person_id, publications::all_columns))
+            .load::<(Option<Uuid>, Publication)>(conn)?;
+
+        let person_ids: Vec<Uuid> = authored.iter().filter_map(|(p, _)| *p).collect();
+
+        let roles = roles::table
+            .filter(roles::person_id.eq_any(&person_ids))
+            .filter(roles::team_id.eq_any(&team_ids))
+            .filter(roles::start_datestamp.lt(NaiveDate::from_ymd(to_year + 1, 1, 1).and_hms(0, 0, 0)))
+            .filter(roles::end_date.is_null().or(roles::end_date.ge(NaiveDate::from_ymd(from_year, 1, 1).and_hms(0, 0, 0))))
+            .load::<Role>(conn)?;
+
+        let mut roles_by_person: HashMap<Uuid, Vec<Role>> = HashMap::new();
+
+        for role in roles {
+            roles_by_person.entry(role.person_id).or_insert_with(Vec::new).push(role);
+        };
+
+        let team_tier: HashMap<Uuid, Uuid> = teams.iter().map(|t| (t.id, t.org_tier_id)).collect();
+        let tier_parent: HashMap<Uuid, Option<Uuid>> = tiers.iter().map(|t| (t.id, t.parent_tier)).collect();
+
+        let mut open: HashSet<Uuid> = HashSet::new();
+        let mut by_team: HashMap<(Uuid, i32), HashSet<Uuid>> = HashMap::new();
+        let mut by_tier: HashMap<(Uuid, i32), HashSet<Uuid>> = HashMap::new();
+
+        for (person_id, publication) in &authored {
+            let year = match publication.publication_year {
+                Some(y) => y,
+                None => continue,
+            };
+
+            if publication.is_open_access() {
+                open.insert(publication.id);
+            };
+
+            let year_start = NaiveDate::from_ymd(year, 1, 1).and_hms(0, 0, 0);
+            let year_end = NaiveDate::from_ymd(year + 1, 1, 1).and_hms(0, 0, 0);
+
+            let person_roles = person_id.and_then(|p| roles_by_person.get(&p));
+
+            for role in person_roles.into_iter().flatten() {
+                let held_in_year = role.start_datestamp < year_end
+                    && role.end_date.map(|e| e >= year_start).unwrap_or(true);
+
+                if !held_in_year {
+                    continue
+                };
+
+                by_team.entry((role.team_id, year)).or_insert_with(HashSet::new).insert(publication.id);
+
+                // Walk up the tier hierarchy, guarding against cycles
+                let mut visited = HashSet::new();
+                let mut tier = team_tier.get(&role.team_id).copied();
+
+                while let Some(tier_id) = tier {
+                    if !visited.insert(tier_id) {
+                        break
+                    };
+
+                    by_tier.entry((tier_id, year)).or_insert_with(HashSet::new).insert(publication.id);
+                    tier = tier_parent.get(&tier_id).copied().flatten();
+                };
+            };
+        };
+
+        let years: Vec<i32> = (from_year..=to_year).collect();
+
+        let rollup = |id: Uuid, name_en: &str, name_fr: &str, counts: &HashMap<(Uuid, i32), HashSet<Uuid>>| {
+            let mut all: HashSet<Uuid> = HashSet::new();
+
+            let years = years.iter()
+                .map(|year| {
+                    let publications = counts.get(&(id, *year));
+                    all.extend(publications.into_iter().flatten());
+
+                    YearOutput {
+                        year: *year,
+                        publications: publications.map(|p| p.len()).unwrap_or(0),
+                        open_access: publications.map(|p| p.iter().filter(|id| open.contains(id)).count()).unwrap_or(0),
+                    }
+                })
+                .collect();
+
+            UnitOutput {
+                id,
+                name_en: name_en.to_owned(),
+                name_fr: name_fr.to_owned(),
+                years,
+                total: all.len(),
+            }
+        };
+
+        Ok(PublicationOutput {
+            organization_id,
+            teams: teams.iter()
+                .map(|t| rollup(t.id, &t.name_en, &t.name_fr, &by_team))
+                .filter(|u| u.total > 0)
+                .collect(),
+            org_tiers: tiers.iter()
+                .map(|t| rollup(t.id, &t.name_en, &t.name_fr, &by_tier))
+                .filter(|u| u.total > 0)
+                .collect(),
+            years,
+        })
+    }
+}
@@ -3,16 +3,17 @@ use std::fmt::Debug;
 use chrono::{prelude::*};
 use serde::{Deserialize, Serialize};
 use diesel::{self, Insertable, PgConnection, Queryable, ExpressionMethods};
-use diesel::{RunQueryDsl, QueryDsl};
+use diesel::{RunQueryDsl, QueryDsl, BoolExpressionMethods};
 use uuid::Uuid;
 use async_graphql::*;
-use rand::{Rng, thread_rng};
 
 use crate::graphql::graphql_translate;
 
 use crate::schema::*;
+use crate::errors::CustomError;
+use crate::database::connection;
 
-#[derive(Debug, Clone, Deserialize, Serialize, Queryable, Insertable, AsChangeset)]
+#[derive(Debug, Clone, Deserialize, Serialize, Queryable, Insertable, AsChangeset, SimpleObject)]
 #[table_name = "reporting_relationships"]
 /// Data structure connecting persons in heirarchical relationship
 pub struct ReportingRelationship {
@@ -20,10 +21,10 @@ pub struct ReportingRelationship {
     pub reporter: Uuid, // Person
     pub reporting_to: Uuid, // Person
     pub description: String,
-    pub start_date: NaiveDate,
-    pub end_date: Option<NaiveDate>,
-    pub created_at: NaiveDate,
-    pub updated_at: NaiveDate,
+    pub start_date: NaiveDateTime,
+    pub end_date: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
 }
 
 
@@ -33,46 +34,50 @@ impl ReportingRelationship {
         let res = diesel::insert_into(reporting_relationships::table)
         .values(reporting_relationship)
         .get_result(conn);
-        
+
         graphql_translate(res)
     }
-    
-    pub fn get_or_create(conn: &PgConnection, reporting_relationship: &NewReportingRelationship) -> FieldResult<ReportingRelationship> {
-        let res = reporting_relationships::table
-        .filter(reporting_relationships::family_name.eq(&reporting_relationship.family_name))
-        .distinct()
-        .first(conn);
-        
-        let reporting_relationship = match res {
-            Ok(p) => p,
-            Err(e) => {
-                // ReportingRelationship not found
-                println!("{:?}", e);
-                let p = ReportingRelationship::create(conn, reporting_relationship).expect("Unable to create reporting_relationship");
-                p
-            }
-        };
-        Ok(reporting_relationship)
-    }
 
     pub fn find_all() -> Result<Vec<Self>, CustomError> {
-        let conn = database::connection()?;
-        let persons = reporting_relationships::table.load::<ReportingRelationship>(&conn)?;
-        Ok(persons)
+        let conn = connection()?;
+        let relationships = reporting_relationships::table.load::<ReportingRelationship>(&conn)?;
+        Ok(relationships)
     }
 
     pub fn find(id: Uuid) -> Result<Self, CustomError> {
-        let conn = database::connection()?;
-        let person = reporting_relationships::table.filter(reporting_relationships::id.eq(id)).first(&conn)?;
-        Ok(person)
+        let conn = connection()?;
+        let relationship = reporting_relationships::table.filter(reporting_relationships::id.eq(id)).first(&conn)?;
+        Ok(relationship)
+    }
+
+    /// Who the person reports to on as_of
+    pub fn find_current_by_reporter(conn: &PgConnection, reporter: Uuid, as_of: NaiveDateTime) -> FieldResult<Vec<Self>> {
+        let res = reporting_relationships::table
+            .filter(reporting_relationships::reporter.eq(reporter))
+            .filter(reporting_relationships::start_date.le(as_of))
+            .filter(reporting_relationships::end_date.is_null().or(reporting_relationships::end_date.gt(as_of)))
+            .load::<Self>(conn);
+
+        graphql_translate(res)
     }
-    
+
+    /// Direct reports of the person on as_of
+    pub fn find_current_by_manager(conn: &PgConnection, reporting_to: Uuid, as_of: NaiveDateTime) -> FieldResult<Vec<Self>> {
+        let res = reporting_relationships::table
+            .filter(reporting_relationships::reporting_to.eq(reporting_to))
+            .filter(reporting_relationships::start_date.le(as_of))
+            .filter(reporting_relationships::end_date.is_null().or(reporting_relationships::end_date.gt(as_of)))
+            .load::<Self>(conn);
+
+        graphql_translate(res)
+    }
+
     pub fn update(&self, conn: &PgConnection) -> FieldResult<Self> {
         let res = diesel::update(reporting_relationships::table)
         .filter(reporting_relationships::id.eq(&self.id))
         .set(self)
         .get_result(conn)?;
-        
+
         Ok(res)
     }
 }
@@ -83,33 +88,25 @@ pub struct NewReportingRelationship {
     pub reporter: Uuid, // Person
     pub reporting_to: Uuid, // Person
     pub description: String,
-    pub start_date: NaiveDate,
-    pub end_date: Option<NaiveDate>,
-    pub created_at: NaiveDate,
-    pub updated_at: NaiveDate,
+    pub start_date: NaiveDateTime,
+    pub end_date: Option<NaiveDateTime>,
 }
 
 impl NewReportingRelationship {
 
     pub fn new(
-        id: Uuid,
         reporter: Uuid, // Person
         reporting_to: Uuid, // Person
         description: String,
-        start_date: NaiveDate,
-        end_date: Option<NaiveDate>,
-        created_at: NaiveDate,
-        updated_at: NaiveDate,
+        start_date: NaiveDateTime,
+        end_date: Option<NaiveDateTime>,
     ) -> Self {
         NewReportingRelationship {
-            id,
             reporter,
             reporting_to,
             description,
             start_date,
             end_date,
-            created_at,
-            updated_at,
         }
     }
 }
@@ -1,9 +1,11 @@
-use std::fmt::Debug;
+use std::fmt::{self, Debug};
+use std::str::FromStr;
 
 use chrono::{prelude::*};
 use serde::{Deserialize, Serialize};
-use diesel::{self, Insertable, PgConnection, Queryable, ExpressionMethods};
-use diesel::{RunQueryDsl, QueryDsl};
+use diesel::{self, Insertable, PgConnection, Queryable, ExpressionMethods, BoolExpressionMethods};
+use diesel::upsert::excluded;
+use diesel::{RunQueryDsl, QueryDsl, Connection, OptionalExtension};
 use uuid::Uuid;
 use async_graphql::*;
 use rand::{Rng, thread_rng};
@@ -13,8 +15,9 @@ use crate::graphql::graphql_translate;
 use crate::schema::*;
 use crate::errors::CustomError;
 use crate::database::connection;
+use crate::models::{NewRoleEvent, RoleEvent, RoleEventType, Team};
 
-#[derive(Debug, Clone, Deserialize, Serialize, Queryable, Insertable, AsChangeset)]
+#[derive(Debug, Clone, Deserialize, Serialize, Queryable, Insertable, AsChangeset, SimpleObject)]
 #[table_name = "roles"]
 /// Intermediary data structure between Person and team
 /// Referenced by Person
@@ -30,8 +33,117 @@ pub struct Role {
     pub end_date: Option<NaiveDateTime>,
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
+    pub role_type: String, // RoleType
+    pub home_role_id: Option<Uuid>, // Substantive Role held while acting or seconded
+    pub hr_position_number: Option<String>, // HRMS position
+    pub classification: Option<String>, // e.g. EC-05
+    pub language_requirement: Option<String>, // LanguageRequirement
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+/// Substantive roles are open-ended. Acting, secondment and surge
+/// deployment roles are temporary and must have an end_date.
+pub enum RoleType {
+    Substantive,
+    Acting,
+    Secondment,
+    Deployment, // Surge deployment to an emergency event, see SurgeEvent
+}
+
+impl RoleType {
+    pub fn is_temporary(&self) -> bool {
+        *self != RoleType::Substantive
+    }
+
+    /// Deployments are held alongside the substantive role rather than replacing it
+    pub fn suspends_home_role(&self) -> bool {
+        *self == RoleType::Acting || *self == RoleType::Secondment
+    }
+
+    /// RoleEvent recorded when a role of this type starts
+    pub fn start_event_type(&self) -> RoleEventType {
+        match self {
+            RoleType::Acting => RoleEventType::ActingStarted,
+            RoleType::Secondment => RoleEventType::SecondmentStarted,
+            RoleType::Deployment => RoleEventType::DeploymentStarted,
+            RoleType::Substantive => RoleEventType::Started,
+        }
+    }
+
+    /// RoleEvent recorded when a role of this type ends
+    pub fn end_event_type(&self) -> RoleEventType {
+        match self {
+            RoleType::Acting => RoleEventType::ActingEnded,
+            RoleType::Secondment => RoleEventType::SecondmentEnded,
+            RoleType::Deployment => RoleEventType::DeploymentEnded,
+            RoleType::Substantive => RoleEventType::Ended,
+        }
+    }
+}
+
+impl fmt::Display for RoleType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            RoleType::Substantive => "substantive",
+            RoleType::Acting => "acting",
+            RoleType::Secondment => "secondment",
+            RoleType::Deployment => "deployment",
+        };
+        f.write_str(s)
+    }
+}
+
+impl FromStr for RoleType {
+    type Err = CustomError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "substantive" => Ok(RoleType::Substantive),
+            "acting" => Ok(RoleType::Acting),
+            "secondment" => Ok(RoleType::Secondment),
+            "deployment" => Ok(RoleType::Deployment),
+            _ => Err(CustomError::new(400, format!("Unknown role type: {}", s))),
+        }
+    }
+}
+
+
+#[derive(Debug, Clone, Deserialize, Serialize, InputObject)]
+/// A role to start through the GraphQL lifecycle mutations
+pub struct RoleAssignment {
+    pub person_id: Uuid,
+    pub team_id: Uuid,
+    pub title_en: String,
+    pub title_fr: String,
+    pub effort: f64,
+    pub start_date: NaiveDateTime,
+    pub end_date: Option<NaiveDateTime>, // Required for temporary roles
+}
+
+impl RoleAssignment {
+    pub fn new_role(&self, role_type: RoleType) -> NewRole {
+        NewRole::new(
+            Uuid::new_v4(),
+            self.person_id,
+            self.team_id,
+            self.title_en.trim().to_owned(),
+            self.title_fr.trim().to_owned(),
+            self.effort,
+            true,
+            self.start_date,
+            self.end_date,
+            role_type,
+            None,
+        )
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+/// Role with its Team for display
+pub struct RoleWithTeam {
+    pub role: Role,
+    pub team: Team,
+}
 
 // Non Graphql
 impl Role {
@@ -43,22 +155,29 @@ impl Role {
         graphql_translate(res)
     }
     
+    /// Natural key: (person_id, team_id, start_datestamp, role_type, title_en)
+    /// Returns the existing role unchanged if the key matches.
     pub fn get_or_create(conn: &PgConnection, role: &NewRole) -> FieldResult<Role> {
-        let res = roles::table
-        .filter(roles::person_id.eq(&role.person_id))
-        .distinct()
-        .first(conn);
-        
-        let role = match res {
-            Ok(p) => p,
-            Err(e) => {
-                // Role not found
-                println!("{:?}", e);
-                let p = Role::create(conn, role).expect("Unable to create role");
-                p
-            }
-        };
-        Ok(role)
+        let res = diesel::insert_into(roles::table)
+            .values(role)
+            .on_conflict((roles::person_id, roles::team_id, roles::start_datestamp, roles::role_type, roles::title_en))
+            .do_update()
+            .set(roles::person_id.eq(excluded(roles::person_id)))
+            .get_result(conn);
+
+        graphql_translate(res)
+    }
+
+    /// The role get_or_create would return for this natural key, if it exists
+    pub fn find_by_natural_key(conn: &PgConnection, role: &NewRole) -> Result<Option<Role>, diesel::result::Error> {
+        roles::table
+            .filter(roles::person_id.eq(role.person_id))
+            .filter(roles::team_id.eq(role.team_id))
+            .filter(roles::start_datestamp.eq(role.start_datestamp))
+            .filter(roles::role_type.eq(&role.role_type))
+            .filter(roles::title_en.eq(&role.title_en))
+            .first(conn)
+            .optional()
     }
 
     pub fn find_all() -> Result<Vec<Self>, CustomError> {
@@ -81,6 +200,289 @@ impl Role {
         
         Ok(res)
     }
+
+    pub fn find_active_by_person(conn: &PgConnection, person_id: Uuid) -> FieldResult<Vec<Role>> {
+        let res = roles::table
+            .filter(roles::person_id.eq(person_id))
+            .filter(roles::active.eq(true))
+            .order(roles::start_datestamp.desc())
+            .load::<Role>(conn);
+
+        graphql_translate(res)
+    }
+
+    pub fn find_by_person(conn: &PgConnection, person_id: Uuid) -> FieldResult<Vec<Role>> {
+        let res = roles::table
+            .filter(roles::person_id.eq(person_id))
+            .order(roles::start_datestamp.desc())
+            .load::<Role>(conn);
+
+        graphql_translate(res)
+    }
+
+    pub fn find_by_person_with_teams(conn: &PgConnection, person_id: Uuid) -> Result<Vec<RoleWithTeam>, CustomError> {
+        let res = roles::table
+            .inner_join(teams::table)
+            .filter(roles::person_id.eq(person_id))
+            .order(roles::start_datestamp.desc())
+            .load::<(Role, Team)>(conn)?;
+
+        Ok(res.into_iter()
+            .map(|(role, team)| RoleWithTeam { role, team })
+            .collect())
+    }
+
+    /// A role is current if it has not reached its end date, even while
+    /// suspended by an acting assignment or secondment
+    pub fn is_current(&self, as_of: NaiveDateTime) -> bool {
+        self.start_datestamp <= as_of && self.end_date.map_or(true, |d| d > as_of)
+    }
+
+    pub fn get_role_type(&self) -> RoleType {
+        RoleType::from_str(&self.role_type).unwrap_or(RoleType::Substantive)
+    }
+
+    /// Move a person to a new substantive role in one transaction. On the new role's
+    /// start date this closes their current substantive role(s), including a home role
+    /// suspended by an acting assignment or secondment, and that acting assignment or
+    /// secondment. Surge deployments carry on, as they do alongside any substantive role.
+    pub fn transfer(conn: &PgConnection, new_role: &NewRole, note: &str) -> FieldResult<Role> {
+        let mut new_role = new_role.clone();
+        new_role.role_type = RoleType::Substantive.to_string();
+        new_role.home_role_id = None;
+        new_role.active = true;
+
+        let res = conn.transaction::<Role, diesel::result::Error, _>(|| {
+            // Active substantive roles and suspended home roles, which stay open without an end date
+            let previous_roles: Vec<Role> = roles::table
+                .filter(roles::person_id.eq(new_role.person_id))
+                .filter(roles::role_type.eq(RoleType::Substantive.to_string()))
+                .filter(roles::active.eq(true).or(roles::end_date.is_null()))
+                .order(roles::active.desc())
+                .load(conn)?;
+
+            let temporary_roles: Vec<Role> = roles::table
+                .filter(roles::person_id.eq(new_role.person_id))
+                .filter(roles::active.eq(true))
+                .filter(roles::role_type.eq_any(vec![
+                    RoleType::Acting.to_string(),
+                    RoleType::Secondment.to_string(),
+                ]))
+                .load(conn)?;
+
+            let role: Role = diesel::insert_into(roles::table)
+                .values(&new_role)
+                .get_result(conn)?;
+
+            for previous in temporary_roles.iter().chain(previous_roles.iter()) {
+                // A temporary role may already be past its end date and waiting to expire
+                let end_date = previous.end_date
+                    .map_or(new_role.start_datestamp, |d| d.min(new_role.start_datestamp));
+
+                close_role(conn, previous, end_date)?;
+
+                RoleEvent::record(conn, &NewRoleEvent::new(
+                    previous.person_id,
+                    previous.id,
+                    Some(role.id),
+                    previous.get_role_type().end_event_type(),
+                    note.to_string(),
+                    end_date,
+                ))?;
+            };
+
+            let event_type = if previous_roles.is_empty() {
+                RoleEventType::Started
+            } else {
+                RoleEventType::Transferred
+            };
+
+            RoleEvent::record(conn, &NewRoleEvent::new(
+                role.person_id,
+                role.id,
+                previous_roles.first().map(|r| r.id),
+                event_type,
+                note.to_string(),
+                role.start_datestamp,
+            ))?;
+
+            Ok(role)
+        });
+
+        graphql_translate(res)
+    }
+
+    /// Start an acting assignment, secondment or surge deployment. For acting
+    /// and secondment the person's current substantive role is suspended and
+    /// restored when the temporary role ends.
+    pub fn assign_temporary(conn: &PgConnection, new_role: &NewRole, role_type: RoleType, note: &str) -> FieldResult<Role> {
+        if !role_type.is_temporary() {
+            return Err(FieldError::new("Use Role::transfer for substantive roles"));
+        };
+
+        let end_date = match new_role.end_date {
+            Some(d) if d > new_role.start_datestamp => d,
+            _ => return Err(FieldError::new("Temporary roles require an end date after the start date")),
+        };
+
+        let mut new_role = new_role.clone();
+        new_role.role_type = role_type.to_string();
+        new_role.end_date = Some(end_date);
+        new_role.active = true;
+
+        let res = conn.transaction::<Role, diesel::result::Error, _>(|| {
+            let home_role: Option<Role> = roles::table
+                .filter(roles::person_id.eq(new_role.person_id))
+                .filter(roles::active.eq(true))
+                .filter(roles::role_type.eq(RoleType::Substantive.to_string()))
+                .order(roles::start_datestamp.desc())
+                .first(conn)
+                .optional()?;
+
+            new_role.home_role_id = home_role.as_ref().map(|r| r.id);
+
+            let role: Role = diesel::insert_into(roles::table)
+                .values(&new_role)
+                .get_result(conn)?;
+
+            if let Some(home) = home_role.as_ref().filter(|_| role_type.suspends_home_role()) {
+                // Suspend the substantive role without ending it
+                diesel::update(roles::table)
+                    .filter(roles::id.eq(home.id))
+                    .set((
+                        roles::active.eq(false),
+                        roles::updated_at.eq(Utc::now().naive_utc()),
+                    ))
+                    .execute(conn)?;
+            };
+
+            RoleEvent::record(conn, &NewRoleEvent::new(
+                role.person_id,
+                role.id,
+                role.home_role_id,
+                role_type.start_event_type(),
+                note.to_string(),
+                role.start_datestamp,
+            ))?;
+
+            Ok(role)
+        });
+
+        graphql_translate(res)
+    }
+
+    /// End a role on end_date. Ending an acting or secondment role
+    /// reactivates the substantive role it suspended.
+    pub fn end_role(&self, conn: &PgConnection, end_date: NaiveDateTime, note: &str) -> FieldResult<Role> {
+        if !self.active && self.end_date.is_some() {
+            return Err(FieldError::new("Role has already ended"));
+        };
+
+        let res = conn.transaction::<Role, diesel::result::Error, _>(|| {
+            let role = close_role(conn, self, end_date)?;
+
+            let role_type = self.get_role_type();
+
+            if role_type.suspends_home_role() {
+                if let Some(home_id) = self.home_role_id {
+                    // Only restore the home role if it hasn't been closed in the meantime
+                    diesel::update(roles::table)
+                        .filter(roles::id.eq(home_id))
+                        .filter(roles::end_date.is_null())
+                        .set((
+                            roles::active.eq(true),
+                            roles::updated_at.eq(Utc::now().naive_utc()),
+                        ))
+                        .execute(conn)?;
+                };
+            };
+
+            RoleEvent::record(conn, &NewRoleEvent::new(
+                role.person_id,
+                role.id,
+                role.home_role_id,
+                role_type.end_event_type(),
+                note.to_string(),
+                end_date,
+            ))?;
+
+            Ok(role)
+        });
+
+        graphql_translate(res)
+    }
+
+    /// Record a role that has already ended, e.g. from a historical import, with its
+    /// start and end events. The person's current roles are left as they are.
+    pub fn record_past(conn: &PgConnection, new_role: &NewRole, note: &str) -> FieldResult<Role> {
+        let end_date = match new_role.end_date {
+            Some(d) if d >= new_role.start_datestamp => d,
+            _ => return Err(FieldError::new("Past roles require an end date on or after the start date")),
+        };
+
+        let mut new_role = new_role.clone();
+        new_role.active = false;
+
+        let res = conn.transaction::<Role, diesel::result::Error, _>(|| {
+            let role: Role = diesel::insert_into(roles::table)
+                .values(&new_role)
+                .get_result(conn)?;
+
+            let role_type = role.get_role_type();
+
+            RoleEvent::record(conn, &NewRoleEvent::new(
+                role.person_id,
+                role.id,
+                role.home_role_id,
+                role_type.start_event_type(),
+                note.to_string(),
+                role.start_datestamp,
+            ))?;
+
+            RoleEvent::record(conn, &NewRoleEvent::new(
+                role.person_id,
+                role.id,
+                role.home_role_id,
+                role_type.end_event_type(),
+                note.to_string(),
+                end_date,
+            ))?;
+
+            Ok(role)
+        });
+
+        graphql_translate(res)
+    }
+
+    /// End every active temporary role whose end_date has passed.
+    pub fn expire_temporary_roles(conn: &PgConnection, as_of: NaiveDateTime) -> FieldResult<Vec<Role>> {
+        let expired: Vec<Role> = graphql_translate(roles::table
+            .filter(roles::active.eq(true))
+            .filter(roles::role_type.ne(RoleType::Substantive.to_string()))
+            .filter(roles::end_date.le(as_of))
+            .load::<Role>(conn))?;
+
+        let mut ended = Vec::new();
+
+        for role in expired {
+            let end_date = role.end_date.unwrap_or(as_of);
+            ended.push(role.end_role(conn, end_date, "Temporary assignment expired")?);
+        };
+
+        Ok(ended)
+    }
+}
+
+/// Set a role inactive with an end date
+fn close_role(conn: &PgConnection, role: &Role, end_date: NaiveDateTime) -> Result<Role, diesel::result::Error> {
+    diesel::update(roles::table)
+        .filter(roles::id.eq(role.id))
+        .set((
+            roles::active.eq(false),
+            roles::end_date.eq(Some(end_date)),
+            roles::updated_at.eq(Utc::now().naive_utc()),
+        ))
+        .get_result(conn)
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, Insertable, SimpleObject)]
@@ -95,6 +497,8 @@ pub struct NewRole {
     pub active: bool,
     pub start_datestamp: NaiveDateTime,
     pub end_date: Option<NaiveDateTime>,
+    pub role_type: String, // RoleType
+    pub home_role_id: Option<Uuid>,
 }
 
 impl NewRole {
@@ -109,6 +513,8 @@ impl NewRole {
         active: bool,
         start_datestamp: NaiveDateTime,
         end_date: Option<NaiveDateTime>,
+        role_type: RoleType,
+        home_role_id: Option<Uuid>,
     ) -> Self {
         NewRole {
             id,
@@ -120,6 +526,8 @@ impl NewRole {
             active,
             start_datestamp,
             end_date,
+            role_type: role_type.to_string(),
+            home_role_id,
         }
     }
 }
@@ -0,0 +1,141 @@
+use std::fmt::{self, Debug};
+
+use chrono::{prelude::*};
+use serde::{Deserialize, Serialize};
+use diesel::{self, Insertable, PgConnection, Queryable, ExpressionMethods};
+use diesel::{RunQueryDsl, QueryDsl};
+use uuid::Uuid;
+use async_graphql::*;
+
+use crate::graphql::graphql_translate;
+
+use crate::schema::*;
+use crate::errors::CustomError;
+use crate::models::{Role, Team};
+
+#[derive(Debug, Clone, Deserialize, Serialize, Queryable, Insertable, SimpleObject)]
+#[table_name = "role_events"]
+/// Immutable record of a change to a Person's Roles
+/// Used to reconstruct a Person's timeline
+pub struct RoleEvent {
+    pub id: Uuid,
+    pub person_id: Uuid,
+    pub role_id: Uuid,
+    pub related_role_id: Option<Uuid>, // Previous, next or home Role
+    pub event_type: String, // RoleEventType
+    pub note: String,
+    pub event_date: NaiveDateTime,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+/// RoleEvent with its Role and Team for display
+pub struct RoleEventWithRole {
+    pub event: RoleEvent,
+    pub role: Role,
+    pub team: Team,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum RoleEventType {
+    Started,
+    Transferred,
+    Ended,
+    ActingStarted,
+    ActingEnded,
+    SecondmentStarted,
+    SecondmentEnded,
+    DeploymentStarted,
+    DeploymentEnded,
+}
+
+impl fmt::Display for RoleEventType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            RoleEventType::Started => "started",
+            RoleEventType::Transferred => "transferred",
+            RoleEventType::Ended => "ended",
+            RoleEventType::ActingStarted => "acting_started",
+            RoleEventType::ActingEnded => "acting_ended",
+            RoleEventType::SecondmentStarted => "secondment_started",
+            RoleEventType::SecondmentEnded => "secondment_ended",
+            RoleEventType::DeploymentStarted => "deployment_started",
+            RoleEventType::DeploymentEnded => "deployment_ended",
+        };
+        f.write_str(s)
+    }
+}
+
+// Non Graphql
+impl RoleEvent {
+    /// Called inside Role transactions, so returns the raw diesel error
+    pub fn record(conn: &PgConnection, role_event: &NewRoleEvent) -> Result<RoleEvent, diesel::result::Error> {
+        diesel::insert_into(role_events::table)
+            .values(role_event)
+            .get_result(conn)
+    }
+
+    /// All events for a person in chronological order
+    pub fn timeline(conn: &PgConnection, person_id: Uuid) -> FieldResult<Vec<RoleEvent>> {
+        let res = role_events::table
+            .filter(role_events::person_id.eq(person_id))
+            .order((role_events::event_date.asc(), role_events::created_at.asc()))
+            .load::<RoleEvent>(conn);
+
+        graphql_translate(res)
+    }
+
+    /// Timeline with the role and team of each event, for the person page
+    pub fn timeline_with_roles(conn: &PgConnection, person_id: Uuid) -> Result<Vec<RoleEventWithRole>, CustomError> {
+        let res = role_events::table
+            .inner_join(roles::table.inner_join(teams::table))
+            .filter(role_events::person_id.eq(person_id))
+            .order((role_events::event_date.asc(), role_events::created_at.asc()))
+            .load::<(RoleEvent, (Role, Team))>(conn)?;
+
+        Ok(res.into_iter()
+            .map(|(event, (role, team))| RoleEventWithRole { event, role, team })
+            .collect())
+    }
+
+    pub fn find_by_role(conn: &PgConnection, role_id: Uuid) -> FieldResult<Vec<RoleEvent>> {
+        let res = role_events::table
+            .filter(role_events::role_id.eq(role_id))
+            .order(role_events::event_date.asc())
+            .load::<RoleEvent>(conn);
+
+        graphql_translate(res)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, Insertable)]
+#[table_name = "role_events"]
+pub struct NewRoleEvent {
+    pub person_id: Uuid,
+    pub role_id: Uuid,
+    pub related_role_id: Option<Uuid>,
+    pub event_type: String,
+    pub note: String,
+    pub event_date: NaiveDateTime,
+}
+
+impl NewRoleEvent {
+
+    pub fn new(
+        person_id: Uuid,
+        role_id: Uuid,
+        related_role_id: Option<Uuid>,
+        event_type: RoleEventType,
+        note: String,
+        event_date: NaiveDateTime,
+    ) -> Self {
+        NewRoleEvent {
+            person_id,
+            role_id,
+            related_role_id,
+            event_type: event_type.to_string(),
+            note,
+            event_date,
+        }
+    }
+}
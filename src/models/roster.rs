@@ -0,0 +1,496 @@
+use std::collections::HashMap;
+
+use chrono::{prelude::*};
+use serde::{Deserialize, Serialize};
+use diesel::{self, Insertable, PgConnection, Queryable, ExpressionMethods};
+use diesel::upsert::excluded;
+use diesel::{Connection, RunQueryDsl, QueryDsl};
+use uuid::Uuid;
+use async_graphql::*;
+
+use crate::graphql::graphql_translate;
+
+use crate::schema::*;
+use crate::errors::CustomError;
+use crate::models::{CapabilityLevel, NewRole, Organization, Person, Role, RoleType, Skill, Team};
+
+#[derive(Debug, Clone, Deserialize, Serialize, Queryable, Insertable, AsChangeset, SimpleObject)]
+#[table_name = "rosters"]
+/// Pool of people pre-qualified for a classification or skill set,
+/// e.g. from a staffing process, that managers can draw on
+pub struct Roster {
+    pub id: Uuid,
+    pub name_en: String,
+    pub name_fr: String,
+    pub description_en: String,
+    pub description_fr: String,
+    pub organization_id: Uuid, // Organization running the roster
+    pub classification: Option<String>, // e.g. EC-05
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+    pub retired_at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, Queryable, Insertable, SimpleObject)]
+#[table_name = "roster_skills"]
+pub struct RosterSkill {
+    pub id: Uuid,
+    pub roster_id: Uuid,
+    pub skill_id: Uuid,
+    pub min_level: i32, // CapabilityLevel
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, Queryable, Insertable, AsChangeset, SimpleObject)]
+#[table_name = "roster_members"]
+/// Qualification of a person on a roster
+pub struct RosterMember {
+    pub id: Uuid,
+    pub roster_id: Uuid,
+    pub person_id: Uuid,
+    pub qualified_at: NaiveDateTime,
+    pub expires_at: Option<NaiveDateTime>,
+    pub note: String,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+    pub removed_at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, Queryable, Insertable, SimpleObject)]
+#[table_name = "roster_placements"]
+/// Role a person was drawn into from a roster
+pub struct RosterPlacement {
+    pub id: Uuid,
+    pub roster_id: Uuid,
+    pub roster_member_id: Uuid,
+    pub person_id: Uuid,
+    pub role_id: Uuid,
+    pub note: String,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RosterSkillWithSkill {
+    pub roster_skill: RosterSkill,
+    pub skill: Skill,
+    pub level_key: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RosterMemberWithPerson {
+    pub member: RosterMember,
+    pub person: Person,
+    pub qualified: bool,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+/// Placement with the person, role and team for display
+pub struct PlacementRecord {
+    pub placement: RosterPlacement,
+    pub person: Person,
+    pub role: Role,
+    pub team: Team,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RosterProfile {
+    pub roster: Roster,
+    pub organization: Organization,
+    pub skills: Vec<RosterSkillWithSkill>,
+    pub members: Vec<RosterMemberWithPerson>, // Qualified first
+    pub placements: Vec<PlacementRecord>, // Most recent first
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct RosterSearch {
+    pub roster_id: Option<Uuid>, // Any roster when None
+    pub classification: Option<String>,
+    pub skill_id: Option<Uuid>,
+    pub min_level: i32, // Of skill_id
+    pub language: Option<String>, // Working language or first official language
+    pub location: Option<String>, // Part of the work location
+    pub include_expired: bool,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RosterMatch {
+    pub roster: Roster,
+    pub member: RosterMember,
+    pub person: Person,
+    pub qualified: bool,
+    pub skill_level: Option<String>, // Level key of the searched skill
+}
+
+impl Roster {
+    pub fn create(conn: &PgConnection, roster: &NewRoster) -> FieldResult<Roster> {
+        let res = diesel::insert_into(rosters::table)
+            .values(roster)
+            .get_result(conn);
+
+        graphql_translate(res)
+    }
+
+    pub fn find(conn: &PgConnection, id: Uuid) -> Result<Self, CustomError> {
+        let roster = rosters::table.find(id).first(conn)?;
+        Ok(roster)
+    }
+
+    /// Rosters still in use, by name
+    pub fn find_active(conn: &PgConnection) -> Result<Vec<Self>, CustomError> {
+        let rosters = rosters::table
+            .filter(rosters::retired_at.is_null())
+            .order(rosters::name_en.asc())
+            .load::<Roster>(conn)?;
+
+        Ok(rosters)
+    }
+
+    pub fn update(&self, conn: &PgConnection) -> FieldResult<Self> {
+        let res = diesel::update(rosters::table)
+            .filter(rosters::id.eq(&self.id))
+            .set(self)
+            .get_result(conn);
+
+        graphql_translate(res)
+    }
+
+    pub fn skills(&self, conn: &PgConnection) -> Result<Vec<RosterSkillWithSkill>, CustomError> {
+        let rows = roster_skills::table
+            .inner_join(skills::table)
+            .filter(roster_skills::roster_id.eq(self.id))
+            .order(skills::name_en.asc())
+            .load::<(RosterSkill, Skill)>(conn)?;
+
+        Ok(rows.into_iter()
+            .map(|(roster_skill, skill)| RosterSkillWithSkill {
+                level_key: CapabilityLevel::from_i32(roster_skill.min_level).key().to_string(),
+                roster_skill,
+                skill,
+            })
+            .collect())
+    }
+
+    pub fn profile(&self, conn: &PgConnection, as_of: NaiveDateTime) -> Result<RosterProfile, CustomError> {
+        let organization = organizations::table
+            .find(self.organization_id)
+            .first::<Organization>(conn)?;
+
+        let mut members: Vec<RosterMemberWithPerson> = roster_members::table
+            .inner_join(persons::table)
+            .filter(roster_members::roster_id.eq(self.id))
+            .filter(roster_members::removed_at.is_null())
+            .load::<(RosterMember, Person)>(conn)?
+            .into_iter()
+            .map(|(member, person)| RosterMemberWithPerson {
+                qualified: member.is_qualified(as_of),
+                member,
+                person,
+            })
+            .collect();
+
+        members.sort_by(|a, b| b.qualified.cmp(&a.qualified)
+            .then_with(|| a.person.family_name.cmp(&b.person.family_name)));
+
+        let placements = roster_placements::table
+            .inner_join(persons::table)
+            .inner_join(roles::table.inner_join(teams::table))
+            .filter(roster_placements::roster_id.eq(self.id))
+            .order(roster_placements::created_at.desc())
+            .load::<(RosterPlacement, Person, (Role, Team))>(conn)?
+            .into_iter()
+            .map(|(placement, person, (role, team))| PlacementRecord { placement, person, role, team })
+            .collect();
+
+        Ok(RosterProfile {
+            roster: self.clone(),
+            organization,
+            skills: self.skills(conn)?,
+            members,
+            placements,
+        })
+    }
+
+    /// Members of active rosters matching every criterion searched,
+    /// qualified members first
+    pub fn search(conn: &PgConnection, search: &RosterSearch, as_of: NaiveDateTime) -> Result<Vec<RosterMatch>, CustomError> {
+        let mut query = roster_members::table
+            .inner_join(rosters::table)
+            .inner_join(persons::table)
+            .filter(rosters::retired_at.is_null())
+            .filter(roster_members::removed_at.is_null())
+            .filter(persons::retired_at.is_null())
+            .into_boxed();
+
+        if let Some(roster_id) = search.roster_id {
+            query = query.filter(rosters::id.eq(roster_id));
+        };
+
+        let rows = query.load::<(RosterMember, Roster, Person)>(conn)?;
+
+        let mut levels: HashMap<Uuid, i32> = HashMap::new();
+
+        if let Some(skill_id) = search.skill_id {
+            let person_ids: Vec<Uuid> = rows.iter().map(|(_, _, p)| p.id).collect();
+
+            for (person_id, level) in capabilities::table
+                .filter(capabilities::skill_id.eq(skill_id))
+                .filter(capabilities::person_id.eq_any(&person_ids))
+                .select((capabilities::person_id, capabilities::self_identified_level))
+                .load::<(Uuid, i32)>(conn)? {
+                levels.insert(person_id, level);
+            };
+        };
+
+        let classification = search.classification.as_ref().map(|c| c.trim().to_uppercase());
+        let language = search.language.as_ref().map(|l| l.trim().to_lowercase());
+        let location = search.location.as_ref().map(|l| l.trim().to_lowercase());
+
+        let mut matches: Vec<RosterMatch> = rows.into_iter()
+            .filter_map(|(member, roster, person)| {
+                let qualified = member.is_qualified(as_of);
+
+                if !qualified && !search.include_expired {
+                    return None
+                };
+
+                if let Some(classification) = &classification {
+                    if roster.classification.as_deref().map(|c| c.to_uppercase()).as_ref() != Some(classification) {
+                        return None
+                    };
+                };
+
+                if let Some(language) = &language {
+                    if !person.languages.contains(language) && person.first_official_language.as_ref() != Some(language) {
+                        return None
+                    };
+                };
+
+                if let Some(location) = &location {
+                    if !person.location.as_deref().map_or(false, |l| l.to_lowercase().contains(location.as_str())) {
+                        return None
+                    };
+                };
+
+                let skill_level = match search.skill_id {
+                    Some(_) => {
+                        let level = *levels.get(&person.id)?;
+                        if level < search.min_level {
+                            return None
+                        };
+                        Some(CapabilityLevel::from_i32(level).key().to_string())
+                    },
+                    None => None,
+                };
+
+                Some(RosterMatch { roster, member, person, qualified, skill_level })
+            })
+            .collect();
+
+        matches.sort_by(|a, b| b.qualified.cmp(&a.qualified)
+            .then_with(|| a.roster.name_en.cmp(&b.roster.name_en))
+            .then_with(|| a.person.family_name.cmp(&b.person.family_name)));
+
+        Ok(matches)
+    }
+}
+
+impl RosterSkill {
+    /// Natural key: (roster_id, skill_id). Updates the level if the key matches.
+    pub fn create_or_update(conn: &PgConnection, roster_skill: &NewRosterSkill) -> FieldResult<RosterSkill> {
+        let res = diesel::insert_into(roster_skills::table)
+            .values(roster_skill)
+            .on_conflict((roster_skills::roster_id, roster_skills::skill_id))
+            .do_update()
+            .set(roster_skills::min_level.eq(excluded(roster_skills::min_level)))
+            .get_result(conn);
+
+        graphql_translate(res)
+    }
+
+    pub fn delete(conn: &PgConnection, id: Uuid) -> Result<RosterSkill, CustomError> {
+        let res = diesel::delete(roster_skills::table.find(id)).get_result(conn)?;
+        Ok(res)
+    }
+}
+
+impl RosterMember {
+    /// Natural key: (roster_id, person_id). Requalifies the person if the key
+    /// matches, including someone removed earlier.
+    pub fn qualify(conn: &PgConnection, member: &NewRosterMember) -> FieldResult<RosterMember> {
+        let res = diesel::insert_into(roster_members::table)
+            .values(member)
+            .on_conflict((roster_members::roster_id, roster_members::person_id))
+            .do_update()
+            .set((
+                roster_members::qualified_at.eq(excluded(roster_members::qualified_at)),
+                roster_members::expires_at.eq(excluded(roster_members::expires_at)),
+                roster_members::note.eq(excluded(roster_members::note)),
+                roster_members::removed_at.eq(None::<NaiveDateTime>),
+                roster_members::updated_at.eq(Utc::now().naive_utc()),
+            ))
+            .get_result(conn);
+
+        graphql_translate(res)
+    }
+
+    pub fn find(conn: &PgConnection, id: Uuid) -> Result<Self, CustomError> {
+        let member = roster_members::table.find(id).first(conn)?;
+        Ok(member)
+    }
+
+    pub fn is_qualified(&self, as_of: NaiveDateTime) -> bool {
+        self.removed_at.is_none()
+            && self.qualified_at <= as_of
+            && self.expires_at.map_or(true, |e| e > as_of)
+    }
+
+    /// Take the person off the roster. Their placements are kept.
+    pub fn remove(&self, conn: &PgConnection) -> FieldResult<RosterMember> {
+        let now = Utc::now().naive_utc();
+
+        let res = diesel::update(roster_members::table.find(self.id))
+            .set((
+                roster_members::removed_at.eq(Some(now)),
+                roster_members::updated_at.eq(now),
+            ))
+            .get_result(conn);
+
+        graphql_translate(res)
+    }
+
+    /// Draw the person into a role on a team. Substantive placements transfer
+    /// the person; acting and secondment placements need an end date.
+    /// The role takes the roster's classification.
+    pub fn place(
+        &self,
+        conn: &PgConnection,
+        roster: &Roster,
+        new_role: &NewRole,
+        role_type: RoleType,
+        note: &str,
+    ) -> FieldResult<RosterPlacement> {
+        if !self.is_qualified(new_role.start_datestamp) {
+            return Err(FieldError::new("Roster qualification is not valid on the start date"));
+        };
+
+        if role_type == RoleType::Deployment {
+            return Err(FieldError::new("Use SurgeEvent::deploy for surge deployments"));
+        };
+
+        let event_note = match note {
+            "" => format!("Placed from roster {}", roster.name_en),
+            n => format!("Placed from roster {}: {}", roster.name_en, n),
+        };
+
+        conn.transaction::<RosterPlacement, FieldError, _>(|| {
+            let mut role = match role_type {
+                RoleType::Substantive => Role::transfer(conn, new_role, &event_note)?,
+                _ => Role::assign_temporary(conn, new_role, role_type, &event_note)?,
+            };
+
+            if roster.classification.is_some() {
+                role.classification = roster.classification.clone();
+                role.update(conn)?;
+            };
+
+            let res = diesel::insert_into(roster_placements::table)
+                .values(&NewRosterPlacement {
+                    roster_id: roster.id,
+                    roster_member_id: self.id,
+                    person_id: self.person_id,
+                    role_id: role.id,
+                    note: note.to_owned(),
+                })
+                .get_result(conn);
+
+            graphql_translate(res)
+        })
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, Insertable)]
+#[table_name = "rosters"]
+pub struct NewRoster {
+    pub name_en: String,
+    pub name_fr: String,
+    pub description_en: String,
+    pub description_fr: String,
+    pub organization_id: Uuid,
+    pub classification: Option<String>,
+}
+
+impl NewRoster {
+    pub fn new(
+        name_en: String,
+        name_fr: String,
+        description_en: String,
+        description_fr: String,
+        organization_id: Uuid,
+        classification: Option<String>,
+    ) -> Self {
+        NewRoster {
+            name_en,
+            name_fr,
+            description_en,
+            description_fr,
+            organization_id,
+            classification: classification.map(|c| c.trim().to_uppercase()).filter(|c| !c.is_empty()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, Insertable)]
+#[table_name = "roster_skills"]
+pub struct NewRosterSkill {
+    pub roster_id: Uuid,
+    pub skill_id: Uuid,
+    pub min_level: i32,
+}
+
+impl NewRosterSkill {
+    pub fn new(roster_id: Uuid, skill_id: Uuid, min_level: CapabilityLevel) -> Self {
+        NewRosterSkill {
+            roster_id,
+            skill_id,
+            min_level: min_level.as_i32(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, Insertable)]
+#[table_name = "roster_members"]
+pub struct NewRosterMember {
+    pub roster_id: Uuid,
+    pub person_id: Uuid,
+    pub qualified_at: NaiveDateTime,
+    pub expires_at: Option<NaiveDateTime>,
+    pub note: String,
+}
+
+impl NewRosterMember {
+    pub fn new(
+        roster_id: Uuid,
+        person_id: Uuid,
+        qualified_at: NaiveDateTime,
+        expires_at: Option<NaiveDateTime>,
+        note: String,
+    ) -> Self {
+        NewRosterMember {
+            roster_id,
+            person_id,
+            qualified_at,
+            expires_at,
+            note,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, Insertable)]
+#[table_name = "roster_placements"]
+struct NewRosterPlacement {
+    roster_id: Uuid,
+    roster_member_id: Uuid,
+    person_id: Uuid,
+    role_id: Uuid,
+    note: String,
+}
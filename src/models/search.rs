@@ -0,0 +1,659 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::{prelude::*};
+use serde::{Deserialize, Serialize};
+use diesel::{PgConnection, ExpressionMethods, BoolExpressionMethods};
+use diesel::sql_types::{Float4, Text};
+use diesel::{sql_query, RunQueryDsl, QueryDsl};
+use uuid::Uuid;
+use async_graphql::*;
+
+use crate::schema::*;
+use crate::errors::CustomError;
+use crate::models::{Capability, CapabilityLevel, ClassificationSkill, Organization, OrgTier, Person, Publication,
+    Role, Skill, Team, normalize_classification};
+
+/// Best matches read for each kind before facets are applied
+const MATCHES_PER_KIND: i64 = 100;
+
+/// Hits returned once facets are applied
+pub const SEARCH_RESULTS_LIMIT: usize = 50;
+
+/// Values listed per facet, most hits first
+const FACET_LIMIT: usize = 20;
+
+/// Words of the query used, the rest are ignored
+const MAX_TERMS: usize = 8;
+
+/// People found through a matching skill rank below people found by name
+const SKILL_MATCH_WEIGHT: f32 = 0.5;
+
+// (dictionary, document) pairs. Each must match an index expression in the
+// search_indexes migration or the search falls back to a sequential scan.
+const PERSON_DOCUMENTS: [(&str, &str); 1] = [
+    ("simple", "given_name || ' ' || family_name"),
+];
+
+const TEAM_DOCUMENTS: [(&str, &str); 2] = [
+    ("english", "name_en || ' ' || description_en"),
+    ("french", "name_fr || ' ' || description_fr"),
+];
+
+const ORG_TIER_DOCUMENTS: [(&str, &str); 2] = [
+    ("english", "name_en"),
+    ("french", "name_fr"),
+];
+
+const SKILL_DOCUMENTS: [(&str, &str); 2] = [
+    ("english", "name_en || ' ' || description_en"),
+    ("french", "name_fr || ' ' || description_fr"),
+];
+
+const PUBLICATION_DOCUMENTS: [(&str, &str); 2] = [
+    ("english", "title || ' ' || coalesce(venue, '')"),
+    ("french", "title || ' ' || coalesce(venue, '')"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SearchKind {
+    Person,
+    Team,
+    OrgTier,
+    Skill,
+    Publication,
+}
+
+impl SearchKind {
+    fn names(&self) -> (&'static str, &'static str) {
+        match self {
+            SearchKind::Person => ("People", "Personnes"),
+            SearchKind::Team => ("Teams", "Équipes"),
+            SearchKind::OrgTier => ("Org tiers", "Paliers organisationnels"),
+            SearchKind::Skill => ("Skills", "Compétences"),
+            SearchKind::Publication => ("Publications", "Publications"),
+        }
+    }
+}
+
+impl fmt::Display for SearchKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            SearchKind::Person => "person",
+            SearchKind::Team => "team",
+            SearchKind::OrgTier => "org_tier",
+            SearchKind::Skill => "skill",
+            SearchKind::Publication => "publication",
+        };
+        f.write_str(s)
+    }
+}
+
+impl FromStr for SearchKind {
+    type Err = CustomError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "person" => Ok(SearchKind::Person),
+            "team" => Ok(SearchKind::Team),
+            "org_tier" => Ok(SearchKind::OrgTier),
+            "skill" => Ok(SearchKind::Skill),
+            "publication" => Ok(SearchKind::Publication),
+            _ => Err(CustomError::new(400, format!("Unknown search kind: {}", s))),
+        }
+    }
+}
+
+#[derive(QueryableByName)]
+struct RankedId {
+    #[sql_type = "diesel::sql_types::Uuid"]
+    id: Uuid,
+    #[sql_type = "Float4"]
+    rank: f32,
+}
+
+#[derive(Debug, Clone, Default)]
+/// Where a hit sits, for filtering and counting facets
+struct HitFacets {
+    organization_ids: HashSet<Uuid>,
+    org_tier_ids: HashSet<Uuid>, // With every tier above them
+    classifications: HashSet<String>,
+    skill_level: Option<i32>, // Highest level held in a matching skill
+}
+
+impl HitFacets {
+    fn merge(&mut self, other: &HitFacets) {
+        self.organization_ids.extend(other.organization_ids.iter().cloned());
+        self.org_tier_ids.extend(other.org_tier_ids.iter().cloned());
+        self.classifications.extend(other.classifications.iter().cloned());
+    }
+
+    fn raise_level(&mut self, level: i32) {
+        if self.skill_level.map_or(true, |l| level > l) {
+            self.skill_level = Some(level);
+        };
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, SimpleObject)]
+pub struct SearchHit {
+    pub kind: String, // SearchKind
+    pub id: Uuid,
+    pub name_en: String,
+    pub name_fr: String,
+    pub detail_en: String,
+    pub detail_fr: String,
+    pub rank: f64,
+    #[serde(skip)]
+    #[graphql(skip)]
+    facets: HitFacets,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, SimpleObject)]
+/// A facet value and the number of hits it would leave
+pub struct FacetCount {
+    pub value: String,
+    pub name_en: String,
+    pub name_fr: String,
+    pub count: i32,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, SimpleObject)]
+/// Hits holding a matching skill at the level or above
+pub struct LevelFacetCount {
+    pub level: i32,
+    pub level_key: String,
+    pub count: i32,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize, SimpleObject)]
+/// Each facet is counted with every other filter applied but its own
+pub struct SearchFacets {
+    pub kinds: Vec<FacetCount>,
+    pub organizations: Vec<FacetCount>,
+    pub org_tiers: Vec<FacetCount>,
+    pub classifications: Vec<FacetCount>,
+    pub skill_levels: Vec<LevelFacetCount>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize, InputObject)]
+pub struct SearchFilters {
+    pub kind: Option<String>, // SearchKind
+    pub organization_id: Option<Uuid>,
+    pub org_tier_id: Option<Uuid>, // The tier and every tier below it
+    pub classification: Option<String>,
+    pub skill_level: Option<i32>, // CapabilityLevel, at or above
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Facet {
+    Kind,
+    Organization,
+    OrgTier,
+    Classification,
+    SkillLevel,
+}
+
+impl SearchFilters {
+    fn allows(&self, hit: &SearchHit, except: Option<Facet>) -> bool {
+        let applies = |facet: Facet| except != Some(facet);
+
+        (!applies(Facet::Kind) || self.kind.as_ref().map_or(true, |k| *k == hit.kind))
+            && (!applies(Facet::Organization) || self.organization_id.map_or(true, |id| hit.facets.organization_ids.contains(&id)))
+            && (!applies(Facet::OrgTier) || self.org_tier_id.map_or(true, |id| hit.facets.org_tier_ids.contains(&id)))
+            && (!applies(Facet::Classification) || self.classification.as_deref()
+                .map_or(true, |c| hit.facets.classifications.contains(&normalize_classification(c))))
+            && (!applies(Facet::SkillLevel) || self.skill_level.map_or(true, |l| hit.facets.skill_level.map_or(false, |h| h >= l)))
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize, SimpleObject)]
+/// Full-text search across people, teams, org tiers, skills and publications
+pub struct SearchResults {
+    pub query: String,
+    pub total: i32, // Hits after filtering, before the limit
+    pub hits: Vec<SearchHit>, // Best first
+    pub facets: SearchFacets,
+}
+
+/// Every word of the query as a prefix, e.g. "data sci" becomes "data:* & sci:*".
+/// Anything but letters and digits is dropped so input can't break tsquery syntax.
+fn prefix_tsquery(query: &str) -> Option<String> {
+    let terms: Vec<String> = query
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .take(MAX_TERMS)
+        .map(|w| format!("{}:*", w.to_lowercase()))
+        .collect();
+
+    if terms.is_empty() {
+        None
+    } else {
+        Some(terms.join(" & "))
+    }
+}
+
+/// Ids of the rows matching in any of the documents, best first
+fn ranked_ids(
+    conn: &PgConnection,
+    table: &str,
+    condition: &str,
+    documents: &[(&str, &str)],
+    tsquery: &str,
+) -> Result<Vec<RankedId>, CustomError> {
+    let ranks: Vec<String> = documents.iter()
+        .map(|(dictionary, document)| format!(
+            "ts_rank(to_tsvector('{0}', {1}), to_tsquery('{0}', $1))", dictionary, document))
+        .collect();
+
+    let matches: Vec<String> = documents.iter()
+        .map(|(dictionary, document)| format!(
+            "to_tsvector('{0}', {1}) @@ to_tsquery('{0}', $1)", dictionary, document))
+        .collect();
+
+    let sql = format!(
+        "SELECT id, GREATEST({}) AS rank FROM {} WHERE {} AND ({}) ORDER BY rank DESC LIMIT {}",
+        ranks.join(", "),
+        table,
+        condition,
+        matches.join(" OR "),
+        MATCHES_PER_KIND,
+    );
+
+    let res = sql_query(sql)
+        .bind::<Text, _>(tsquery)
+        .load::<RankedId>(conn)?;
+
+    Ok(res)
+}
+
+fn excerpt(text: &str) -> String {
+    let mut excerpt: String = text.chars().take(160).collect();
+    if excerpt.len() < text.len() {
+        excerpt.push('…');
+    };
+    excerpt
+}
+
+fn sort_facet(counts: HashMap<String, i32>, names: impl Fn(&str) -> Option<(String, String)>) -> Vec<FacetCount> {
+    let mut facet: Vec<FacetCount> = counts.into_iter()
+        .filter_map(|(value, count)| {
+            let (name_en, name_fr) = names(&value)?;
+            Some(FacetCount { value, name_en, name_fr, count })
+        })
+        .collect();
+
+    facet.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.name_en.cmp(&b.name_en)));
+    facet.truncate(FACET_LIMIT);
+    facet
+}
+
+impl SearchResults {
+    pub fn search(conn: &PgConnection, query: &str, filters: &SearchFilters) -> Result<SearchResults, CustomError> {
+        if let Some(kind) = &filters.kind {
+            SearchKind::from_str(kind)?;
+        };
+
+        let tsquery = match prefix_tsquery(query) {
+            Some(q) => q,
+            None => return Ok(SearchResults { query: query.to_owned(), ..SearchResults::default() }),
+        };
+
+        let now = Utc::now().naive_utc();
+
+        let organizations: HashMap<Uuid, Organization> = organizations::table
+            .load::<Organization>(conn)?
+            .into_iter()
+            .map(|o| (o.id, o))
+            .collect();
+
+        let tiers: HashMap<Uuid, OrgTier> = org_tiers::table
+            .load::<OrgTier>(conn)?
+            .into_iter()
+            .map(|t| (t.id, t))
+            .collect();
+
+        let with_ancestors = |tier_id: Uuid| -> Vec<Uuid> {
+            let mut chain = Vec::new();
+            let mut current = Some(tier_id);
+
+            while let Some(id) = current {
+                if chain.contains(&id) {
+                    break
+                };
+                chain.push(id);
+                current = tiers.get(&id).and_then(|t| t.parent_tier);
+            };
+
+            chain
+        };
+
+        let person_matches = ranked_ids(conn, "persons", "retired_at IS NULL", &PERSON_DOCUMENTS, &tsquery)?;
+        let team_matches = ranked_ids(conn, "teams", "retired_at IS NULL", &TEAM_DOCUMENTS, &tsquery)?;
+        let tier_matches = ranked_ids(conn, "org_tiers", "retired_at IS NULL", &ORG_TIER_DOCUMENTS, &tsquery)?;
+        let skill_matches = ranked_ids(conn, "skills", "TRUE", &SKILL_DOCUMENTS, &tsquery)?;
+        let publication_matches = ranked_ids(conn, "publications", "TRUE", &PUBLICATION_DOCUMENTS, &tsquery)?;
+
+        let skill_rank: HashMap<Uuid, f32> = skill_matches.iter().map(|m| (m.id, m.rank)).collect();
+        let skill_ids: Vec<Uuid> = skill_rank.keys().cloned().collect();
+
+        // Current holders of matching skills
+        let held = capabilities::table
+            .inner_join(persons::table)
+            .filter(capabilities::skill_id.eq_any(&skill_ids))
+            .filter(capabilities::self_identified_level.ge(CapabilityLevel::Novice.as_i32()))
+            .filter(persons::retired_at.is_null())
+            .select(capabilities::all_columns)
+            .load::<Capability>(conn)?;
+
+        // People are found by name or through a matching skill
+        let mut person_rank: HashMap<Uuid, f32> = person_matches.iter().map(|m| (m.id, m.rank)).collect();
+
+        for capability in &held {
+            let rank = skill_rank.get(&capability.skill_id).cloned().unwrap_or(0.0) * SKILL_MATCH_WEIGHT;
+            let entry = person_rank.entry(capability.person_id).or_insert(rank);
+            if rank > *entry {
+                *entry = rank;
+            };
+        };
+
+        let publication_ids: Vec<Uuid> = publication_matches.iter().map(|m| m.id).collect();
+
+        let authors: Vec<(Uuid, Uuid)> = publication_authors::table
+            .filter(publication_authors::publication_id.eq_any(&publication_ids))
+            .select((publication_authors::publication_id, publication_authors::person_id))
+            .load::<(Uuid, Option<Uuid>)>(conn)?
+            .into_iter()
+            .filter_map(|(publication_id, person_id)| Some((publication_id, person_id?)))
+            .collect();
+
+        let mut person_ids: HashSet<Uuid> = person_rank.keys().cloned().collect();
+        person_ids.extend(authors.iter().map(|(_, p)| *p));
+        let person_ids: Vec<Uuid> = person_ids.into_iter().collect();
+
+        let persons: HashMap<Uuid, Person> = persons::table
+            .filter(persons::id.eq_any(&person_ids))
+            .load::<Person>(conn)?
+            .into_iter()
+            .map(|p| (p.id, p))
+            .collect();
+
+        let mut roles_by_person: HashMap<Uuid, Vec<(Role, Team)>> = HashMap::new();
+
+        for (role, team) in roles::table
+            .inner_join(teams::table)
+            .filter(roles::person_id.eq_any(&person_ids))
+            .filter(roles::start_datestamp.le(now))
+            .filter(roles::end_date.is_null().or(roles::end_date.gt(now)))
+            .order(roles::effort.desc())
+            .load::<(Role, Team)>(conn)? {
+            roles_by_person.entry(role.person_id).or_insert_with(Vec::new).push((role, team));
+        };
+
+        let person_facets = |person_id: &Uuid| -> HitFacets {
+            let mut facets = HitFacets::default();
+
+            if let Some(person) = persons.get(person_id) {
+                facets.organization_ids.insert(person.organization_id);
+            };
+
+            for (role, team) in roles_by_person.get(person_id).into_iter().flatten() {
+                facets.org_tier_ids.extend(with_ancestors(team.org_tier_id));
+                if let Some(classification) = &role.classification {
+                    facets.classifications.insert(normalize_classification(classification));
+                };
+            };
+
+            facets
+        };
+
+        let mut hits: Vec<SearchHit> = Vec::new();
+
+        // People
+        let mut person_levels: HashMap<Uuid, i32> = HashMap::new();
+        for capability in &held {
+            let level = person_levels.entry(capability.person_id).or_insert(capability.self_identified_level);
+            if capability.self_identified_level > *level {
+                *level = capability.self_identified_level;
+            };
+        };
+
+        for (person_id, rank) in &person_rank {
+            let person = match persons.get(person_id) {
+                Some(p) => p,
+                None => continue,
+            };
+
+            let (detail_en, detail_fr) = match roles_by_person.get(person_id).and_then(|r| r.first()) {
+                Some((role, team)) => (
+                    format!("{}, {}", role.title_en, team.name_en),
+                    format!("{}, {}", role.title_fr, team.name_fr),
+                ),
+                None => (String::new(), String::new()),
+            };
+
+            let mut facets = person_facets(person_id);
+            if let Some(level) = person_levels.get(person_id) {
+                facets.raise_level(*level);
+            };
+
+            hits.push(SearchHit {
+                kind: SearchKind::Person.to_string(),
+                id: person.id,
+                name_en: format!("{} {}", person.given_name, person.family_name),
+                name_fr: format!("{} {}", person.given_name, person.family_name),
+                detail_en,
+                detail_fr,
+                rank: *rank as f64,
+                facets,
+            });
+        };
+
+        // Teams, with the classifications of their current roles
+        let team_rank: HashMap<Uuid, f32> = team_matches.iter().map(|m| (m.id, m.rank)).collect();
+        let team_ids: Vec<Uuid> = team_rank.keys().cloned().collect();
+
+        let mut team_classifications: HashMap<Uuid, HashSet<String>> = HashMap::new();
+
+        for (team_id, classification) in roles::table
+            .filter(roles::team_id.eq_any(&team_ids))
+            .filter(roles::start_datestamp.le(now))
+            .filter(roles::end_date.is_null().or(roles::end_date.gt(now)))
+            .select((roles::team_id, roles::classification))
+            .load::<(Uuid, Option<String>)>(conn)? {
+            if let Some(classification) = classification {
+                team_classifications.entry(team_id).or_insert_with(HashSet::new)
+                    .insert(normalize_classification(&classification));
+            };
+        };
+
+        for team in teams::table.filter(teams::id.eq_any(&team_ids)).load::<Team>(conn)? {
+            let tier = tiers.get(&team.org_tier_id);
+
+            hits.push(SearchHit {
+                kind: SearchKind::Team.to_string(),
+                id: team.id,
+                detail_en: tier.map(|t| t.name_en.clone()).unwrap_or_default(),
+                detail_fr: tier.map(|t| t.name_fr.clone()).unwrap_or_default(),
+                rank: team_rank.get(&team.id).cloned().unwrap_or(0.0) as f64,
+                facets: HitFacets {
+                    organization_ids: vec![team.organization_id].into_iter().collect(),
+                    org_tier_ids: with_ancestors(team.org_tier_id).into_iter().collect(),
+                    classifications: team_classifications.remove(&team.id).unwrap_or_default(),
+                    skill_level: None,
+                },
+                name_en: team.name_en,
+                name_fr: team.name_fr,
+            });
+        };
+
+        // Org tiers
+        for m in &tier_matches {
+            let tier = match tiers.get(&m.id) {
+                Some(t) => t,
+                None => continue,
+            };
+
+            let organization = organizations.get(&tier.organization_id);
+
+            hits.push(SearchHit {
+                kind: SearchKind::OrgTier.to_string(),
+                id: tier.id,
+                name_en: tier.name_en.clone(),
+                name_fr: tier.name_fr.clone(),
+                detail_en: organization.map(|o| o.name_en.clone()).unwrap_or_default(),
+                detail_fr: organization.map(|o| o.name_fr.clone()).unwrap_or_default(),
+                rank: m.rank as f64,
+                facets: HitFacets {
+                    organization_ids: vec![tier.organization_id].into_iter().collect(),
+                    org_tier_ids: with_ancestors(tier.id).into_iter().collect(),
+                    ..HitFacets::default()
+                },
+            });
+        };
+
+        // Skills sit wherever their holders do, and under the classifications expecting them
+        let mut skill_facets: HashMap<Uuid, HitFacets> = HashMap::new();
+
+        for capability in &held {
+            let facets = skill_facets.entry(capability.skill_id).or_insert_with(HitFacets::default);
+            facets.merge(&person_facets(&capability.person_id));
+            facets.raise_level(capability.self_identified_level);
+        };
+
+        let expectations = classification_skills::table
+            .filter(classification_skills::skill_id.eq_any(&skill_ids))
+            .load::<ClassificationSkill>(conn)?;
+
+        for expectation in expectations {
+            skill_facets.entry(expectation.skill_id).or_insert_with(HitFacets::default)
+                .classifications.insert(expectation.classification);
+        };
+
+        for skill in skills::table.filter(skills::id.eq_any(&skill_ids)).load::<Skill>(conn)? {
+            hits.push(SearchHit {
+                kind: SearchKind::Skill.to_string(),
+                id: skill.id,
+                name_en: skill.name_en.clone(),
+                name_fr: skill.name_fr.clone(),
+                detail_en: excerpt(&skill.description_en),
+                detail_fr: excerpt(&skill.description_fr),
+                rank: skill_rank.get(&skill.id).cloned().unwrap_or(0.0) as f64,
+                facets: skill_facets.remove(&skill.id).unwrap_or_default(),
+            });
+        };
+
+        // Publications sit wherever their authors in the app do
+        let publication_rank: HashMap<Uuid, f32> = publication_matches.iter().map(|m| (m.id, m.rank)).collect();
+
+        let mut publication_facets: HashMap<Uuid, HitFacets> = HashMap::new();
+
+        for (publication_id, person_id) in &authors {
+            publication_facets.entry(*publication_id).or_insert_with(HitFacets::default)
+                .merge(&person_facets(person_id));
+        };
+
+        for publication in publications::table.filter(publications::id.eq_any(&publication_ids)).load::<Publication>(conn)? {
+            let detail = match (&publication.venue, publication.publication_year) {
+                (Some(venue), Some(year)) => format!("{}, {}", venue, year),
+                (Some(venue), None) => venue.clone(),
+                (None, Some(year)) => year.to_string(),
+                (None, None) => String::new(),
+            };
+
+            hits.push(SearchHit {
+                kind: SearchKind::Publication.to_string(),
+                id: publication.id,
+                name_en: publication.title.clone(),
+                name_fr: publication.title.clone(),
+                detail_en: detail.clone(),
+                detail_fr: detail,
+                rank: publication_rank.get(&publication.id).cloned().unwrap_or(0.0) as f64,
+                facets: publication_facets.remove(&publication.id).unwrap_or_default(),
+            });
+        };
+
+        let facets = SearchResults::count_facets(&hits, filters, &organizations, &tiers);
+
+        let mut hits: Vec<SearchHit> = hits.into_iter()
+            .filter(|h| filters.allows(h, None))
+            .collect();
+
+        hits.sort_by(|a, b| b.rank.partial_cmp(&a.rank).unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.name_en.cmp(&b.name_en)));
+
+        let total = hits.len() as i32;
+        hits.truncate(SEARCH_RESULTS_LIMIT);
+
+        Ok(SearchResults {
+            query: query.to_owned(),
+            total,
+            hits,
+            facets,
+        })
+    }
+
+    fn count_facets(
+        hits: &[SearchHit],
+        filters: &SearchFilters,
+        organizations: &HashMap<Uuid, Organization>,
+        tiers: &HashMap<Uuid, OrgTier>,
+    ) -> SearchFacets {
+        let counted = |facet: Facet, values: &dyn Fn(&SearchHit) -> Vec<String>| -> HashMap<String, i32> {
+            let mut counts = HashMap::new();
+            for hit in hits.iter().filter(|h| filters.allows(h, Some(facet))) {
+                for value in values(hit) {
+                    *counts.entry(value).or_insert(0) += 1;
+                };
+            };
+            counts
+        };
+
+        let kinds = sort_facet(
+            counted(Facet::Kind, &|h: &SearchHit| vec![h.kind.clone()]),
+            |value| SearchKind::from_str(value).ok()
+                .map(|k| (k.names().0.to_owned(), k.names().1.to_owned())),
+        );
+
+        let organizations = sort_facet(
+            counted(Facet::Organization, &|h: &SearchHit| h.facets.organization_ids.iter().map(|id| id.to_string()).collect()),
+            |value| Uuid::parse_str(value).ok()
+                .and_then(|id| organizations.get(&id))
+                .map(|o| (o.name_en.clone(), o.name_fr.clone())),
+        );
+
+        let org_tiers = sort_facet(
+            counted(Facet::OrgTier, &|h: &SearchHit| h.facets.org_tier_ids.iter().map(|id| id.to_string()).collect()),
+            |value| Uuid::parse_str(value).ok()
+                .and_then(|id| tiers.get(&id))
+                .map(|t| (t.name_en.clone(), t.name_fr.clone())),
+        );
+
+        let classifications = sort_facet(
+            counted(Facet::Classification, &|h: &SearchHit| h.facets.classifications.iter().cloned().collect()),
+            |value| Some((value.to_owned(), value.to_owned())),
+        );
+
+        // Levels are "at or above", so a hit counts towards every level up to its own
+        let skill_levels = (CapabilityLevel::Novice.as_i32()..=CapabilityLevel::Specialist.as_i32())
+            .map(|level| LevelFacetCount {
+                level,
+                level_key: CapabilityLevel::from_i32(level).key().to_string(),
+                count: hits.iter()
+                    .filter(|h| filters.allows(h, Some(Facet::SkillLevel)))
+                    .filter(|h| h.facets.skill_level.map_or(false, |l| l >= level))
+                    .count() as i32,
+            })
+            .filter(|f| f.count > 0)
+            .collect();
+
+        SearchFacets {
+            kinds,
+            organizations,
+            org_tiers,
+            classifications,
+            skill_levels,
+        }
+    }
+}
@@ -1,10 +1,12 @@
 use std::collections::HashMap;
 
-use crate::PgConnection;
+use chrono::NaiveDateTime;
+use diesel::PgConnection;
 use serde::{Serialize, Deserialize};
 use diesel::prelude::*;
 use diesel::{self, Insertable, Queryable};
 use diesel::{RunQueryDsl, QueryDsl};
+use diesel::upsert::excluded;
 //use juniper::{FieldResult};
 use uuid::Uuid;
 
@@ -17,10 +19,10 @@ use crate::schema::*;
 #[table_name = "skills"]
 /// Represents an insertable Skill
 pub struct NewSkill {
-    name_en: String,
-    name_fr: String,
-    description_en: String,
-    description_fr: String,
+    pub name_en: String,
+    pub name_fr: String,
+    pub description_en: String,
+    pub description_fr: String,
 }
 
 impl NewSkill {
@@ -33,7 +35,7 @@ impl NewSkill {
         NewSkill {
             name_en,
             name_fr,
-            acroynm_en,
+            description_en,
             description_fr,
         }
     }
@@ -49,6 +51,8 @@ pub struct Skill {
     pub name_fr: String,
     pub description_en: String,
     pub description_fr: String,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
 }
 
 impl Skill {
@@ -60,6 +64,19 @@ impl Skill {
         graphql_translate(res)
     }
 
+    /// Natural key: name_en
+    /// Returns the existing skill unchanged if the key matches.
+    pub fn get_or_create(conn: &PgConnection, skill: &NewSkill) -> FieldResult<Skill> {
+        let res = diesel::insert_into(skills::table)
+            .values(skill)
+            .on_conflict(skills::name_en)
+            .do_update()
+            .set(skills::name_en.eq(excluded(skills::name_en)))
+            .get_result(conn);
+
+        graphql_translate(res)
+    }
+
     pub fn get_by_id(conn: &PgConnection, id: &Uuid) -> FieldResult<Skill> {
         let res = skills::table.filter(skills::id.eq(id))
             .first(conn);
@@ -67,16 +84,23 @@ impl Skill {
         graphql_translate(res)
     }
 
-    pub fn load_into_hash(conn: &PgConnection) -> HashMap<Uuid, Skill> {
+    pub fn find_all(conn: &PgConnection) -> FieldResult<Vec<Skill>> {
         let res = skills::table
-            .load::<Skill>(conn)
-            .expect("Unable to load skills");
+            .order(skills::name_en.asc())
+            .load::<Skill>(conn);
+
+        graphql_translate(res)
+    }
+
+    pub fn load_into_hash(conn: &PgConnection) -> FieldResult<HashMap<Uuid, Skill>> {
+        let res = graphql_translate(skills::table
+            .load::<Skill>(conn))?;
 
         let mut skills: HashMap<Uuid, Skill> = HashMap::new();
         for c in res {
             skills.insert(c.id, c);
         };
 
-        skills 
+        Ok(skills)
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,211 @@
+use std::collections::{HashMap, HashSet};
+
+use chrono::{prelude::*};
+use serde::{Deserialize, Serialize};
+use diesel::{PgConnection, ExpressionMethods, BoolExpressionMethods};
+use diesel::{RunQueryDsl, QueryDsl};
+use uuid::Uuid;
+
+use crate::schema::*;
+use crate::errors::CustomError;
+use crate::models::{Capability, CapabilityLevel, ClassificationSkill, OrgTier, Person, Role, Skill, Team,
+    Work, WorkSkillRequirement, normalize_classification};
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+/// Demand for a skill at one level against the people in scope who hold it at that level or above.
+/// Each active work item requiring the skill counts as one person needed, each current role whose
+/// classification expects it counts as the role's effort.
+pub struct SkillGap {
+    pub skill: Skill,
+    pub required_level: i32,
+    pub level_key: String,
+    pub work_items: usize,
+    pub classified_effort: f64,
+    pub demand: f64,
+    pub holders: usize,
+    pub supply: f64, // Effort of holders
+    pub shortfall: f64, // Demand less supply, negative if covered
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+/// Skill held by only one person in scope
+pub struct SinglePointOfFailure {
+    pub skill: Skill,
+    pub person: Person,
+    pub level_key: String,
+    pub effort: f64,
+    pub demanded: bool, // Required by work or a classification in scope
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+/// Skill demand against supply for a team or an org tier and every tier below it
+pub struct SkillGapReport {
+    pub name_en: String,
+    pub name_fr: String,
+    pub teams: Vec<Team>,
+    pub people: usize,
+    pub total_effort: f64,
+    pub gaps: Vec<SkillGap>, // Largest shortfall first
+    pub single_points: Vec<SinglePointOfFailure>, // Demanded skills first
+}
+
+impl SkillGapReport {
+    pub fn for_team(conn: &PgConnection, team: &Team, as_of: NaiveDateTime) -> Result<SkillGapReport, CustomError> {
+        SkillGapReport::build(conn, &team.name_en, &team.name_fr, vec![team.clone()], as_of)
+    }
+
+    pub fn for_org_tier(conn: &PgConnection, org_tier: &OrgTier, as_of: NaiveDateTime) -> Result<SkillGapReport, CustomError> {
+        let tier_ids = org_tier.find_subtree_ids(conn)?;
+
+        let teams = teams::table
+            .filter(teams::org_tier_id.eq_any(&tier_ids))
+            .filter(teams::retired_at.is_null().or(teams::retired_at.gt(as_of)))
+            .order(teams::name_en.asc())
+            .load::<Team>(conn)?;
+
+        SkillGapReport::build(conn, &org_tier.name_en, &org_tier.name_fr, teams, as_of)
+    }
+
+    fn build(conn: &PgConnection, name_en: &str, name_fr: &str, teams: Vec<Team>, as_of: NaiveDateTime) -> Result<SkillGapReport, CustomError> {
+        let team_ids: Vec<Uuid> = teams.iter().map(|t| t.id).collect();
+
+        let roles = roles::table
+            .inner_join(persons::table)
+            .filter(roles::team_id.eq_any(&team_ids))
+            .filter(roles::start_datestamp.le(as_of))
+            .filter(roles::end_date.is_null().or(roles::end_date.gt(as_of)))
+            .load::<(Role, Person)>(conn)?;
+
+        let mut effort_by_person: HashMap<Uuid, f64> = HashMap::new();
+        let mut persons: HashMap<Uuid, Person> = HashMap::new();
+
+        for (role, person) in &roles {
+            *effort_by_person.entry(person.id).or_insert(0.0) += role.effort;
+            persons.entry(person.id).or_insert_with(|| person.clone());
+        };
+
+        let person_ids: Vec<Uuid> = persons.keys().cloned().collect();
+
+        let held = capabilities::table
+            .filter(capabilities::person_id.eq_any(&person_ids))
+            .filter(capabilities::self_identified_level.ge(CapabilityLevel::Novice.as_i32()))
+            .load::<Capability>(conn)?;
+
+        // Demand by (skill, level): active work items and classified effort
+        let mut demand: HashMap<(Uuid, i32), (usize, f64)> = HashMap::new();
+
+        let works: Vec<Work> = works::table
+            .filter(works::team_id.eq_any(&team_ids))
+            .load::<Work>(conn)?
+            .into_iter()
+            .filter(|w| w.is_active())
+            .collect();
+
+        let work_ids: Vec<Uuid> = works.iter().map(|w| w.id).collect();
+
+        for required in WorkSkillRequirement::find_by_works(conn, &work_ids)?.values().flatten() {
+            demand.entry((required.skill.id, required.level)).or_insert((0, 0.0)).0 += 1;
+        };
+
+        let classifications: Vec<String> = roles.iter()
+            .filter_map(|(r, _)| r.classification.as_deref().map(normalize_classification))
+            .collect::<HashSet<String>>()
+            .into_iter()
+            .collect();
+
+        let mut expected: HashMap<String, Vec<ClassificationSkill>> = HashMap::new();
+
+        for expectation in ClassificationSkill::find_by_classifications(conn, &classifications)? {
+            expected.entry(expectation.classification.clone()).or_insert_with(Vec::new).push(expectation);
+        };
+
+        for (role, _) in &roles {
+            let classification = match role.classification.as_deref() {
+                Some(c) => normalize_classification(c),
+                None => continue,
+            };
+
+            for expectation in expected.get(&classification).into_iter().flatten() {
+                demand.entry((expectation.skill_id, expectation.expected_level)).or_insert((0, 0.0)).1 += role.effort;
+            };
+        };
+
+        let mut skill_ids: HashSet<Uuid> = demand.keys().map(|(s, _)| *s).collect();
+        skill_ids.extend(held.iter().map(|c| c.skill_id));
+
+        let skills: HashMap<Uuid, Skill> = skills::table
+            .filter(skills::id.eq_any(skill_ids.into_iter().collect::<Vec<Uuid>>()))
+            .load::<Skill>(conn)?
+            .into_iter()
+            .map(|s| (s.id, s))
+            .collect();
+
+        let mut gaps: Vec<SkillGap> = demand.iter()
+            .filter_map(|((skill_id, level), (work_items, classified_effort))| {
+                let skill = skills.get(skill_id)?.clone();
+
+                let holders: Vec<&Capability> = held.iter()
+                    .filter(|c| c.skill_id == *skill_id && c.self_identified_level >= *level)
+                    .collect();
+
+                let supply: f64 = holders.iter()
+                    .map(|c| effort_by_person.get(&c.person_id).cloned().unwrap_or(0.0))
+                    .sum();
+
+                let demand = *work_items as f64 + classified_effort;
+
+                Some(SkillGap {
+                    skill,
+                    required_level: *level,
+                    level_key: CapabilityLevel::from_i32(*level).key().to_string(),
+                    work_items: *work_items,
+                    classified_effort: *classified_effort,
+                    demand,
+                    holders: holders.len(),
+                    supply,
+                    shortfall: demand - supply,
+                })
+            })
+            .collect();
+
+        gaps.sort_by(|a, b| b.shortfall.partial_cmp(&a.shortfall).unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.skill.name_en.cmp(&b.skill.name_en))
+            .then_with(|| b.required_level.cmp(&a.required_level)));
+
+        let demanded: HashSet<Uuid> = demand.keys().map(|(s, _)| *s).collect();
+
+        let mut holders_by_skill: HashMap<Uuid, Vec<&Capability>> = HashMap::new();
+
+        for capability in &held {
+            holders_by_skill.entry(capability.skill_id).or_insert_with(Vec::new).push(capability);
+        };
+
+        let mut single_points: Vec<SinglePointOfFailure> = holders_by_skill.into_iter()
+            .filter(|(_, holders)| holders.len() == 1)
+            .filter_map(|(skill_id, holders)| {
+                let capability = holders[0];
+
+                Some(SinglePointOfFailure {
+                    skill: skills.get(&skill_id)?.clone(),
+                    person: persons.get(&capability.person_id)?.clone(),
+                    level_key: capability.get_level().key().to_string(),
+                    effort: effort_by_person.get(&capability.person_id).cloned().unwrap_or(0.0),
+                    demanded: demanded.contains(&skill_id),
+                })
+            })
+            .collect();
+
+        single_points.sort_by(|a, b| b.demanded.cmp(&a.demanded)
+            .then_with(|| a.skill.name_en.cmp(&b.skill.name_en)));
+
+        Ok(SkillGapReport {
+            name_en: name_en.to_owned(),
+            name_fr: name_fr.to_owned(),
+            teams,
+            people: persons.len(),
+            total_effort: effort_by_person.values().sum(),
+            gaps,
+            single_points,
+        })
+    }
+}
@@ -0,0 +1,205 @@
+use std::collections::HashMap;
+
+use chrono::{prelude::*};
+use serde::{Deserialize, Serialize};
+use diesel::{self, Insertable, PgConnection, Queryable, ExpressionMethods};
+use diesel::upsert::excluded;
+use diesel::{RunQueryDsl, QueryDsl};
+use uuid::Uuid;
+use async_graphql::*;
+
+use crate::graphql::graphql_translate;
+
+use crate::schema::*;
+use crate::errors::CustomError;
+use crate::models::{CapabilityLevel, Skill};
+
+#[derive(Debug, Clone, Deserialize, Serialize, Queryable, Insertable, AsChangeset)]
+#[table_name = "work_skill_requirements"]
+/// Skill a Work needs, at a CapabilityLevel
+pub struct WorkSkillRequirement {
+    pub id: Uuid,
+    pub work_id: Uuid, // Work
+    pub skill_id: Uuid, // Skill
+    pub required_level: i32, // CapabilityLevel
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, Queryable, Insertable, AsChangeset)]
+#[table_name = "classification_skills"]
+/// Skill expected of anyone holding a role of a classification, e.g. EC-05
+pub struct ClassificationSkill {
+    pub id: Uuid,
+    pub classification: String,
+    pub skill_id: Uuid, // Skill
+    pub expected_level: i32, // CapabilityLevel
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+/// A work or classification requirement with its Skill for display
+pub struct RequiredSkill {
+    pub requirement_id: Uuid,
+    pub skill: Skill,
+    pub level: i32,
+    pub level_key: String,
+}
+
+impl RequiredSkill {
+    fn new(requirement_id: Uuid, skill: Skill, level: i32) -> Self {
+        RequiredSkill {
+            requirement_id,
+            skill,
+            level,
+            level_key: CapabilityLevel::from_i32(level).key().to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ClassificationSkills {
+    pub classification: String,
+    pub skills: Vec<RequiredSkill>,
+}
+
+/// Classifications are compared upper case, e.g. ec-05 is EC-05
+pub fn normalize_classification(classification: &str) -> String {
+    classification.trim().to_uppercase()
+}
+
+impl WorkSkillRequirement {
+    /// Natural key: (work_id, skill_id). Updates the level if the key matches.
+    pub fn create_or_update(conn: &PgConnection, requirement: &NewWorkSkillRequirement) -> FieldResult<WorkSkillRequirement> {
+        let res = diesel::insert_into(work_skill_requirements::table)
+            .values(requirement)
+            .on_conflict((work_skill_requirements::work_id, work_skill_requirements::skill_id))
+            .do_update()
+            .set((
+                work_skill_requirements::required_level.eq(excluded(work_skill_requirements::required_level)),
+                work_skill_requirements::updated_at.eq(Utc::now().naive_utc()),
+            ))
+            .get_result(conn);
+
+        graphql_translate(res)
+    }
+
+    pub fn delete(conn: &PgConnection, id: Uuid) -> Result<WorkSkillRequirement, CustomError> {
+        let res = diesel::delete(work_skill_requirements::table.find(id)).get_result(conn)?;
+        Ok(res)
+    }
+
+    /// Requirements of each work, highest level first
+    pub fn find_by_works(conn: &PgConnection, work_ids: &[Uuid]) -> Result<HashMap<Uuid, Vec<RequiredSkill>>, CustomError> {
+        let res = work_skill_requirements::table
+            .inner_join(skills::table)
+            .filter(work_skill_requirements::work_id.eq_any(work_ids))
+            .order((work_skill_requirements::required_level.desc(), skills::name_en.asc()))
+            .load::<(WorkSkillRequirement, Skill)>(conn)?;
+
+        let mut by_work: HashMap<Uuid, Vec<RequiredSkill>> = HashMap::new();
+
+        for (requirement, skill) in res {
+            by_work
+                .entry(requirement.work_id)
+                .or_insert_with(Vec::new)
+                .push(RequiredSkill::new(requirement.id, skill, requirement.required_level));
+        };
+
+        Ok(by_work)
+    }
+}
+
+impl ClassificationSkill {
+    /// Natural key: (classification, skill_id). Updates the level if the key matches.
+    pub fn create_or_update(conn: &PgConnection, expectation: &NewClassificationSkill) -> FieldResult<ClassificationSkill> {
+        let res = diesel::insert_into(classification_skills::table)
+            .values(expectation)
+            .on_conflict((classification_skills::classification, classification_skills::skill_id))
+            .do_update()
+            .set((
+                classification_skills::expected_level.eq(excluded(classification_skills::expected_level)),
+                classification_skills::updated_at.eq(Utc::now().naive_utc()),
+            ))
+            .get_result(conn);
+
+        graphql_translate(res)
+    }
+
+    pub fn delete(conn: &PgConnection, id: Uuid) -> Result<ClassificationSkill, CustomError> {
+        let res = diesel::delete(classification_skills::table.find(id)).get_result(conn)?;
+        Ok(res)
+    }
+
+    /// Every classification with expectations, in classification order
+    pub fn find_all_grouped(conn: &PgConnection) -> Result<Vec<ClassificationSkills>, CustomError> {
+        let res = classification_skills::table
+            .inner_join(skills::table)
+            .order((
+                classification_skills::classification.asc(),
+                classification_skills::expected_level.desc(),
+                skills::name_en.asc(),
+            ))
+            .load::<(ClassificationSkill, Skill)>(conn)?;
+
+        let mut grouped: Vec<ClassificationSkills> = Vec::new();
+
+        for (expectation, skill) in res {
+            let required = RequiredSkill::new(expectation.id, skill, expectation.expected_level);
+
+            match grouped.last_mut() {
+                Some(g) if g.classification == expectation.classification => g.skills.push(required),
+                _ => grouped.push(ClassificationSkills {
+                    classification: expectation.classification,
+                    skills: vec![required],
+                }),
+            };
+        };
+
+        Ok(grouped)
+    }
+
+    pub fn find_by_classifications(conn: &PgConnection, classifications: &[String]) -> Result<Vec<Self>, CustomError> {
+        let res = classification_skills::table
+            .filter(classification_skills::classification.eq_any(classifications))
+            .load::<ClassificationSkill>(conn)?;
+        Ok(res)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, Insertable)]
+#[table_name = "work_skill_requirements"]
+pub struct NewWorkSkillRequirement {
+    pub work_id: Uuid,
+    pub skill_id: Uuid,
+    pub required_level: i32,
+}
+
+impl NewWorkSkillRequirement {
+    pub fn new(work_id: Uuid, skill_id: Uuid, required_level: CapabilityLevel) -> Self {
+        NewWorkSkillRequirement {
+            work_id,
+            skill_id,
+            required_level: required_level.as_i32(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, Insertable)]
+#[table_name = "classification_skills"]
+pub struct NewClassificationSkill {
+    pub classification: String,
+    pub skill_id: Uuid,
+    pub expected_level: i32,
+}
+
+impl NewClassificationSkill {
+    pub fn new(classification: &str, skill_id: Uuid, expected_level: CapabilityLevel) -> Self {
+        NewClassificationSkill {
+            classification: normalize_classification(classification),
+            skill_id,
+            expected_level: expected_level.as_i32(),
+        }
+    }
+}
@@ -0,0 +1,322 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use chrono::{prelude::*, Duration};
+use serde::{Deserialize, Serialize};
+use diesel::{PgConnection, ExpressionMethods, BoolExpressionMethods};
+use diesel::{RunQueryDsl, QueryDsl};
+use uuid::Uuid;
+
+use crate::schema::*;
+use crate::errors::CustomError;
+use crate::models::{Capability, CapabilityLevel, OrgOwnership, OrgTier, OwnershipType, Person,
+    ReportingRelationship, Skill, Team, TeamOwnership};
+
+/// Months ahead in which retirement eligibility counts as a risk when none are asked for
+pub const DEFAULT_RETIREMENT_HORIZON_MONTHS: u32 = 24;
+
+/// Level at which someone can carry a skill on their own
+pub const ADEQUATE_LEVEL: CapabilityLevel = CapabilityLevel::Experienced;
+
+/// Successors listed per critical position
+const MAX_SUCCESSORS: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum CriticalPositionKind {
+    TeamOwner,
+    OrgTierOwner,
+    SoleSkillHolder, // Only person holding a skill at ADEQUATE_LEVEL
+}
+
+impl fmt::Display for CriticalPositionKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            CriticalPositionKind::TeamOwner => "team_owner",
+            CriticalPositionKind::OrgTierOwner => "org_tier_owner",
+            CriticalPositionKind::SoleSkillHolder => "sole_skill_holder",
+        };
+        f.write_str(s)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+/// High: no ready successor and the incumbent can retire within the horizon.
+/// Medium: either one. Low: neither.
+pub enum SuccessionRisk {
+    High,
+    Medium,
+    Low,
+}
+
+impl fmt::Display for SuccessionRisk {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            SuccessionRisk::High => "high",
+            SuccessionRisk::Medium => "medium",
+            SuccessionRisk::Low => "low",
+        };
+        f.write_str(s)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Successor {
+    pub person: Person,
+    pub direct_report: bool,
+    pub matched_skills: usize, // Key skills held at an adequate level
+    pub ready: bool, // Holds every key skill at an adequate level
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+/// Position whose loss would leave a team, tier or skill uncovered
+pub struct CriticalPosition {
+    pub kind: String, // CriticalPositionKind
+    pub person: Person,
+    pub team: Option<Team>,
+    pub org_tier: Option<OrgTier>,
+    pub skill: Option<Skill>,
+    pub key_skills: usize, // Skills the incumbent holds at ADEQUATE_LEVEL or above
+    pub successors: Vec<Successor>, // Ready first, then direct reports
+    pub ready_successor: bool,
+    pub retirement_eligible: bool, // Eligible now or within the horizon
+    pub risk: String, // SuccessionRisk
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+/// Critical positions of an organization, or every organization, highest risk first
+pub struct SuccessionReport {
+    pub organization_id: Option<Uuid>,
+    pub horizon_months: u32,
+    pub positions: Vec<CriticalPosition>,
+}
+
+/// What the report knows about the people in scope
+struct Workforce {
+    persons: HashMap<Uuid, Person>,
+    levels: HashMap<Uuid, HashMap<Uuid, i32>>, // Person -> skill -> level
+    reports: HashMap<Uuid, HashSet<Uuid>>, // Manager -> current direct reports
+}
+
+impl Workforce {
+    fn level(&self, person_id: Uuid, skill_id: Uuid) -> i32 {
+        self.levels.get(&person_id).and_then(|l| l.get(&skill_id)).cloned().unwrap_or(-1)
+    }
+
+    fn is_direct_report(&self, manager_id: Uuid, person_id: Uuid) -> bool {
+        self.reports.get(&manager_id).map_or(false, |r| r.contains(&person_id))
+    }
+
+    /// A successor can step in if they hold each of the incumbent's key skills at
+    /// ADEQUATE_LEVEL and no more than one level below the incumbent
+    fn successors_to(&self, incumbent: &Person) -> (usize, Vec<Successor>) {
+        let adequate = ADEQUATE_LEVEL.as_i32();
+
+        let key_skills: Vec<(Uuid, i32)> = self.levels.get(&incumbent.id)
+            .map(|l| l.iter()
+                .filter(|(_, level)| **level >= adequate)
+                .map(|(skill_id, level)| (*skill_id, (*level - 1).max(adequate)))
+                .collect())
+            .unwrap_or_default();
+
+        let mut successors: Vec<Successor> = self.persons.values()
+            .filter(|p| p.id != incumbent.id && p.organization_id == incumbent.organization_id)
+            .filter_map(|p| {
+                let direct_report = self.is_direct_report(incumbent.id, p.id);
+                let matched_skills = key_skills.iter()
+                    .filter(|(skill_id, needed)| self.level(p.id, *skill_id) >= *needed)
+                    .count();
+
+                if matched_skills == 0 && !direct_report {
+                    return None
+                };
+
+                Some(Successor {
+                    person: p.clone(),
+                    direct_report,
+                    matched_skills,
+                    ready: !key_skills.is_empty() && matched_skills == key_skills.len(),
+                })
+            })
+            .collect();
+
+        sort_successors(&mut successors);
+
+        (key_skills.len(), successors)
+    }
+
+    /// Nobody else holds the skill at an adequate level, so list those developing it
+    fn developing(&self, holder: &Person, skill_id: Uuid) -> Vec<Successor> {
+        let mut successors: Vec<Successor> = self.persons.values()
+            .filter(|p| p.id != holder.id && p.organization_id == holder.organization_id)
+            .filter(|p| self.level(p.id, skill_id) >= CapabilityLevel::Novice.as_i32())
+            .map(|p| Successor {
+                person: p.clone(),
+                direct_report: self.is_direct_report(holder.id, p.id),
+                matched_skills: 0,
+                ready: false,
+            })
+            .collect();
+
+        successors.sort_by(|a, b| self.level(b.person.id, skill_id).cmp(&self.level(a.person.id, skill_id))
+            .then_with(|| b.direct_report.cmp(&a.direct_report))
+            .then_with(|| a.person.family_name.cmp(&b.person.family_name)));
+        successors.truncate(MAX_SUCCESSORS);
+
+        successors
+    }
+}
+
+fn sort_successors(successors: &mut Vec<Successor>) {
+    successors.sort_by(|a, b| b.ready.cmp(&a.ready)
+        .then_with(|| b.direct_report.cmp(&a.direct_report))
+        .then_with(|| b.matched_skills.cmp(&a.matched_skills))
+        .then_with(|| a.person.family_name.cmp(&b.person.family_name)));
+    successors.truncate(MAX_SUCCESSORS);
+}
+
+impl SuccessionReport {
+    pub fn build(
+        conn: &PgConnection,
+        organization_id: Option<Uuid>,
+        horizon_months: u32,
+        as_of: NaiveDateTime,
+    ) -> Result<SuccessionReport, CustomError> {
+        let mut query = persons::table
+            .filter(persons::retired_at.is_null())
+            .into_boxed();
+
+        if let Some(organization_id) = organization_id {
+            query = query.filter(persons::organization_id.eq(organization_id));
+        };
+
+        let persons: HashMap<Uuid, Person> = query.load::<Person>(conn)?
+            .into_iter()
+            .map(|p| (p.id, p))
+            .collect();
+
+        let person_ids: Vec<Uuid> = persons.keys().cloned().collect();
+
+        let mut levels: HashMap<Uuid, HashMap<Uuid, i32>> = HashMap::new();
+
+        for capability in capabilities::table
+            .filter(capabilities::person_id.eq_any(&person_ids))
+            .load::<Capability>(conn)? {
+            levels.entry(capability.person_id).or_insert_with(HashMap::new)
+                .insert(capability.skill_id, capability.self_identified_level);
+        };
+
+        let mut reports: HashMap<Uuid, HashSet<Uuid>> = HashMap::new();
+
+        for line in reporting_relationships::table
+            .filter(reporting_relationships::reporting_to.eq_any(&person_ids))
+            .filter(reporting_relationships::start_date.le(as_of))
+            .filter(reporting_relationships::end_date.is_null().or(reporting_relationships::end_date.gt(as_of)))
+            .load::<ReportingRelationship>(conn)? {
+            reports.entry(line.reporting_to).or_insert_with(HashSet::new).insert(line.reporter);
+        };
+
+        let workforce = Workforce { persons, levels, reports };
+
+        let horizon = as_of.date() + Duration::days((f64::from(horizon_months) * 365.25 / 12.0) as i64);
+
+        let position = |kind: CriticalPositionKind, person: &Person, team: Option<Team>, org_tier: Option<OrgTier>,
+            skill: Option<Skill>, key_skills: usize, successors: Vec<Successor>| {
+
+            let ready_successor = successors.iter().any(|s| s.ready);
+            let retirement_eligible = person.retirement_eligible_on.map_or(false, |d| d <= horizon);
+
+            let risk = match (ready_successor, retirement_eligible) {
+                (false, true) => SuccessionRisk::High,
+                (true, false) => SuccessionRisk::Low,
+                _ => SuccessionRisk::Medium,
+            };
+
+            (risk, CriticalPosition {
+                kind: kind.to_string(),
+                person: person.clone(),
+                team,
+                org_tier,
+                skill,
+                key_skills,
+                successors,
+                ready_successor,
+                retirement_eligible,
+                risk: risk.to_string(),
+            })
+        };
+
+        let mut positions: Vec<(SuccessionRisk, CriticalPosition)> = Vec::new();
+
+        let team_owners = team_ownerships::table
+            .inner_join(teams::table)
+            .filter(team_ownerships::person_id.eq_any(&person_ids))
+            .filter(team_ownerships::ownership_type.eq(OwnershipType::Owner.to_string()))
+            .filter(team_ownerships::start_datestamp.le(as_of))
+            .filter(team_ownerships::end_date.is_null().or(team_ownerships::end_date.gt(as_of)))
+            .filter(teams::retired_at.is_null())
+            .load::<(TeamOwnership, Team)>(conn)?;
+
+        for (ownership, team) in team_owners {
+            let person = &workforce.persons[&ownership.person_id];
+            let (key_skills, successors) = workforce.successors_to(person);
+            positions.push(position(CriticalPositionKind::TeamOwner, person, Some(team), None, None, key_skills, successors));
+        };
+
+        let tier_owners = org_tier_ownerships::table
+            .inner_join(org_tiers::table)
+            .filter(org_tier_ownerships::owner_id.eq_any(&person_ids))
+            .filter(org_tier_ownerships::ownership_type.eq(OwnershipType::Owner.to_string()))
+            .filter(org_tier_ownerships::start_datestamp.le(as_of))
+            .filter(org_tier_ownerships::retired_at.is_null().or(org_tier_ownerships::retired_at.gt(as_of)))
+            .filter(org_tiers::retired_at.is_null())
+            .load::<(OrgOwnership, OrgTier)>(conn)?;
+
+        for (ownership, org_tier) in tier_owners {
+            let person = &workforce.persons[&ownership.owner_id];
+            let (key_skills, successors) = workforce.successors_to(person);
+            positions.push(position(CriticalPositionKind::OrgTierOwner, person, None, Some(org_tier), None, key_skills, successors));
+        };
+
+        // Adequate holders of each skill, per organization
+        let mut holders: HashMap<(Uuid, Uuid), Vec<Uuid>> = HashMap::new();
+
+        for (person_id, skills) in &workforce.levels {
+            let organization_id = workforce.persons[person_id].organization_id;
+            for (skill_id, level) in skills {
+                if *level >= ADEQUATE_LEVEL.as_i32() {
+                    holders.entry((organization_id, *skill_id)).or_insert_with(Vec::new).push(*person_id);
+                };
+            };
+        };
+
+        let sole: Vec<(Uuid, Uuid)> = holders.iter()
+            .filter(|(_, h)| h.len() == 1)
+            .map(|((_, skill_id), h)| (*skill_id, h[0]))
+            .collect();
+
+        let skill_ids: Vec<Uuid> = sole.iter().map(|(s, _)| *s).collect();
+
+        let skills: HashMap<Uuid, Skill> = skills::table
+            .filter(skills::id.eq_any(&skill_ids))
+            .load::<Skill>(conn)?
+            .into_iter()
+            .map(|s| (s.id, s))
+            .collect();
+
+        for (skill_id, person_id) in sole {
+            let person = &workforce.persons[&person_id];
+            let successors = workforce.developing(person, skill_id);
+            positions.push(position(CriticalPositionKind::SoleSkillHolder, person, None, None, skills.get(&skill_id).cloned(), 1, successors));
+        };
+
+        positions.sort_by(|(ra, a), (rb, b)| ra.cmp(rb)
+            .then_with(|| a.person.family_name.cmp(&b.person.family_name))
+            .then_with(|| a.kind.cmp(&b.kind)));
+
+        Ok(SuccessionReport {
+            organization_id,
+            horizon_months,
+            positions: positions.into_iter().map(|(_, p)| p).collect(),
+        })
+    }
+}
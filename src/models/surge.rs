@@ -0,0 +1,742 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::{prelude::*};
+use serde::{Deserialize, Serialize};
+use diesel::{self, Insertable, PgConnection, Queryable, ExpressionMethods, BoolExpressionMethods};
+use diesel::{Connection, RunQueryDsl, QueryDsl};
+use uuid::Uuid;
+use async_graphql::*;
+
+use crate::graphql::graphql_translate;
+
+use crate::schema::*;
+use crate::errors::CustomError;
+use crate::models::{CapabilityLevel, Credential, DeploymentFatigue, DEFAULT_FATIGUE_WINDOW_MONTHS, NewRole, Organization, Person, Role, RoleType, Skill, Team};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum SurgeEventStatus {
+    Active,
+    Closed,
+}
+
+impl fmt::Display for SurgeEventStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            SurgeEventStatus::Active => "active",
+            SurgeEventStatus::Closed => "closed",
+        };
+        f.write_str(s)
+    }
+}
+
+impl FromStr for SurgeEventStatus {
+    type Err = CustomError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "active" => Ok(SurgeEventStatus::Active),
+            "closed" => Ok(SurgeEventStatus::Closed),
+            _ => Err(CustomError::new(400, format!("Unknown surge event status: {}", s))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, Queryable, Insertable, AsChangeset, SimpleObject)]
+#[table_name = "surge_events"]
+/// A public health emergency or other event people are mobilized to.
+/// Deployments are temporary Roles on the response team.
+pub struct SurgeEvent {
+    pub id: Uuid,
+    pub name_en: String,
+    pub name_fr: String,
+    pub description_en: String,
+    pub description_fr: String,
+    pub team_id: Uuid, // Team deployments are made to
+    pub headcount: i32, // People needed at once
+    pub deployment_effort: f64, // Default effort of a deployment
+    pub languages: Vec<String>, // ISO 639-1 codes every deployed person must work in
+    pub status: String, // SurgeEventStatus
+    pub start_date: NaiveDateTime,
+    pub end_date: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, Queryable, Insertable, SimpleObject)]
+#[table_name = "surge_requirements"]
+/// A skill at a minimum level, or a credential matched on its
+/// provider or description
+pub struct SurgeRequirement {
+    pub id: Uuid,
+    pub surge_event_id: Uuid,
+    pub skill_id: Option<Uuid>,
+    pub min_level: i32, // CapabilityLevel
+    pub credential: Option<String>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+/// Requirement with its Skill for display
+pub struct SurgeRequirementWithSkill {
+    pub requirement: SurgeRequirement,
+    pub skill: Option<Skill>,
+    pub level_key: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, Queryable, Insertable, SimpleObject)]
+#[table_name = "surge_deployments"]
+/// Links a Person's deployment Role to the SurgeEvent it serves
+pub struct SurgeDeployment {
+    pub id: Uuid,
+    pub surge_event_id: Uuid,
+    pub person_id: Uuid,
+    pub role_id: Uuid,
+    pub created_at: NaiveDateTime,
+    pub released_at: Option<NaiveDateTime>, // Ended before the role's planned end_date
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+/// Deployment with its event, person and role for display
+pub struct DeploymentRecord {
+    pub deployment: SurgeDeployment,
+    pub event: SurgeEvent,
+    pub person: Person,
+    pub role: Role,
+}
+
+impl DeploymentRecord {
+    /// Under way or yet to start
+    pub fn is_open(&self, as_of: NaiveDateTime) -> bool {
+        self.deployment.released_at.is_none() && self.role.end_date.map_or(true, |e| e > as_of)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+/// Event with its requirements and deployments
+pub struct SurgeEventProfile {
+    pub event: SurgeEvent,
+    pub team: Team,
+    pub requirements: Vec<SurgeRequirementWithSkill>,
+    pub current_deployments: Vec<DeploymentRecord>, // Including upcoming
+    pub past_deployments: Vec<DeploymentRecord>,
+    pub open_positions: i32,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SurgeSearch {
+    pub organization_id: Option<Uuid>, // Any department when None
+    pub include_unavailable: bool, // Include people without the effort headroom
+    pub window_months: u32, // Deployment history weighed in the rotation
+}
+
+impl Default for SurgeSearch {
+    fn default() -> Self {
+        SurgeSearch {
+            organization_id: None,
+            include_unavailable: false,
+            window_months: DEFAULT_FATIGUE_WINDOW_MONTHS,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+/// Person who meets every requirement of an event
+pub struct SurgeCandidate {
+    pub person: Person,
+    pub organization: Organization,
+    pub matched: Vec<String>, // Requirements met, e.g. "Epidemiology (level-expert)"
+    pub current_effort: f64, // Sum of effort of current roles
+    pub headroom: f64,
+    pub available: bool,
+    pub fatigue: DeploymentFatigue,
+}
+
+impl SurgeEvent {
+    pub fn create(conn: &PgConnection, event: &NewSurgeEvent) -> FieldResult<SurgeEvent> {
+        let res = diesel::insert_into(surge_events::table)
+            .values(event)
+            .get_result(conn);
+
+        graphql_translate(res)
+    }
+
+    pub fn find(conn: &PgConnection, id: Uuid) -> Result<Self, CustomError> {
+        let event = surge_events::table.find(id).first(conn)?;
+        Ok(event)
+    }
+
+    /// Active events first, most recent first
+    pub fn find_all(conn: &PgConnection) -> Result<Vec<Self>, CustomError> {
+        let events = surge_events::table
+            .order((surge_events::status.asc(), surge_events::start_date.desc()))
+            .load::<SurgeEvent>(conn)?;
+        Ok(events)
+    }
+
+    pub fn update(&self, conn: &PgConnection) -> FieldResult<Self> {
+        let res = diesel::update(surge_events::table)
+            .filter(surge_events::id.eq(self.id))
+            .set(self)
+            .get_result(conn);
+
+        graphql_translate(res)
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.status == SurgeEventStatus::Active.to_string()
+    }
+
+    pub fn requirements(&self, conn: &PgConnection) -> Result<Vec<SurgeRequirementWithSkill>, CustomError> {
+        let res = surge_requirements::table
+            .left_join(skills::table)
+            .filter(surge_requirements::surge_event_id.eq(self.id))
+            .order(surge_requirements::created_at.asc())
+            .load::<(SurgeRequirement, Option<Skill>)>(conn)?;
+
+        Ok(res.into_iter()
+            .map(|(requirement, skill)| {
+                let level_key = CapabilityLevel::from_i32(requirement.min_level).key().to_string();
+                SurgeRequirementWithSkill { requirement, skill, level_key }
+            })
+            .collect())
+    }
+
+    pub fn profile(&self, conn: &PgConnection) -> Result<SurgeEventProfile, CustomError> {
+        let team = teams::table.find(self.team_id).first::<Team>(conn)?;
+
+        let now = Utc::now().naive_utc();
+
+        let (current_deployments, past_deployments): (Vec<DeploymentRecord>, Vec<DeploymentRecord>) =
+            SurgeDeployment::find_by_event(conn, self.id)?
+                .into_iter()
+                .partition(|d| d.is_open(now));
+
+        Ok(SurgeEventProfile {
+            event: self.clone(),
+            team,
+            requirements: self.requirements(conn)?,
+            open_positions: (self.headcount - current_deployments.len() as i32).max(0),
+            current_deployments,
+            past_deployments,
+        })
+    }
+
+    /// People in any organization, or the one searched, who meet every
+    /// requirement and language of the event and haven't been deployed to it.
+    /// Available people come first, ranked by rotation so recently or often
+    /// deployed people are asked last, then by headroom.
+    pub fn find_candidates(&self, conn: &PgConnection, search: &SurgeSearch) -> Result<Vec<SurgeCandidate>, CustomError> {
+        let now = Utc::now().naive_utc();
+        let requirements = self.requirements(conn)?;
+
+        let mut query = persons::table
+            .filter(persons::retired_at.is_null())
+            .into_boxed();
+
+        if let Some(organization_id) = search.organization_id {
+            query = query.filter(persons::organization_id.eq(organization_id));
+        };
+
+        let persons: Vec<Person> = query
+            .load::<Person>(conn)?
+            .into_iter()
+            .filter(|p| self.languages.iter().all(|l| p.languages.contains(l)))
+            .collect();
+
+        let person_ids: Vec<Uuid> = persons.iter().map(|p| p.id).collect();
+
+        // A person is deployed to an event at most once
+        let deployed: Vec<Uuid> = SurgeDeployment::find_by_event(conn, self.id)?
+            .into_iter()
+            .map(|d| d.person.id)
+            .collect();
+
+        let levels = load_levels(conn, &person_ids)?;
+        let credentials = load_credentials(conn, &requirements, &person_ids)?;
+
+        let mut effort: HashMap<Uuid, f64> = HashMap::new();
+
+        for role in roles::table
+            .filter(roles::person_id.eq_any(&person_ids))
+            .filter(roles::active.eq(true))
+            .filter(roles::start_datestamp.le(now))
+            .filter(roles::end_date.is_null().or(roles::end_date.gt(now)))
+            .load::<Role>(conn)? {
+            *effort.entry(role.person_id).or_insert(0.0) += role.effort;
+        };
+
+        let mut fatigue = DeploymentFatigue::find_by_persons(conn, &person_ids, search.window_months, now)?;
+
+        let organizations: HashMap<Uuid, Organization> = organizations::table
+            .load::<Organization>(conn)?
+            .into_iter()
+            .map(|o| (o.id, o))
+            .collect();
+
+        let mut candidates: Vec<SurgeCandidate> = persons.into_iter()
+            .filter(|p| !deployed.contains(&p.id))
+            .filter_map(|person| {
+                let matched = match_requirements(&requirements, &levels, &credentials, person.id)?;
+
+                let current_effort = effort.get(&person.id).copied().unwrap_or(0.0);
+                let headroom = (1.0 - current_effort).max(0.0);
+                let available = headroom + f64::EPSILON >= self.deployment_effort;
+
+                if !available && !search.include_unavailable {
+                    return None
+                };
+
+                Some(SurgeCandidate {
+                    organization: organizations.get(&person.organization_id)?.clone(),
+                    fatigue: fatigue.remove(&person.id)?,
+                    person,
+                    matched,
+                    current_effort,
+                    headroom,
+                    available,
+                })
+            })
+            .collect();
+
+        candidates.sort_by(|a, b| b.available.cmp(&a.available)
+            .then_with(|| a.fatigue.rotation_cmp(&b.fatigue))
+            .then_with(|| b.headroom.partial_cmp(&a.headroom).unwrap_or(std::cmp::Ordering::Equal))
+            .then_with(|| a.person.family_name.cmp(&b.person.family_name)));
+
+        Ok(candidates)
+    }
+
+    /// Deploy a person as a temporary Role on the response team. Their
+    /// substantive role carries on alongside the deployment.
+    /// The person must meet every requirement and language of the event, must not
+    /// have been deployed to it before, and the event must have an open position.
+    pub fn deploy(
+        &self,
+        conn: &PgConnection,
+        person: &Person,
+        effort: f64,
+        start_date: NaiveDateTime,
+        end_date: NaiveDateTime,
+        note: &str,
+    ) -> FieldResult<SurgeDeployment> {
+        if !self.is_active() {
+            return Err(FieldError::new("Surge event is closed"));
+        };
+
+        if !self.languages.iter().all(|l| person.languages.contains(l)) {
+            return Err(FieldError::new(format!("{} {} does not work in every language the event requires",
+                person.given_name, person.family_name)));
+        };
+
+        let new_role = NewRole::new(
+            Uuid::new_v4(),
+            person.id,
+            self.team_id,
+            format!("Surge deployment: {}", self.name_en),
+            format!("Déploiement d'urgence : {}", self.name_fr),
+            effort,
+            true,
+            start_date,
+            Some(end_date),
+            RoleType::Deployment,
+            None,
+        );
+
+        conn.transaction::<SurgeDeployment, FieldError, _>(|| {
+            // Hold the event until the transaction ends, so deployments to it are checked one at a time
+            surge_events::table
+                .select(surge_events::id)
+                .filter(surge_events::id.eq(self.id))
+                .for_update()
+                .first::<Uuid>(conn)?;
+
+            let deployments = SurgeDeployment::find_by_event(conn, self.id)?;
+
+            if deployments.iter().any(|d| d.person.id == person.id) {
+                return Err(FieldError::new(format!("{} {} has already been deployed to this event",
+                    person.given_name, person.family_name)));
+            };
+
+            if deployments.iter().filter(|d| d.is_open(start_date)).count() as i32 >= self.headcount {
+                return Err(FieldError::new(format!("The event already has its {} people deployed", self.headcount)));
+            };
+
+            let requirements = self.requirements(conn)?;
+            let person_ids = vec![person.id];
+
+            let levels = load_levels(conn, &person_ids)?;
+            let credentials = load_credentials(conn, &requirements, &person_ids)?;
+
+            if match_requirements(&requirements, &levels, &credentials, person.id).is_none() {
+                return Err(FieldError::new(format!("{} {} does not meet every skill and credential the event requires",
+                    person.given_name, person.family_name)));
+            };
+
+            let role = Role::assign_temporary(conn, &new_role, RoleType::Deployment, note)?;
+
+            let res = diesel::insert_into(surge_deployments::table)
+                .values(&NewSurgeDeployment {
+                    surge_event_id: self.id,
+                    person_id: person.id,
+                    role_id: role.id,
+                })
+                .get_result(conn);
+
+            graphql_translate(res)
+        })
+    }
+
+    /// Close the event and release everyone still deployed to it
+    pub fn close(&self, conn: &PgConnection, as_of: NaiveDateTime) -> FieldResult<SurgeEvent> {
+        conn.transaction::<SurgeEvent, FieldError, _>(|| {
+            for record in SurgeDeployment::find_by_event(conn, self.id)? {
+                if record.is_open(as_of) {
+                    record.deployment.release(conn, as_of, "Surge event closed")?;
+                };
+            };
+
+            let mut event = self.clone();
+            event.status = SurgeEventStatus::Closed.to_string();
+            event.end_date = Some(event.end_date.map_or(as_of, |e| e.min(as_of)));
+            event.updated_at = Utc::now().naive_utc();
+
+            event.update(conn)
+        })
+    }
+}
+
+/// Self-identified skill levels by (person, skill)
+fn load_levels(conn: &PgConnection, person_ids: &[Uuid]) -> Result<HashMap<(Uuid, Uuid), i32>, CustomError> {
+    let mut levels = HashMap::new();
+
+    for (person_id, skill_id, level) in capabilities::table
+        .filter(capabilities::person_id.eq_any(person_ids))
+        .select((capabilities::person_id, capabilities::skill_id, capabilities::self_identified_level))
+        .load::<(Uuid, Uuid, i32)>(conn)? {
+        levels.insert((person_id, skill_id), level);
+    };
+
+    Ok(levels)
+}
+
+/// Credentials by person, only loaded if a requirement asks for one
+fn load_credentials(conn: &PgConnection, requirements: &[SurgeRequirementWithSkill], person_ids: &[Uuid]) -> Result<HashMap<Uuid, Vec<Credential>>, CustomError> {
+    let mut credentials: HashMap<Uuid, Vec<Credential>> = HashMap::new();
+
+    if requirements.iter().any(|r| r.requirement.credential.is_some()) {
+        for credential in credentials::table
+            .filter(credentials::person_id.eq_any(person_ids))
+            .load::<Credential>(conn)? {
+            credentials.entry(credential.person_id).or_insert_with(Vec::new).push(credential);
+        };
+    };
+
+    Ok(credentials)
+}
+
+/// Requirements met by the person, e.g. "Epidemiology (level-expert)",
+/// or None if they miss any of them
+fn match_requirements(
+    requirements: &[SurgeRequirementWithSkill],
+    levels: &HashMap<(Uuid, Uuid), i32>,
+    credentials: &HashMap<Uuid, Vec<Credential>>,
+    person_id: Uuid,
+) -> Option<Vec<String>> {
+    let mut matched = Vec::new();
+
+    for r in requirements {
+        if let Some(skill) = &r.skill {
+            let level = levels.get(&(person_id, skill.id))?;
+            if *level < r.requirement.min_level {
+                return None
+            };
+            matched.push(format!("{} ({})", skill.name_en, CapabilityLevel::from_i32(*level).key()));
+        };
+
+        if let Some(wanted) = &r.requirement.credential {
+            let wanted = wanted.to_lowercase();
+            let credential = credentials.get(&person_id)?.iter().find(|c| {
+                c.description.to_lowercase().contains(&wanted) || c.provider.to_lowercase().contains(&wanted)
+            })?;
+            matched.push(format!("{} ({})", credential.description, credential.provider));
+        };
+    };
+
+    Some(matched)
+}
+
+impl SurgeRequirement {
+    pub fn create(conn: &PgConnection, requirement: &NewSurgeRequirement) -> FieldResult<SurgeRequirement> {
+        let res = diesel::insert_into(surge_requirements::table)
+            .values(requirement)
+            .get_result(conn);
+
+        graphql_translate(res)
+    }
+
+    pub fn delete(conn: &PgConnection, id: Uuid) -> Result<SurgeRequirement, CustomError> {
+        let res = diesel::delete(surge_requirements::table.find(id)).get_result(conn)?;
+        Ok(res)
+    }
+}
+
+impl SurgeDeployment {
+    pub fn find(conn: &PgConnection, id: Uuid) -> Result<Self, CustomError> {
+        let deployment = surge_deployments::table.find(id).first(conn)?;
+        Ok(deployment)
+    }
+
+    fn load_records(conn: &PgConnection, rows: Vec<SurgeDeployment>) -> Result<Vec<DeploymentRecord>, CustomError> {
+        let event_ids: Vec<Uuid> = rows.iter().map(|d| d.surge_event_id).collect();
+        let person_ids: Vec<Uuid> = rows.iter().map(|d| d.person_id).collect();
+        let role_ids: Vec<Uuid> = rows.iter().map(|d| d.role_id).collect();
+
+        let events: HashMap<Uuid, SurgeEvent> = surge_events::table
+            .filter(surge_events::id.eq_any(&event_ids))
+            .load::<SurgeEvent>(conn)?
+            .into_iter()
+            .map(|e| (e.id, e))
+            .collect();
+
+        let persons: HashMap<Uuid, Person> = persons::table
+            .filter(persons::id.eq_any(&person_ids))
+            .load::<Person>(conn)?
+            .into_iter()
+            .map(|p| (p.id, p))
+            .collect();
+
+        let roles: HashMap<Uuid, Role> = roles::table
+            .filter(roles::id.eq_any(&role_ids))
+            .load::<Role>(conn)?
+            .into_iter()
+            .map(|r| (r.id, r))
+            .collect();
+
+        Ok(rows.into_iter()
+            .filter_map(|deployment| Some(DeploymentRecord {
+                event: events.get(&deployment.surge_event_id)?.clone(),
+                person: persons.get(&deployment.person_id)?.clone(),
+                role: roles.get(&deployment.role_id)?.clone(),
+                deployment,
+            }))
+            .collect())
+    }
+
+    pub fn find_by_event(conn: &PgConnection, surge_event_id: Uuid) -> Result<Vec<DeploymentRecord>, CustomError> {
+        let rows = surge_deployments::table
+            .filter(surge_deployments::surge_event_id.eq(surge_event_id))
+            .order(surge_deployments::created_at.asc())
+            .load::<SurgeDeployment>(conn)?;
+
+        SurgeDeployment::load_records(conn, rows)
+    }
+
+    /// Deployment history of a person, most recent first
+    pub fn find_by_person(conn: &PgConnection, person_id: Uuid) -> Result<Vec<DeploymentRecord>, CustomError> {
+        let rows = surge_deployments::table
+            .filter(surge_deployments::person_id.eq(person_id))
+            .order(surge_deployments::created_at.desc())
+            .load::<SurgeDeployment>(conn)?;
+
+        SurgeDeployment::load_records(conn, rows)
+    }
+
+    /// End the deployment role early, on end_date
+    pub fn release(&self, conn: &PgConnection, end_date: NaiveDateTime, note: &str) -> FieldResult<SurgeDeployment> {
+        if self.released_at.is_some() {
+            return Err(FieldError::new("Deployment has already been released"));
+        };
+
+        conn.transaction::<SurgeDeployment, FieldError, _>(|| {
+            let role = graphql_translate(roles::table.find(self.role_id).first::<Role>(conn))?;
+
+            if role.end_date.map_or(true, |e| e > end_date) {
+                role.end_role(conn, end_date, note)?;
+            };
+
+            let res = diesel::update(surge_deployments::table.find(self.id))
+                .set(surge_deployments::released_at.eq(Some(end_date)))
+                .get_result(conn);
+
+            graphql_translate(res)
+        })
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, Insertable)]
+#[table_name = "surge_events"]
+pub struct NewSurgeEvent {
+    pub name_en: String,
+    pub name_fr: String,
+    pub description_en: String,
+    pub description_fr: String,
+    pub team_id: Uuid,
+    pub headcount: i32,
+    pub deployment_effort: f64,
+    pub languages: Vec<String>,
+    pub start_date: NaiveDateTime,
+    pub end_date: Option<NaiveDateTime>,
+}
+
+impl NewSurgeEvent {
+    pub fn new(
+        name_en: String,
+        name_fr: String,
+        description_en: String,
+        description_fr: String,
+        team_id: Uuid,
+        headcount: i32,
+        deployment_effort: f64,
+        languages: Vec<String>,
+        start_date: NaiveDateTime,
+        end_date: Option<NaiveDateTime>,
+    ) -> Self {
+        NewSurgeEvent {
+            name_en,
+            name_fr,
+            description_en,
+            description_fr,
+            team_id,
+            headcount,
+            deployment_effort,
+            languages,
+            start_date,
+            end_date,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, Insertable)]
+#[table_name = "surge_requirements"]
+pub struct NewSurgeRequirement {
+    pub surge_event_id: Uuid,
+    pub skill_id: Option<Uuid>,
+    pub min_level: i32,
+    pub credential: Option<String>,
+}
+
+impl NewSurgeRequirement {
+    pub fn skill(surge_event_id: Uuid, skill_id: Uuid, min_level: CapabilityLevel) -> Self {
+        NewSurgeRequirement {
+            surge_event_id,
+            skill_id: Some(skill_id),
+            min_level: min_level.as_i32(),
+            credential: None,
+        }
+    }
+
+    pub fn credential(surge_event_id: Uuid, credential: String) -> Self {
+        NewSurgeRequirement {
+            surge_event_id,
+            skill_id: None,
+            min_level: 0,
+            credential: Some(credential),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, Insertable)]
+#[table_name = "surge_deployments"]
+struct NewSurgeDeployment {
+    pub surge_event_id: Uuid,
+    pub person_id: Uuid,
+    pub role_id: Uuid,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn skill(name_en: &str) -> Skill {
+        let now = Utc::now().naive_utc();
+
+        Skill {
+            id: Uuid::new_v4(),
+            name_en: name_en.to_string(),
+            name_fr: name_en.to_string(),
+            description_en: String::new(),
+            description_fr: String::new(),
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    fn requirement(skill: Option<&Skill>, min_level: CapabilityLevel, credential: Option<&str>) -> SurgeRequirementWithSkill {
+        SurgeRequirementWithSkill {
+            requirement: SurgeRequirement {
+                id: Uuid::new_v4(),
+                surge_event_id: Uuid::nil(),
+                skill_id: skill.map(|s| s.id),
+                min_level: min_level.as_i32(),
+                credential: credential.map(|c| c.to_string()),
+                created_at: Utc::now().naive_utc(),
+            },
+            skill: skill.cloned(),
+            level_key: min_level.key().to_string(),
+        }
+    }
+
+    fn credential(person_id: Uuid, provider: &str, description: &str) -> Credential {
+        let now = Utc::now().naive_utc();
+
+        Credential {
+            id: Uuid::new_v4(),
+            person_id,
+            provider: provider.to_string(),
+            description: description.to_string(),
+            received_date: now.date(),
+            validated: true,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    #[test]
+    fn skill_requirements_need_the_minimum_level() {
+        let person_id = Uuid::new_v4();
+        let epidemiology = skill("Epidemiology");
+        let requirements = vec![requirement(Some(&epidemiology), CapabilityLevel::Experienced, None)];
+
+        let mut levels = HashMap::new();
+        assert_eq!(match_requirements(&requirements, &levels, &HashMap::new(), person_id), None);
+
+        levels.insert((person_id, epidemiology.id), CapabilityLevel::Novice.as_i32());
+        assert_eq!(match_requirements(&requirements, &levels, &HashMap::new(), person_id), None);
+
+        levels.insert((person_id, epidemiology.id), CapabilityLevel::Expert.as_i32());
+        assert_eq!(match_requirements(&requirements, &levels, &HashMap::new(), person_id),
+            Some(vec!["Epidemiology (level-expert)".to_string()]));
+    }
+
+    #[test]
+    fn credential_requirements_match_provider_or_description() {
+        let person_id = Uuid::new_v4();
+        let requirements = vec![requirement(None, CapabilityLevel::Desired, Some("first aid"))];
+
+        let mut credentials = HashMap::new();
+        credentials.insert(person_id, vec![credential(person_id, "Red Cross", "Standard First Aid")]);
+        assert_eq!(match_requirements(&requirements, &HashMap::new(), &credentials, person_id),
+            Some(vec!["Standard First Aid (Red Cross)".to_string()]));
+
+        credentials.insert(person_id, vec![credential(person_id, "St. John Ambulance", "CPR level C")]);
+        assert_eq!(match_requirements(&requirements, &HashMap::new(), &credentials, person_id), None);
+    }
+
+    #[test]
+    fn every_requirement_must_be_met() {
+        let person_id = Uuid::new_v4();
+        let epidemiology = skill("Epidemiology");
+        let requirements = vec![
+            requirement(Some(&epidemiology), CapabilityLevel::Novice, None),
+            requirement(None, CapabilityLevel::Desired, Some("first aid")),
+        ];
+
+        let mut levels = HashMap::new();
+        levels.insert((person_id, epidemiology.id), CapabilityLevel::Experienced.as_i32());
+
+        assert_eq!(match_requirements(&requirements, &levels, &HashMap::new(), person_id), None);
+        assert_eq!(match_requirements(&[], &HashMap::new(), &HashMap::new(), person_id), Some(vec![]));
+    }
+}
@@ -1,8 +1,10 @@
+use std::collections::HashMap;
 use std::fmt::Debug;
 
 use chrono::{prelude::*};
 use serde::{Deserialize, Serialize};
-use diesel::{self, Insertable, PgConnection, Queryable, ExpressionMethods};
+use diesel::{self, Insertable, PgConnection, Queryable, ExpressionMethods, BoolExpressionMethods};
+use diesel::upsert::excluded;
 use diesel::{RunQueryDsl, QueryDsl};
 use uuid::Uuid;
 use async_graphql::*;
@@ -13,6 +15,7 @@ use crate::graphql::graphql_translate;
 use crate::schema::*;
 use crate::errors::CustomError;
 use crate::database::connection;
+use crate::models::{Organization, OrgTier, Person, Role, TeamOwnership, Capability, Skill, WorkWithStatus, Work};
 
 #[derive(Debug, Clone, Deserialize, Serialize, Queryable, Insertable, AsChangeset)]
 #[table_name = "teams"]
@@ -35,6 +38,40 @@ pub struct Team {
     // pub milestones: Uuid // Refers to Github Milestones
 }
 
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TeamMember {
+    pub role: Role,
+    pub person: Person,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TeamOwner {
+    pub ownership: TeamOwnership,
+    pub person: Person,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+/// How well a skill is covered by a team's current members
+pub struct SkillCoverage {
+    pub skill: Skill,
+    pub people_count: usize,
+    pub max_level: i32,
+    pub average_level: f64,
+    pub fte: f64, // Sum of effort of members holding the skill
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TeamProfile {
+    pub team: Team,
+    pub organization: Organization,
+    pub org_tier: OrgTier,
+    pub owners: Vec<TeamOwner>,
+    pub members: Vec<TeamMember>,
+    pub total_effort: f64,
+    pub skills: Vec<SkillCoverage>,
+    pub works: Vec<WorkWithStatus>,
+}
+
 // Non Graphql
 impl Team {
     pub fn create(conn: &PgConnection, team: &NewTeam) -> FieldResult<Team> {
@@ -45,24 +82,17 @@ impl Team {
         graphql_translate(res)
     }
     
+    /// Natural key: (organization_id, name_en)
+    /// Returns the existing team unchanged if the key matches.
     pub fn get_or_create(conn: &PgConnection, team: &NewTeam) -> FieldResult<Team> {
-        let res = teams::table
-        .filter(teams::name_en.eq(&team.name_en))
-        .filter(teams::name_fr.eq(&team.name_fr))
-        .filter(teams::organization_id.eq(&team.organization_id))
-        .distinct()
-        .first(conn);
-        
-        let team = match res {
-            Ok(p) => p,
-            Err(e) => {
-                // Team not found
-                println!("{:?}", e);
-                let p = Team::create(conn, team).expect("Unable to create team");
-                p
-            }
-        };
-        Ok(team)
+        let res = diesel::insert_into(teams::table)
+            .values(team)
+            .on_conflict((teams::organization_id, teams::name_en))
+            .do_update()
+            .set(teams::organization_id.eq(excluded(teams::organization_id)))
+            .get_result(conn);
+
+        graphql_translate(res)
     }
     
     pub fn update(&self, conn: &PgConnection) -> FieldResult<Self> {
@@ -73,6 +103,132 @@ impl Team {
         
         Ok(res)
     }
+
+    pub fn find_all() -> Result<Vec<Self>, CustomError> {
+        let conn = connection()?;
+        let teams = teams::table
+            .order(teams::name_en.asc())
+            .load::<Team>(&conn)?;
+        Ok(teams)
+    }
+
+    pub fn find(id: Uuid) -> Result<Self, CustomError> {
+        let conn = connection()?;
+        let team = teams::table.filter(teams::id.eq(id)).first(&conn)?;
+        Ok(team)
+    }
+
+    pub fn find_by_org_tier(org_tier_id: Uuid) -> Result<Vec<Self>, CustomError> {
+        let conn = connection()?;
+        let teams = teams::table
+            .filter(teams::org_tier_id.eq(org_tier_id))
+            .order(teams::name_en.asc())
+            .load::<Team>(&conn)?;
+        Ok(teams)
+    }
+
+    /// Current members (role holders) on as_of, highest effort first
+    pub fn find_members(conn: &PgConnection, team_id: Uuid, as_of: NaiveDateTime) -> Result<Vec<TeamMember>, CustomError> {
+        let res = roles::table
+            .inner_join(persons::table)
+            .filter(roles::team_id.eq(team_id))
+            .filter(roles::start_datestamp.le(as_of))
+            .filter(roles::end_date.is_null().or(roles::end_date.gt(as_of)))
+            .order((roles::effort.desc(), persons::family_name.asc()))
+            .load::<(Role, Person)>(conn)?;
+
+        Ok(res.into_iter()
+            .map(|(role, person)| TeamMember { role, person })
+            .collect())
+    }
+
+    /// Aggregate skill profile of the given members, most widely held first
+    pub fn skill_coverage(conn: &PgConnection, members: &[TeamMember]) -> Result<Vec<SkillCoverage>, CustomError> {
+        let effort_by_person: HashMap<Uuid, f64> = members.iter()
+            .fold(HashMap::new(), |mut acc, m| {
+                *acc.entry(m.person.id).or_insert(0.0) += m.role.effort;
+                acc
+            });
+
+        let person_ids: Vec<Uuid> = effort_by_person.keys().cloned().collect();
+
+        let res = capabilities::table
+            .inner_join(skills::table)
+            .filter(capabilities::person_id.eq_any(&person_ids))
+            .load::<(Capability, Skill)>(conn)?;
+
+        let mut by_skill: HashMap<Uuid, (Skill, Vec<(i32, f64)>)> = HashMap::new();
+
+        for (capability, skill) in res {
+            let effort = effort_by_person.get(&capability.person_id).cloned().unwrap_or(0.0);
+
+            by_skill
+                .entry(skill.id)
+                .or_insert_with(|| (skill, Vec::new()))
+                .1
+                .push((capability.self_identified_level, effort));
+        };
+
+        let mut coverage: Vec<SkillCoverage> = by_skill.into_iter()
+            .map(|(_, (skill, holders))| {
+                let people_count = holders.len();
+                let max_level = holders.iter().map(|h| h.0).max().unwrap_or(0);
+                let average_level = holders.iter().map(|h| h.0 as f64).sum::<f64>() / people_count as f64;
+                let fte = holders.iter().map(|h| h.1).sum();
+
+                SkillCoverage {
+                    skill,
+                    people_count,
+                    max_level,
+                    average_level,
+                    fte,
+                }
+            })
+            .collect();
+
+        coverage.sort_by(|a, b| b.people_count.cmp(&a.people_count)
+            .then(a.skill.name_en.cmp(&b.skill.name_en)));
+
+        Ok(coverage)
+    }
+
+    pub fn profile(&self, as_of: NaiveDateTime) -> Result<TeamProfile, CustomError> {
+        let conn = connection()?;
+
+        let organization = organizations::table
+            .filter(organizations::id.eq(self.organization_id))
+            .first::<Organization>(&conn)?;
+
+        let org_tier = org_tiers::table
+            .filter(org_tiers::id.eq(self.org_tier_id))
+            .first::<OrgTier>(&conn)?;
+
+        let owners = team_ownerships::table
+            .inner_join(persons::table)
+            .filter(team_ownerships::team_id.eq(self.id))
+            .filter(team_ownerships::start_datestamp.le(as_of))
+            .filter(team_ownerships::end_date.is_null().or(team_ownerships::end_date.gt(as_of)))
+            .load::<(TeamOwnership, Person)>(&conn)?
+            .into_iter()
+            .map(|(ownership, person)| TeamOwner { ownership, person })
+            .collect();
+
+        let members = Team::find_members(&conn, self.id, as_of)?;
+        let total_effort = members.iter().map(|m| m.role.effort).sum();
+        let skills = Team::skill_coverage(&conn, &members)?;
+        let works = Work::find_by_team_with_status(&conn, self.id)?;
+
+        Ok(TeamProfile {
+            team: self.clone(),
+            organization,
+            org_tier,
+            owners,
+            members,
+            total_effort,
+            skills,
+            works,
+        })
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, Insertable, SimpleObject)]
@@ -1,18 +1,18 @@
-use std::fmt::Debug;
+use std::fmt::{self, Debug};
+use std::str::FromStr;
 
 use chrono::{prelude::*};
 use serde::{Deserialize, Serialize};
-use diesel::{self, Insertable, PgConnection, Queryable, ExpressionMethods};
-use diesel::{RunQueryDsl, QueryDsl};
+use diesel::{self, Insertable, PgConnection, Queryable, ExpressionMethods, BoolExpressionMethods};
+use diesel::{RunQueryDsl, QueryDsl, Connection, OptionalExtension};
 use uuid::Uuid;
 use async_graphql::*;
-use rand::{Rng, thread_rng};
 use crate::graphql::graphql_translate;
 
 
 use crate::schema::*;
-use crate::database::connection;
 use crate::errors::CustomError;
+use crate::models::Team;
 
 #[derive(Debug, Clone, Deserialize, Serialize, Queryable, Insertable, AsChangeset)]
 #[table_name = "team_ownerships"]
@@ -28,36 +28,113 @@ pub struct TeamOwnership {
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
 
+    pub ownership_type: String, // OwnershipType
+
     // pub milestones: Uuid // Refers to Github Milestones
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+/// Every active team and org tier has exactly one primary Owner.
+/// Delegates share the work, Acting owners cover temporarily.
+pub enum OwnershipType {
+    Owner,
+    Delegate,
+    Acting,
+}
+
+impl fmt::Display for OwnershipType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            OwnershipType::Owner => "owner",
+            OwnershipType::Delegate => "delegate",
+            OwnershipType::Acting => "acting",
+        };
+        f.write_str(s)
+    }
+}
+
+impl FromStr for OwnershipType {
+    type Err = CustomError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "owner" => Ok(OwnershipType::Owner),
+            "delegate" => Ok(OwnershipType::Delegate),
+            "acting" => Ok(OwnershipType::Acting),
+            _ => Err(CustomError::new(400, format!("Unknown ownership type: {}", s))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, SimpleObject)]
+/// Team or OrgTier that does not have exactly one current primary owner
+pub struct OwnershipIssue {
+    pub entity_type: String, // "team" or "org_tier"
+    pub entity_id: Uuid,
+    pub name_en: String,
+    pub name_fr: String,
+    pub owner_count: usize,
+    pub acting_owner_ids: Vec<Uuid>,
+}
+
+impl OwnershipIssue {
+    pub fn is_orphaned(&self) -> bool {
+        self.owner_count == 0
+    }
+}
+
 // Non Graphql
 impl TeamOwnership {
+    /// Primary owners can't overlap, so a team has at most one at any time
     pub fn create(conn: &PgConnection, team_ownership: &NewTeamOwnership) -> FieldResult<TeamOwnership> {
-        let res = diesel::insert_into(team_ownerships::table)
-        .values(team_ownership)
-        .get_result(conn);
-        
-        graphql_translate(res)
+        if team_ownership.ownership_type == OwnershipType::Acting.to_string() && team_ownership.end_date.is_none() {
+            return Err(FieldError::new("Acting ownership requires an end date"));
+        };
+
+        if team_ownership.end_date.map_or(false, |e| e <= team_ownership.start_datestamp) {
+            return Err(FieldError::new("Ownership must end after it starts"));
+        };
+
+        conn.transaction::<TeamOwnership, FieldError, _>(|| {
+            if team_ownership.ownership_type == OwnershipType::Owner.to_string() {
+                lock_team(conn, team_ownership.team_id)?;
+
+                let overlapping = find_overlapping_owners(
+                    conn,
+                    team_ownership.team_id,
+                    team_ownership.start_datestamp,
+                    team_ownership.end_date,
+                )?;
+
+                if let Some(owner) = overlapping.first() {
+                    return Err(FieldError::new(format!(
+                        "The team already has a primary owner from {}", owner.start_datestamp.date())));
+                };
+            };
+
+            graphql_translate(diesel::insert_into(team_ownerships::table)
+                .values(team_ownership)
+                .get_result(conn))
+        })
     }
     
+    /// Natural key: (person_id, team_id, start_datestamp)
+    /// Returns the existing team_ownership unchanged if the key matches,
+    /// otherwise creates it with the same checks as create.
     pub fn get_or_create(conn: &PgConnection, team_ownership: &NewTeamOwnership) -> FieldResult<TeamOwnership> {
-        let res = team_ownerships::table
-        .filter(team_ownerships::person_id.eq(&team_ownership.person_id))
-        .filter(team_ownerships::team_id.eq(&team_ownership.team_id))
-        .distinct()
-        .first(conn);
-        
-        let team_ownership = match res {
-            Ok(p) => p,
-            Err(e) => {
-                // TeamOwnership not found
-                println!("{:?}", e);
-                let p = TeamOwnership::create(conn, team_ownership).expect("Unable to create team_ownership");
-                p
+        conn.transaction::<TeamOwnership, FieldError, _>(|| {
+            let existing = graphql_translate(team_ownerships::table
+                .filter(team_ownerships::person_id.eq(team_ownership.person_id))
+                .filter(team_ownerships::team_id.eq(team_ownership.team_id))
+                .filter(team_ownerships::start_datestamp.eq(team_ownership.start_datestamp))
+                .first::<TeamOwnership>(conn)
+                .optional())?;
+
+            match existing {
+                Some(o) => Ok(o),
+                None => TeamOwnership::create(conn, team_ownership),
             }
-        };
-        Ok(team_ownership)
+        })
     }
     
     pub fn update(&self, conn: &PgConnection) -> FieldResult<Self> {
@@ -68,6 +145,136 @@ impl TeamOwnership {
         
         Ok(res)
     }
+
+    /// Ownerships of any type in effect for a team on as_of
+    pub fn find_current_by_team(conn: &PgConnection, team_id: Uuid, as_of: NaiveDateTime) -> FieldResult<Vec<TeamOwnership>> {
+        let res = team_ownerships::table
+            .filter(team_ownerships::team_id.eq(team_id))
+            .filter(team_ownerships::start_datestamp.le(as_of))
+            .filter(team_ownerships::end_date.is_null().or(team_ownerships::end_date.gt(as_of)))
+            .load::<TeamOwnership>(conn);
+
+        graphql_translate(res)
+    }
+
+    pub fn get_ownership_type(&self) -> OwnershipType {
+        OwnershipType::from_str(&self.ownership_type).unwrap_or(OwnershipType::Delegate)
+    }
+
+    /// Hand primary ownership of a team to a new owner, ending the
+    /// current owner's term on start_datestamp in the same transaction.
+    /// The current term must have started before start_datestamp.
+    pub fn succeed(conn: &PgConnection, team_id: Uuid, new_owner_id: Uuid, start_datestamp: NaiveDateTime) -> FieldResult<TeamOwnership> {
+        let new_ownership = NewTeamOwnership::new(
+            new_owner_id,
+            team_id,
+            start_datestamp,
+            None,
+            OwnershipType::Owner,
+        );
+
+        conn.transaction::<TeamOwnership, FieldError, _>(|| {
+            lock_team(conn, team_id)?;
+
+            for current in find_overlapping_owners(conn, team_id, start_datestamp, None)? {
+                if let Some(end_date) = current.end_date {
+                    return Err(FieldError::new(format!(
+                        "The team already has a primary owner until {}", end_date.date())));
+                };
+
+                if start_datestamp <= current.start_datestamp {
+                    return Err(FieldError::new(format!(
+                        "The successor must start after the current owner, who started {}", current.start_datestamp.date())));
+                };
+
+                graphql_translate(diesel::update(team_ownerships::table)
+                    .filter(team_ownerships::id.eq(current.id))
+                    .set((
+                        team_ownerships::end_date.eq(Some(start_datestamp)),
+                        team_ownerships::updated_at.eq(Utc::now().naive_utc()),
+                    ))
+                    .execute(conn))?;
+            };
+
+            graphql_translate(diesel::insert_into(team_ownerships::table)
+                .values(&new_ownership)
+                .get_result(conn))
+        })
+    }
+
+    /// Active teams without exactly one current primary owner on as_of
+    pub fn validate_ownership(conn: &PgConnection, as_of: NaiveDateTime) -> FieldResult<Vec<OwnershipIssue>> {
+        let teams = graphql_translate(teams::table
+            .filter(teams::retired_at.is_null())
+            .load::<Team>(conn))?;
+
+        let current = graphql_translate(team_ownerships::table
+            .filter(team_ownerships::start_datestamp.le(as_of))
+            .filter(team_ownerships::end_date.is_null().or(team_ownerships::end_date.gt(as_of)))
+            .load::<TeamOwnership>(conn))?;
+
+        let mut issues = Vec::new();
+
+        for team in teams {
+            let owner_count = current.iter()
+                .filter(|o| o.team_id == team.id && o.get_ownership_type() == OwnershipType::Owner)
+                .count();
+
+            if owner_count == 1 {
+                continue;
+            };
+
+            let acting_owner_ids = current.iter()
+                .filter(|o| o.team_id == team.id && o.get_ownership_type() == OwnershipType::Acting)
+                .map(|o| o.person_id)
+                .collect();
+
+            issues.push(OwnershipIssue {
+                entity_type: "team".to_string(),
+                entity_id: team.id,
+                name_en: team.name_en,
+                name_fr: team.name_fr,
+                owner_count,
+                acting_owner_ids,
+            });
+        };
+
+        Ok(issues)
+    }
+
+    /// Active teams with no current primary owner
+    pub fn find_orphaned_teams(conn: &PgConnection, as_of: NaiveDateTime) -> FieldResult<Vec<OwnershipIssue>> {
+        let issues = TeamOwnership::validate_ownership(conn, as_of)?;
+
+        Ok(issues.into_iter().filter(|i| i.is_orphaned()).collect())
+    }
+}
+
+/// Hold the team row until the transaction ends, so primary owner
+/// changes to one team are checked one at a time
+fn lock_team(conn: &PgConnection, team_id: Uuid) -> FieldResult<()> {
+    graphql_translate(teams::table
+        .select(teams::id)
+        .filter(teams::id.eq(team_id))
+        .for_update()
+        .first::<Uuid>(conn))?;
+
+    Ok(())
+}
+
+/// Primary ownerships of the team overlapping start..end, open-ended if end is None
+fn find_overlapping_owners(conn: &PgConnection, team_id: Uuid, start: NaiveDateTime, end: Option<NaiveDateTime>) -> FieldResult<Vec<TeamOwnership>> {
+    let mut query = team_ownerships::table
+        .filter(team_ownerships::team_id.eq(team_id))
+        .filter(team_ownerships::ownership_type.eq(OwnershipType::Owner.to_string()))
+        .filter(team_ownerships::end_date.is_null().or(team_ownerships::end_date.gt(start)))
+        .into_boxed();
+
+    if let Some(end) = end {
+        query = query.filter(team_ownerships::start_datestamp.lt(end));
+    };
+
+    graphql_translate(query.load::<TeamOwnership>(conn))
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, Insertable, SimpleObject)]
@@ -80,6 +287,8 @@ pub struct NewTeamOwnership {
 
     pub start_datestamp: NaiveDateTime,
     pub end_date: Option<NaiveDateTime>,
+
+    pub ownership_type: String, // OwnershipType
 }
 
 impl NewTeamOwnership {
@@ -89,12 +298,14 @@ impl NewTeamOwnership {
         team_id: Uuid,
         start_datestamp: NaiveDateTime,
         end_date: Option<NaiveDateTime>,
+        ownership_type: OwnershipType,
     ) -> Self {
         NewTeamOwnership {
             person_id,
             team_id,
             start_datestamp,
             end_date,
+            ownership_type: ownership_type.to_string(),
         }
     }
 }
@@ -0,0 +1,204 @@
+use chrono::{Duration, prelude::*};
+use serde::{Deserialize, Serialize};
+use diesel::{self, Insertable, PgConnection, Queryable, ExpressionMethods, BoolExpressionMethods};
+use diesel::{Connection, RunQueryDsl, QueryDsl};
+use inflector::Inflector;
+use uuid::Uuid;
+
+use crate::schema::*;
+use crate::errors::CustomError;
+use crate::generate_unique_code;
+use crate::models::{InsertableUser, User, UserData};
+use crate::oidc::OidcClaims;
+
+// Emails are stored as typed at registration, so they are compared in lower case
+sql_function!(fn lower(x: diesel::sql_types::Text) -> diesel::sql_types::Text);
+
+/// Minutes a person has to complete sign-in at the identity provider
+pub const LOGIN_STATE_MINUTES: i64 = 10;
+
+#[derive(Debug, Clone, Deserialize, Serialize, Queryable)]
+/// A sign-in in progress. State ties the callback to this request, the nonce ties
+/// the ID token to it and the PKCE code verifier proves we asked for the code.
+pub struct OidcLoginState {
+    pub id: Uuid,
+    pub state: String,
+    pub nonce: String,
+    pub code_verifier: String,
+    pub lang: String,
+    pub expires_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Insertable)]
+#[table_name = "oidc_login_states"]
+struct NewOidcLoginState {
+    state: String,
+    nonce: String,
+    code_verifier: String,
+    lang: String,
+    expires_at: NaiveDateTime,
+}
+
+impl OidcLoginState {
+    pub fn create(conn: &PgConnection, lang: &str) -> Result<Self, CustomError> {
+        let now = Utc::now().naive_utc();
+
+        // Abandoned sign-ins are cleared as new ones start
+        diesel::delete(oidc_login_states::table.filter(oidc_login_states::expires_at.lt(now)))
+            .execute(conn)?;
+
+        let login_state = diesel::insert_into(oidc_login_states::table)
+            .values(&NewOidcLoginState {
+                state: generate_unique_code(32, false),
+                nonce: generate_unique_code(32, false),
+                code_verifier: generate_unique_code(64, false),
+                lang: if lang == "fr" { "fr" } else { "en" }.to_string(),
+                expires_at: now + Duration::minutes(LOGIN_STATE_MINUTES),
+            })
+            .get_result(conn)?;
+
+        Ok(login_state)
+    }
+
+    /// Remove and return the sign-in for a callback's state, so it can only be used once
+    pub fn take(conn: &PgConnection, state: &str) -> Result<Self, CustomError> {
+        let login_state: OidcLoginState = diesel::delete(oidc_login_states::table
+            .filter(oidc_login_states::state.eq(state)))
+            .get_result(conn)
+            .map_err(|_| CustomError::new(401, "Unknown OIDC sign-in state".to_string()))?;
+
+        if login_state.expires_at < Utc::now().naive_utc() {
+            return Err(CustomError::new(401, "OIDC sign-in expired".to_string()))
+        };
+
+        Ok(login_state)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, Queryable)]
+/// An identity provider account linked to a user
+pub struct UserIdentity {
+    pub id: Uuid,
+    pub user_id: Uuid, // User
+    pub issuer: String,
+    pub subject: String, // The provider's sub claim
+    pub email: String,
+    pub created_at: NaiveDateTime,
+    pub last_login_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Insertable)]
+#[table_name = "user_identities"]
+struct NewUserIdentity {
+    user_id: Uuid,
+    issuer: String,
+    subject: String,
+    email: String,
+}
+
+impl UserIdentity {
+    /// The user signing in with the provider's claims. An account already linked to the
+    /// provider's subject is used first, then a validated user with the same verified email,
+    /// which is linked. Otherwise a user is created from the claims and linked.
+    ///
+    /// Unvalidated users with the email are never linked: anyone can register with someone
+    /// else's email and would keep their password and sessions on the linked account.
+    pub fn sign_in(conn: &PgConnection, issuer: &str, claims: &OidcClaims) -> Result<User, CustomError> {
+        let email = claims.email.as_ref()
+            .map(|e| e.trim().to_lowercase())
+            .filter(|e| !e.is_empty())
+            .ok_or_else(|| CustomError::new(406, "The identity provider did not share an email address".to_string()))?;
+
+        let now = Utc::now().naive_utc();
+
+        conn.transaction::<_, CustomError, _>(|| {
+            let linked = user_identities::table
+                .filter(user_identities::issuer.eq(issuer))
+                .filter(user_identities::subject.eq(&claims.sub))
+                .first::<UserIdentity>(conn)
+                .ok();
+
+            if let Some(identity) = linked {
+                diesel::update(user_identities::table.filter(user_identities::id.eq(identity.id)))
+                    .set((
+                        user_identities::email.eq(&email),
+                        user_identities::last_login_at.eq(now),
+                    ))
+                    .execute(conn)?;
+
+                let user = users::table.filter(users::id.eq(identity.user_id)).first::<User>(conn)?;
+                return Ok(user)
+            };
+
+            // Without a verified email we can't tell the person owns a matching account
+            if !claims.email_verified {
+                return Err(CustomError::new(406, "The identity provider has not verified this email address".to_string()))
+            };
+
+            let mut existing = users::table
+                .filter(lower(users::email).eq(&email))
+                .load::<User>(conn)?;
+
+            if existing.len() > 1 {
+                return Err(CustomError::new(409, "More than one account uses this email address".to_string()))
+            };
+
+            let user = match existing.pop() {
+                Some(user) if user.validated => user,
+                Some(_) => return Err(CustomError::new(409, "An account with this email address has not been verified".to_string())),
+                None => create_user(conn, &email, claims)?,
+            };
+
+            diesel::insert_into(user_identities::table)
+                .values(&NewUserIdentity {
+                    user_id: user.id,
+                    issuer: issuer.to_owned(),
+                    subject: claims.sub.clone(),
+                    email,
+                })
+                .execute(conn)?;
+
+            Ok(user)
+        })
+    }
+
+    pub fn find_by_user(conn: &PgConnection, user_id: Uuid) -> Result<Vec<Self>, CustomError> {
+        let identities = user_identities::table
+            .filter(user_identities::user_id.eq(user_id))
+            .load::<UserIdentity>(conn)?;
+
+        Ok(identities)
+    }
+}
+
+/// A validated user for a first sign-in. The random password can be replaced through a
+/// password reset, for people who also want to log in locally.
+fn create_user(conn: &PgConnection, email: &str, claims: &OidcClaims) -> Result<User, CustomError> {
+    let base = claims.preferred_username.as_ref()
+        .or(claims.name.as_ref())
+        .map(|n| n.trim().to_owned())
+        .filter(|n| !n.is_empty())
+        .unwrap_or_else(|| email.split('@').next().unwrap_or(email).to_owned());
+
+    // user_name and slug are unique and at most 32 characters
+    let mut user_name: String = base.chars().take(32).collect();
+
+    while users::table
+        .filter(users::user_name.eq(&user_name).or(users::slug.eq(user_name.to_snake_case())))
+        .first::<User>(conn)
+        .is_ok() {
+        user_name = format!("{} {}", base.chars().take(26).collect::<String>(), generate_unique_code(5, false));
+    };
+
+    let user = diesel::insert_into(users::table)
+        .values(InsertableUser::from(UserData {
+            user_name,
+            email: email.to_owned(),
+            password: generate_unique_code(64, false),
+            role: "user".to_string(),
+            validated: true,
+        }))
+        .get_result(conn)?;
+
+    Ok(user)
+}
@@ -0,0 +1,109 @@
+use std::collections::HashMap;
+
+use chrono::{prelude::*};
+use serde::{Deserialize, Serialize};
+use diesel::{self, Insertable, PgConnection, Queryable, ExpressionMethods};
+use diesel::upsert::excluded;
+use diesel::{RunQueryDsl, QueryDsl};
+use uuid::Uuid;
+use async_graphql::*;
+
+use crate::graphql::graphql_translate;
+
+use crate::schema::*;
+use crate::errors::CustomError;
+use crate::models::{Capability, CapabilityLevel};
+
+#[derive(Debug, Clone, Deserialize, Serialize, Queryable, Insertable, AsChangeset)]
+#[table_name = "validations"]
+/// Another person's assessment of a Capability's level
+pub struct Validation {
+    pub id: Uuid,
+    pub validator_id: Uuid, // Person
+    pub capability_id: Uuid, // Capability
+    pub validated_level: i32, // CapabilityLevel
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize, SimpleObject)]
+/// What validators make of a Capability
+pub struct ValidationConsensus {
+    pub validators: i32,
+    pub average_level: Option<f64>,
+    pub confirming: i32, // Validators at or above the self-identified level
+    pub level_key: Option<String>, // Fluent key of the rounded average
+}
+
+impl ValidationConsensus {
+    /// Share of validators confirming the self-identified level, 0 without any
+    pub fn agreement(&self) -> f64 {
+        if self.validators == 0 {
+            0.0
+        } else {
+            self.confirming as f64 / self.validators as f64
+        }
+    }
+}
+
+impl Validation {
+    /// Natural key: (validator_id, capability_id). Updates the level if the key matches.
+    pub fn create_or_update(conn: &PgConnection, validation: &NewValidation) -> FieldResult<Validation> {
+        let res = diesel::insert_into(validations::table)
+            .values(validation)
+            .on_conflict((validations::validator_id, validations::capability_id))
+            .do_update()
+            .set((
+                validations::validated_level.eq(excluded(validations::validated_level)),
+                validations::updated_at.eq(Utc::now().naive_utc()),
+            ))
+            .get_result(conn);
+
+        graphql_translate(res)
+    }
+
+    /// Consensus for each of the capabilities, by capability id
+    pub fn find_consensus(conn: &PgConnection, capabilities: &[Capability]) -> Result<HashMap<Uuid, ValidationConsensus>, CustomError> {
+        let ids: Vec<Uuid> = capabilities.iter().map(|c| c.id).collect();
+
+        let mut levels: HashMap<Uuid, Vec<i32>> = HashMap::new();
+
+        for validation in validations::table
+            .filter(validations::capability_id.eq_any(&ids))
+            .load::<Validation>(conn)? {
+            levels.entry(validation.capability_id).or_insert_with(Vec::new).push(validation.validated_level);
+        };
+
+        Ok(capabilities.iter()
+            .filter_map(|capability| {
+                let levels = levels.get(&capability.id)?;
+                let average = levels.iter().sum::<i32>() as f64 / levels.len() as f64;
+
+                Some((capability.id, ValidationConsensus {
+                    validators: levels.len() as i32,
+                    average_level: Some(average),
+                    confirming: levels.iter().filter(|l| **l >= capability.self_identified_level).count() as i32,
+                    level_key: Some(CapabilityLevel::from_i32(average.round() as i32).key().to_string()),
+                }))
+            })
+            .collect())
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, Insertable)]
+#[table_name = "validations"]
+pub struct NewValidation {
+    pub validator_id: Uuid,
+    pub capability_id: Uuid,
+    pub validated_level: i32,
+}
+
+impl NewValidation {
+    pub fn new(validator_id: Uuid, capability_id: Uuid, validated_level: CapabilityLevel) -> Self {
+        NewValidation {
+            validator_id,
+            capability_id,
+            validated_level: validated_level.as_i32(),
+        }
+    }
+}
@@ -6,28 +6,33 @@ use diesel::{self, Insertable, PgConnection, Queryable, ExpressionMethods};
 use diesel::{RunQueryDsl, QueryDsl};
 use uuid::Uuid;
 use async_graphql::*;
-use rand::{Rng, thread_rng};
 
 use crate::graphql::graphql_translate;
 
 use crate::schema::*;
+use crate::errors::CustomError;
+use crate::database::connection;
+use crate::models::{RequiredSkill, WorkSkillRequirement};
 
 #[derive(Debug, Clone, Deserialize, Serialize, Queryable, Insertable, AsChangeset)]
 #[table_name = "works"]
+/// Outcome a Team is working toward, optionally assigned to a Person
 pub struct Work {
     pub id: Uuid,
+    pub team_id: Uuid, // Team
     pub person_id: Option<Uuid>, // Person
-    pub work_id: Option<Uuid>, // Work
+    pub parent_work_id: Option<Uuid>, // Work
     pub outcome_en: String,
     pub outcome_fr: String,
     pub start_date: NaiveDate,
-    pub target_completion_data: NaiveDate,
-    pub work_status: usize,
+    pub target_completion_date: NaiveDate,
+    pub work_status: i32, // WorkStatus
     pub completed_date: Option<NaiveDate>,
-    pub created_at: NaiveDate,
-    pub updated_at: NaiveDate,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
 pub enum WorkStatus {
     Planning, // 0
     InProgress, // 1
@@ -35,6 +40,39 @@ pub enum WorkStatus {
     Blocked, // 3
 }
 
+impl WorkStatus {
+    pub fn from_i32(status: i32) -> Self {
+        match status {
+            1 => WorkStatus::InProgress,
+            2 => WorkStatus::Complete,
+            3 => WorkStatus::Blocked,
+            _ => WorkStatus::Planning,
+        }
+    }
+
+    pub fn as_i32(&self) -> i32 {
+        *self as i32
+    }
+
+    /// Fluent key for the status name
+    pub fn key(&self) -> &'static str {
+        match self {
+            WorkStatus::Planning => "work-planning",
+            WorkStatus::InProgress => "work-in-progress",
+            WorkStatus::Complete => "work-complete",
+            WorkStatus::Blocked => "work-blocked",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+/// Work with its status key and skill requirements for display
+pub struct WorkWithStatus {
+    pub work: Work,
+    pub status_key: String,
+    pub requirements: Vec<RequiredSkill>,
+}
+
 // Non Graphql
 impl Work {
     pub fn create(conn: &PgConnection, work: &NewWork) -> FieldResult<Work> {
@@ -44,36 +82,49 @@ impl Work {
         
         graphql_translate(res)
     }
-    
-    pub fn get_or_create(conn: &PgConnection, work: &NewWork) -> FieldResult<Work> {
-        let res = works::table
-        .filter(works::family_name.eq(&work.family_name))
-        .distinct()
-        .first(conn);
-        
-        let work = match res {
-            Ok(p) => p,
-            Err(e) => {
-                // Work not found
-                println!("{:?}", e);
-                let p = Work::create(conn, work).expect("Unable to create work");
-                p
-            }
-        };
-        Ok(work)
-    }
 
     pub fn find_all() -> Result<Vec<Self>, CustomError> {
-        let conn = database::connection()?;
+        let conn = connection()?;
         let works = works::table.load::<Work>(&conn)?;
         Ok(works)
     }
 
     pub fn find(id: Uuid) -> Result<Self, CustomError> {
-        let conn = database::connection()?;
+        let conn = connection()?;
         let work = works::table.filter(works::id.eq(id)).first(&conn)?;
         Ok(work)
     }
+
+    pub fn find_by_team(conn: &PgConnection, team_id: Uuid) -> Result<Vec<Self>, CustomError> {
+        let works = works::table
+            .filter(works::team_id.eq(team_id))
+            .order((works::work_status.asc(), works::target_completion_date.asc()))
+            .load::<Work>(conn)?;
+        Ok(works)
+    }
+
+    pub fn find_by_team_with_status(conn: &PgConnection, team_id: Uuid) -> Result<Vec<WorkWithStatus>, CustomError> {
+        let works = Work::find_by_team(conn, team_id)?;
+        let work_ids: Vec<Uuid> = works.iter().map(|w| w.id).collect();
+        let mut requirements = WorkSkillRequirement::find_by_works(conn, &work_ids)?;
+
+        Ok(works.into_iter()
+            .map(|work| {
+                let status_key = work.get_status().key().to_string();
+                let requirements = requirements.remove(&work.id).unwrap_or_default();
+                WorkWithStatus { work, status_key, requirements }
+            })
+            .collect())
+    }
+
+    pub fn get_status(&self) -> WorkStatus {
+        WorkStatus::from_i32(self.work_status)
+    }
+
+    /// Work still to be done: planned, in progress or blocked
+    pub fn is_active(&self) -> bool {
+        self.get_status() != WorkStatus::Complete
+    }
     
     pub fn update(&self, conn: &PgConnection) -> FieldResult<Self> {
         let res = diesel::update(works::table)
@@ -88,43 +139,40 @@ impl Work {
 #[derive(Debug, Clone, Deserialize, Serialize, Insertable, SimpleObject)]
 #[table_name = "works"]
 pub struct NewWork {
+    pub team_id: Uuid, // Team
     pub person_id: Option<Uuid>, // Person
-    pub work_id: Option<Uuid>, // Work
+    pub parent_work_id: Option<Uuid>, // Work
     pub outcome_en: String,
     pub outcome_fr: String,
     pub start_date: NaiveDate,
-    pub target_completion_data: NaiveDate,
-    pub work_status: usize,
+    pub target_completion_date: NaiveDate,
+    pub work_status: i32,
     pub completed_date: Option<NaiveDate>,
-    pub created_at: NaiveDate,
-    pub updated_at: NaiveDate,
 }
 
 impl NewWork {
 
     pub fn new(
+        team_id: Uuid, // Team
         person_id: Option<Uuid>, // Person
-        work_id: Option<Uuid>, // Work
+        parent_work_id: Option<Uuid>, // Work
         outcome_en: String,
         outcome_fr: String,
         start_date: NaiveDate,
-        target_completion_data: NaiveDate,
-        work_status: usize,
+        target_completion_date: NaiveDate,
+        work_status: WorkStatus,
         completed_date: Option<NaiveDate>,
-        created_at: NaiveDate,
-        updated_at: NaiveDate,
     ) -> Self {
         NewWork {
+            team_id,
             person_id,
-            work_id,
+            parent_work_id,
             outcome_en,
             outcome_fr,
             start_date,
-            target_completion_data,
-            work_status,
+            target_completion_date,
+            work_status: work_status.as_i32(),
             completed_date,
-            created_at,
-            updated_at,
         }
     }
 }
@@ -0,0 +1,407 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::{prelude::*, Duration};
+use serde::{Deserialize, Serialize};
+use diesel::{self, Connection, Insertable, PgConnection, Queryable, ExpressionMethods};
+use diesel::dsl::max;
+use diesel::{RunQueryDsl, QueryDsl};
+use uuid::Uuid;
+use async_graphql::*;
+
+use crate::schema::*;
+use crate::errors::CustomError;
+use crate::models::{Organization, Role, RoleType};
+
+/// Longest history, in months, that a refresh rebuilds
+pub const WORKFORCE_HISTORY_MONTHS: u32 = 120;
+
+/// Rows per insert, well under the Postgres bind parameter limit
+const INSERT_CHUNK: usize = 2000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WorkforceScope {
+    Team,
+    OrgTier, // The tier and every tier below it
+}
+
+impl fmt::Display for WorkforceScope {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            WorkforceScope::Team => "team",
+            WorkforceScope::OrgTier => "org_tier",
+        };
+        f.write_str(s)
+    }
+}
+
+impl FromStr for WorkforceScope {
+    type Err = CustomError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "team" => Ok(WorkforceScope::Team),
+            "org_tier" => Ok(WorkforceScope::OrgTier),
+            _ => Err(CustomError::new(400, format!("Unknown workforce scope: {}", s))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, Queryable, SimpleObject)]
+/// One month of workforce figures for a team, or an org tier with every tier below it.
+/// Headcount, FTE and tenure are as of the last day of the month. Hires and departures
+/// are people joining or leaving the organization, moves are people changing team or
+/// tier within it. Turnover is departures over the average of opening and closing headcount.
+pub struct WorkforceMetric {
+    pub id: Uuid,
+    pub organization_id: Uuid, // Organization
+    pub scope_type: String, // WorkforceScope
+    pub scope_id: Uuid, // Team or OrgTier
+    pub period: NaiveDate, // First day of the month
+    pub headcount: i32,
+    pub fte: f64,
+    pub hires: i32,
+    pub departures: i32,
+    pub moves_in: i32,
+    pub moves_out: i32,
+    pub turnover_rate: f64,
+    pub average_tenure_months: f64,
+    pub refreshed_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, SimpleObject)]
+/// A team or org tier with workforce figures
+pub struct WorkforceUnit {
+    pub scope_type: String, // WorkforceScope
+    pub scope_id: Uuid,
+    pub organization_id: Uuid,
+    pub name_en: String,
+    pub name_fr: String,
+}
+
+/// Who is in each unit at one instant
+#[derive(Debug, Default)]
+struct Snapshot {
+    members: HashMap<(WorkforceScope, Uuid), HashSet<Uuid>>,
+    fte: HashMap<(WorkforceScope, Uuid), f64>,
+    present: HashSet<Uuid>, // Anyone holding a role in the organization
+}
+
+impl Snapshot {
+    /// Deployments are held on top of a home role and so are left out, as are
+    /// substantive roles suspended while their holder is acting or seconded.
+    fn at(
+        as_of: NaiveDateTime,
+        roles: &[Role],
+        retired: &HashMap<Uuid, Option<NaiveDateTime>>,
+        team_units: &HashMap<Uuid, Vec<(WorkforceScope, Uuid)>>,
+    ) -> Self {
+        let current: Vec<&Role> = roles.iter()
+            .filter(|r| r.start_datestamp <= as_of && r.end_date.map_or(true, |e| e > as_of))
+            .filter(|r| r.role_type != RoleType::Deployment.to_string())
+            .filter(|r| retired.get(&r.person_id).cloned().flatten().map_or(true, |d| d > as_of))
+            .collect();
+
+        let suspended: HashSet<Uuid> = current.iter()
+            .filter(|r| r.role_type != RoleType::Substantive.to_string())
+            .filter_map(|r| r.home_role_id)
+            .collect();
+
+        let mut snapshot = Snapshot::default();
+
+        for role in current.into_iter().filter(|r| !suspended.contains(&r.id)) {
+            snapshot.present.insert(role.person_id);
+
+            for unit in team_units.get(&role.team_id).into_iter().flatten() {
+                snapshot.members.entry(*unit).or_insert_with(HashSet::new).insert(role.person_id);
+                *snapshot.fte.entry(*unit).or_insert(0.0) += role.effort;
+            };
+        };
+
+        snapshot
+    }
+}
+
+fn month_start(date: NaiveDate) -> NaiveDate {
+    NaiveDate::from_ymd(date.year(), date.month(), 1)
+}
+
+/// First day of the month `months` after (or before, if negative) period
+fn shift_months(period: NaiveDate, months: i32) -> NaiveDate {
+    let index = period.year() * 12 + period.month0() as i32 + months;
+    NaiveDate::from_ymd(index.div_euclid(12), index.rem_euclid(12) as u32 + 1, 1)
+}
+
+/// Last second of the month starting on period
+fn month_end(period: NaiveDate) -> NaiveDateTime {
+    shift_months(period, 1).and_hms(0, 0, 0) - Duration::seconds(1)
+}
+
+impl WorkforceMetric {
+    /// Rebuild the rollup for an organization from role and person history,
+    /// month by month up to the month of `through`. Returns the rows written.
+    pub fn refresh(conn: &PgConnection, organization_id: Uuid, through: NaiveDate) -> Result<usize, CustomError> {
+        let parents: HashMap<Uuid, Option<Uuid>> = org_tiers::table
+            .filter(org_tiers::organization_id.eq(organization_id))
+            .select((org_tiers::id, org_tiers::parent_tier))
+            .load::<(Uuid, Option<Uuid>)>(conn)?
+            .into_iter()
+            .collect();
+
+        // Retired teams are kept so their history still rolls up
+        let teams = teams::table
+            .filter(teams::organization_id.eq(organization_id))
+            .select((teams::id, teams::org_tier_id))
+            .load::<(Uuid, Uuid)>(conn)?;
+
+        // Each team counts towards itself and every tier above it
+        let team_units: HashMap<Uuid, Vec<(WorkforceScope, Uuid)>> = teams.iter()
+            .map(|(team_id, tier_id)| {
+                let mut units = vec![(WorkforceScope::Team, *team_id)];
+                let mut current = Some(*tier_id);
+
+                while let Some(tier_id) = current {
+                    if units.contains(&(WorkforceScope::OrgTier, tier_id)) {
+                        break
+                    };
+                    units.push((WorkforceScope::OrgTier, tier_id));
+                    current = parents.get(&tier_id).cloned().flatten();
+                };
+
+                (*team_id, units)
+            })
+            .collect();
+
+        let team_ids: Vec<Uuid> = teams.iter().map(|(id, _)| *id).collect();
+
+        let roles = roles::table
+            .filter(roles::team_id.eq_any(&team_ids))
+            .load::<Role>(conn)?;
+
+        let person_ids: Vec<Uuid> = roles.iter()
+            .map(|r| r.person_id)
+            .collect::<HashSet<Uuid>>()
+            .into_iter()
+            .collect();
+
+        let retired: HashMap<Uuid, Option<NaiveDateTime>> = persons::table
+            .filter(persons::id.eq_any(&person_ids))
+            .select((persons::id, persons::retired_at))
+            .load::<(Uuid, Option<NaiveDateTime>)>(conn)?
+            .into_iter()
+            .collect();
+
+        // Tenure runs from the start of someone's first role in the organization
+        let mut first_start: HashMap<Uuid, NaiveDateTime> = HashMap::new();
+
+        for role in roles.iter().filter(|r| r.role_type != RoleType::Deployment.to_string()) {
+            let start = first_start.entry(role.person_id).or_insert(role.start_datestamp);
+            if role.start_datestamp < *start {
+                *start = role.start_datestamp;
+            };
+        };
+
+        let last = month_start(through);
+        let earliest = first_start.values()
+            .min()
+            .map(|d| month_start(d.date()))
+            .unwrap_or(last);
+
+        let mut period = earliest.max(shift_months(last, 1 - WORKFORCE_HISTORY_MONTHS as i32));
+        let mut previous = Snapshot::at(month_end(shift_months(period, -1)), &roles, &retired, &team_units);
+
+        let nobody = HashSet::new();
+        let mut rows: Vec<NewWorkforceMetric> = Vec::new();
+
+        while period <= last {
+            let as_of = month_end(period);
+            let current = Snapshot::at(as_of, &roles, &retired, &team_units);
+
+            let units: HashSet<(WorkforceScope, Uuid)> = previous.members.keys()
+                .chain(current.members.keys())
+                .cloned()
+                .collect();
+
+            for unit in units {
+                let before = previous.members.get(&unit).unwrap_or(&nobody);
+                let after = current.members.get(&unit).unwrap_or(&nobody);
+
+                let (mut hires, mut moves_in, mut departures, mut moves_out) = (0, 0, 0, 0);
+
+                for person_id in after.difference(before) {
+                    if previous.present.contains(person_id) { moves_in += 1 } else { hires += 1 };
+                };
+
+                for person_id in before.difference(after) {
+                    if current.present.contains(person_id) { moves_out += 1 } else { departures += 1 };
+                };
+
+                let average_headcount = (before.len() + after.len()) as f64 / 2.0;
+
+                let tenure_days: i64 = after.iter()
+                    .filter_map(|p| first_start.get(p))
+                    .map(|start| (as_of - *start).num_days())
+                    .sum();
+
+                rows.push(NewWorkforceMetric {
+                    organization_id,
+                    scope_type: unit.0.to_string(),
+                    scope_id: unit.1,
+                    period,
+                    headcount: after.len() as i32,
+                    fte: current.fte.get(&unit).cloned().unwrap_or(0.0),
+                    hires,
+                    departures,
+                    moves_in,
+                    moves_out,
+                    turnover_rate: if average_headcount > 0.0 { departures as f64 / average_headcount } else { 0.0 },
+                    average_tenure_months: if after.is_empty() { 0.0 } else { tenure_days as f64 / 30.4375 / after.len() as f64 },
+                });
+            };
+
+            previous = current;
+            period = shift_months(period, 1);
+        };
+
+        conn.transaction::<_, CustomError, _>(|| {
+            diesel::delete(workforce_metrics::table.filter(workforce_metrics::organization_id.eq(organization_id)))
+                .execute(conn)?;
+
+            for chunk in rows.chunks(INSERT_CHUNK) {
+                diesel::insert_into(workforce_metrics::table)
+                    .values(chunk)
+                    .execute(conn)?;
+            };
+
+            Ok(())
+        })?;
+
+        Ok(rows.len())
+    }
+
+    /// Refresh every organization, returning the rows written
+    pub fn refresh_all(conn: &PgConnection, through: NaiveDate) -> Result<usize, CustomError> {
+        let mut written = 0;
+
+        for organization in organizations::table.load::<Organization>(conn)? {
+            written += WorkforceMetric::refresh(conn, organization.id, through)?;
+        };
+
+        Ok(written)
+    }
+
+    /// Monthly figures for one team or org tier, oldest first
+    pub fn find_series(
+        conn: &PgConnection,
+        scope: WorkforceScope,
+        scope_id: Uuid,
+        from: Option<NaiveDate>,
+        to: Option<NaiveDate>,
+    ) -> Result<Vec<Self>, CustomError> {
+        let mut query = workforce_metrics::table
+            .filter(workforce_metrics::scope_type.eq(scope.to_string()))
+            .filter(workforce_metrics::scope_id.eq(scope_id))
+            .order(workforce_metrics::period.asc())
+            .into_boxed();
+
+        if let Some(from) = from {
+            query = query.filter(workforce_metrics::period.ge(month_start(from)));
+        };
+
+        if let Some(to) = to {
+            query = query.filter(workforce_metrics::period.le(to));
+        };
+
+        Ok(query.load::<WorkforceMetric>(conn)?)
+    }
+
+    /// Every row for an organization, or all organizations
+    pub fn find_by_organization(conn: &PgConnection, organization_id: Option<Uuid>) -> Result<Vec<Self>, CustomError> {
+        let mut query = workforce_metrics::table
+            .order((workforce_metrics::scope_type.asc(), workforce_metrics::scope_id.asc(), workforce_metrics::period.asc()))
+            .into_boxed();
+
+        if let Some(organization_id) = organization_id {
+            query = query.filter(workforce_metrics::organization_id.eq(organization_id));
+        };
+
+        Ok(query.load::<WorkforceMetric>(conn)?)
+    }
+
+    /// Teams and org tiers in the rollup, tiers first, by English name
+    pub fn find_units(conn: &PgConnection, organization_id: Option<Uuid>) -> Result<Vec<WorkforceUnit>, CustomError> {
+        let mut query = workforce_metrics::table
+            .select((workforce_metrics::scope_type, workforce_metrics::scope_id, workforce_metrics::organization_id))
+            .distinct()
+            .into_boxed();
+
+        if let Some(organization_id) = organization_id {
+            query = query.filter(workforce_metrics::organization_id.eq(organization_id));
+        };
+
+        let keys = query.load::<(String, Uuid, Uuid)>(conn)?;
+
+        let ids: Vec<Uuid> = keys.iter().map(|(_, id, _)| *id).collect();
+
+        let mut names: HashMap<Uuid, (String, String)> = teams::table
+            .filter(teams::id.eq_any(&ids))
+            .select((teams::id, teams::name_en, teams::name_fr))
+            .load::<(Uuid, String, String)>(conn)?
+            .into_iter()
+            .map(|(id, en, fr)| (id, (en, fr)))
+            .collect();
+
+        names.extend(org_tiers::table
+            .filter(org_tiers::id.eq_any(&ids))
+            .select((org_tiers::id, org_tiers::name_en, org_tiers::name_fr))
+            .load::<(Uuid, String, String)>(conn)?
+            .into_iter()
+            .map(|(id, en, fr)| (id, (en, fr))));
+
+        let mut units: Vec<WorkforceUnit> = keys.into_iter()
+            .filter_map(|(scope_type, scope_id, organization_id)| {
+                let (name_en, name_fr) = names.get(&scope_id)?.clone();
+
+                Some(WorkforceUnit {
+                    scope_type,
+                    scope_id,
+                    organization_id,
+                    name_en,
+                    name_fr,
+                })
+            })
+            .collect();
+
+        units.sort_by(|a, b| b.scope_type.cmp(&a.scope_type).then_with(|| a.name_en.cmp(&b.name_en)));
+
+        Ok(units)
+    }
+
+    /// When the rollup for an organization was last rebuilt, if ever
+    pub fn last_refreshed(conn: &PgConnection, organization_id: Uuid) -> Result<Option<NaiveDateTime>, CustomError> {
+        let res = workforce_metrics::table
+            .filter(workforce_metrics::organization_id.eq(organization_id))
+            .select(max(workforce_metrics::refreshed_at))
+            .first::<Option<NaiveDateTime>>(conn)?;
+
+        Ok(res)
+    }
+}
+
+#[derive(Debug, Clone, Insertable)]
+#[table_name = "workforce_metrics"]
+pub struct NewWorkforceMetric {
+    pub organization_id: Uuid,
+    pub scope_type: String,
+    pub scope_id: Uuid,
+    pub period: NaiveDate,
+    pub headcount: i32,
+    pub fte: f64,
+    pub hires: i32,
+    pub departures: i32,
+    pub moves_in: i32,
+    pub moves_out: i32,
+    pub turnover_rate: f64,
+    pub average_tenure_months: f64,
+}
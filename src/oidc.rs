@@ -0,0 +1,201 @@
+// OpenID Connect sign-in with the authorization code flow and PKCE.
+// See https://openid.net/specs/openid-connect-core-1_0.html
+
+use std::env;
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde::de::DeserializeOwned;
+use sha2::{Digest, Sha256};
+use url::Url;
+
+use crate::errors::CustomError;
+
+/// Identity provider and client settings, read from the environment.
+/// Single sign-on is off unless OIDC_ISSUER, OIDC_CLIENT_ID and OIDC_REDIRECT_URL are set.
+#[derive(Debug, Clone)]
+pub struct OidcConfig {
+    pub issuer: String,
+    pub client_id: String,
+    pub client_secret: Option<String>, // None for public clients relying on PKCE alone
+    pub redirect_url: String, // Our /oidc/callback as registered with the provider
+    pub provider_name: String, // Shown on the log in button
+}
+
+impl OidcConfig {
+    pub fn from_env() -> Option<Self> {
+        let var = |name: &str| env::var(name).ok()
+            .map(|v| v.trim().to_owned())
+            .filter(|v| !v.is_empty());
+
+        Some(OidcConfig {
+            issuer: var("OIDC_ISSUER")?.trim_end_matches('/').to_owned(),
+            client_id: var("OIDC_CLIENT_ID")?,
+            client_secret: var("OIDC_CLIENT_SECRET"),
+            redirect_url: var("OIDC_REDIRECT_URL")?,
+            provider_name: var("OIDC_PROVIDER_NAME").unwrap_or_else(|| "SSO".to_string()),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+/// The parts of the provider's discovery document we use
+pub struct OidcProvider {
+    pub issuer: String,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub userinfo_endpoint: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TokenResponse {
+    pub access_token: String,
+    pub id_token: String,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+/// Claims about the signed in person, from the ID token or the userinfo endpoint
+pub struct OidcClaims {
+    pub sub: String,
+    pub email: Option<String>,
+    #[serde(default)]
+    pub email_verified: bool,
+    pub name: Option<String>,
+    pub preferred_username: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct IdTokenClaims {
+    iss: String,
+    aud: Audience,
+    exp: i64,
+    nonce: Option<String>,
+    #[serde(flatten)]
+    claims: OidcClaims,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+enum Audience {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl Audience {
+    fn contains(&self, client_id: &str) -> bool {
+        match self {
+            Audience::One(aud) => aud == client_id,
+            Audience::Many(auds) => auds.iter().any(|a| a == client_id),
+        }
+    }
+}
+
+/// PKCE S256 code challenge for a code verifier
+pub fn pkce_challenge(code_verifier: &str) -> String {
+    base64::encode_config(Sha256::digest(code_verifier.as_bytes()), base64::URL_SAFE_NO_PAD)
+}
+
+fn provider_error(step: &str, e: impl std::fmt::Display) -> CustomError {
+    CustomError::new(502, format!("OIDC {} failed: {}", step, e))
+}
+
+fn read_json<T: DeserializeOwned>(response: Result<ureq::Response, ureq::Error>, step: &str) -> Result<T, CustomError> {
+    let body = response
+        .map_err(|e| provider_error(step, e))?
+        .into_string()
+        .map_err(|e| provider_error(step, e))?;
+
+    serde_json::from_str(&body).map_err(|e| provider_error(step, e))
+}
+
+impl OidcProvider {
+    /// Read the provider's discovery document. Blocks, so call from web::block.
+    pub fn discover(config: &OidcConfig) -> Result<Self, CustomError> {
+        let url = format!("{}/.well-known/openid-configuration", config.issuer);
+
+        let provider: OidcProvider = read_json(ureq::get(&url).call(), "discovery")?;
+
+        if provider.issuer.trim_end_matches('/') != config.issuer {
+            return Err(CustomError::new(502, format!("OIDC issuer mismatch: {}", provider.issuer)))
+        };
+
+        Ok(provider)
+    }
+
+    /// Where to send the browser to sign in
+    pub fn authorization_url(&self, config: &OidcConfig, state: &str, nonce: &str, code_verifier: &str) -> Result<String, CustomError> {
+        let url = Url::parse_with_params(&self.authorization_endpoint, &[
+            ("response_type", "code"),
+            ("client_id", config.client_id.as_str()),
+            ("redirect_uri", config.redirect_url.as_str()),
+            ("scope", "openid email profile"),
+            ("state", state),
+            ("nonce", nonce),
+            ("code_challenge", pkce_challenge(code_verifier).as_str()),
+            ("code_challenge_method", "S256"),
+        ]).map_err(|e| provider_error("authorization URL", e))?;
+
+        Ok(url.to_string())
+    }
+
+    /// Exchange the authorization code for tokens and return the claims of the ID token,
+    /// filled in from the userinfo endpoint if it has no email. Blocks, so call from web::block.
+    ///
+    /// The ID token comes straight from the token endpoint over TLS, so its issuer, audience,
+    /// expiry and nonce are checked but not its signature (OIDC Core 3.1.3.7).
+    pub fn sign_in(&self, config: &OidcConfig, code: &str, code_verifier: &str, nonce: &str) -> Result<OidcClaims, CustomError> {
+        let mut form = vec![
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", config.redirect_url.as_str()),
+            ("client_id", config.client_id.as_str()),
+            ("code_verifier", code_verifier),
+        ];
+
+        if let Some(secret) = config.client_secret.as_deref() {
+            form.push(("client_secret", secret));
+        };
+
+        let tokens: TokenResponse = read_json(ureq::post(&self.token_endpoint).send_form(&form), "token exchange")?;
+
+        let id_token = decode_id_token(&tokens.id_token)?;
+
+        if id_token.iss.trim_end_matches('/') != config.issuer
+            || !id_token.aud.contains(&config.client_id)
+            || id_token.exp < Utc::now().timestamp()
+            || id_token.nonce.as_deref() != Some(nonce) {
+            return Err(CustomError::new(401, "OIDC ID token rejected".to_string()))
+        };
+
+        let mut claims = id_token.claims;
+
+        if claims.email.is_none() {
+            if let Some(userinfo_endpoint) = &self.userinfo_endpoint {
+                let userinfo: OidcClaims = read_json(
+                    ureq::get(userinfo_endpoint)
+                        .set("Authorization", &format!("Bearer {}", tokens.access_token))
+                        .call(),
+                    "userinfo",
+                )?;
+
+                // The userinfo subject must match the ID token's
+                if userinfo.sub == claims.sub {
+                    claims = userinfo;
+                };
+            };
+        };
+
+        Ok(claims)
+    }
+}
+
+fn decode_id_token(id_token: &str) -> Result<IdTokenClaims, CustomError> {
+    let payload = id_token.split('.').nth(1)
+        .ok_or_else(|| CustomError::new(401, "Malformed OIDC ID token".to_string()))?;
+
+    let json = base64::decode_config(payload, base64::URL_SAFE_NO_PAD)
+        .map_err(|_| CustomError::new(401, "Malformed OIDC ID token".to_string()))?;
+
+    serde_json::from_slice(&json)
+        .map_err(|_| CustomError::new(401, "Malformed OIDC ID token".to_string()))
+}
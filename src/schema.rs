@@ -1,5 +1,82 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    affiliations (id) {
+        id -> Uuid,
+        person_id -> Uuid,
+        organization_id -> Uuid,
+        role -> Varchar,
+        start_date -> Date,
+        end_date -> Nullable<Date>,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    api_tokens (id) {
+        id -> Uuid,
+        user_id -> Uuid,
+        name -> Varchar,
+        token_prefix -> Varchar,
+        token_hash -> Varchar,
+        scopes -> Array<Text>,
+        expires_at -> Timestamp,
+        last_used_at -> Nullable<Timestamp>,
+        revoked_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    author_matches (id) {
+        id -> Uuid,
+        publication_author_id -> Uuid,
+        person_id -> Uuid,
+        method -> Varchar,
+        score -> Float8,
+        status -> Varchar,
+        created_at -> Timestamp,
+        resolved_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    capabilities (id) {
+        id -> Uuid,
+        person_id -> Uuid,
+        skill_id -> Uuid,
+        self_identified_level -> Int4,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    classification_skills (id) {
+        id -> Uuid,
+        classification -> Varchar,
+        skill_id -> Uuid,
+        expected_level -> Int4,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    credentials (id) {
+        id -> Uuid,
+        person_id -> Uuid,
+        provider -> Varchar,
+        description -> Text,
+        received_date -> Date,
+        validated -> Bool,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     email_verification_code (id) {
         id -> Uuid,
@@ -9,6 +86,62 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    hr_sync_changes (id) {
+        id -> Uuid,
+        sync_run_id -> Uuid,
+        hr_key -> Varchar,
+        entity_type -> Varchar,
+        entity_id -> Uuid,
+        field_name -> Varchar,
+        old_value -> Nullable<Text>,
+        new_value -> Nullable<Text>,
+        status -> Varchar,
+        created_at -> Timestamp,
+        resolved_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    hr_sync_runs (id) {
+        id -> Uuid,
+        source -> Varchar,
+        file_name -> Varchar,
+        organization_id -> Uuid,
+        rows_read -> Int4,
+        changes_applied -> Int4,
+        conflicts -> Int4,
+        started_at -> Timestamp,
+        completed_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    language_evaluations (id) {
+        id -> Uuid,
+        person_id -> Uuid,
+        language -> Varchar,
+        reading -> Nullable<Varchar>,
+        writing -> Nullable<Varchar>,
+        oral -> Nullable<Varchar>,
+        tested_at -> Timestamp,
+        expires_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    oidc_login_states (id) {
+        id -> Uuid,
+        state -> Varchar,
+        nonce -> Varchar,
+        code_verifier -> Varchar,
+        lang -> Varchar,
+        expires_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     org_tier_ownerships (id) {
         id -> Uuid,
@@ -17,6 +150,8 @@ diesel::table! {
         created_at -> Timestamp,
         updated_at -> Timestamp,
         retired_at -> Nullable<Timestamp>,
+        ownership_type -> Varchar,
+        start_datestamp -> Timestamp,
     }
 }
 
@@ -31,6 +166,7 @@ diesel::table! {
         created_at -> Timestamp,
         updated_at -> Timestamp,
         retired_at -> Nullable<Timestamp>,
+        hr_org_unit -> Nullable<Varchar>,
     }
 }
 
@@ -60,13 +196,75 @@ diesel::table! {
 diesel::table! {
     persons (id) {
         id -> Uuid,
-        user_id -> Uuid,
+        user_id -> Nullable<Uuid>,
         family_name -> Varchar,
         given_name -> Varchar,
         organization_id -> Uuid,
         created_at -> Timestamp,
         updated_at -> Timestamp,
         retired_at -> Nullable<Timestamp>,
+        employee_number -> Nullable<Varchar>,
+        orcid -> Nullable<Varchar>,
+        languages -> Array<Text>,
+        first_official_language -> Nullable<Varchar>,
+        location -> Nullable<Varchar>,
+        retirement_eligible_on -> Nullable<Date>,
+    }
+}
+
+diesel::table! {
+    publication_authors (id) {
+        id -> Uuid,
+        publication_id -> Uuid,
+        person_id -> Nullable<Uuid>,
+        author_name -> Varchar,
+        author_order -> Nullable<Int4>,
+        external_affiliation -> Nullable<Varchar>,
+        external_orcid -> Nullable<Varchar>,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    publications (id) {
+        id -> Uuid,
+        title -> Text,
+        doi -> Nullable<Varchar>,
+        venue -> Nullable<Varchar>,
+        publication_year -> Nullable<Int4>,
+        publication_type -> Varchar,
+        open_access -> Varchar,
+        topics -> Array<Text>,
+        url -> Nullable<Text>,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    reporting_relationships (id) {
+        id -> Uuid,
+        reporter -> Uuid,
+        reporting_to -> Uuid,
+        description -> Varchar,
+        start_date -> Timestamp,
+        end_date -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    role_events (id) {
+        id -> Uuid,
+        person_id -> Uuid,
+        role_id -> Uuid,
+        related_role_id -> Nullable<Uuid>,
+        event_type -> Varchar,
+        note -> Text,
+        event_date -> Timestamp,
+        created_at -> Timestamp,
     }
 }
 
@@ -83,6 +281,115 @@ diesel::table! {
         end_date -> Nullable<Timestamp>,
         created_at -> Timestamp,
         updated_at -> Timestamp,
+        role_type -> Varchar,
+        home_role_id -> Nullable<Uuid>,
+        hr_position_number -> Nullable<Varchar>,
+        classification -> Nullable<Varchar>,
+        language_requirement -> Nullable<Varchar>,
+    }
+}
+
+diesel::table! {
+    roster_members (id) {
+        id -> Uuid,
+        roster_id -> Uuid,
+        person_id -> Uuid,
+        qualified_at -> Timestamp,
+        expires_at -> Nullable<Timestamp>,
+        note -> Text,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+        removed_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    roster_placements (id) {
+        id -> Uuid,
+        roster_id -> Uuid,
+        roster_member_id -> Uuid,
+        person_id -> Uuid,
+        role_id -> Uuid,
+        note -> Text,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    roster_skills (id) {
+        id -> Uuid,
+        roster_id -> Uuid,
+        skill_id -> Uuid,
+        min_level -> Int4,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    rosters (id) {
+        id -> Uuid,
+        name_en -> Varchar,
+        name_fr -> Varchar,
+        description_en -> Text,
+        description_fr -> Text,
+        organization_id -> Uuid,
+        classification -> Nullable<Varchar>,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+        retired_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    skills (id) {
+        id -> Uuid,
+        name_en -> Varchar,
+        name_fr -> Varchar,
+        description_en -> Text,
+        description_fr -> Text,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    surge_deployments (id) {
+        id -> Uuid,
+        surge_event_id -> Uuid,
+        person_id -> Uuid,
+        role_id -> Uuid,
+        created_at -> Timestamp,
+        released_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    surge_events (id) {
+        id -> Uuid,
+        name_en -> Varchar,
+        name_fr -> Varchar,
+        description_en -> Text,
+        description_fr -> Text,
+        team_id -> Uuid,
+        headcount -> Int4,
+        deployment_effort -> Float8,
+        languages -> Array<Text>,
+        status -> Varchar,
+        start_date -> Timestamp,
+        end_date -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    surge_requirements (id) {
+        id -> Uuid,
+        surge_event_id -> Uuid,
+        skill_id -> Nullable<Uuid>,
+        min_level -> Int4,
+        credential -> Nullable<Varchar>,
+        created_at -> Timestamp,
     }
 }
 
@@ -95,6 +402,7 @@ diesel::table! {
         end_date -> Nullable<Timestamp>,
         created_at -> Timestamp,
         updated_at -> Timestamp,
+        ownership_type -> Varchar,
     }
 }
 
@@ -113,6 +421,18 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    user_identities (id) {
+        id -> Uuid,
+        user_id -> Uuid,
+        issuer -> Varchar,
+        subject -> Varchar,
+        email -> Varchar,
+        created_at -> Timestamp,
+        last_login_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     users (id) {
         id -> Uuid,
@@ -127,26 +447,150 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    validations (id) {
+        id -> Uuid,
+        validator_id -> Uuid,
+        capability_id -> Uuid,
+        validated_level -> Int4,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    work_skill_requirements (id) {
+        id -> Uuid,
+        work_id -> Uuid,
+        skill_id -> Uuid,
+        required_level -> Int4,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    workforce_metrics (id) {
+        id -> Uuid,
+        organization_id -> Uuid,
+        scope_type -> Varchar,
+        scope_id -> Uuid,
+        period -> Date,
+        headcount -> Int4,
+        fte -> Float8,
+        hires -> Int4,
+        departures -> Int4,
+        moves_in -> Int4,
+        moves_out -> Int4,
+        turnover_rate -> Float8,
+        average_tenure_months -> Float8,
+        refreshed_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    works (id) {
+        id -> Uuid,
+        team_id -> Uuid,
+        person_id -> Nullable<Uuid>,
+        parent_work_id -> Nullable<Uuid>,
+        outcome_en -> Text,
+        outcome_fr -> Text,
+        start_date -> Date,
+        target_completion_date -> Date,
+        work_status -> Int4,
+        completed_date -> Nullable<Date>,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::joinable!(affiliations -> organizations (organization_id));
+diesel::joinable!(affiliations -> persons (person_id));
+diesel::joinable!(api_tokens -> users (user_id));
+diesel::joinable!(author_matches -> persons (person_id));
+diesel::joinable!(author_matches -> publication_authors (publication_author_id));
+diesel::joinable!(capabilities -> persons (person_id));
+diesel::joinable!(capabilities -> skills (skill_id));
+diesel::joinable!(classification_skills -> skills (skill_id));
+diesel::joinable!(credentials -> persons (person_id));
+diesel::joinable!(hr_sync_changes -> hr_sync_runs (sync_run_id));
+diesel::joinable!(hr_sync_runs -> organizations (organization_id));
+diesel::joinable!(language_evaluations -> persons (person_id));
 diesel::joinable!(org_tier_ownerships -> org_tiers (org_tier_id));
 diesel::joinable!(org_tier_ownerships -> persons (owner_id));
 diesel::joinable!(org_tiers -> organizations (organization_id));
 diesel::joinable!(persons -> organizations (organization_id));
+diesel::joinable!(publication_authors -> persons (person_id));
+diesel::joinable!(publication_authors -> publications (publication_id));
+diesel::joinable!(role_events -> persons (person_id));
+diesel::joinable!(role_events -> roles (role_id));
 diesel::joinable!(roles -> persons (person_id));
 diesel::joinable!(roles -> teams (team_id));
+diesel::joinable!(roster_members -> persons (person_id));
+diesel::joinable!(roster_members -> rosters (roster_id));
+diesel::joinable!(roster_placements -> persons (person_id));
+diesel::joinable!(roster_placements -> roles (role_id));
+diesel::joinable!(roster_placements -> roster_members (roster_member_id));
+diesel::joinable!(roster_placements -> rosters (roster_id));
+diesel::joinable!(roster_skills -> rosters (roster_id));
+diesel::joinable!(roster_skills -> skills (skill_id));
+diesel::joinable!(rosters -> organizations (organization_id));
+diesel::joinable!(surge_deployments -> persons (person_id));
+diesel::joinable!(surge_deployments -> roles (role_id));
+diesel::joinable!(surge_deployments -> surge_events (surge_event_id));
+diesel::joinable!(surge_events -> teams (team_id));
+diesel::joinable!(surge_requirements -> skills (skill_id));
+diesel::joinable!(surge_requirements -> surge_events (surge_event_id));
 diesel::joinable!(team_ownerships -> persons (person_id));
 diesel::joinable!(team_ownerships -> teams (team_id));
 diesel::joinable!(teams -> org_tiers (org_tier_id));
 diesel::joinable!(teams -> organizations (organization_id));
+diesel::joinable!(user_identities -> users (user_id));
+diesel::joinable!(validations -> capabilities (capability_id));
+diesel::joinable!(validations -> persons (validator_id));
+diesel::joinable!(work_skill_requirements -> skills (skill_id));
+diesel::joinable!(work_skill_requirements -> works (work_id));
+diesel::joinable!(workforce_metrics -> organizations (organization_id));
+diesel::joinable!(works -> persons (person_id));
+diesel::joinable!(works -> teams (team_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
+    affiliations,
+    api_tokens,
+    author_matches,
+    capabilities,
+    classification_skills,
+    credentials,
     email_verification_code,
+    hr_sync_changes,
+    hr_sync_runs,
+    language_evaluations,
+    oidc_login_states,
     org_tier_ownerships,
     org_tiers,
     organizations,
     password_reset_token,
     persons,
+    publication_authors,
+    publications,
+    reporting_relationships,
+    role_events,
     roles,
+    roster_members,
+    roster_placements,
+    roster_skills,
+    rosters,
+    skills,
+    surge_deployments,
+    surge_events,
+    surge_requirements,
     team_ownerships,
     teams,
+    user_identities,
     users,
+    validations,
+    work_skill_requirements,
+    workforce_metrics,
+    works,
 );
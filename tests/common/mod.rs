@@ -0,0 +1,94 @@
+// Fixtures for the database tests. They need DATABASE_URL and are run with
+// cargo test -- --ignored, inside test_transaction so nothing is kept.
+#![allow(dead_code)]
+
+use std::env;
+
+use chrono::{NaiveDate, NaiveDateTime};
+use diesel::prelude::*;
+use diesel::PgConnection;
+
+use people_data::generate_unique_code;
+use people_data::models::{NewOrgTier, NewOrganization, NewPerson, NewTeam, OrgTier, Organization, Person, Team};
+use people_data::schema::*;
+
+pub fn establish() -> PgConnection {
+    PgConnection::establish(&env::var("DATABASE_URL").expect("DATABASE_URL must be set"))
+        .expect("Unable to connect to DATABASE_URL")
+}
+
+pub fn date(y: i32, m: u32, d: u32) -> NaiveDateTime {
+    NaiveDate::from_ymd(y, m, d).and_hms(0, 0, 0)
+}
+
+/// An organization with one top tier and one team in it
+pub struct Fixture {
+    pub organization: Organization,
+    pub org_tier: OrgTier,
+    pub team: Team,
+}
+
+impl Fixture {
+    pub fn new(conn: &PgConnection) -> Self {
+        let code = generate_unique_code(8, false);
+
+        let organization: Organization = diesel::insert_into(organizations::table)
+            .values(&NewOrganization::new(
+                format!("Test organization {}", code),
+                format!("Organisation de test {}", code),
+                format!("T{}", code),
+                format!("T{}", code),
+                "test".to_string(),
+            ))
+            .get_result(conn)
+            .unwrap();
+
+        let org_tier: OrgTier = diesel::insert_into(org_tiers::table)
+            .values(&NewOrgTier::new(organization.id, 1, "Test branch".to_string(), "Direction de test".to_string(), None))
+            .get_result(conn)
+            .unwrap();
+
+        let team = self::team(conn, &organization, &org_tier, "Test team");
+
+        Fixture {
+            organization,
+            org_tier,
+            team,
+        }
+    }
+
+    pub fn team(&self, conn: &PgConnection, name: &str) -> Team {
+        team(conn, &self.organization, &self.org_tier, name)
+    }
+
+    pub fn person(&self, conn: &PgConnection, family_name: &str) -> Person {
+        self.employee(conn, family_name, None)
+    }
+
+    pub fn employee(&self, conn: &PgConnection, family_name: &str, employee_number: Option<&str>) -> Person {
+        diesel::insert_into(persons::table)
+            .values(&NewPerson::new(
+                None,
+                family_name.to_string(),
+                "Test".to_string(),
+                self.organization.id,
+                employee_number.map(|n| n.to_string()),
+            ))
+            .get_result(conn)
+            .unwrap()
+    }
+}
+
+fn team(conn: &PgConnection, organization: &Organization, org_tier: &OrgTier, name: &str) -> Team {
+    diesel::insert_into(teams::table)
+        .values(&NewTeam::new(
+            name.to_string(),
+            name.to_string(),
+            organization.id,
+            org_tier.id,
+            String::new(),
+            String::new(),
+        ))
+        .get_result(conn)
+        .unwrap()
+}
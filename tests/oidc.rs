@@ -0,0 +1,298 @@
+// Single sign-on against the mock identity provider, started on a free port.
+// Needs the mock-idp feature. The email linking tests need DATABASE_URL and roll back:
+// cargo test --features mock-idp --test oidc -- --ignored
+
+use std::env;
+use std::net::TcpListener;
+use std::thread;
+
+use actix_web::{web, App, HttpServer};
+use diesel::prelude::*;
+use diesel::PgConnection;
+use url::Url;
+
+use people_data::errors::CustomError;
+use people_data::generate_unique_code;
+use people_data::mock_idp::{self, MockIdp};
+use people_data::models::{InsertableUser, User, UserData, UserIdentity};
+use people_data::oidc::{pkce_challenge, OidcClaims, OidcConfig, OidcProvider};
+use people_data::schema::users;
+
+fn start_mock_idp() -> OidcConfig {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("Unable to bind the mock identity provider");
+    let issuer = format!("http://127.0.0.1:{}", listener.local_addr().unwrap().port());
+
+    let idp = web::Data::new(MockIdp::new(&issuer));
+
+    thread::spawn(move || {
+        let mut system = actix_rt::System::new("mock_idp");
+
+        let server = HttpServer::new(move || {
+            App::new()
+                .app_data(idp.clone())
+                .configure(mock_idp::configure)
+        })
+        .listen(listener)
+        .expect("Unable to start the mock identity provider")
+        .run();
+
+        system.block_on(server)
+    });
+
+    OidcConfig {
+        issuer,
+        client_id: "people-data".to_string(),
+        client_secret: None,
+        redirect_url: "http://127.0.0.1:8080/oidc/callback".to_string(),
+        provider_name: "Mock".to_string(),
+    }
+}
+
+/// A sign-in started by the app, as OidcLoginState would hold it
+struct LoginState {
+    state: String,
+    nonce: String,
+    code_verifier: String,
+}
+
+impl LoginState {
+    fn new() -> Self {
+        LoginState {
+            state: generate_unique_code(32, false),
+            nonce: generate_unique_code(32, false),
+            code_verifier: generate_unique_code(64, false),
+        }
+    }
+}
+
+/// Sign in at the provider as the browser would and return the authorization code
+fn authorize(config: &OidcConfig, provider: &OidcProvider, login: &LoginState, email: &str, email_verified: bool) -> String {
+    let url = Url::parse(&provider.authorization_url(config, &login.state, &login.nonce, &login.code_verifier).unwrap()).unwrap();
+    let param = |name: &str| url.query_pairs().find(|(k, _)| k == name).map(|(_, v)| v.into_owned());
+
+    assert_eq!(param("code_challenge"), Some(pkce_challenge(&login.code_verifier)));
+    assert_eq!(param("code_challenge_method").as_deref(), Some("S256"));
+    assert_eq!(param("nonce").as_deref(), Some(login.nonce.as_str()));
+
+    // The provider redirects back to the app, which isn't running
+    let agent = ureq::AgentBuilder::new().redirects(0).build();
+
+    assert_eq!(agent.request_url("GET", &url).call().unwrap().status(), 200);
+
+    let mut form = vec![
+        ("client_id", config.client_id.clone()),
+        ("redirect_uri", config.redirect_url.clone()),
+        ("state", login.state.clone()),
+        ("nonce", login.nonce.clone()),
+        ("code_challenge", pkce_challenge(&login.code_verifier)),
+        ("email", email.to_string()),
+        ("name", "Josiah Carberry".to_string()),
+    ];
+
+    if email_verified {
+        form.push(("email_verified", "on".to_string()));
+    };
+
+    let form: Vec<(&str, &str)> = form.iter().map(|(k, v)| (*k, v.as_str())).collect();
+
+    let response = agent.post(&format!("{}/authorize", config.issuer)).send_form(&form).unwrap();
+    assert_eq!(response.status(), 302);
+
+    let callback = Url::parse(response.header("Location").unwrap()).unwrap();
+    let param = |name: &str| callback.query_pairs().find(|(k, _)| k == name).map(|(_, v)| v.into_owned());
+
+    assert!(callback.as_str().starts_with(&config.redirect_url));
+    assert_eq!(param("state").as_deref(), Some(login.state.as_str()));
+
+    param("code").expect("No authorization code")
+}
+
+fn sign_in_claims(config: &OidcConfig, provider: &OidcProvider, email: &str, email_verified: bool) -> OidcClaims {
+    let login = LoginState::new();
+    let code = authorize(config, provider, &login, email, email_verified);
+
+    provider.sign_in(config, &code, &login.code_verifier, &login.nonce).unwrap()
+}
+
+#[test]
+fn pkce_and_nonce_round_trip() {
+    let config = start_mock_idp();
+    let provider = OidcProvider::discover(&config).unwrap();
+
+    let claims = sign_in_claims(&config, &provider, "Josiah.Carberry@example.com", true);
+
+    assert_eq!(claims.sub, "mock|josiah.carberry@example.com");
+    assert_eq!(claims.email.as_deref(), Some("josiah.carberry@example.com"));
+    assert!(claims.email_verified);
+    assert_eq!(claims.name.as_deref(), Some("Josiah Carberry"));
+}
+
+#[test]
+fn wrong_code_verifier_is_refused() {
+    let config = start_mock_idp();
+    let provider = OidcProvider::discover(&config).unwrap();
+
+    let login = LoginState::new();
+    let code = authorize(&config, &provider, &login, "carberry@example.com", true);
+
+    let intercepted = generate_unique_code(64, false);
+    assert!(provider.sign_in(&config, &code, &intercepted, &login.nonce).is_err());
+}
+
+#[test]
+fn wrong_nonce_is_refused() {
+    let config = start_mock_idp();
+    let provider = OidcProvider::discover(&config).unwrap();
+
+    let login = LoginState::new();
+    let code = authorize(&config, &provider, &login, "carberry@example.com", true);
+
+    let err = provider.sign_in(&config, &code, &login.code_verifier, "another sign-in").unwrap_err();
+    assert_eq!(err.error_status_code, 401);
+}
+
+#[test]
+fn codes_are_single_use() {
+    let config = start_mock_idp();
+    let provider = OidcProvider::discover(&config).unwrap();
+
+    let login = LoginState::new();
+    let code = authorize(&config, &provider, &login, "carberry@example.com", true);
+
+    assert!(provider.sign_in(&config, &code, &login.code_verifier, &login.nonce).is_ok());
+    assert!(provider.sign_in(&config, &code, &login.code_verifier, &login.nonce).is_err());
+}
+
+fn establish() -> PgConnection {
+    PgConnection::establish(&env::var("DATABASE_URL").expect("DATABASE_URL must be set"))
+        .expect("Unable to connect to DATABASE_URL")
+}
+
+fn insert_user(conn: &PgConnection, email: &str, validated: bool) -> User {
+    diesel::insert_into(users::table)
+        .values(InsertableUser::from(UserData {
+            user_name: format!("oidc test {}", generate_unique_code(8, false)),
+            email: email.to_owned(),
+            password: "registered password".to_string(),
+            role: "user".to_string(),
+            validated,
+        }))
+        .get_result(conn)
+        .unwrap()
+}
+
+fn test_email() -> String {
+    format!("{}@example.com", generate_unique_code(12, false).to_lowercase())
+}
+
+#[test]
+#[ignore]
+fn unverified_provider_email_is_not_linked() {
+    let config = start_mock_idp();
+    let provider = OidcProvider::discover(&config).unwrap();
+    let conn = establish();
+
+    conn.test_transaction::<_, CustomError, _>(|| {
+        let email = test_email();
+        insert_user(&conn, &email, true);
+
+        let claims = sign_in_claims(&config, &provider, &email, false);
+        assert_eq!(UserIdentity::sign_in(&conn, &config.issuer, &claims).unwrap_err().error_status_code, 406);
+
+        Ok(())
+    });
+}
+
+#[test]
+#[ignore]
+fn unvalidated_user_is_not_linked() {
+    let config = start_mock_idp();
+    let provider = OidcProvider::discover(&config).unwrap();
+    let conn = establish();
+
+    conn.test_transaction::<_, CustomError, _>(|| {
+        // Registered by someone else with the person's email
+        let email = test_email();
+        let squatter = insert_user(&conn, &email, false);
+
+        let claims = sign_in_claims(&config, &provider, &email, true);
+        assert_eq!(UserIdentity::sign_in(&conn, &config.issuer, &claims).unwrap_err().error_status_code, 409);
+        assert!(UserIdentity::find_by_user(&conn, squatter.id)?.is_empty());
+
+        Ok(())
+    });
+}
+
+#[test]
+#[ignore]
+fn validated_user_is_linked_then_found_by_subject() {
+    let config = start_mock_idp();
+    let provider = OidcProvider::discover(&config).unwrap();
+    let conn = establish();
+
+    conn.test_transaction::<_, CustomError, _>(|| {
+        let email = test_email();
+        let user = insert_user(&conn, &email, true);
+
+        let claims = sign_in_claims(&config, &provider, &email, true);
+        assert_eq!(UserIdentity::sign_in(&conn, &config.issuer, &claims)?.id, user.id);
+        assert_eq!(UserIdentity::find_by_user(&conn, user.id)?.len(), 1);
+
+        // The provider account keeps the link when its email changes
+        let moved = OidcClaims {
+            email: Some(test_email()),
+            ..claims
+        };
+        assert_eq!(UserIdentity::sign_in(&conn, &config.issuer, &moved)?.id, user.id);
+        assert_eq!(UserIdentity::find_by_user(&conn, user.id)?[0].email, moved.email.unwrap());
+
+        Ok(())
+    });
+}
+
+#[test]
+#[ignore]
+fn registered_email_case_is_ignored() {
+    let config = start_mock_idp();
+    let provider = OidcProvider::discover(&config).unwrap();
+    let conn = establish();
+
+    conn.test_transaction::<_, CustomError, _>(|| {
+        // Registration keeps the email as typed, the provider's is lower cased
+        let email = format!("Josiah.Carberry.{}@Example.com", generate_unique_code(8, false));
+        let user = insert_user(&conn, &email, true);
+
+        let claims = sign_in_claims(&config, &provider, &email, true);
+        assert_eq!(claims.email, Some(email.to_lowercase()));
+        assert_eq!(UserIdentity::sign_in(&conn, &config.issuer, &claims)?.id, user.id);
+
+        let users: i64 = users::table
+            .filter(users::email.eq(&email).or(users::email.eq(email.to_lowercase())))
+            .count()
+            .get_result(&conn)?;
+        assert_eq!(users, 1);
+
+        Ok(())
+    });
+}
+
+#[test]
+#[ignore]
+fn new_email_creates_validated_user() {
+    let config = start_mock_idp();
+    let provider = OidcProvider::discover(&config).unwrap();
+    let conn = establish();
+
+    conn.test_transaction::<_, CustomError, _>(|| {
+        let email = test_email();
+
+        let claims = sign_in_claims(&config, &provider, &email, true);
+        let user = UserIdentity::sign_in(&conn, &config.issuer, &claims)?;
+
+        assert_eq!(user.email, email);
+        assert!(user.validated);
+        assert_eq!(UserIdentity::find_by_user(&conn, user.id)?.len(), 1);
+
+        Ok(())
+    });
+}
@@ -0,0 +1,161 @@
+// Primary owners of teams and org tiers can't overlap.
+// Needs DATABASE_URL and rolls back: cargo test --test ownership -- --ignored
+
+mod common;
+
+use people_data::errors::CustomError;
+use people_data::models::{NewOrgOwnership, NewTeamOwnership, OrgOwnership, OwnershipType, TeamOwnership};
+
+use common::{date, establish, Fixture};
+
+#[test]
+#[ignore]
+fn overlapping_team_owners_are_refused() {
+    let conn = establish();
+
+    conn.test_transaction::<_, CustomError, _>(|| {
+        let fixture = Fixture::new(&conn);
+        let first = fixture.person(&conn, "Carberry");
+        let second = fixture.person(&conn, "Sidle");
+
+        TeamOwnership::create(&conn, &NewTeamOwnership::new(
+            first.id, fixture.team.id, date(2020, 1, 1), Some(date(2021, 1, 1)), OwnershipType::Owner)).unwrap();
+
+        // Overlaps the end of the first term
+        let overlapping = NewTeamOwnership::new(second.id, fixture.team.id, date(2020, 6, 1), None, OwnershipType::Owner);
+        assert!(TeamOwnership::create(&conn, &overlapping).is_err());
+        assert!(TeamOwnership::get_or_create(&conn, &overlapping).is_err());
+
+        // Starts as the first term ends
+        TeamOwnership::create(&conn, &NewTeamOwnership::new(
+            second.id, fixture.team.id, date(2021, 1, 1), None, OwnershipType::Owner)).unwrap();
+
+        // Delegates share the team with its owner
+        TeamOwnership::create(&conn, &NewTeamOwnership::new(
+            first.id, fixture.team.id, date(2021, 2, 1), None, OwnershipType::Delegate)).unwrap();
+
+        assert_eq!(TeamOwnership::find_current_by_team(&conn, fixture.team.id, date(2021, 3, 1)).unwrap().len(), 2);
+
+        Ok(())
+    });
+}
+
+#[test]
+#[ignore]
+fn ownership_dates_are_checked() {
+    let conn = establish();
+
+    conn.test_transaction::<_, CustomError, _>(|| {
+        let fixture = Fixture::new(&conn);
+        let person = fixture.person(&conn, "Carberry");
+
+        let open_acting = NewTeamOwnership::new(person.id, fixture.team.id, date(2020, 1, 1), None, OwnershipType::Acting);
+        assert!(TeamOwnership::create(&conn, &open_acting).is_err());
+        assert!(TeamOwnership::get_or_create(&conn, &open_acting).is_err());
+
+        let backwards = NewTeamOwnership::new(person.id, fixture.team.id, date(2020, 1, 1), Some(date(2020, 1, 1)), OwnershipType::Delegate);
+        assert!(TeamOwnership::create(&conn, &backwards).is_err());
+
+        Ok(())
+    });
+}
+
+#[test]
+#[ignore]
+fn get_or_create_returns_the_existing_ownership() {
+    let conn = establish();
+
+    conn.test_transaction::<_, CustomError, _>(|| {
+        let fixture = Fixture::new(&conn);
+        let person = fixture.person(&conn, "Carberry");
+
+        let ownership = NewTeamOwnership::new(person.id, fixture.team.id, date(2020, 1, 1), None, OwnershipType::Owner);
+        let created = TeamOwnership::get_or_create(&conn, &ownership).unwrap();
+        assert_eq!(TeamOwnership::get_or_create(&conn, &ownership).unwrap().id, created.id);
+
+        let tier_ownership = NewOrgOwnership::new(person.id, fixture.org_tier.id, OwnershipType::Owner, date(2020, 1, 1), None);
+        let created = OrgOwnership::get_or_create(&conn, &tier_ownership).unwrap();
+        assert_eq!(OrgOwnership::get_or_create(&conn, &tier_ownership).unwrap().id, created.id);
+
+        Ok(())
+    });
+}
+
+#[test]
+#[ignore]
+fn overlapping_org_tier_owners_are_refused() {
+    let conn = establish();
+
+    conn.test_transaction::<_, CustomError, _>(|| {
+        let fixture = Fixture::new(&conn);
+        let first = fixture.person(&conn, "Carberry");
+        let second = fixture.person(&conn, "Sidle");
+
+        OrgOwnership::get_or_create(&conn, &NewOrgOwnership::new(
+            first.id, fixture.org_tier.id, OwnershipType::Owner, date(2020, 1, 1), None)).unwrap();
+
+        let overlapping = NewOrgOwnership::new(second.id, fixture.org_tier.id, OwnershipType::Owner, date(2022, 1, 1), None);
+        assert!(OrgOwnership::get_or_create(&conn, &overlapping).is_err());
+
+        let open_acting = NewOrgOwnership::new(second.id, fixture.org_tier.id, OwnershipType::Acting, date(2022, 1, 1), None);
+        assert!(OrgOwnership::get_or_create(&conn, &open_acting).is_err());
+
+        Ok(())
+    });
+}
+
+#[test]
+#[ignore]
+fn successor_ends_the_current_term() {
+    let conn = establish();
+
+    conn.test_transaction::<_, CustomError, _>(|| {
+        let fixture = Fixture::new(&conn);
+        let first = fixture.person(&conn, "Carberry");
+        let second = fixture.person(&conn, "Sidle");
+
+        let current = TeamOwnership::create(&conn, &NewTeamOwnership::new(
+            first.id, fixture.team.id, date(2020, 1, 1), None, OwnershipType::Owner)).unwrap();
+
+        // Must start after the current owner
+        assert!(TeamOwnership::succeed(&conn, fixture.team.id, second.id, date(2020, 1, 1)).is_err());
+
+        TeamOwnership::succeed(&conn, fixture.team.id, second.id, date(2022, 1, 1)).unwrap();
+
+        let owners = TeamOwnership::find_current_by_team(&conn, fixture.team.id, date(2022, 6, 1)).unwrap();
+        assert_eq!(owners.len(), 1);
+        assert_eq!(owners[0].person_id, second.id);
+
+        let before = TeamOwnership::find_current_by_team(&conn, fixture.team.id, date(2021, 6, 1)).unwrap();
+        assert_eq!(before[0].id, current.id);
+        assert_eq!(before[0].end_date, Some(date(2022, 1, 1)));
+
+        Ok(())
+    });
+}
+
+#[test]
+#[ignore]
+fn team_without_an_owner_is_orphaned() {
+    let conn = establish();
+
+    conn.test_transaction::<_, CustomError, _>(|| {
+        let fixture = Fixture::new(&conn);
+        let person = fixture.person(&conn, "Carberry");
+
+        TeamOwnership::create(&conn, &NewTeamOwnership::new(
+            person.id, fixture.team.id, date(2020, 1, 1), Some(date(2021, 1, 1)), OwnershipType::Acting)).unwrap();
+
+        let orphaned = TeamOwnership::find_orphaned_teams(&conn, date(2020, 6, 1)).unwrap();
+        let issue = orphaned.iter().find(|i| i.entity_id == fixture.team.id).expect("Team should be orphaned");
+        assert_eq!(issue.acting_owner_ids, vec![person.id]);
+
+        TeamOwnership::create(&conn, &NewTeamOwnership::new(
+            person.id, fixture.team.id, date(2021, 1, 1), None, OwnershipType::Owner)).unwrap();
+
+        let orphaned = TeamOwnership::find_orphaned_teams(&conn, date(2021, 6, 1)).unwrap();
+        assert!(orphaned.iter().all(|i| i.entity_id != fixture.team.id));
+
+        Ok(())
+    });
+}
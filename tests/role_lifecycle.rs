@@ -0,0 +1,225 @@
+// Role transfers, temporary assignments and their events.
+// Needs DATABASE_URL and rolls back: cargo test --test role_lifecycle -- --ignored
+
+mod common;
+
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use diesel::PgConnection;
+use uuid::Uuid;
+
+use people_data::errors::CustomError;
+use people_data::models::{NewRole, Person, Role, RoleEvent, RoleEventType, RoleType, Team};
+use people_data::schema::roles;
+
+use common::{date, establish, Fixture};
+
+fn new_role(person: &Person, team: &Team, title: &str, start: NaiveDateTime, end: Option<NaiveDateTime>) -> NewRole {
+    NewRole::new(
+        Uuid::new_v4(),
+        person.id,
+        team.id,
+        title.to_string(),
+        title.to_string(),
+        1.0,
+        true,
+        start,
+        end,
+        RoleType::Substantive,
+        None,
+    )
+}
+
+fn reload(conn: &PgConnection, role: &Role) -> Role {
+    roles::table.find(role.id).first(conn).unwrap()
+}
+
+fn event_types(conn: &PgConnection, role: &Role) -> Vec<String> {
+    RoleEvent::find_by_role(conn, role.id).unwrap().into_iter().map(|e| e.event_type).collect()
+}
+
+#[test]
+#[ignore]
+fn transfer_closes_the_previous_substantive_role() {
+    let conn = establish();
+
+    conn.test_transaction::<_, CustomError, _>(|| {
+        let fixture = Fixture::new(&conn);
+        let other_team = fixture.team(&conn, "Other team");
+        let person = fixture.person(&conn, "Carberry");
+
+        let first = Role::transfer(&conn, &new_role(&person, &fixture.team, "Analyst", date(2020, 1, 1), None), "hired").unwrap();
+        let second = Role::transfer(&conn, &new_role(&person, &other_team, "Senior analyst", date(2022, 4, 1), None), "moved").unwrap();
+
+        let first = reload(&conn, &first);
+        assert!(!first.active);
+        assert_eq!(first.end_date, Some(date(2022, 4, 1)));
+        assert!(second.active);
+
+        assert_eq!(event_types(&conn, &first), vec![RoleEventType::Started.to_string(), RoleEventType::Ended.to_string()]);
+
+        let transferred = RoleEvent::find_by_role(&conn, second.id).unwrap();
+        assert_eq!(transferred[0].event_type, RoleEventType::Transferred.to_string());
+        assert_eq!(transferred[0].related_role_id, Some(first.id));
+
+        Ok(())
+    });
+}
+
+#[test]
+#[ignore]
+fn acting_suspends_and_restores_the_home_role() {
+    let conn = establish();
+
+    conn.test_transaction::<_, CustomError, _>(|| {
+        let fixture = Fixture::new(&conn);
+        let person = fixture.person(&conn, "Carberry");
+
+        let home = Role::transfer(&conn, &new_role(&person, &fixture.team, "Analyst", date(2020, 1, 1), None), "hired").unwrap();
+        let acting = Role::assign_temporary(&conn,
+            &new_role(&person, &fixture.team, "Acting manager", date(2021, 1, 1), Some(date(2021, 7, 1))),
+            RoleType::Acting, "acting").unwrap();
+
+        assert_eq!(acting.home_role_id, Some(home.id));
+
+        let suspended = reload(&conn, &home);
+        assert!(!suspended.active);
+        assert_eq!(suspended.end_date, None);
+
+        let ended = acting.end_role(&conn, date(2021, 7, 1), "back home").unwrap();
+        assert!(!ended.active);
+
+        let restored = reload(&conn, &home);
+        assert!(restored.active);
+        assert_eq!(restored.end_date, None);
+
+        assert_eq!(event_types(&conn, &acting), vec![
+            RoleEventType::ActingStarted.to_string(),
+            RoleEventType::ActingEnded.to_string(),
+        ]);
+
+        // Ending it again is refused
+        assert!(reload(&conn, &acting).end_role(&conn, date(2021, 8, 1), "again").is_err());
+
+        Ok(())
+    });
+}
+
+#[test]
+#[ignore]
+fn transfer_during_secondment_ends_it_and_the_suspended_home_role() {
+    let conn = establish();
+
+    conn.test_transaction::<_, CustomError, _>(|| {
+        let fixture = Fixture::new(&conn);
+        let other_team = fixture.team(&conn, "Other team");
+        let person = fixture.person(&conn, "Carberry");
+
+        let home = Role::transfer(&conn, &new_role(&person, &fixture.team, "Analyst", date(2020, 1, 1), None), "hired").unwrap();
+        let secondment = Role::assign_temporary(&conn,
+            &new_role(&person, &other_team, "Advisor", date(2021, 1, 1), Some(date(2022, 1, 1))),
+            RoleType::Secondment, "secondment").unwrap();
+
+        Role::transfer(&conn, &new_role(&person, &other_team, "Senior advisor", date(2021, 6, 1), None), "stayed").unwrap();
+
+        for role in &[home, secondment] {
+            let role = reload(&conn, role);
+            assert!(!role.active);
+            assert_eq!(role.end_date, Some(date(2021, 6, 1)));
+        };
+
+        Ok(())
+    });
+}
+
+#[test]
+#[ignore]
+fn deployment_runs_alongside_the_home_role() {
+    let conn = establish();
+
+    conn.test_transaction::<_, CustomError, _>(|| {
+        let fixture = Fixture::new(&conn);
+        let person = fixture.person(&conn, "Carberry");
+
+        let home = Role::transfer(&conn, &new_role(&person, &fixture.team, "Analyst", date(2020, 1, 1), None), "hired").unwrap();
+        let deployment = Role::assign_temporary(&conn,
+            &new_role(&person, &fixture.team, "Surge analyst", date(2021, 1, 1), Some(date(2021, 2, 1))),
+            RoleType::Deployment, "deployed").unwrap();
+
+        assert_eq!(deployment.home_role_id, Some(home.id));
+        assert!(reload(&conn, &home).active);
+
+        Ok(())
+    });
+}
+
+#[test]
+#[ignore]
+fn temporary_roles_need_an_end_date() {
+    let conn = establish();
+
+    conn.test_transaction::<_, CustomError, _>(|| {
+        let fixture = Fixture::new(&conn);
+        let person = fixture.person(&conn, "Carberry");
+
+        let open_ended = new_role(&person, &fixture.team, "Acting manager", date(2021, 1, 1), None);
+        assert!(Role::assign_temporary(&conn, &open_ended, RoleType::Acting, "acting").is_err());
+
+        let backwards = new_role(&person, &fixture.team, "Acting manager", date(2021, 1, 1), Some(date(2020, 1, 1)));
+        assert!(Role::assign_temporary(&conn, &backwards, RoleType::Acting, "acting").is_err());
+
+        let dated = new_role(&person, &fixture.team, "Analyst", date(2021, 1, 1), Some(date(2022, 1, 1)));
+        assert!(Role::assign_temporary(&conn, &dated, RoleType::Substantive, "hired").is_err());
+
+        Ok(())
+    });
+}
+
+#[test]
+#[ignore]
+fn expired_acting_role_restores_the_home_role() {
+    let conn = establish();
+
+    conn.test_transaction::<_, CustomError, _>(|| {
+        let fixture = Fixture::new(&conn);
+        let person = fixture.person(&conn, "Carberry");
+
+        let home = Role::transfer(&conn, &new_role(&person, &fixture.team, "Analyst", date(2020, 1, 1), None), "hired").unwrap();
+        let acting = Role::assign_temporary(&conn,
+            &new_role(&person, &fixture.team, "Acting manager", date(2021, 1, 1), Some(date(2021, 3, 1))),
+            RoleType::Acting, "acting").unwrap();
+
+        let expired = Role::expire_temporary_roles(&conn, date(2021, 3, 2)).unwrap();
+        assert!(expired.iter().any(|r| r.id == acting.id));
+
+        let acting = reload(&conn, &acting);
+        assert!(!acting.active);
+        assert_eq!(acting.end_date, Some(date(2021, 3, 1)));
+        assert!(reload(&conn, &home).active);
+
+        Ok(())
+    });
+}
+
+#[test]
+#[ignore]
+fn past_role_leaves_current_roles_alone() {
+    let conn = establish();
+
+    conn.test_transaction::<_, CustomError, _>(|| {
+        let fixture = Fixture::new(&conn);
+        let person = fixture.person(&conn, "Carberry");
+
+        let current = Role::transfer(&conn, &new_role(&person, &fixture.team, "Analyst", date(2020, 1, 1), None), "hired").unwrap();
+        let past = Role::record_past(&conn, &new_role(&person, &fixture.team, "Student", date(2018, 5, 1), Some(date(2018, 9, 1))), "imported").unwrap();
+
+        assert!(!past.active);
+        assert!(reload(&conn, &current).active);
+        assert_eq!(event_types(&conn, &past), vec![RoleEventType::Started.to_string(), RoleEventType::Ended.to_string()]);
+
+        let undated = new_role(&person, &fixture.team, "Student", date(2018, 5, 1), None);
+        assert!(Role::record_past(&conn, &undated, "imported").is_err());
+
+        Ok(())
+    });
+}
@@ -0,0 +1,113 @@
+// Checks made before a person is deployed to a surge event.
+// Needs DATABASE_URL and rolls back: cargo test --test surge -- --ignored
+
+mod common;
+
+use diesel::prelude::*;
+use diesel::PgConnection;
+
+use people_data::errors::CustomError;
+use people_data::models::{Credential, NewCredential, NewSurgeEvent, NewSurgeRequirement, Person, Role, SurgeEvent,
+    SurgeRequirement};
+use people_data::schema::{persons, roles};
+
+use common::{date, establish, Fixture};
+
+fn event(conn: &PgConnection, fixture: &Fixture, headcount: i32) -> SurgeEvent {
+    let event = SurgeEvent::create(conn, &NewSurgeEvent::new(
+        "Flood response".to_string(),
+        "Intervention inondations".to_string(),
+        String::new(),
+        String::new(),
+        fixture.team.id,
+        headcount,
+        0.5,
+        vec!["fr".to_string()],
+        date(2030, 1, 1),
+        None,
+    )).unwrap();
+
+    SurgeRequirement::create(conn, &NewSurgeRequirement::credential(event.id, "first aid".to_string())).unwrap();
+
+    event
+}
+
+/// A person who works in the given languages, with a first aid credential if qualified
+fn responder(conn: &PgConnection, fixture: &Fixture, family_name: &str, languages: &[&str], qualified: bool) -> Person {
+    let person = fixture.person(conn, family_name);
+
+    if qualified {
+        Credential::create(conn, &NewCredential::new(
+            person.id,
+            "Red Cross".to_string(),
+            "Standard First Aid".to_string(),
+            date(2020, 1, 1).date(),
+            true,
+        )).unwrap();
+    };
+
+    diesel::update(persons::table.find(person.id))
+        .set(persons::languages.eq(languages.iter().map(|l| l.to_string()).collect::<Vec<String>>()))
+        .get_result(conn)
+        .unwrap()
+}
+
+#[test]
+#[ignore]
+fn deployments_need_languages_requirements_and_an_open_position() {
+    let conn = establish();
+
+    conn.test_transaction::<_, CustomError, _>(|| {
+        let fixture = Fixture::new(&conn);
+        let event = event(&conn, &fixture, 1);
+        let (start, end) = (date(2030, 1, 1), date(2030, 3, 1));
+
+        let unilingual = responder(&conn, &fixture, "Carberry", &["en"], true);
+        assert!(event.deploy(&conn, &unilingual, 0.5, start, end, "").is_err());
+
+        let unqualified = responder(&conn, &fixture, "Sidle", &["en", "fr"], false);
+        assert!(event.deploy(&conn, &unqualified, 0.5, start, end, "").is_err());
+
+        let first = responder(&conn, &fixture, "Tremblay", &["en", "fr"], true);
+        let deployment = event.deploy(&conn, &first, 0.5, start, end, "").unwrap();
+
+        // Once per event
+        assert!(event.deploy(&conn, &first, 0.5, date(2030, 4, 1), date(2030, 5, 1), "").is_err());
+
+        // The one position is taken until the first person is released
+        let second = responder(&conn, &fixture, "Gagnon", &["fr"], true);
+        assert!(event.deploy(&conn, &second, 0.5, start, end, "").is_err());
+
+        deployment.release(&conn, date(2030, 1, 1), "").unwrap();
+        event.deploy(&conn, &second, 0.5, start, end, "").unwrap();
+
+        Ok(())
+    });
+}
+
+#[test]
+#[ignore]
+fn closing_releases_open_deployments() {
+    let conn = establish();
+
+    conn.test_transaction::<_, CustomError, _>(|| {
+        let fixture = Fixture::new(&conn);
+        let event = event(&conn, &fixture, 2);
+
+        let person = responder(&conn, &fixture, "Tremblay", &["fr"], true);
+        let deployment = event.deploy(&conn, &person, 0.5, date(2030, 1, 1), date(2030, 3, 1), "").unwrap();
+
+        let closed = event.close(&conn, date(2030, 2, 1)).unwrap();
+        assert!(!closed.is_active());
+        assert_eq!(closed.end_date, Some(date(2030, 2, 1)));
+
+        let role: Role = roles::table.find(deployment.role_id).first(&conn)?;
+        assert_eq!(role.end_date, Some(date(2030, 2, 1)));
+        assert!(!role.active);
+
+        let late = responder(&conn, &fixture, "Gagnon", &["fr"], true);
+        assert!(closed.deploy(&conn, &late, 0.5, date(2030, 2, 2), date(2030, 3, 1), "").is_err());
+
+        Ok(())
+    });
+}